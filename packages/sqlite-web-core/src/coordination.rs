@@ -1,6 +1,7 @@
-use js_sys::{Function, Object, Promise, Reflect};
+use js_sys::{Function, Int32Array, Object, Promise, Reflect, Uint8Array};
 use std::cell::{Cell, RefCell};
 use std::collections::{HashMap, VecDeque};
+use std::ffi::CString;
 use std::future::Future;
 use std::pin::Pin;
 use std::rc::Rc;
@@ -14,12 +15,21 @@ use web_sys::{
     Blob, BlobPropertyBag, BroadcastChannel, DedicatedWorkerGlobalScope, MessageEvent, Url, Worker,
 };
 
-use crate::database::SQLiteDatabase;
+use crate::database::{
+    cancel_requested, FunctionSelection, InterruptHandle, OpenOptions, SQLiteDatabase,
+    CURSOR_IDLE_TIMEOUT_MS,
+};
 use crate::messages::{
-    ChannelMessage, MainThreadMessage, WorkerErrorPayload, WorkerMessage,
-    WORKER_ERROR_TYPE_INITIALIZATION_PENDING,
+    ChannelMessage, DbOperation, InitStage, LogLevel, MainThreadMessage, QueryOptions, QueryTiming,
+    RoleInfo, SqliteErrorDetail, WorkerErrorPayload, WorkerEvent, WorkerMessage,
+    WORKER_ERROR_TYPE_INITIALIZATION_PENDING, WORKER_ERROR_TYPE_LEADER_ELECTION_TIMEOUT,
+    WORKER_ERROR_TYPE_LEADER_STEPDOWN, WORKER_ERROR_TYPE_QUERY_ABORTED,
+    WORKER_ERROR_TYPE_QUERY_TIMEOUT, WORKER_ERROR_TYPE_SERVER_BUSY,
+    WORKER_ERROR_TYPE_UNSUPPORTED_OPERATION,
+};
+use crate::util::{
+    creates_temp_object, js_value_to_string, namespaced_identifier, now_ms, set_js_property,
 };
-use crate::util::{js_value_to_string, sanitize_identifier, set_js_property};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum LeadershipRole {
@@ -27,12 +37,63 @@ pub enum LeadershipRole {
     Follower,
 }
 
+impl LeadershipRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LeadershipRole::Leader => "leader",
+            LeadershipRole::Follower => "follower",
+        }
+    }
+}
+
 const MAX_DB_WORKER_RESPAWNS: u32 = 3;
+/// How long a newly promoted worker waits for a competing leader to object
+/// before it spawns the DB worker.
+const LEADER_CLAIM_WINDOW_MS: i32 = 50;
+/// Delay before a worker whose claim was rejected queues for the lock again.
+const LEADER_CLAIM_RETRY_MS: i32 = 250;
+/// Forwarded follower queries a leader runs at once when
+/// `__SQLITE_MAX_FOLLOWER_QUERIES` is not set.
+const DEFAULT_MAX_FOLLOWER_QUERIES: usize = 64;
+const DEFAULT_FOLLOWER_RETRIES: u32 = 2;
+/// Jobs the DB worker holds queued when `__SQLITE_MAX_QUEUE_DEPTH` is not
+/// set.
+const DEFAULT_MAX_QUEUE_DEPTH: usize = 1024;
+/// Probe windows a follower waits through before reporting that no leader
+/// answered; the second covers a leader that was still opening its database.
+const LEADER_PROBE_ATTEMPTS: u32 = 2;
+/// Bytes of forwarded responses a leader remembers, and for how long, so a
+/// request resent to the same leader is answered again without running
+/// twice. Each leader has its own cache: a request resent to a new leader,
+/// or one whose answer was evicted or too large to keep, runs again.
+const ANSWERED_QUERY_CACHE_BYTES: usize = 1024 * 1024;
+const ANSWERED_QUERY_TTL_MS: f64 = 60_000.0;
+/// Error a leader answers a forwarded query with while
+/// `max_follower_queries` of them are already running.
+const TOO_MANY_CONCURRENT_REQUESTS: &str = "Too many concurrent requests";
+const FOLLOWER_TEMP_OBJECT_ERROR: &str =
+    "Temporary objects can only be created from the leader tab; followers share its connection";
+/// Request ids a DB worker's cancel slots hold before the oldest is
+/// overwritten.
+const CANCEL_SIGNAL_SLOTS: u32 = 16;
 
 pub struct WorkerConfig {
     pub db_name: String,
     pub follower_timeout_ms: f64,
     pub query_timeout_ms: f64,
+    /// Forwarded follower queries the leader runs at once before rejecting
+    /// new ones.
+    pub max_follower_queries: usize,
+    /// Times a forwarded query is resent to a new leader after the one it
+    /// was sent to goes away without answering.
+    pub max_follower_retries: u32,
+    /// Jobs the DB worker holds queued before it rejects new ones as busy.
+    pub max_queue_depth: usize,
+    /// Minimum gap between answers to `LeaderPing`; pings arriving inside it
+    /// share one delayed answer. `0` answers every ping.
+    pub announce_interval_ms: f64,
+    /// Settings the DB worker opens the database with.
+    pub open_options: OpenOptions,
 }
 
 pub fn worker_config_from_global() -> Result<WorkerConfig, JsValue> {
@@ -75,22 +136,285 @@ pub fn worker_config_from_global() -> Result<WorkerConfig, JsValue> {
         30000.0
     }
 
+    fn get_max_follower_queries_from_global() -> usize {
+        let global = js_sys::global();
+        let val = Reflect::get(&global, &JsValue::from_str("__SQLITE_MAX_FOLLOWER_QUERIES"))
+            .unwrap_or(JsValue::UNDEFINED);
+        if let Some(n) = val.as_f64() {
+            if n.is_finite() && n >= 1.0 {
+                return n as usize;
+            }
+        }
+        DEFAULT_MAX_FOLLOWER_QUERIES
+    }
+
+    fn get_max_follower_retries_from_global() -> u32 {
+        let global = js_sys::global();
+        let val = Reflect::get(&global, &JsValue::from_str("__SQLITE_FOLLOWER_RETRIES"))
+            .unwrap_or(JsValue::UNDEFINED);
+        if let Some(n) = val.as_f64() {
+            if n.is_finite() && n >= 0.0 {
+                return n.min(u32::MAX as f64) as u32;
+            }
+        }
+        DEFAULT_FOLLOWER_RETRIES
+    }
+
+    fn get_max_queue_depth_from_global() -> usize {
+        let global = js_sys::global();
+        let val = Reflect::get(&global, &JsValue::from_str("__SQLITE_MAX_QUEUE_DEPTH"))
+            .unwrap_or(JsValue::UNDEFINED);
+        if let Some(n) = val.as_f64() {
+            if n.is_finite() && n >= 1.0 {
+                return n as usize;
+            }
+        }
+        DEFAULT_MAX_QUEUE_DEPTH
+    }
+
+    fn get_announce_interval_from_global() -> f64 {
+        let global = js_sys::global();
+        let val = Reflect::get(&global, &JsValue::from_str("__SQLITE_ANNOUNCE_INTERVAL_MS"))
+            .unwrap_or(JsValue::UNDEFINED);
+        if let Some(n) = val.as_f64() {
+            if n.is_finite() && n >= 0.0 {
+                return n;
+            }
+        }
+        0.0
+    }
+
+    /// `false` disables every custom function and an array of names keeps
+    /// only those; anything else registers them all.
+    fn get_custom_functions_from_global() -> FunctionSelection {
+        let global = js_sys::global();
+        let val = Reflect::get(&global, &JsValue::from_str("__SQLITE_CUSTOM_FUNCTIONS"))
+            .unwrap_or(JsValue::UNDEFINED);
+        if val.as_bool() == Some(false) {
+            return FunctionSelection::Only(vec![]);
+        }
+        if js_sys::Array::is_array(&val) {
+            let names = js_sys::Array::from(&val)
+                .iter()
+                .filter_map(|name| name.as_string())
+                .collect();
+            return FunctionSelection::Only(names);
+        }
+        FunctionSelection::All
+    }
+
+    /// A non-empty string isolates the OPFS pool, channel and lock from
+    /// other namespaces.
+    fn get_namespace_from_global() -> Option<String> {
+        let global = js_sys::global();
+        let val = Reflect::get(&global, &JsValue::from_str("__SQLITE_NAMESPACE"))
+            .unwrap_or(JsValue::UNDEFINED);
+        val.as_string()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+    }
+
+    /// `true` lets the DB worker open the database in memory when OPFS
+    /// cannot be installed.
+    fn get_memory_fallback_from_global() -> bool {
+        let global = js_sys::global();
+        Reflect::get(&global, &JsValue::from_str("__SQLITE_MEMORY_FALLBACK"))
+            .ok()
+            .and_then(|val| val.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// `true` has the DB worker open a private in-memory database instead
+    /// of the OPFS file.
+    fn get_in_memory_from_global() -> bool {
+        let global = js_sys::global();
+        Reflect::get(&global, &JsValue::from_str("__SQLITE_IN_MEMORY"))
+            .ok()
+            .and_then(|val| val.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// A JSON array of pragma strings; anything else means none.
+    fn get_pragmas_from_global() -> Vec<String> {
+        let global = js_sys::global();
+        let val = Reflect::get(&global, &JsValue::from_str("__SQLITE_PRAGMAS"))
+            .unwrap_or(JsValue::UNDEFINED);
+        if !js_sys::Array::is_array(&val) {
+            return Vec::new();
+        }
+        js_sys::Array::from(&val)
+            .iter()
+            .filter_map(|pragma| pragma.as_string())
+            .collect()
+    }
+
     Ok(WorkerConfig {
         db_name: get_db_name_from_global()?,
         follower_timeout_ms: get_follower_timeout_from_global(),
         query_timeout_ms: get_query_timeout_from_global(),
+        max_follower_queries: get_max_follower_queries_from_global(),
+        max_follower_retries: get_max_follower_retries_from_global(),
+        max_queue_depth: get_max_queue_depth_from_global(),
+        announce_interval_ms: get_announce_interval_from_global(),
+        open_options: OpenOptions {
+            custom_functions: get_custom_functions_from_global(),
+            namespace: get_namespace_from_global(),
+            pragmas: get_pragmas_from_global(),
+            allow_memory_fallback: get_memory_fallback_from_global(),
+            in_memory: get_in_memory_from_global(),
+        },
     })
 }
 
+/// Install the OPFS VFS with `install` and open `db_name` in it. When the
+/// install fails and `options.allow_memory_fallback` is set, the database is
+/// opened in memory instead; the flag returned says whether it was.
+async fn open_database(
+    db_name: &str,
+    options: &OpenOptions,
+    install: impl Future<Output = Result<CString, JsValue>>,
+) -> Result<(SQLiteDatabase, bool), JsValue> {
+    match install.await {
+        Ok(vfs) => {
+            send_init_progress(InitStage::OpfsInstalled);
+            let db = SQLiteDatabase::open_opfs(db_name, &vfs, options).await?;
+            Ok((db, false))
+        }
+        Err(err) if options.allow_memory_fallback => {
+            send_worker_log(LogLevel::Warn, || {
+                format!(
+                    "{}; opening {db_name} in memory, without persistence",
+                    js_value_to_string(&err)
+                )
+            });
+            send_init_progress(InitStage::MemoryFallback);
+            let db = SQLiteDatabase::open_in_memory(options).await?;
+            Ok((db, true))
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Successful result of a DB job. Binary results are posted as a
+/// transferable `Uint8Array` instead of being encoded into the result string.
+enum DbJobOutput {
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
 enum DbRequestOrigin {
     Local { request_id: u32 },
     Forwarded { query_id: String },
 }
 
+#[derive(Clone)]
+enum DbJobKind {
+    Query {
+        sql: String,
+        params: Option<Vec<serde_json::Value>>,
+        options: Option<QueryOptions>,
+    },
+    Operation(DbOperation),
+}
+
+impl DbJobKind {
+    /// The query's own `timeoutMs`, which replaces the coordinator's
+    /// default wait for a forwarded answer.
+    fn timeout_ms(&self) -> Option<f64> {
+        match self {
+            DbJobKind::Query {
+                options: Some(options),
+                ..
+            } => options.timeout_ms,
+            _ => None,
+        }
+    }
+
+    /// Whether running this job would create a temporary object, checking
+    /// every statement of a transaction.
+    fn creates_temp_object(&self) -> bool {
+        match self {
+            DbJobKind::Query { sql, .. } => creates_temp_object(sql),
+            DbJobKind::Operation(DbOperation::Transaction { statements, .. }) => statements
+                .iter()
+                .any(|statement| creates_temp_object(&statement.sql)),
+            DbJobKind::Operation(_) => false,
+        }
+    }
+
+    fn into_worker_message(self, request_id: u32) -> WorkerMessage {
+        match self {
+            DbJobKind::Query {
+                sql,
+                params,
+                options,
+            } => WorkerMessage::ExecuteQuery {
+                request_id,
+                sql,
+                params,
+                options,
+            },
+            DbJobKind::Operation(operation) => WorkerMessage::RunOperation {
+                request_id,
+                operation,
+            },
+        }
+    }
+
+    fn into_channel_message(self, query_id: String) -> ChannelMessage {
+        match self {
+            DbJobKind::Query {
+                sql,
+                params,
+                options,
+            } => ChannelMessage::QueryRequest {
+                query_id,
+                sql,
+                params,
+                options,
+            },
+            DbJobKind::Operation(operation) => ChannelMessage::OperationRequest {
+                query_id,
+                operation,
+            },
+        }
+    }
+}
+
+/// A request this follower forwarded to the leader and is waiting on.
+struct ForwardedRequest {
+    request_id: u32,
+    /// Kept so the request can be resent under the same query id.
+    kind: DbJobKind,
+    retries: u32,
+    /// Set when leadership moved after the request was sent; it is resent
+    /// once the new leader is ready.
+    leader_changed: bool,
+}
+
+/// A response the leader sent for a forwarded query.
+struct AnsweredQuery {
+    query_id: String,
+    answered_at_ms: f64,
+    result: Option<String>,
+    error: Option<String>,
+    sqlite: Option<SqliteErrorDetail>,
+    timing: Option<QueryTiming>,
+}
+
+impl AnsweredQuery {
+    /// Roughly what keeping this answer costs.
+    fn bytes(&self) -> usize {
+        self.query_id.len()
+            + self.result.as_ref().map_or(0, String::len)
+            + self.error.as_ref().map_or(0, String::len)
+    }
+}
+
 struct DbJob {
     request_id: u32,
-    sql: String,
-    params: Option<Vec<serde_json::Value>>,
+    kind: DbJobKind,
+    enqueued_at_ms: f64,
 }
 
 type DbExecFuture = Pin<Box<dyn Future<Output = Result<String, String>> + 'static>>;
@@ -98,6 +422,7 @@ type DbExecFn = dyn Fn(
     Rc<RefCell<Option<SQLiteDatabase>>>,
     String,
     Option<Vec<serde_json::Value>>,
+    QueryOptions,
 ) -> DbExecFuture;
 type DbDeliverFn = dyn Fn(&js_sys::Object);
 
@@ -116,7 +441,9 @@ impl DbWorkerHooks {
 impl Default for DbWorkerHooks {
     fn default() -> Self {
         Self {
-            exec: Rc::new(|db, sql, params| Box::pin(exec_on_db(db, sql, params))),
+            exec: Rc::new(|db, sql, params, options| {
+                Box::pin(exec_on_db(db, sql, params, options))
+            }),
             deliver: Rc::new(deliver_db_result),
         }
     }
@@ -130,29 +457,122 @@ pub struct CoordinatorState {
     pub ready_signaled: Rc<RefCell<bool>>,
     pub follower_timeout_ms: f64,
     pub query_timeout_ms: f64,
+    pub max_follower_queries: usize,
+    pub max_follower_retries: u32,
+    pub max_queue_depth: usize,
+    pub announce_interval_ms: f64,
     pub channel: BroadcastChannel,
     pub db_worker_ready: Rc<RefCell<bool>>,
     pub db_worker: Rc<RefCell<Option<Worker>>>,
+    db_cancel_signal: Rc<RefCell<Option<CancelSignal>>>,
     pub db_name: String,
+    pub open_options: OpenOptions,
     db_pending: Rc<RefCell<HashMap<u32, DbRequestOrigin>>>,
-    pub follower_pending: Rc<RefCell<HashMap<String, u32>>>,
+    follower_pending: Rc<RefCell<HashMap<String, ForwardedRequest>>>,
     pub next_db_request_id: Rc<RefCell<u32>>,
     db_worker_restart_attempts: Rc<Cell<u32>>,
+    leadership_claim: Rc<Cell<bool>>,
+    lock_release: Rc<RefCell<Option<Function>>>,
+    /// Recent forwarded responses, oldest first.
+    answered_queries: RefCell<VecDeque<AnsweredQuery>>,
+    /// Total `AnsweredQuery::bytes` of `answered_queries`.
+    answered_bytes: Cell<usize>,
+    /// When the last `LeaderPing` was answered.
+    last_announcement_ms: Cell<Option<f64>>,
+    /// Set while a delayed answer to coalesced pings is scheduled.
+    announcement_pending: Rc<Cell<bool>>,
 }
 
 pub struct DbWorkerState {
     pub db: Rc<RefCell<Option<SQLiteDatabase>>>,
     pub db_name: String,
+    pub open_options: OpenOptions,
     db_queue: Rc<RefCell<VecDeque<DbJob>>>,
+    max_queue_depth: usize,
     db_processing: Rc<Cell<bool>>,
+    db_active_request: Rc<Cell<Option<u32>>>,
+    db_active_cancelled: Rc<Cell<bool>>,
+    interrupt: Rc<RefCell<Option<InterruptHandle>>>,
+    /// Slots the worker that owns this one writes cancelled request ids to,
+    /// handed over with `worker-ready`. `None` without `SharedArrayBuffer`.
+    cancel_slots: Rc<RefCell<Option<Int32Array>>>,
+    /// Set when the database was opened in memory because OPFS failed.
+    memory_only: Rc<Cell<bool>>,
     hooks: DbWorkerHooks,
 }
 
-pub fn create_broadcast_channel(db_name: &str) -> Result<BroadcastChannel, JsValue> {
-    let channel_name = format!("sqlite-queries-{}", sanitize_identifier(db_name));
+/// Writer side of a DB worker's cancel slots. The DB worker is blocked in
+/// `sqlite3_step` while a statement runs, so a cancel message only reaches
+/// it afterwards; a request id stored here is seen by its progress handler
+/// straight away.
+struct CancelSignal {
+    slots: Int32Array,
+    next: Cell<u32>,
+}
+
+impl CancelSignal {
+    /// The slots a `worker-ready` message carries, if any.
+    fn from_ready_message(data: &JsValue) -> Option<Self> {
+        let slots = Reflect::get(data, &JsValue::from_str("cancelSlots"))
+            .ok()?
+            .dyn_into::<Int32Array>()
+            .ok()?;
+        Some(Self {
+            slots,
+            next: Cell::new(0),
+        })
+    }
+
+    fn cancel(&self, request_id: u32) {
+        let index = self.next.get();
+        self.next.set((index + 1) % self.slots.length().max(1));
+        let _ = js_sys::Atomics::store(&self.slots, index, request_id as i32);
+    }
+}
+
+/// Cancel slots backed by a `SharedArrayBuffer`, which only exists on
+/// cross-origin isolated pages. Elsewhere there is nothing to share and a
+/// cancel waits for the running statement to finish.
+fn new_cancel_slots() -> Option<Int32Array> {
+    let constructor =
+        Reflect::get(&js_sys::global(), &JsValue::from_str("SharedArrayBuffer")).ok()?;
+    if constructor.is_undefined() {
+        return None;
+    }
+    let buffer = js_sys::SharedArrayBuffer::new(CANCEL_SIGNAL_SLOTS * 4);
+    Some(Int32Array::new(&buffer))
+}
+
+/// JavaScript literal for `__SQLITE_CUSTOM_FUNCTIONS` that reads back as
+/// `selection`.
+fn custom_functions_literal(selection: &FunctionSelection) -> String {
+    match selection {
+        FunctionSelection::All => "true".to_string(),
+        FunctionSelection::Only(names) => {
+            serde_json::to_string(names).unwrap_or_else(|_| "false".to_string())
+        }
+    }
+}
+
+pub fn create_broadcast_channel(
+    db_name: &str,
+    namespace: Option<&str>,
+) -> Result<BroadcastChannel, JsValue> {
+    let channel_name = format!(
+        "sqlite-queries-{}",
+        namespaced_identifier(namespace, db_name)
+    );
     BroadcastChannel::new(&channel_name)
 }
 
+/// Web Lock held by the leader for `db_name` in `namespace`.
+pub fn database_lock_id(db_name: &str, namespace: Option<&str>) -> String {
+    format!(
+        "sqlite-database-{}",
+        namespaced_identifier(namespace, db_name)
+    )
+}
+
 impl CoordinatorState {
     pub fn new(config: WorkerConfig) -> Result<Rc<Self>, JsValue> {
         Ok(Rc::new(CoordinatorState {
@@ -163,21 +583,41 @@ impl CoordinatorState {
             ready_signaled: Rc::new(RefCell::new(false)),
             follower_timeout_ms: config.follower_timeout_ms,
             query_timeout_ms: config.query_timeout_ms,
-            channel: create_broadcast_channel(&config.db_name)?,
+            max_follower_queries: config.max_follower_queries,
+            max_follower_retries: config.max_follower_retries,
+            max_queue_depth: config.max_queue_depth,
+            announce_interval_ms: config.announce_interval_ms,
+            channel: create_broadcast_channel(
+                &config.db_name,
+                config.open_options.namespace.as_deref(),
+            )?,
             db_worker_ready: Rc::new(RefCell::new(false)),
             db_worker: Rc::new(RefCell::new(None)),
+            db_cancel_signal: Rc::new(RefCell::new(None)),
             db_name: config.db_name,
+            open_options: config.open_options,
             db_pending: Rc::new(RefCell::new(HashMap::new())),
             follower_pending: Rc::new(RefCell::new(HashMap::new())),
             next_db_request_id: Rc::new(RefCell::new(1)),
             db_worker_restart_attempts: Rc::new(Cell::new(0)),
+            leadership_claim: Rc::new(Cell::new(false)),
+            lock_release: Rc::new(RefCell::new(None)),
+            answered_queries: RefCell::new(VecDeque::new()),
+            answered_bytes: Cell::new(0),
+            last_announcement_ms: Cell::new(None),
+            announcement_pending: Rc::new(Cell::new(false)),
         }))
     }
 
     pub fn setup_channel_listener(self: &Rc<Self>) -> Result<(), JsValue> {
         let state = Rc::clone(self);
         let onmessage = Closure::wrap(Box::new(move |event: MessageEvent| {
-            if let Ok(msg) = serde_wasm_bindgen::from_value::<ChannelMessage>(event.data()) {
+            let data = event.data();
+            if let Some(bytes) = bytes_payload(&data) {
+                state.handle_channel_bytes_response(&data, bytes);
+                return;
+            }
+            if let Ok(msg) = serde_wasm_bindgen::from_value::<ChannelMessage>(data) {
                 state.handle_channel_message(msg);
             }
         }) as Box<dyn FnMut(MessageEvent)>);
@@ -196,53 +636,10 @@ impl CoordinatorState {
         let worker_id = self.worker_id.clone();
         let channel = self.channel.clone();
         spawn_local(async move {
-            const POLL_INTERVAL_MS: f64 = 250.0;
-            let mut remaining_ms = if timeout_ms.is_finite() {
-                timeout_ms.max(0.0)
-            } else {
-                f64::INFINITY
-            };
-
-            if remaining_ms <= 0.0 {
-                if has_leader.borrow().is_none() {
-                    let message = format!(
-                        "Leader election timed out after {:.0}ms",
-                        timeout_ms.max(0.0)
-                    );
-                    let _ = send_worker_error_message(&message);
-                }
-                return;
-            }
-
-            while remaining_ms.is_infinite() || remaining_ms > 0.0 {
-                if has_leader.borrow().is_some() {
-                    break;
-                }
-                let ping = ChannelMessage::LeaderPing {
-                    requester_id: worker_id.clone(),
-                };
-                if let Err(err_msg) = send_channel_message(&channel, &ping) {
-                    let _ = send_worker_error_message(&err_msg);
-                    break;
-                }
-
-                let sleep_duration = if remaining_ms.is_infinite() {
-                    POLL_INTERVAL_MS
-                } else {
-                    remaining_ms.min(POLL_INTERVAL_MS)
-                };
-                if sleep_duration <= 0.0 {
-                    break;
-                }
-                sleep_ms(sleep_duration.ceil() as i32).await;
-                if remaining_ms.is_finite() {
-                    remaining_ms -= sleep_duration;
-                }
-            }
-            if has_leader.borrow().is_none() {
-                let timeout = timeout_ms.max(0.0);
-                let message = format!("Leader election timed out after {:.0}ms", timeout);
-                let _ = send_worker_error_message(&message);
+            if let Some(message) =
+                leader_probe_failure(&has_leader, &channel, &worker_id, timeout_ms).await
+            {
+                let _ = post_worker_message(&message);
             }
         });
     }
@@ -267,11 +664,17 @@ impl CoordinatorState {
 
         let options = Object::new();
         set_js_property(&options, "mode", &JsValue::from_str("exclusive"))?;
-        let lock_id = format!("sqlite-database-{}", sanitize_identifier(&self.db_name));
+        let lock_id = database_lock_id(&self.db_name, self.open_options.namespace.as_deref());
         let state = Rc::clone(self);
         let handler = Closure::once(move |_lock: JsValue| -> Promise {
+            // The lock is held until this promise resolves, which only happens
+            // if the leadership claim is rejected.
+            let lock_release = Rc::clone(&state.lock_release);
+            let held = Promise::new(&mut |resolve, _| {
+                lock_release.borrow_mut().replace(resolve);
+            });
             state.on_lock_granted();
-            Promise::new(&mut |_, _| {})
+            held
         });
 
         request_fn.call3(
@@ -284,7 +687,81 @@ impl CoordinatorState {
         Ok(())
     }
 
+    /// Claim leadership once the lock is granted. The DB worker is only spawned
+    /// after the claim window passes without objection, so two workers that
+    /// both believe they won the election never open the same OPFS file.
     fn on_lock_granted(self: &Rc<Self>) {
+        self.leadership_claim.set(true);
+        *self.leader_ready.borrow_mut() = false;
+        let claim = ChannelMessage::LeaderClaim {
+            candidate_id: self.worker_id.clone(),
+        };
+        if let Err(err) = send_channel_message(&self.channel, &claim) {
+            let _ = send_worker_error_message(&err);
+        }
+
+        let state = Rc::clone(self);
+        spawn_local(async move {
+            sleep_ms(LEADER_CLAIM_WINDOW_MS).await;
+            if state.leadership_claim.replace(false) {
+                state.promote_to_leader();
+            }
+        });
+    }
+
+    fn handle_leader_claim(self: &Rc<Self>, candidate_id: String) {
+        if candidate_id == self.worker_id {
+            return;
+        }
+        if matches!(*self.role.borrow(), LeadershipRole::Leader) {
+            let rejection = ChannelMessage::LeaderClaimRejected {
+                candidate_id,
+                leader_id: self.worker_id.clone(),
+            };
+            if let Err(err) = send_channel_message(&self.channel, &rejection) {
+                let _ = send_worker_error_message(&err);
+            }
+            if *self.db_worker_ready.borrow() {
+                let ready = ChannelMessage::LeaderReady {
+                    leader_id: self.worker_id.clone(),
+                };
+                let _ = send_channel_message(&self.channel, &ready);
+            }
+            return;
+        }
+        // Both workers are still claiming: the lower worker id keeps its claim.
+        if self.leadership_claim.get() && candidate_id < self.worker_id {
+            self.abandon_leadership_claim(None);
+        }
+    }
+
+    /// Drop a pending claim and release the lock so this worker can queue for
+    /// it again once the current leader goes away.
+    fn abandon_leadership_claim(self: &Rc<Self>, leader_id: Option<String>) {
+        if !self.leadership_claim.replace(false) {
+            return;
+        }
+        if let Some(leader_id) = leader_id {
+            self.mark_leader_known(leader_id);
+        }
+        let Some(release) = self.lock_release.borrow_mut().take() else {
+            return;
+        };
+        let _ = release.call0(&JsValue::NULL);
+        let state = Rc::clone(self);
+        spawn_local(async move {
+            sleep_ms(LEADER_CLAIM_RETRY_MS).await;
+            state.try_become_leader();
+        });
+    }
+
+    fn promote_to_leader(self: &Rc<Self>) {
+        send_worker_log(LogLevel::Info, || {
+            format!(
+                "Worker {} leads {}; starting the DB worker",
+                self.worker_id, self.db_name
+            )
+        });
         *self.role.borrow_mut() = LeadershipRole::Leader;
         self.mark_leader_known(self.worker_id.clone());
 
@@ -332,10 +809,16 @@ impl CoordinatorState {
             serde_json::to_string(&self.db_name).unwrap_or_else(|_| "\"unknown\"".to_string());
         // __SQLITE_DB_ONLY=true runs the embedded worker in DB-only mode, separating coordinator work from DB tasks.
         format!(
-            "self.__SQLITE_DB_ONLY = true;\nself.__SQLITE_DB_NAME = {};\nself.__SQLITE_FOLLOWER_TIMEOUT_MS = {};\nself.__SQLITE_QUERY_TIMEOUT_MS = {};\n",
+            "self.__SQLITE_DB_ONLY = true;\nself.__SQLITE_DB_NAME = {};\nself.__SQLITE_FOLLOWER_TIMEOUT_MS = {};\nself.__SQLITE_QUERY_TIMEOUT_MS = {};\nself.__SQLITE_MAX_QUEUE_DEPTH = {};\nself.__SQLITE_CUSTOM_FUNCTIONS = {};\nself.__SQLITE_NAMESPACE = {};\nself.__SQLITE_PRAGMAS = {};\nself.__SQLITE_MEMORY_FALLBACK = {};\nself.__SQLITE_DEBUG = {};\n",
             db_name_encoded,
             self.follower_timeout_ms,
             self.query_timeout_ms,
+            self.max_queue_depth,
+            custom_functions_literal(&self.open_options.custom_functions),
+            serde_json::to_string(&self.open_options.namespace).unwrap_or_else(|_| "null".to_string()),
+            serde_json::to_string(&self.open_options.pragmas).unwrap_or_else(|_| "[]".to_string()),
+            self.open_options.allow_memory_fallback,
+            debug_logging(),
         )
     }
 
@@ -359,8 +842,15 @@ impl CoordinatorState {
     }
 
     pub fn handle_db_worker_value(self: &Rc<Self>, data: JsValue) {
+        if let Some(bytes) = bytes_payload(&data) {
+            if let Some(request_id) = request_id_of(&data) {
+                self.handle_db_bytes_result(request_id, bytes);
+            }
+            return;
+        }
         match serde_wasm_bindgen::from_value::<MainThreadMessage>(data.clone()) {
             Ok(MainThreadMessage::WorkerReady) => {
+                *self.db_cancel_signal.borrow_mut() = CancelSignal::from_ready_message(&data);
                 *self.db_worker_ready.borrow_mut() = true;
                 *self.leader_ready.borrow_mut() = true;
                 self.db_worker_restart_attempts.set(0);
@@ -371,13 +861,31 @@ impl CoordinatorState {
                     let _ = send_worker_error_message(&err);
                 }
                 self.signal_ready_once();
+                self.resend_follower_pending();
             }
             Ok(MainThreadMessage::QueryResult {
                 request_id,
                 result,
                 error,
+                timing,
             }) => {
-                self.handle_db_query_result(request_id, result, error);
+                self.handle_db_query_result(request_id, result, error, timing);
+            }
+            Ok(MainThreadMessage::WorkerEvent { event }) => {
+                if event.is_shared() {
+                    let relay = ChannelMessage::WorkerEvent {
+                        event: event.clone(),
+                    };
+                    if let Err(err) = send_channel_message(&self.channel, &relay) {
+                        let _ = send_worker_error_message(&err);
+                    }
+                }
+                let _ = send_worker_event(event);
+            }
+            // The DB worker can only post to this worker; pass its log
+            // lines on to the main thread unchanged.
+            Ok(MainThreadMessage::WorkerLog { .. }) => {
+                let _ = post_worker_message(data.unchecked_ref());
             }
             Err(_) => {
                 if let Some(err) = parse_worker_error_payload(&data) {
@@ -388,6 +896,7 @@ impl CoordinatorState {
     }
 
     fn handle_db_worker_failure(self: &Rc<Self>, error: String) {
+        send_worker_log(LogLevel::Warn, || format!("DB worker failed: {error}"));
         *self.db_worker_ready.borrow_mut() = false;
         *self.leader_ready.borrow_mut() = false;
         *self.ready_signaled.borrow_mut() = false;
@@ -419,131 +928,465 @@ impl CoordinatorState {
                 request_id,
                 sql,
                 params,
-            } => match *self.role.borrow() {
-                LeadershipRole::Leader => {
-                    if !*self.db_worker_ready.borrow() {
+                options,
+            } => self.route_request(
+                request_id,
+                DbJobKind::Query {
+                    sql,
+                    params,
+                    options,
+                },
+            ),
+            WorkerMessage::RunOperation {
+                request_id,
+                operation,
+            } => self.route_request(request_id, DbJobKind::Operation(operation)),
+            WorkerMessage::CancelQuery { request_id } => self.cancel_local_query(request_id),
+            WorkerMessage::CancelAll => self.cancel_all_local_queries(),
+            WorkerMessage::GetRole { request_id } => self.report_role(request_id),
+            WorkerMessage::StepDown => self.step_down(),
+        }
+    }
+
+    /// Hand the database off before this worker's page unloads. In-flight
+    /// requests fail with `LeaderStepdown` so callers can resend them to
+    /// whichever worker takes over, and followers start looking for it as
+    /// soon as the stepdown is broadcast instead of waiting to time out.
+    pub fn step_down(self: &Rc<Self>) {
+        if !matches!(*self.role.borrow(), LeadershipRole::Leader) {
+            return;
+        }
+        *self.role.borrow_mut() = LeadershipRole::Follower;
+        *self.db_worker_ready.borrow_mut() = false;
+        *self.leader_ready.borrow_mut() = false;
+        self.leader_id.borrow_mut().take();
+        // Close the database before the lock is released so the next
+        // leader can open the OPFS files.
+        if let Some(worker) = self.db_worker.borrow_mut().take() {
+            worker.terminate();
+        }
+        let pending = self.db_pending.borrow_mut().drain().collect::<Vec<_>>();
+        for (_, origin) in pending {
+            self.fail_origin(origin, WORKER_ERROR_TYPE_LEADER_STEPDOWN.to_string());
+        }
+        self.fail_follower_pending();
+
+        let stepdown = ChannelMessage::LeaderStepdown {
+            leader_id: self.worker_id.clone(),
+        };
+        if let Err(err) = send_channel_message(&self.channel, &stepdown) {
+            let _ = send_worker_error_message(&err);
+        }
+        if let Some(release) = self.lock_release.borrow_mut().take() {
+            let _ = release.call0(&JsValue::NULL);
+        }
+    }
+
+    fn handle_leader_stepdown(self: &Rc<Self>, leader_id: String) {
+        if self.leader_id.borrow().as_deref() != Some(leader_id.as_str()) {
+            return;
+        }
+        self.leader_id.borrow_mut().take();
+        *self.leader_ready.borrow_mut() = false;
+        // Anything the old leader had not answered by now never will be;
+        // it is resent once the next leader is ready.
+        for request in self.follower_pending.borrow_mut().values_mut() {
+            request.leader_changed = true;
+        }
+        self.start_leader_probe();
+    }
+
+    fn fail_follower_pending(&self) {
+        let pending = self
+            .follower_pending
+            .borrow_mut()
+            .drain()
+            .collect::<Vec<_>>();
+        for (_, request) in pending {
+            let _ = send_query_result_to_main(
+                request.request_id,
+                Err(WORKER_ERROR_TYPE_LEADER_STEPDOWN.to_string()),
+            );
+        }
+    }
+
+    /// Resend forwarded requests the previous leader never answered, under
+    /// their original query ids, or fail them once their retries run out.
+    /// A worker that has since become leader runs them itself.
+    fn resend_follower_pending(self: &Rc<Self>) {
+        let stale = self
+            .follower_pending
+            .borrow()
+            .iter()
+            .filter(|(_, request)| request.leader_changed)
+            .map(|(query_id, _)| query_id.clone())
+            .collect::<Vec<_>>();
+        for query_id in stale {
+            let Some(mut request) = self.follower_pending.borrow_mut().remove(&query_id) else {
+                continue;
+            };
+            if request.retries >= self.max_follower_retries {
+                let _ = send_query_result_to_main(
+                    request.request_id,
+                    Err(WORKER_ERROR_TYPE_LEADER_STEPDOWN.to_string()),
+                );
+                continue;
+            }
+            if matches!(*self.role.borrow(), LeadershipRole::Leader) {
+                let origin = DbRequestOrigin::Local {
+                    request_id: request.request_id,
+                };
+                self.forward_to_db(origin, request.kind);
+                continue;
+            }
+            request.retries += 1;
+            request.leader_changed = false;
+            let message = request.kind.clone().into_channel_message(query_id.clone());
+            self.follower_pending.borrow_mut().insert(query_id, request);
+            if let Err(err) = send_channel_message(&self.channel, &message) {
+                let _ = send_worker_error_message(&err);
+            }
+        }
+    }
+
+    fn role_info(&self) -> RoleInfo {
+        RoleInfo {
+            role: self.role.borrow().as_str().to_string(),
+            leader_id: self.leader_id.borrow().clone(),
+        }
+    }
+
+    fn report_role(&self, request_id: u32) {
+        let result = serde_json::to_string(&self.role_info())
+            .map_err(|e| format!("Failed to serialize role: {e}"));
+        let _ = send_query_result_to_main(request_id, result);
+    }
+
+    fn route_request(self: &Rc<Self>, request_id: u32, kind: DbJobKind) {
+        let role = *self.role.borrow();
+        match role {
+            LeadershipRole::Leader => {
+                if !*self.db_worker_ready.borrow() {
+                    let _ = send_query_result_to_main(
+                        request_id,
+                        Err(WORKER_ERROR_TYPE_INITIALIZATION_PENDING.to_string()),
+                    );
+                    return;
+                }
+                self.forward_to_db(DbRequestOrigin::Local { request_id }, kind);
+            }
+            LeadershipRole::Follower => {
+                if !*self.leader_ready.borrow() {
+                    let _ = send_query_result_to_main(
+                        request_id,
+                        Err(WORKER_ERROR_TYPE_INITIALIZATION_PENDING.to_string()),
+                    );
+                    return;
+                }
+                // The leader runs every tab's queries on one connection, so a
+                // temporary object made here would be shared with every tab
+                // and live only as long as the leader does.
+                if kind.creates_temp_object() {
+                    let _ = send_query_result_to_main(
+                        request_id,
+                        Err(FOLLOWER_TEMP_OBJECT_ERROR.to_string()),
+                    );
+                    return;
+                }
+                let query_id = Uuid::new_v4().to_string();
+                self.follower_pending.borrow_mut().insert(
+                    query_id.clone(),
+                    ForwardedRequest {
+                        request_id,
+                        kind: kind.clone(),
+                        retries: 0,
+                        leader_changed: false,
+                    },
+                );
+                let pending = Rc::clone(&self.follower_pending);
+                let timeout = kind.timeout_ms().unwrap_or(self.query_timeout_ms);
+                let timeout_query_id = query_id.clone();
+                // Retries share this deadline rather than starting a new one.
+                spawn_local(async move {
+                    sleep_ms(timeout.ceil() as i32).await;
+                    if let Some(original) = pending.borrow_mut().remove(&timeout_query_id) {
                         let _ = send_query_result_to_main(
-                            request_id,
-                            Err(WORKER_ERROR_TYPE_INITIALIZATION_PENDING.to_string()),
+                            original.request_id,
+                            Err("Query timeout".to_string()),
                         );
-                        return;
                     }
-                    self.forward_query_to_db(DbRequestOrigin::Local { request_id }, sql, params);
+                });
+                let request = kind.into_channel_message(query_id);
+                if let Err(err) = send_channel_message(&self.channel, &request) {
+                    let _ = send_worker_error_message(&err);
                 }
-                LeadershipRole::Follower => {
-                    if !*self.leader_ready.borrow() {
-                        let _ = send_query_result_to_main(
-                            request_id,
-                            Err(WORKER_ERROR_TYPE_INITIALIZATION_PENDING.to_string()),
-                        );
-                        return;
-                    }
-                    let query_id = Uuid::new_v4().to_string();
-                    self.follower_pending
-                        .borrow_mut()
-                        .insert(query_id.clone(), request_id);
-                    let pending = Rc::clone(&self.follower_pending);
-                    let timeout = self.query_timeout_ms;
-                    let timeout_query_id = query_id.clone();
-                    spawn_local(async move {
-                        sleep_ms(timeout.ceil() as i32).await;
-                        if let Some(original) = pending.borrow_mut().remove(&timeout_query_id) {
-                            let _ = send_query_result_to_main(
-                                original,
-                                Err("Query timeout".to_string()),
-                            );
-                        }
-                    });
-                    let request = ChannelMessage::QueryRequest {
-                        query_id,
-                        sql,
-                        params,
-                    };
-                    if let Err(err) = send_channel_message(&self.channel, &request) {
-                        let _ = send_worker_error_message(&err);
-                    }
+            }
+        }
+    }
+
+    fn cancel_local_query(self: &Rc<Self>, request_id: u32) {
+        let forwarded = self
+            .follower_pending
+            .borrow()
+            .iter()
+            .find(|(_, original)| original.request_id == request_id)
+            .map(|(query_id, _)| query_id.clone());
+        if let Some(query_id) = forwarded {
+            self.follower_pending.borrow_mut().remove(&query_id);
+            let _ = send_query_result_to_main(
+                request_id,
+                Err(WORKER_ERROR_TYPE_QUERY_ABORTED.to_string()),
+            );
+            let cancel = ChannelMessage::CancelQuery { query_id };
+            if let Err(err) = send_channel_message(&self.channel, &cancel) {
+                let _ = send_worker_error_message(&err);
+            }
+            return;
+        }
+
+        let db_request_id = self.find_db_request(|origin| {
+            matches!(origin, DbRequestOrigin::Local { request_id: id } if *id == request_id)
+        });
+        if let Some(db_request_id) = db_request_id {
+            self.post_cancel_to_db(db_request_id);
+        }
+    }
+
+    /// Cancel every request from this worker's page, leaving queries
+    /// forwarded by other tabs running.
+    fn cancel_all_local_queries(self: &Rc<Self>) {
+        let mut request_ids = self
+            .follower_pending
+            .borrow()
+            .values()
+            .map(|original| original.request_id)
+            .collect::<Vec<_>>();
+        request_ids.extend(
+            self.db_pending
+                .borrow()
+                .values()
+                .filter_map(|origin| match origin {
+                    DbRequestOrigin::Local { request_id } => Some(*request_id),
+                    DbRequestOrigin::Forwarded { .. } => None,
+                }),
+        );
+        for request_id in request_ids {
+            self.cancel_local_query(request_id);
+        }
+    }
+
+    fn find_db_request(&self, predicate: impl Fn(&DbRequestOrigin) -> bool) -> Option<u32> {
+        self.db_pending
+            .borrow()
+            .iter()
+            .find(|(_, origin)| predicate(origin))
+            .map(|(db_request_id, _)| *db_request_id)
+    }
+
+    fn post_cancel_to_db(&self, db_request_id: u32) {
+        let Some(worker) = self.db_worker.borrow().as_ref().cloned() else {
+            return;
+        };
+        if let Some(signal) = self.db_cancel_signal.borrow().as_ref() {
+            signal.cancel(db_request_id);
+        }
+        let msg = WorkerMessage::CancelQuery {
+            request_id: db_request_id,
+        };
+        match serde_wasm_bindgen::to_value(&msg) {
+            Ok(val) => {
+                if let Err(err) = worker.post_message(&val) {
+                    let _ = send_worker_error_message(&js_value_to_string(&err));
                 }
-            },
+            }
+            Err(err) => {
+                let _ = send_worker_error_message(&format!("{err:?}"));
+            }
         }
     }
 
-    fn handle_channel_message(self: &Rc<Self>, msg: ChannelMessage) {
-        match msg {
-            ChannelMessage::LeaderPing { requester_id: _ } => {
-                if matches!(*self.role.borrow(), LeadershipRole::Leader) {
-                    let response = if *self.db_worker_ready.borrow() {
-                        ChannelMessage::LeaderReady {
-                            leader_id: self.worker_id.clone(),
-                        }
-                    } else {
-                        ChannelMessage::NewLeader {
-                            leader_id: self.worker_id.clone(),
-                        }
-                    };
-                    if let Err(err) = send_channel_message(&self.channel, &response) {
-                        let _ = send_worker_error_message(&err);
+    /// Answers a ping at most once per `announce_interval_ms`. A ping inside
+    /// the interval schedules one answer for when it ends, so every pinging
+    /// tab still hears from the leader.
+    fn handle_leader_ping(self: &Rc<Self>) {
+        if self.announce_interval_ms > 0.0 {
+            if let Some(last) = self.last_announcement_ms.get() {
+                let wait = last + self.announce_interval_ms - js_sys::Date::now();
+                if wait > 0.0 {
+                    if !self.announcement_pending.replace(true) {
+                        let state = Rc::clone(self);
+                        spawn_local(async move {
+                            sleep_ms(wait.ceil() as i32).await;
+                            state.announcement_pending.set(false);
+                            state.answer_leader_ping();
+                        });
                     }
-                } else if *self.leader_ready.borrow() {
-                    let leader_id = self
-                        .leader_id
-                        .borrow()
-                        .clone()
-                        .unwrap_or_else(|| self.worker_id.clone());
-                    let response = ChannelMessage::LeaderReady { leader_id };
-                    let _ = send_channel_message(&self.channel, &response);
+                    return;
+                }
+            }
+        }
+        self.answer_leader_ping();
+    }
+
+    fn answer_leader_ping(&self) {
+        let response = if matches!(*self.role.borrow(), LeadershipRole::Leader) {
+            if *self.db_worker_ready.borrow() {
+                ChannelMessage::LeaderReady {
+                    leader_id: self.worker_id.clone(),
+                }
+            } else {
+                ChannelMessage::NewLeader {
+                    leader_id: self.worker_id.clone(),
                 }
             }
+        } else if *self.leader_ready.borrow() {
+            let leader_id = self
+                .leader_id
+                .borrow()
+                .clone()
+                .unwrap_or_else(|| self.worker_id.clone());
+            ChannelMessage::LeaderReady { leader_id }
+        } else {
+            return;
+        };
+        self.last_announcement_ms.set(Some(js_sys::Date::now()));
+        if let Err(err) = send_channel_message(&self.channel, &response) {
+            if matches!(*self.role.borrow(), LeadershipRole::Leader) {
+                let _ = send_worker_error_message(&err);
+            }
+        }
+    }
+
+    fn handle_channel_message(self: &Rc<Self>, msg: ChannelMessage) {
+        match msg {
+            ChannelMessage::LeaderPing { requester_id: _ } => self.handle_leader_ping(),
             ChannelMessage::NewLeader { leader_id } => {
                 self.mark_leader_known(leader_id);
             }
+            ChannelMessage::LeaderStepdown { leader_id } => self.handle_leader_stepdown(leader_id),
+            ChannelMessage::LeaderClaim { candidate_id } => self.handle_leader_claim(candidate_id),
+            ChannelMessage::LeaderClaimRejected {
+                candidate_id,
+                leader_id,
+            } => {
+                if candidate_id == self.worker_id {
+                    self.abandon_leadership_claim(Some(leader_id));
+                }
+            }
             ChannelMessage::LeaderReady { leader_id } => {
                 self.mark_leader_known(leader_id);
                 *self.leader_ready.borrow_mut() = true;
                 self.signal_ready_once();
+                self.resend_follower_pending();
             }
             ChannelMessage::QueryRequest {
                 query_id,
                 sql,
                 params,
-            } => {
-                if matches!(*self.role.borrow(), LeadershipRole::Leader) {
-                    if !*self.db_worker_ready.borrow() {
-                        let _ = send_channel_message(
-                            &self.channel,
-                            &ChannelMessage::QueryResponse {
-                                query_id,
-                                result: None,
-                                error: Some(WORKER_ERROR_TYPE_INITIALIZATION_PENDING.to_string()),
-                            },
-                        );
-                        return;
-                    }
-                    self.forward_query_to_db(DbRequestOrigin::Forwarded { query_id }, sql, params);
-                }
-            }
+                options,
+            } => self.handle_forwarded_request(
+                query_id,
+                DbJobKind::Query {
+                    sql,
+                    params,
+                    options,
+                },
+            ),
+            ChannelMessage::OperationRequest {
+                query_id,
+                operation,
+            } => self.handle_forwarded_request(query_id, DbJobKind::Operation(operation)),
             ChannelMessage::QueryResponse {
                 query_id,
                 result,
                 error,
+                sqlite,
+                timing,
             } => {
-                if let Some(request_id) = self.follower_pending.borrow_mut().remove(&query_id) {
+                if let Some(request) = self.follower_pending.borrow_mut().remove(&query_id) {
                     let outcome = match (result, error) {
                         (Some(res), _) => Ok(res),
                         (_, Some(err)) => Err(err),
                         _ => Err("Unknown query response".to_string()),
                     };
-                    let _ = send_query_result_to_main(request_id, outcome);
+                    let _ = send_query_outcome_to_main(
+                        request.request_id,
+                        outcome,
+                        sqlite.as_ref(),
+                        timing,
+                    );
+                }
+            }
+            ChannelMessage::WorkerEvent { event } => {
+                let _ = send_worker_event(event);
+            }
+            ChannelMessage::CancelQuery { query_id } => {
+                if !matches!(*self.role.borrow(), LeadershipRole::Leader) {
+                    return;
+                }
+                let db_request_id = self.find_db_request(|origin| {
+                    matches!(origin, DbRequestOrigin::Forwarded { query_id: id } if *id == query_id)
+                });
+                if let Some(db_request_id) = db_request_id {
+                    self.post_cancel_to_db(db_request_id);
                 }
             }
         }
     }
 
-    fn forward_query_to_db(
-        self: &Rc<Self>,
-        origin: DbRequestOrigin,
-        sql: String,
-        params: Option<Vec<serde_json::Value>>,
-    ) {
+    fn handle_forwarded_request(self: &Rc<Self>, query_id: String, kind: DbJobKind) {
+        if !matches!(*self.role.borrow(), LeadershipRole::Leader) {
+            return;
+        }
+        if let Some(replay) = self.answered_response(&query_id) {
+            if let Err(err) = send_channel_message(&self.channel, &replay) {
+                let _ = send_worker_error_message(&err);
+            }
+            return;
+        }
+        if !*self.db_worker_ready.borrow() {
+            let _ = send_channel_message(
+                &self.channel,
+                &ChannelMessage::QueryResponse {
+                    query_id,
+                    result: None,
+                    error: Some(WORKER_ERROR_TYPE_INITIALIZATION_PENDING.to_string()),
+                    sqlite: None,
+                    timing: None,
+                },
+            );
+            return;
+        }
+        // A resent request the leader is still running is answered once.
+        let duplicate = self.find_db_request(|origin| {
+            matches!(origin, DbRequestOrigin::Forwarded { query_id: id } if *id == query_id)
+        });
+        if duplicate.is_some() {
+            return;
+        }
+        let in_flight = self
+            .db_pending
+            .borrow()
+            .values()
+            .filter(|origin| matches!(origin, DbRequestOrigin::Forwarded { .. }))
+            .count();
+        if in_flight >= self.max_follower_queries {
+            let _ = send_channel_message(
+                &self.channel,
+                &ChannelMessage::QueryResponse {
+                    query_id,
+                    result: None,
+                    error: Some(TOO_MANY_CONCURRENT_REQUESTS.to_string()),
+                    sqlite: None,
+                    timing: None,
+                },
+            );
+            return;
+        }
+        self.forward_to_db(DbRequestOrigin::Forwarded { query_id }, kind);
+    }
+
+    fn forward_to_db(self: &Rc<Self>, origin: DbRequestOrigin, kind: DbJobKind) {
         let worker = {
             let borrow = self.db_worker.borrow();
             let Some(worker) = borrow.as_ref() else {
@@ -561,6 +1404,8 @@ impl CoordinatorState {
                                 query_id,
                                 result: None,
                                 error: Some(WORKER_ERROR_TYPE_INITIALIZATION_PENDING.to_string()),
+                                sqlite: None,
+                                timing: None,
                             },
                         );
                     }
@@ -578,14 +1423,15 @@ impl CoordinatorState {
         };
         self.db_pending.borrow_mut().insert(db_request_id, origin);
 
-        let msg = WorkerMessage::ExecuteQuery {
-            request_id: db_request_id,
-            sql,
-            params,
-        };
+        // Move an imported image on to the DB worker rather than copy it.
+        let transfer = js_sys::Array::new();
+        if let DbJobKind::Operation(DbOperation::Import { bytes }) = &kind {
+            transfer.push(&bytes.buffer());
+        }
+        let msg = kind.into_worker_message(db_request_id);
         match serde_wasm_bindgen::to_value(&msg) {
             Ok(val) => {
-                if let Err(err) = worker.post_message(&val) {
+                if let Err(err) = worker.post_message_with_transfer(&val, &transfer) {
                     let _ = send_worker_error_message(&js_value_to_string(&err));
                     if let Some(origin) = self.db_pending.borrow_mut().remove(&db_request_id) {
                         self.fail_origin(
@@ -616,6 +1462,8 @@ impl CoordinatorState {
                         query_id,
                         result: None,
                         error: Some(error),
+                        sqlite: None,
+                        timing: None,
                     },
                 );
             }
@@ -627,10 +1475,12 @@ impl CoordinatorState {
         db_request_id: u32,
         result: Option<String>,
         error: Option<WorkerErrorPayload>,
+        timing: Option<QueryTiming>,
     ) {
         let Some(origin) = self.db_pending.borrow_mut().remove(&db_request_id) else {
             return;
         };
+        let sqlite = error.as_ref().and_then(|err| err.sqlite.clone());
         let outcome = match (result, error) {
             (Some(res), _) => Ok(res),
             (_, Some(err)) => Err(error_payload_to_string(&err)),
@@ -638,35 +1488,136 @@ impl CoordinatorState {
         };
         match origin {
             DbRequestOrigin::Local { request_id } => {
-                let _ = send_query_result_to_main(request_id, outcome);
-            }
-            DbRequestOrigin::Forwarded { query_id } => match outcome {
-                Ok(res) => {
-                    let _ = send_channel_message(
-                        &self.channel,
-                        &ChannelMessage::QueryResponse {
-                            query_id,
-                            result: Some(res),
-                            error: None,
-                        },
-                    );
-                }
-                Err(err) => {
-                    let _ = send_channel_message(
-                        &self.channel,
-                        &ChannelMessage::QueryResponse {
-                            query_id,
-                            result: None,
-                            error: Some(err),
-                        },
-                    );
-                }
-            },
+                let _ = send_query_outcome_to_main(request_id, outcome, sqlite.as_ref(), timing);
+            }
+            DbRequestOrigin::Forwarded { query_id } => {
+                let (result, error) = match outcome {
+                    Ok(res) => (Some(res), None),
+                    Err(err) => (None, Some(err)),
+                };
+                self.remember_answer(AnsweredQuery {
+                    query_id: query_id.clone(),
+                    answered_at_ms: js_sys::Date::now(),
+                    result: result.clone(),
+                    error: error.clone(),
+                    sqlite: sqlite.clone(),
+                    timing,
+                });
+                let _ = send_channel_message(
+                    &self.channel,
+                    &ChannelMessage::QueryResponse {
+                        query_id,
+                        result,
+                        error,
+                        sqlite,
+                        timing,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Keep a forwarded response for replay, evicting the oldest past
+    /// `ANSWERED_QUERY_CACHE_BYTES`. A response larger than that on its own
+    /// is not kept. Binary responses are not kept either; they come from
+    /// read-only operations that are safe to run again.
+    fn remember_answer(&self, answer: AnsweredQuery) {
+        let bytes = answer.bytes();
+        if bytes > ANSWERED_QUERY_CACHE_BYTES {
+            return;
+        }
+        let mut answered = self.answered_queries.borrow_mut();
+        let mut total = self.answered_bytes.get() + bytes;
+        answered.push_back(answer);
+        while total > ANSWERED_QUERY_CACHE_BYTES {
+            let Some(evicted) = answered.pop_front() else {
+                break;
+            };
+            total -= evicted.bytes();
+        }
+        self.answered_bytes.set(total);
+    }
+
+    /// The response already sent for `query_id`, if it is recent enough to
+    /// still be kept.
+    fn answered_response(&self, query_id: &str) -> Option<ChannelMessage> {
+        let mut answered = self.answered_queries.borrow_mut();
+        let now = js_sys::Date::now();
+        while answered
+            .front()
+            .is_some_and(|answer| now - answer.answered_at_ms > ANSWERED_QUERY_TTL_MS)
+        {
+            if let Some(expired) = answered.pop_front() {
+                self.answered_bytes
+                    .set(self.answered_bytes.get() - expired.bytes());
+            }
+        }
+        answered
+            .iter()
+            .find(|answer| answer.query_id == query_id)
+            .map(|answer| ChannelMessage::QueryResponse {
+                query_id: answer.query_id.clone(),
+                result: answer.result.clone(),
+                error: answer.error.clone(),
+                sqlite: answer.sqlite.clone(),
+                timing: answer.timing,
+            })
+    }
+
+    fn handle_db_bytes_result(&self, db_request_id: u32, bytes: Uint8Array) {
+        let Some(origin) = self.db_pending.borrow_mut().remove(&db_request_id) else {
+            return;
+        };
+        let posted = match origin {
+            DbRequestOrigin::Local { request_id } => make_bytes_result_message(request_id, &bytes)
+                .map_err(|err| js_value_to_string(&err))
+                .and_then(|message| post_worker_message(&message)),
+            DbRequestOrigin::Forwarded { query_id } => {
+                send_channel_bytes_response(&self.channel, &query_id, &bytes)
+            }
+        };
+        if let Err(err) = posted {
+            let _ = send_worker_error_message(&err);
+        }
+    }
+
+    /// Deliver a binary response from the leader to the request it answers.
+    fn handle_channel_bytes_response(&self, data: &JsValue, bytes: Uint8Array) {
+        let Some(query_id) = Reflect::get(data, &JsValue::from_str("queryId"))
+            .ok()
+            .and_then(|value| value.as_string())
+        else {
+            return;
+        };
+        let Some(request) = self.follower_pending.borrow_mut().remove(&query_id) else {
+            return;
+        };
+        let posted = make_bytes_result_message(request.request_id, &bytes)
+            .map_err(|err| js_value_to_string(&err))
+            .and_then(|message| post_worker_message(&message));
+        if let Err(err) = posted {
+            let _ = send_worker_error_message(&err);
         }
     }
 
     fn mark_leader_known(&self, leader_id: String) {
-        *self.leader_id.borrow_mut() = Some(leader_id);
+        let previous = self.leader_id.borrow_mut().replace(leader_id.clone());
+        if previous.as_deref() == Some(leader_id.as_str()) {
+            return;
+        }
+        for request in self.follower_pending.borrow_mut().values_mut() {
+            request.leader_changed = true;
+        }
+        send_worker_log(LogLevel::Debug, || format!("Leader is now {leader_id}"));
+        // Later leadership changes are reported by `LeadershipChange` alone.
+        if !*self.ready_signaled.borrow() {
+            send_init_progress(InitStage::LeaderElected);
+        }
+        let event = WorkerEvent::LeadershipChange {
+            is_leader: leader_id == self.worker_id,
+            leader_id,
+        };
+        let _ = send_worker_event(event);
     }
 
     fn signal_ready_once(&self) {
@@ -674,7 +1625,7 @@ impl CoordinatorState {
             return;
         }
         *self.ready_signaled.borrow_mut() = true;
-        if let Err(err) = send_worker_ready_message() {
+        if let Err(err) = send_worker_ready_message(None) {
             let _ = send_worker_error_message(&err);
         }
     }
@@ -689,8 +1640,15 @@ impl DbWorkerState {
         Rc::new(DbWorkerState {
             db: Rc::new(RefCell::new(None)),
             db_name: config.db_name,
+            open_options: config.open_options,
             db_queue: Rc::new(RefCell::new(VecDeque::new())),
+            max_queue_depth: config.max_queue_depth,
             db_processing: Rc::new(Cell::new(false)),
+            db_active_request: Rc::new(Cell::new(None)),
+            db_active_cancelled: Rc::new(Cell::new(false)),
+            interrupt: Rc::new(RefCell::new(None)),
+            cancel_slots: Rc::new(RefCell::new(new_cancel_slots())),
+            memory_only: Rc::new(Cell::new(false)),
             hooks,
         })
     }
@@ -698,13 +1656,53 @@ impl DbWorkerState {
     pub fn start(self: &Rc<Self>) {
         let state = Rc::clone(self);
         spawn_local(async move {
-            match SQLiteDatabase::initialize_opfs(&state.db_name).await {
-                Ok(db) => {
+            let opened = if state.open_options.in_memory {
+                SQLiteDatabase::open_in_memory(&state.open_options)
+                    .await
+                    .map(|db| (db, true))
+            } else {
+                open_database(
+                    &state.db_name,
+                    &state.open_options,
+                    SQLiteDatabase::install_opfs_vfs(&state.open_options),
+                )
+                .await
+            };
+            match opened {
+                Ok((db, memory_only)) => {
+                    state.memory_only.set(memory_only);
+                    send_worker_log(LogLevel::Info, || {
+                        format!("Opened database {}", state.db_name)
+                    });
+                    *state.interrupt.borrow_mut() = Some(db.interrupt_handle());
                     *state.db.borrow_mut() = Some(db);
-                    let _ = send_worker_ready_message();
+                    send_init_progress(InitStage::DbOpen);
+                    let _ = send_worker_ready_message(state.cancel_slots.borrow().as_ref());
+                    state.start_cursor_sweeper();
                 }
                 Err(err) => {
-                    let _ = send_worker_error_message(&js_value_to_string(&err));
+                    let err = js_value_to_string(&err);
+                    send_worker_log(LogLevel::Error, || {
+                        format!("Opening database {} failed: {err}", state.db_name)
+                    });
+                    let _ = send_worker_error_message(&err);
+                }
+            }
+        });
+    }
+
+    /// Periodically finalize cursors whose `queryStream()` was abandoned
+    /// without being read to the end or closed.
+    fn start_cursor_sweeper(self: &Rc<Self>) {
+        let state = Rc::downgrade(self);
+        spawn_local(async move {
+            loop {
+                sleep_ms(CURSOR_IDLE_TIMEOUT_MS as i32).await;
+                let Some(state) = state.upgrade() else { break };
+                // While a job runs it holds the database; the next pass
+                // catches anything this one skips.
+                if let Some(db) = state.db.borrow_mut().as_mut() {
+                    db.close_idle_cursors(CURSOR_IDLE_TIMEOUT_MS);
                 }
             }
         });
@@ -716,22 +1714,125 @@ impl DbWorkerState {
                 request_id,
                 sql,
                 params,
+                options,
+            } => self.enqueue_job(
+                request_id,
+                DbJobKind::Query {
+                    sql,
+                    params,
+                    options,
+                },
+            ),
+            WorkerMessage::RunOperation {
+                request_id,
+                operation: DbOperation::QueueDepth,
             } => {
-                self.enqueue_query(request_id, sql, params);
+                let depth = self.db_queue.borrow().len();
+                self.reply(request_id, Ok(depth.to_string()));
+            }
+            WorkerMessage::RunOperation {
+                request_id,
+                operation: DbOperation::Ping,
+            } => self.reply(request_id, Ok("pong".to_string())),
+            WorkerMessage::RunOperation {
+                request_id,
+                operation: DbOperation::MemoryOnly,
+            } => self.reply(request_id, Ok(self.memory_only.get().to_string())),
+            WorkerMessage::RunOperation {
+                request_id,
+                operation,
+            } => self.enqueue_job(request_id, DbJobKind::Operation(operation)),
+            WorkerMessage::CancelQuery { request_id } => self.cancel_query(request_id),
+            // Roles are tracked by the coordinator, not the DB worker.
+            WorkerMessage::GetRole { request_id } => {
+                self.reject_unsupported(request_id, "get-role")
             }
+            // The coordinator terminates this worker when it steps down.
+            WorkerMessage::StepDown => {}
+            // The coordinator cancels its own requests one at a time, since
+            // this worker also runs queries forwarded by other tabs.
+            WorkerMessage::CancelAll => {}
         }
     }
 
-    fn enqueue_query(
-        self: &Rc<Self>,
-        request_id: u32,
-        sql: String,
-        params: Option<Vec<serde_json::Value>>,
-    ) {
+    /// Answers a message that did not decode as a `WorkerMessage`. If it
+    /// carries a `requestId` the sender gets an unsupported-operation error
+    /// for it; returns false when there is nobody to answer.
+    pub fn handle_unknown_message(&self, data: &JsValue) -> bool {
+        let Some(request_id) = Reflect::get(data, &JsValue::from_str("requestId"))
+            .ok()
+            .and_then(|value| value.as_f64())
+        else {
+            return false;
+        };
+        let operation = Reflect::get(data, &JsValue::from_str("type"))
+            .ok()
+            .and_then(|value| value.as_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        self.reject_unsupported(request_id as u32, &operation);
+        true
+    }
+
+    fn reject_unsupported(&self, request_id: u32, operation: &str) {
+        self.reply(
+            request_id,
+            Err(format!(
+                "{WORKER_ERROR_TYPE_UNSUPPORTED_OPERATION}: {operation}"
+            )),
+        );
+    }
+
+    /// Answer a request without running it on the queue.
+    fn reply(&self, request_id: u32, result: Result<String, String>) {
+        match make_query_result_message(request_id, result, None) {
+            Ok(resp) => self.hooks.deliver.as_ref()(&resp),
+            Err(err) => {
+                let _ = send_worker_error(err);
+            }
+        }
+    }
+
+    /// Drops a queued job before it starts, or interrupts it if it is the job
+    /// currently executing. Unknown or completed requests are ignored.
+    fn cancel_query(self: &Rc<Self>, request_id: u32) {
+        let dropped = {
+            let mut queue = self.db_queue.borrow_mut();
+            queue
+                .iter()
+                .position(|job| job.request_id == request_id)
+                .and_then(|index| queue.remove(index))
+        };
+        if let Some(job) = dropped {
+            let aborted = Err(WORKER_ERROR_TYPE_QUERY_ABORTED.to_string());
+            match make_query_result_message(job.request_id, aborted, None) {
+                Ok(resp) => self.hooks.deliver.as_ref()(&resp),
+                Err(err) => {
+                    let _ = send_worker_error(err);
+                }
+            }
+            return;
+        }
+
+        if self.db_active_request.get() == Some(request_id) {
+            self.db_active_cancelled.set(true);
+            if let Some(handle) = self.interrupt.borrow().as_ref() {
+                handle.interrupt();
+            }
+        }
+    }
+
+    /// Queue a job, or reject it as busy once `max_queue_depth` jobs are
+    /// already waiting so a flood of requests cannot grow the queue without
+    /// bound.
+    fn enqueue_job(self: &Rc<Self>, request_id: u32, kind: DbJobKind) {
+        if self.db_queue.borrow().len() >= self.max_queue_depth {
+            self.reply(request_id, Err(WORKER_ERROR_TYPE_SERVER_BUSY.to_string()));
+            return;
+        }
         self.db_queue.borrow_mut().push_back(DbJob {
             request_id,
-            sql,
-            params,
+            kind,
+            enqueued_at_ms: now_ms(),
         });
         self.start_queue_processor();
     }
@@ -752,8 +1853,76 @@ impl DbWorkerState {
                 let db = Rc::clone(&state.db);
                 let exec = Rc::clone(&hooks.exec);
                 let deliver = Rc::clone(&hooks.deliver);
-                let result = exec.as_ref()(db, job.sql, job.params).await;
-                match make_query_result_message(job.request_id, result) {
+                let cancel_slots = state.cancel_slots.borrow().clone();
+                let cancelled = || {
+                    cancel_slots
+                        .as_ref()
+                        .is_some_and(|slots| cancel_requested(slots, job.request_id))
+                };
+                // Cancel messages for queued jobs arrive only once this loop
+                // yields, so jobs cancelled through the slots are dropped here.
+                if cancelled() {
+                    let aborted = Err(WORKER_ERROR_TYPE_QUERY_ABORTED.to_string());
+                    match make_query_result_message(job.request_id, aborted, None) {
+                        Ok(resp) => deliver.as_ref()(&resp),
+                        Err(err) => {
+                            let _ = send_worker_error(err);
+                        }
+                    }
+                    continue;
+                }
+                if let (Some(db), Some(slots)) = (db.borrow().as_ref(), cancel_slots.as_ref()) {
+                    db.watch_cancel(Some((slots.clone(), job.request_id)));
+                }
+                state.db_active_request.set(Some(job.request_id));
+                state.db_active_cancelled.set(false);
+                let started_at_ms = now_ms();
+                let max_result_bytes = match &job.kind {
+                    DbJobKind::Query {
+                        options: Some(options),
+                        ..
+                    } => options.max_result_bytes,
+                    _ => None,
+                };
+                let is_query = matches!(job.kind, DbJobKind::Query { .. });
+                let mut timing = None;
+                let mut result = match job.kind {
+                    DbJobKind::Query {
+                        sql,
+                        params,
+                        options,
+                    } => {
+                        let options = options.unwrap_or_default();
+                        let profile = options.profile.unwrap_or(false);
+                        let result = exec.as_ref()(db, sql, params, options).await;
+                        if profile && result.is_ok() {
+                            timing = Some(QueryTiming {
+                                queued_ms: (started_at_ms - job.enqueued_at_ms).max(0.0),
+                                exec_ms: (now_ms() - started_at_ms).max(0.0),
+                            });
+                        }
+                        result.map(DbJobOutput::Text)
+                    }
+                    DbJobKind::Operation(operation) => run_operation_on_db(db, operation).await,
+                };
+                state.db_active_request.set(None);
+                if let Some(db) = state.db.borrow().as_ref() {
+                    db.watch_cancel(None);
+                }
+                let mut sqlite = None;
+                if (state.db_active_cancelled.replace(false) || cancelled()) && result.is_err() {
+                    result = Err(WORKER_ERROR_TYPE_QUERY_ABORTED.to_string());
+                } else if is_query && result.is_err() {
+                    sqlite = state
+                        .db
+                        .borrow()
+                        .as_ref()
+                        .and_then(|db| db.last_error_detail());
+                }
+                let message = make_job_result_message(job.request_id, result, max_result_bytes)
+                    .and_then(|resp| attach_sqlite_error(&resp, sqlite.as_ref()).map(|_| resp))
+                    .and_then(|resp| attach_timing(&resp, timing).map(|_| resp));
+                match message {
                     Ok(resp) => deliver.as_ref()(&resp),
                     Err(err) => {
                         let _ = send_worker_error(err);
@@ -768,6 +1937,26 @@ impl DbWorkerState {
     }
 }
 
+/// Post a leader's binary response over the channel. BroadcastChannel cannot
+/// transfer buffers, so the follower receives a copy.
+fn send_channel_bytes_response(
+    channel: &BroadcastChannel,
+    query_id: &str,
+    bytes: &Uint8Array,
+) -> Result<(), String> {
+    let message = js_sys::Object::new();
+    set_js_property(&message, "type", &JsValue::from_str("query-response"))
+        .and_then(|_| set_js_property(&message, "queryId", &JsValue::from_str(query_id)))
+        .and_then(|_| set_js_property(&message, "bytes", bytes))
+        .map_err(|err| js_value_to_string(&err))?;
+    channel.post_message(&message).map_err(|err| {
+        format!(
+            "Failed to post channel message: {}",
+            js_value_to_string(&err)
+        )
+    })
+}
+
 fn send_channel_message(
     channel: &BroadcastChannel,
     message: &ChannelMessage,
@@ -789,30 +1978,173 @@ fn error_payload_to_string(payload: &WorkerErrorPayload) -> String {
         .unwrap_or_else(|| payload.error_type.clone())
 }
 
-pub fn send_worker_ready_message() -> Result<(), String> {
+/// Tell the parent this worker is ready. A DB worker also hands over the
+/// cancel slots its progress handler watches.
+pub fn send_worker_ready_message(cancel_slots: Option<&Int32Array>) -> Result<(), String> {
     let message = js_sys::Object::new();
     set_js_property(&message, "type", &JsValue::from_str("worker-ready"))
         .map_err(|err| js_value_to_string(&err))?;
+    if let Some(slots) = cancel_slots {
+        set_js_property(&message, "cancelSlots", slots).map_err(|err| js_value_to_string(&err))?;
+    }
     post_worker_message(&message)
 }
 
-pub fn send_worker_error_message(error: &str) -> Result<(), String> {
-    let message = js_sys::Object::new();
-    set_js_property(&message, "type", &JsValue::from_str("worker-error"))
-        .map_err(|err| js_value_to_string(&err))?;
-    set_js_property(&message, "error", &JsValue::from_str(error))
-        .map_err(|err| js_value_to_string(&err))?;
-    post_worker_message(&message)
+pub fn send_worker_event(event: WorkerEvent) -> Result<(), String> {
+    let message = MainThreadMessage::WorkerEvent { event };
+    let value = serde_wasm_bindgen::to_value(&message)
+        .map_err(|err| format!("Failed to serialize worker event: {err:?}"))?;
+    post_worker_message(value.unchecked_ref())
 }
 
-pub fn post_worker_message(obj: &js_sys::Object) -> Result<(), String> {
-    let global = js_sys::global();
+/// Report an initialization milestone to the main thread. The DB worker's
+/// reach the main thread through the coordinator like any other event.
+pub fn send_init_progress(stage: InitStage) {
+    let _ = send_worker_event(WorkerEvent::InitProgress { stage });
+}
+
+thread_local! {
+    /// Whether `send_worker_log` posts anything, set once at startup.
+    static DEBUG_LOGGING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// `true` when the connection was opened with `debug: true`.
+pub fn debug_logging_from_global() -> bool {
+    Reflect::get(&js_sys::global(), &JsValue::from_str("__SQLITE_DEBUG"))
+        .ok()
+        .and_then(|val| val.as_bool())
+        .unwrap_or(false)
+}
+
+pub fn set_debug_logging(enabled: bool) {
+    DEBUG_LOGGING.with(|debug| debug.set(enabled));
+}
+
+pub fn debug_logging() -> bool {
+    DEBUG_LOGGING.with(Cell::get)
+}
+
+/// Post a `worker-log` line to the main thread when debug logging is on.
+/// `message` is only built in that case, so logging costs nothing otherwise.
+pub fn send_worker_log(level: LogLevel, message: impl FnOnce() -> String) {
+    if !debug_logging() {
+        return;
+    }
+    let message = MainThreadMessage::WorkerLog {
+        level,
+        message: message(),
+        timestamp: js_sys::Date::now(),
+    };
+    if let Ok(value) = serde_wasm_bindgen::to_value(&message) {
+        let _ = post_worker_message(value.unchecked_ref());
+    }
+}
+
+/// Probes for a leader up to `LEADER_PROBE_ATTEMPTS` times and returns the
+/// `worker-error` to send if none answered.
+async fn leader_probe_failure(
+    has_leader: &Rc<RefCell<Option<String>>>,
+    channel: &BroadcastChannel,
+    worker_id: &str,
+    timeout_ms: f64,
+) -> Option<js_sys::Object> {
+    for _ in 0..LEADER_PROBE_ATTEMPTS {
+        match probe_for_leader(has_leader, channel, worker_id, timeout_ms).await {
+            Ok(true) => return None,
+            Ok(false) => {}
+            Err(err_msg) => return Some(worker_error_message(&err_msg)),
+        }
+    }
+    Some(leader_election_timeout_message(timeout_ms))
+}
+
+/// Pings for a leader until one is known or `timeout_ms` runs out, and
+/// reports whether one answered.
+async fn probe_for_leader(
+    has_leader: &Rc<RefCell<Option<String>>>,
+    channel: &BroadcastChannel,
+    worker_id: &str,
+    timeout_ms: f64,
+) -> Result<bool, String> {
+    const POLL_INTERVAL_MS: f64 = 250.0;
+    let mut remaining_ms = if timeout_ms.is_finite() {
+        timeout_ms.max(0.0)
+    } else {
+        f64::INFINITY
+    };
+
+    while remaining_ms > 0.0 {
+        if has_leader.borrow().is_some() {
+            break;
+        }
+        let ping = ChannelMessage::LeaderPing {
+            requester_id: worker_id.to_string(),
+        };
+        send_channel_message(channel, &ping)?;
+
+        let sleep_duration = remaining_ms.min(POLL_INTERVAL_MS);
+        sleep_ms(sleep_duration.ceil() as i32).await;
+        if remaining_ms.is_finite() {
+            remaining_ms -= sleep_duration;
+        }
+    }
+    Ok(has_leader.borrow().is_some())
+}
+
+/// The `worker-error` sent when no leader answered any probe. It carries an
+/// `errorType` so the page can offer a retry instead of a fatal failure.
+fn leader_election_timeout_message(timeout_ms: f64) -> js_sys::Object {
+    let message = worker_error_message(&format!(
+        "Leader election timed out after {:.0}ms",
+        timeout_ms.max(0.0)
+    ));
+    let _ = set_js_property(
+        &message,
+        "errorType",
+        &JsValue::from_str(WORKER_ERROR_TYPE_LEADER_ELECTION_TIMEOUT),
+    );
+    message
+}
+
+pub fn send_worker_error_message(error: &str) -> Result<(), String> {
+    post_worker_message(&worker_error_message(error))
+}
+
+fn worker_error_message(error: &str) -> js_sys::Object {
+    let message = js_sys::Object::new();
+    let _ = set_js_property(&message, "type", &JsValue::from_str("worker-error"));
+    let _ = set_js_property(&message, "error", &JsValue::from_str(error));
+    message
+}
+
+pub fn post_worker_message(obj: &js_sys::Object) -> Result<(), String> {
+    let global = js_sys::global();
     let scope: DedicatedWorkerGlobalScope = global
         .dyn_into()
         .map_err(|_| "Failed to access worker scope".to_string())?;
-    scope
-        .post_message(obj.as_ref())
-        .map_err(|err| js_value_to_string(&err))
+    // Hand binary payloads over instead of copying them.
+    let posted = match bytes_payload(obj) {
+        Some(bytes) => {
+            scope.post_message_with_transfer(obj.as_ref(), &js_sys::Array::of1(&bytes.buffer()))
+        }
+        None => scope.post_message(obj.as_ref()),
+    };
+    posted.map_err(|err| js_value_to_string(&err))
+}
+
+/// The binary payload of a result message, if it carries one.
+fn bytes_payload(data: &JsValue) -> Option<Uint8Array> {
+    Reflect::get(data, &JsValue::from_str("bytes"))
+        .ok()?
+        .dyn_into::<Uint8Array>()
+        .ok()
+}
+
+fn request_id_of(data: &JsValue) -> Option<u32> {
+    Reflect::get(data, &JsValue::from_str("requestId"))
+        .ok()?
+        .as_f64()
+        .map(|id| id as u32)
 }
 
 pub fn send_worker_error(err: JsValue) -> Result<(), JsValue> {
@@ -846,10 +2178,16 @@ fn parse_worker_error_payload(data: &JsValue) -> Option<String> {
 
 fn make_structured_error(err: &str) -> Result<JsValue, JsValue> {
     let error_object = js_sys::Object::new();
-    let error_type = if err == WORKER_ERROR_TYPE_INITIALIZATION_PENDING {
-        WORKER_ERROR_TYPE_INITIALIZATION_PENDING
-    } else {
-        crate::messages::WORKER_ERROR_TYPE_GENERIC
+    let error_type = match err {
+        WORKER_ERROR_TYPE_INITIALIZATION_PENDING => WORKER_ERROR_TYPE_INITIALIZATION_PENDING,
+        WORKER_ERROR_TYPE_QUERY_ABORTED => WORKER_ERROR_TYPE_QUERY_ABORTED,
+        WORKER_ERROR_TYPE_QUERY_TIMEOUT => WORKER_ERROR_TYPE_QUERY_TIMEOUT,
+        WORKER_ERROR_TYPE_LEADER_STEPDOWN => WORKER_ERROR_TYPE_LEADER_STEPDOWN,
+        WORKER_ERROR_TYPE_SERVER_BUSY => WORKER_ERROR_TYPE_SERVER_BUSY,
+        _ if err.starts_with(WORKER_ERROR_TYPE_UNSUPPORTED_OPERATION) => {
+            WORKER_ERROR_TYPE_UNSUPPORTED_OPERATION
+        }
+        _ => crate::messages::WORKER_ERROR_TYPE_GENERIC,
     };
     set_js_property(
         error_object.as_ref(),
@@ -860,10 +2198,21 @@ fn make_structured_error(err: &str) -> Result<JsValue, JsValue> {
     Ok(error_object.into())
 }
 
+/// Build the `query-result` reply for `request_id`. A result longer than
+/// `max_result_bytes` is replaced by an error, since posting one huge
+/// string can exceed what the browser will clone between workers.
 pub fn make_query_result_message(
     request_id: u32,
     result: Result<String, String>,
+    max_result_bytes: Option<u32>,
 ) -> Result<js_sys::Object, JsValue> {
+    let result = match (result, max_result_bytes) {
+        (Ok(res), Some(max)) if res.len() > max as usize => Err(format!(
+            "Result too large: {} bytes exceeds maxResultBytes ({max}); use queryStream to read it in chunks",
+            res.len()
+        )),
+        (result, _) => result,
+    };
     let response = js_sys::Object::new();
     set_js_property(&response, "type", &JsValue::from_str("query-result"))?;
     set_js_property(
@@ -885,14 +2234,95 @@ pub fn make_query_result_message(
     Ok(response)
 }
 
+pub fn make_bytes_result_message(
+    request_id: u32,
+    bytes: &Uint8Array,
+) -> Result<js_sys::Object, JsValue> {
+    let response = js_sys::Object::new();
+    set_js_property(&response, "type", &JsValue::from_str("query-result"))?;
+    set_js_property(
+        &response,
+        "requestId",
+        &JsValue::from_f64(request_id as f64),
+    )?;
+    set_js_property(&response, "result", &JsValue::NULL)?;
+    set_js_property(&response, "error", &JsValue::NULL)?;
+    set_js_property(&response, "bytes", bytes)?;
+    Ok(response)
+}
+
+fn make_job_result_message(
+    request_id: u32,
+    result: Result<DbJobOutput, String>,
+    max_result_bytes: Option<u32>,
+) -> Result<js_sys::Object, JsValue> {
+    match result {
+        Ok(DbJobOutput::Text(text)) => {
+            make_query_result_message(request_id, Ok(text), max_result_bytes)
+        }
+        Ok(DbJobOutput::Bytes(bytes)) => {
+            make_bytes_result_message(request_id, &Uint8Array::from(bytes.as_slice()))
+        }
+        Err(err) => make_query_result_message(request_id, Err(err), None),
+    }
+}
+
 pub fn send_query_result_to_main(
     request_id: u32,
     result: Result<String, String>,
 ) -> Result<(), JsValue> {
-    let message = make_query_result_message(request_id, result)?;
+    send_query_outcome_to_main(request_id, result, None, None)
+}
+
+/// Like `send_query_result_to_main`, with SQLite's report of the failure
+/// attached to the error and a profiled query's timing beside its result.
+fn send_query_outcome_to_main(
+    request_id: u32,
+    result: Result<String, String>,
+    sqlite: Option<&SqliteErrorDetail>,
+    timing: Option<QueryTiming>,
+) -> Result<(), JsValue> {
+    let message = make_query_result_message(request_id, result, None)?;
+    attach_sqlite_error(&message, sqlite)?;
+    attach_timing(&message, timing)?;
     post_worker_message(&message).map_err(|err| JsValue::from_str(&err))
 }
 
+/// Set `error.sqlite` on a failed `query-result` message.
+fn attach_sqlite_error(
+    response: &js_sys::Object,
+    sqlite: Option<&SqliteErrorDetail>,
+) -> Result<(), JsValue> {
+    let Some(sqlite) = sqlite else {
+        return Ok(());
+    };
+    // Timeouts, aborts and the like keep their own error type.
+    let error = Reflect::get(response, &JsValue::from_str("error"))?;
+    if !error.is_object() {
+        return Ok(());
+    }
+    let error_type = Reflect::get(&error, &JsValue::from_str("type"))?;
+    if error_type.as_string().as_deref() != Some(crate::messages::WORKER_ERROR_TYPE_GENERIC) {
+        return Ok(());
+    }
+    let detail = serde_wasm_bindgen::to_value(sqlite).map_err(JsValue::from)?;
+    set_js_property(&error, "sqlite", &detail)
+}
+
+/// Set `timing` on a successful `query-result` message, leaving the result
+/// text as it is.
+fn attach_timing(response: &js_sys::Object, timing: Option<QueryTiming>) -> Result<(), JsValue> {
+    let Some(timing) = timing else {
+        return Ok(());
+    };
+    let result = Reflect::get(response, &JsValue::from_str("result"))?;
+    if result.is_null() || result.is_undefined() {
+        return Ok(());
+    }
+    let timing = serde_wasm_bindgen::to_value(&timing).map_err(JsValue::from)?;
+    set_js_property(response, "timing", &timing)
+}
+
 fn deliver_db_result(obj: &js_sys::Object) {
     if let Err(err) = post_worker_message(obj) {
         let _ = send_worker_error(JsValue::from_str(&err));
@@ -902,15 +2332,17 @@ async fn exec_on_db(
     db: Rc<RefCell<Option<SQLiteDatabase>>>,
     sql: String,
     params: Option<Vec<serde_json::Value>>,
+    options: QueryOptions,
 ) -> Result<String, String> {
     let db_opt = db.borrow_mut().take();
     let result = match db_opt {
         Some(mut database) => {
-            let result = match params {
-                Some(p) => database.exec_with_params(&sql, p).await,
-                None => database.exec(&sql).await,
-            };
+            let result = database.exec_with_options(&sql, params, &options).await;
+            let events = database.take_events();
             *db.borrow_mut() = Some(database);
+            for event in events {
+                let _ = send_worker_event(event);
+            }
             result
         }
         None => Err(WORKER_ERROR_TYPE_INITIALIZATION_PENDING.to_string()),
@@ -918,6 +2350,70 @@ async fn exec_on_db(
     result
 }
 
+async fn run_operation_on_db(
+    db: Rc<RefCell<Option<SQLiteDatabase>>>,
+    operation: DbOperation,
+) -> Result<DbJobOutput, String> {
+    let Some(mut database) = db.borrow_mut().take() else {
+        return Err(WORKER_ERROR_TYPE_INITIALIZATION_PENDING.to_string());
+    };
+    let result = match operation {
+        DbOperation::Flush => database.flush().await.map(DbJobOutput::Text),
+        DbOperation::Export => database.export().map(DbJobOutput::Bytes),
+        DbOperation::Vacuum => database.vacuum().await.map(DbJobOutput::Text),
+        DbOperation::IntegrityCheck => database.integrity_check().await.map(DbJobOutput::Text),
+        DbOperation::DatabaseSize => database.database_size().await.map(DbJobOutput::Text),
+        DbOperation::PageCount => database
+            .pragma_i64("page_count")
+            .await
+            .map(|count| DbJobOutput::Text(count.to_string())),
+        DbOperation::PageSize { value } => database.page_size(value).await.map(DbJobOutput::Text),
+        // Answered by `DbWorkerState::handle_message` before queueing.
+        DbOperation::QueueDepth => Err("queue-depth is not a queued operation".to_string()),
+        DbOperation::Ping => Err("ping is not a queued operation".to_string()),
+        DbOperation::MemoryOnly => Err("memory-only is not a queued operation".to_string()),
+        DbOperation::HeaderValue { pragma, value } => database
+            .header_value(pragma, value)
+            .await
+            .map(DbJobOutput::Text),
+        DbOperation::BackupInto { name } => {
+            database.backup_into(&name).await.map(DbJobOutput::Text)
+        }
+        DbOperation::Import { bytes } => database
+            .import(&bytes.to_vec())
+            .await
+            .map(DbJobOutput::Text),
+        DbOperation::OpenCursor {
+            sql,
+            params,
+            chunk_size,
+            max_cell_bytes,
+        } => database
+            .open_cursor(&sql, params, chunk_size, max_cell_bytes)
+            .map(DbJobOutput::Text),
+        DbOperation::QueryChunk { cursor } => database.query_chunk(cursor).map(DbJobOutput::Text),
+        DbOperation::CloseCursor { cursor } => database.close_cursor(cursor).map(DbJobOutput::Text),
+        DbOperation::Transaction {
+            statements,
+            chunk_size,
+        } => database
+            .transaction(statements, chunk_size)
+            .await
+            .map(DbJobOutput::Text),
+        DbOperation::Explain { sql, params } => {
+            database.explain(&sql, params).await.map(DbJobOutput::Text)
+        }
+        DbOperation::Validate { sql } => database.validate(&sql).map(DbJobOutput::Text),
+        DbOperation::LastError => database.last_error().map(DbJobOutput::Text),
+    };
+    let events = database.take_events();
+    *db.borrow_mut() = Some(database);
+    for event in events {
+        let _ = send_worker_event(event);
+    }
+    result
+}
+
 pub async fn sleep_ms(ms: i32) {
     let promise = js_sys::Promise::new(&mut |resolve, _| {
         let resolve_for_timeout = resolve.clone();
@@ -994,6 +2490,239 @@ mod tests {
         assert_eq!(cfg.query_timeout_ms, 4321.0);
     }
 
+    #[wasm_bindgen_test]
+    fn worker_config_reads_max_follower_queries() {
+        set_global_str("__SQLITE_DB_NAME", "testdb-max-followers");
+        set_global_num("__SQLITE_MAX_FOLLOWER_QUERIES", 3.0);
+        assert_eq!(
+            worker_config_from_global()
+                .expect("config")
+                .max_follower_queries,
+            3
+        );
+
+        set_global_num("__SQLITE_MAX_FOLLOWER_QUERIES", 0.0);
+        assert_eq!(
+            worker_config_from_global()
+                .expect("config")
+                .max_follower_queries,
+            DEFAULT_MAX_FOLLOWER_QUERIES
+        );
+        let _ = Reflect::delete_property(
+            &js_sys::global(),
+            &JsValue::from_str("__SQLITE_MAX_FOLLOWER_QUERIES"),
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn worker_config_reads_max_queue_depth() {
+        set_global_str("__SQLITE_DB_NAME", "testdb-queue-depth-config");
+        set_global_num("__SQLITE_MAX_QUEUE_DEPTH", 8.0);
+        assert_eq!(
+            worker_config_from_global().expect("config").max_queue_depth,
+            8
+        );
+        let _ = Reflect::delete_property(
+            &js_sys::global(),
+            &JsValue::from_str("__SQLITE_MAX_QUEUE_DEPTH"),
+        );
+        assert_eq!(
+            worker_config_from_global().expect("config").max_queue_depth,
+            DEFAULT_MAX_QUEUE_DEPTH
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn worker_config_reads_follower_retries() {
+        set_global_str("__SQLITE_DB_NAME", "testdb-follower-retries");
+        set_global_num("__SQLITE_FOLLOWER_RETRIES", 0.0);
+        assert_eq!(
+            worker_config_from_global()
+                .expect("config")
+                .max_follower_retries,
+            0
+        );
+
+        let _ = Reflect::delete_property(
+            &js_sys::global(),
+            &JsValue::from_str("__SQLITE_FOLLOWER_RETRIES"),
+        );
+        assert_eq!(
+            worker_config_from_global()
+                .expect("config")
+                .max_follower_retries,
+            DEFAULT_FOLLOWER_RETRIES
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn worker_config_reads_custom_functions_flag() {
+        set_global_str("__SQLITE_DB_NAME", "testdb-custom-functions");
+        let _ = Reflect::set(
+            &js_sys::global(),
+            &JsValue::from_str("__SQLITE_CUSTOM_FUNCTIONS"),
+            &JsValue::FALSE,
+        );
+        let cfg = worker_config_from_global().expect("config");
+        assert_eq!(
+            cfg.open_options.custom_functions,
+            FunctionSelection::Only(vec![])
+        );
+
+        let names = js_sys::Array::of1(&JsValue::from_str("BIGINT_SUM"));
+        let _ = Reflect::set(
+            &js_sys::global(),
+            &JsValue::from_str("__SQLITE_CUSTOM_FUNCTIONS"),
+            &names,
+        );
+        let cfg = worker_config_from_global().expect("config");
+        assert_eq!(
+            cfg.open_options.custom_functions,
+            FunctionSelection::Only(vec!["BIGINT_SUM".to_string()])
+        );
+
+        let _ = Reflect::delete_property(
+            &js_sys::global(),
+            &JsValue::from_str("__SQLITE_CUSTOM_FUNCTIONS"),
+        );
+        let cfg = worker_config_from_global().expect("config");
+        assert_eq!(cfg.open_options.custom_functions, FunctionSelection::All);
+    }
+
+    #[wasm_bindgen_test]
+    fn worker_config_reads_namespace() {
+        set_global_str("__SQLITE_DB_NAME", "testdb-namespace");
+        set_global_str("__SQLITE_NAMESPACE", " app-one ");
+        let cfg = worker_config_from_global().expect("config");
+        assert_eq!(cfg.open_options.namespace.as_deref(), Some("app-one"));
+
+        set_global_str("__SQLITE_NAMESPACE", "");
+        let cfg = worker_config_from_global().expect("config");
+        assert_eq!(cfg.open_options.namespace, None);
+        let _ =
+            Reflect::delete_property(&js_sys::global(), &JsValue::from_str("__SQLITE_NAMESPACE"));
+    }
+
+    #[wasm_bindgen_test]
+    fn debug_logging_follows_the_global_flag() {
+        let global = js_sys::global();
+        let _ = Reflect::delete_property(&global, &JsValue::from_str("__SQLITE_DEBUG"));
+        assert!(!debug_logging_from_global());
+
+        let _ = Reflect::set(
+            &global,
+            &JsValue::from_str("__SQLITE_DEBUG"),
+            &JsValue::TRUE,
+        );
+        assert!(debug_logging_from_global());
+        let _ = Reflect::delete_property(&global, &JsValue::from_str("__SQLITE_DEBUG"));
+
+        // Off by default, so nothing is built or posted.
+        let built = Cell::new(false);
+        send_worker_log(LogLevel::Info, || {
+            built.set(true);
+            String::new()
+        });
+        assert!(!debug_logging());
+        assert!(!built.get(), "disabled logging must not build the message");
+    }
+
+    #[wasm_bindgen_test]
+    fn worker_config_reads_pragmas() {
+        set_global_str("__SQLITE_DB_NAME", "testdb-pragmas-config");
+        let pragmas = js_sys::Array::of2(
+            &JsValue::from_str("foreign_keys = ON"),
+            &JsValue::from_str("cache_size = -4096"),
+        );
+        let _ = Reflect::set(
+            &js_sys::global(),
+            &JsValue::from_str("__SQLITE_PRAGMAS"),
+            &pragmas,
+        );
+        let cfg = worker_config_from_global().expect("config");
+        assert_eq!(
+            cfg.open_options.pragmas,
+            vec!["foreign_keys = ON", "cache_size = -4096"]
+        );
+
+        let _ = Reflect::delete_property(&js_sys::global(), &JsValue::from_str("__SQLITE_PRAGMAS"));
+        let cfg = worker_config_from_global().expect("config");
+        assert!(cfg.open_options.pragmas.is_empty());
+    }
+
+    #[wasm_bindgen_test]
+    fn worker_config_reads_memory_fallback() {
+        set_global_str("__SQLITE_DB_NAME", "testdb-memory-fallback-config");
+        let key = JsValue::from_str("__SQLITE_MEMORY_FALLBACK");
+        let _ = Reflect::set(&js_sys::global(), &key, &JsValue::TRUE);
+        let cfg = worker_config_from_global().expect("config");
+        assert!(cfg.open_options.allow_memory_fallback);
+
+        let _ = Reflect::delete_property(&js_sys::global(), &key);
+        let cfg = worker_config_from_global().expect("config");
+        assert!(!cfg.open_options.allow_memory_fallback);
+    }
+
+    #[wasm_bindgen_test]
+    fn worker_config_reads_in_memory() {
+        set_global_str("__SQLITE_DB_NAME", "testdb-in-memory-config");
+        let key = JsValue::from_str("__SQLITE_IN_MEMORY");
+        let _ = Reflect::set(&js_sys::global(), &key, &JsValue::TRUE);
+        let cfg = worker_config_from_global().expect("config");
+        assert!(cfg.open_options.in_memory);
+
+        let _ = Reflect::delete_property(&js_sys::global(), &key);
+        let cfg = worker_config_from_global().expect("config");
+        assert!(!cfg.open_options.in_memory);
+    }
+
+    #[wasm_bindgen_test(async)]
+    async fn namespaces_use_separate_channels_and_locks() {
+        assert_ne!(
+            database_lock_id("shared", Some("app-one")),
+            database_lock_id("shared", Some("app-two"))
+        );
+        assert_eq!(database_lock_id("shared", None), "sqlite-database-shared");
+
+        let sender = create_broadcast_channel("shared", Some("app-one")).expect("sender");
+        let observer = create_broadcast_channel("shared", Some("app-two")).expect("observer");
+        let same_namespace = create_broadcast_channel("shared", Some("app-one")).expect("peer");
+
+        let counts = Rc::new(RefCell::new((0, 0)));
+        let other = Rc::clone(&counts);
+        let other_listener = Closure::wrap(Box::new(move |_: MessageEvent| {
+            other.borrow_mut().0 += 1;
+        }) as Box<dyn FnMut(MessageEvent)>);
+        observer.set_onmessage(Some(other_listener.as_ref().unchecked_ref()));
+        let peer = Rc::clone(&counts);
+        let peer_listener = Closure::wrap(Box::new(move |_: MessageEvent| {
+            peer.borrow_mut().1 += 1;
+        }) as Box<dyn FnMut(MessageEvent)>);
+        same_namespace.set_onmessage(Some(peer_listener.as_ref().unchecked_ref()));
+
+        sender
+            .post_message(&JsValue::from_str("hello"))
+            .expect("post");
+        sleep_ms(50).await;
+
+        assert_eq!(*counts.borrow(), (0, 1));
+        observer.set_onmessage(None);
+        same_namespace.set_onmessage(None);
+    }
+
+    #[wasm_bindgen_test]
+    fn custom_functions_literal_round_trips_selection() {
+        assert_eq!(custom_functions_literal(&FunctionSelection::All), "true");
+        assert_eq!(
+            custom_functions_literal(&FunctionSelection::Only(vec!["FLOAT_SUM".to_string()])),
+            "[\"FLOAT_SUM\"]"
+        );
+        assert_eq!(
+            custom_functions_literal(&FunctionSelection::Only(vec![])),
+            "[]"
+        );
+    }
+
     #[wasm_bindgen_test]
     fn worker_config_defaults_query_timeout() {
         set_global_str("__SQLITE_DB_NAME", "testdb-timeouts-default");
@@ -1033,7 +2762,7 @@ mod tests {
         listener.forget();
 
         state.on_lock_granted();
-        sleep_ms(50).await;
+        sleep_ms(150).await;
 
         let msgs = received.borrow();
         assert!(
@@ -1100,6 +2829,102 @@ mod tests {
         );
     }
 
+    #[wasm_bindgen_test(async)]
+    async fn leader_ping_answers_are_coalesced_per_interval() {
+        set_global_str("__SQLITE_DB_NAME", "testdb-announce-interval");
+        set_global_num("__SQLITE_ANNOUNCE_INTERVAL_MS", 100.0);
+        set_global_str("__SQLITE_EMBEDDED_WORKER", "");
+
+        let cfg = worker_config_from_global().expect("config");
+        let _ = Reflect::delete_property(
+            &js_sys::global(),
+            &JsValue::from_str("__SQLITE_ANNOUNCE_INTERVAL_MS"),
+        );
+        assert_eq!(cfg.announce_interval_ms, 100.0);
+        let state = CoordinatorState::new(cfg).expect("state");
+        *state.role.borrow_mut() = LeadershipRole::Leader;
+        *state.db_worker_ready.borrow_mut() = true;
+
+        let channel_name = format!("sqlite-queries-{}", sanitize_identifier(&state.db_name));
+        let observer = BroadcastChannel::new(&channel_name).expect("observer channel");
+        let announcements = Rc::new(Cell::new(0u32));
+        let counter = Rc::clone(&announcements);
+        let listener = Closure::wrap(Box::new(move |event: MessageEvent| {
+            if let Ok(ChannelMessage::LeaderReady { .. }) =
+                serde_wasm_bindgen::from_value::<ChannelMessage>(event.data())
+            {
+                counter.set(counter.get() + 1);
+            }
+        }) as Box<dyn FnMut(MessageEvent)>);
+        observer.set_onmessage(Some(listener.as_ref().unchecked_ref()));
+        listener.forget();
+
+        for i in 0..50 {
+            state.handle_channel_message(ChannelMessage::LeaderPing {
+                requester_id: format!("follower-{i}"),
+            });
+        }
+        sleep_ms(20).await;
+        assert_eq!(announcements.get(), 1, "the burst should be answered once");
+
+        sleep_ms(150).await;
+        assert_eq!(
+            announcements.get(),
+            2,
+            "pings inside the interval should share one delayed answer"
+        );
+    }
+
+    #[wasm_bindgen_test(async)]
+    async fn leader_probe_retries_once_before_timing_out() {
+        set_global_str("__SQLITE_DB_NAME", "testdb-election-timeout");
+        set_global_num("__SQLITE_FOLLOWER_TIMEOUT_MS", 20.0);
+        set_global_num("__SQLITE_QUERY_TIMEOUT_MS", 50.0);
+
+        let state =
+            CoordinatorState::new(worker_config_from_global().expect("config")).expect("state");
+        let observer = BroadcastChannel::new(&format!(
+            "sqlite-queries-{}",
+            sanitize_identifier(&state.db_name)
+        ))
+        .expect("observer channel");
+        let pings = Rc::new(Cell::new(0));
+        let onmessage = {
+            let pings = Rc::clone(&pings);
+            Closure::wrap(Box::new(move |event: MessageEvent| {
+                if let Ok(ChannelMessage::LeaderPing { .. }) =
+                    serde_wasm_bindgen::from_value::<ChannelMessage>(event.data())
+                {
+                    pings.set(pings.get() + 1);
+                }
+            }) as Box<dyn FnMut(MessageEvent)>)
+        };
+        observer.set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+
+        // Nobody holds the lock or answers, so both probe windows run out.
+        let message = leader_probe_failure(
+            &state.leader_id,
+            &state.channel,
+            &state.worker_id,
+            state.follower_timeout_ms,
+        )
+        .await
+        .expect("no leader answered");
+        assert_eq!(pings.get(), LEADER_PROBE_ATTEMPTS);
+
+        let error_type = Reflect::get(&message, &JsValue::from_str("errorType")).unwrap();
+        assert_eq!(
+            error_type.as_string().as_deref(),
+            Some(WORKER_ERROR_TYPE_LEADER_ELECTION_TIMEOUT)
+        );
+        let error = Reflect::get(&message, &JsValue::from_str("error")).unwrap();
+        assert_eq!(
+            error.as_string().as_deref(),
+            Some("Leader election timed out after 20ms")
+        );
+        observer.set_onmessage(None);
+    }
+
     #[wasm_bindgen_test(async)]
     async fn lock_request_failure_keeps_follower_role() {
         set_global_str("__SQLITE_DB_NAME", "testdb-lock-failure");
@@ -1144,8 +2969,766 @@ mod tests {
     }
 
     #[wasm_bindgen_test(async)]
-    async fn db_worker_failure_resets_and_reports() {
-        set_global_str("__SQLITE_DB_NAME", "testdb-db-failure");
+    async fn simultaneous_promotions_keep_a_single_db_worker() {
+        set_global_str("__SQLITE_DB_NAME", "testdb-split-brain");
+        set_global_num("__SQLITE_FOLLOWER_TIMEOUT_MS", 100.0);
+        set_global_num("__SQLITE_QUERY_TIMEOUT_MS", 100.0);
+        set_global_str(
+            "__SQLITE_EMBEDDED_WORKER",
+            "self.postMessage({type:'worker-ready'});",
+        );
+
+        let first = CoordinatorState::new(worker_config_from_global().expect("config"))
+            .expect("first state");
+        let second = CoordinatorState::new(worker_config_from_global().expect("config"))
+            .expect("second state");
+        first.setup_channel_listener().expect("first listener");
+        second.setup_channel_listener().expect("second listener");
+
+        // Both workers believe they were granted the lock at the same time.
+        first.on_lock_granted();
+        second.on_lock_granted();
+        sleep_ms(200).await;
+
+        let states = [&first, &second];
+        let leaders = states
+            .iter()
+            .filter(|state| matches!(*state.role.borrow(), LeadershipRole::Leader))
+            .count();
+        let with_db_worker = states
+            .iter()
+            .filter(|state| state.db_worker.borrow().is_some())
+            .count();
+        assert_eq!(leaders, 1, "exactly one worker should remain leader");
+        assert_eq!(with_db_worker, 1, "exactly one DB worker should be spawned");
+
+        let winner = if first.worker_id < second.worker_id {
+            &first
+        } else {
+            &second
+        };
+        assert_eq!(*winner.role.borrow(), LeadershipRole::Leader);
+        assert!(winner.db_worker.borrow().is_some());
+
+        let loser = if Rc::ptr_eq(winner, &first) {
+            &second
+        } else {
+            &first
+        };
+        assert_eq!(*loser.role.borrow(), LeadershipRole::Follower);
+        assert_eq!(
+            loser.leader_id.borrow().as_deref(),
+            Some(winner.worker_id.as_str())
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn role_info_reflects_coordinator_state() {
+        set_global_str("__SQLITE_DB_NAME", "testdb-role");
+        let state =
+            CoordinatorState::new(worker_config_from_global().expect("config")).expect("state");
+
+        let info = state.role_info();
+        assert_eq!(info.role, "follower");
+        assert_eq!(info.leader_id, None);
+
+        *state.role.borrow_mut() = LeadershipRole::Leader;
+        state.mark_leader_known(state.worker_id.clone());
+        let info = state.role_info();
+        assert_eq!(info.role, "leader");
+        assert_eq!(info.leader_id.as_deref(), Some(state.worker_id.as_str()));
+    }
+
+    #[wasm_bindgen_test(async)]
+    async fn leader_rejects_late_claims() {
+        set_global_str("__SQLITE_DB_NAME", "testdb-late-claim");
+        set_global_num("__SQLITE_FOLLOWER_TIMEOUT_MS", 100.0);
+        set_global_num("__SQLITE_QUERY_TIMEOUT_MS", 100.0);
+        set_global_str("__SQLITE_EMBEDDED_WORKER", "");
+
+        let leader = CoordinatorState::new(worker_config_from_global().expect("config"))
+            .expect("leader state");
+        let candidate = CoordinatorState::new(worker_config_from_global().expect("config"))
+            .expect("candidate state");
+        *leader.role.borrow_mut() = LeadershipRole::Leader;
+        leader.setup_channel_listener().expect("leader listener");
+        candidate
+            .setup_channel_listener()
+            .expect("candidate listener");
+
+        candidate.on_lock_granted();
+        sleep_ms(150).await;
+
+        assert_eq!(*candidate.role.borrow(), LeadershipRole::Follower);
+        assert!(candidate.db_worker.borrow().is_none());
+        assert_eq!(
+            candidate.leader_id.borrow().as_deref(),
+            Some(leader.worker_id.as_str())
+        );
+    }
+
+    #[wasm_bindgen_test(async)]
+    async fn leader_rejects_follower_queries_beyond_the_cap() {
+        set_global_str("__SQLITE_DB_NAME", "testdb-follower-cap");
+        set_global_num("__SQLITE_FOLLOWER_TIMEOUT_MS", 100.0);
+        set_global_num("__SQLITE_QUERY_TIMEOUT_MS", 1000.0);
+        set_global_num("__SQLITE_MAX_FOLLOWER_QUERIES", 2.0);
+        set_global_str(
+            "__SQLITE_EMBEDDED_WORKER",
+            "self.postMessage({type:'worker-ready'}); self.onmessage = ev => { const d = ev.data || {}; if (d.type === 'execute-query') { setTimeout(() => self.postMessage({type:'query-result', requestId:d.requestId, result:'done', error:null}), 50); } };",
+        );
+
+        let state =
+            CoordinatorState::new(worker_config_from_global().expect("config")).expect("state");
+        let _ = Reflect::delete_property(
+            &js_sys::global(),
+            &JsValue::from_str("__SQLITE_MAX_FOLLOWER_QUERIES"),
+        );
+        state.on_lock_granted();
+        sleep_ms(150).await;
+        assert!(*state.db_worker_ready.borrow());
+
+        let channel_name = format!("sqlite-queries-{}", sanitize_identifier(&state.db_name));
+        let observer = BroadcastChannel::new(&channel_name).expect("observer channel");
+        let received: Rc<RefCell<Vec<ChannelMessage>>> = Rc::new(RefCell::new(Vec::new()));
+        let recv_clone = Rc::clone(&received);
+        let listener = Closure::wrap(Box::new(move |event: MessageEvent| {
+            if let Ok(msg) = serde_wasm_bindgen::from_value::<ChannelMessage>(event.data()) {
+                recv_clone.borrow_mut().push(msg);
+            }
+        }) as Box<dyn FnMut(MessageEvent)>);
+        observer.set_onmessage(Some(listener.as_ref().unchecked_ref()));
+        listener.forget();
+
+        for query_id in ["q1", "q2", "q3"] {
+            state.handle_channel_message(ChannelMessage::QueryRequest {
+                query_id: query_id.to_string(),
+                sql: "SELECT 1".to_string(),
+                params: None,
+                options: None,
+            });
+        }
+        sleep_ms(200).await;
+
+        let responses: HashMap<String, (Option<String>, Option<String>)> = received
+            .borrow()
+            .iter()
+            .filter_map(|msg| match msg {
+                ChannelMessage::QueryResponse {
+                    query_id,
+                    result,
+                    error,
+                    ..
+                } => Some((query_id.clone(), (result.clone(), error.clone()))),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(responses["q1"], (Some("done".to_string()), None));
+        assert_eq!(responses["q2"], (Some("done".to_string()), None));
+        assert_eq!(
+            responses["q3"],
+            (None, Some(TOO_MANY_CONCURRENT_REQUESTS.to_string()))
+        );
+        assert!(state.db_pending.borrow().is_empty());
+    }
+
+    #[wasm_bindgen_test(async)]
+    async fn leader_stepdown_hands_off_without_losing_retried_queries() {
+        set_global_str("__SQLITE_DB_NAME", "testdb-stepdown");
+        set_global_num("__SQLITE_FOLLOWER_TIMEOUT_MS", 1000.0);
+        set_global_num("__SQLITE_QUERY_TIMEOUT_MS", 1000.0);
+        set_global_str(
+            "__SQLITE_EMBEDDED_WORKER",
+            "self.postMessage({type:'worker-ready'}); self.onmessage = ev => { const d = ev.data || {}; if (d.type === 'execute-query') { setTimeout(() => self.postMessage({type:'query-result', requestId:d.requestId, result:'done', error:null}), 50); } };",
+        );
+
+        let leader = CoordinatorState::new(worker_config_from_global().expect("config"))
+            .expect("leader state");
+        let follower = CoordinatorState::new(worker_config_from_global().expect("config"))
+            .expect("follower state");
+        leader.setup_channel_listener().expect("leader listener");
+        follower
+            .setup_channel_listener()
+            .expect("follower listener");
+        leader.on_lock_granted();
+        sleep_ms(150).await;
+        assert_eq!(*leader.role.borrow(), LeadershipRole::Leader);
+        assert!(*follower.leader_ready.borrow());
+
+        let channel_name = format!("sqlite-queries-{}", sanitize_identifier(&leader.db_name));
+        let observer = BroadcastChannel::new(&channel_name).expect("observer channel");
+        let received: Rc<RefCell<Vec<ChannelMessage>>> = Rc::new(RefCell::new(Vec::new()));
+        let recv_clone = Rc::clone(&received);
+        let listener = Closure::wrap(Box::new(move |event: MessageEvent| {
+            if let Ok(msg) = serde_wasm_bindgen::from_value::<ChannelMessage>(event.data()) {
+                recv_clone.borrow_mut().push(msg);
+            }
+        }) as Box<dyn FnMut(MessageEvent)>);
+        observer.set_onmessage(Some(listener.as_ref().unchecked_ref()));
+        listener.forget();
+
+        // The leader's page unloads while a forwarded query is still running.
+        leader.handle_channel_message(ChannelMessage::QueryRequest {
+            query_id: "in-flight".to_string(),
+            sql: "INSERT INTO t VALUES (1)".to_string(),
+            params: None,
+            options: None,
+        });
+        leader.handle_main_message(WorkerMessage::StepDown);
+        sleep_ms(20).await;
+
+        assert_eq!(*leader.role.borrow(), LeadershipRole::Follower);
+        assert!(leader.db_worker.borrow().is_none());
+        assert!(leader.db_pending.borrow().is_empty());
+        assert!(follower.leader_id.borrow().is_none());
+        assert!(!*follower.leader_ready.borrow());
+        assert!(received.borrow().iter().any(|msg| matches!(
+            msg,
+            ChannelMessage::QueryResponse { query_id, error: Some(error), .. }
+                if query_id == "in-flight" && error == WORKER_ERROR_TYPE_LEADER_STEPDOWN
+        )));
+
+        // The follower's queued lock request is granted once the old leader
+        // lets go, and the resent query runs on the new leader.
+        follower.on_lock_granted();
+        sleep_ms(150).await;
+        assert_eq!(*follower.role.borrow(), LeadershipRole::Leader);
+        assert_eq!(
+            leader.leader_id.borrow().as_deref(),
+            Some(follower.worker_id.as_str())
+        );
+
+        follower.handle_channel_message(ChannelMessage::QueryRequest {
+            query_id: "in-flight-retry".to_string(),
+            sql: "INSERT INTO t VALUES (1)".to_string(),
+            params: None,
+            options: None,
+        });
+        sleep_ms(150).await;
+        assert!(received.borrow().iter().any(|msg| matches!(
+            msg,
+            ChannelMessage::QueryResponse { query_id, result: Some(result), .. }
+                if query_id == "in-flight-retry" && result == "done"
+        )));
+        observer.set_onmessage(None);
+    }
+
+    #[wasm_bindgen_test(async)]
+    async fn follower_takes_over_as_soon_as_the_leader_steps_down() {
+        set_global_str("__SQLITE_DB_NAME", "testdb-stepdown-takeover");
+        // Far longer than the test waits, so only the stepdown can explain
+        // the handoff.
+        set_global_num("__SQLITE_FOLLOWER_TIMEOUT_MS", 10000.0);
+        set_global_num("__SQLITE_QUERY_TIMEOUT_MS", 1000.0);
+        set_global_str(
+            "__SQLITE_EMBEDDED_WORKER",
+            "self.postMessage({type:'worker-ready'});",
+        );
+
+        let leader = CoordinatorState::new(worker_config_from_global().expect("config"))
+            .expect("leader state");
+        let follower = CoordinatorState::new(worker_config_from_global().expect("config"))
+            .expect("follower state");
+        leader.setup_channel_listener().expect("leader listener");
+        follower
+            .setup_channel_listener()
+            .expect("follower listener");
+
+        // Both ask for the real Web Lock; the follower's request queues.
+        leader.try_become_leader();
+        sleep_ms(100).await;
+        follower.try_become_leader();
+        sleep_ms(100).await;
+        assert_eq!(*leader.role.borrow(), LeadershipRole::Leader);
+        assert_eq!(*follower.role.borrow(), LeadershipRole::Follower);
+        assert_eq!(
+            follower.leader_id.borrow().as_deref(),
+            Some(leader.worker_id.as_str())
+        );
+
+        leader.step_down();
+        sleep_ms(200).await;
+
+        assert_eq!(*follower.role.borrow(), LeadershipRole::Leader);
+        assert!(*follower.db_worker_ready.borrow());
+        assert_eq!(
+            leader.leader_id.borrow().as_deref(),
+            Some(follower.worker_id.as_str()),
+            "the old leader follows the new one"
+        );
+        follower.step_down();
+    }
+
+    #[wasm_bindgen_test(async)]
+    async fn cancel_all_cancels_every_forwarded_query() {
+        set_global_str("__SQLITE_DB_NAME", "testdb-cancel-all");
+        set_global_num("__SQLITE_QUERY_TIMEOUT_MS", 1000.0);
+        set_global_str("__SQLITE_EMBEDDED_WORKER", "");
+
+        let follower = CoordinatorState::new(worker_config_from_global().expect("config"))
+            .expect("follower state");
+        let channel_name = format!("sqlite-queries-{}", sanitize_identifier(&follower.db_name));
+        let observer = BroadcastChannel::new(&channel_name).expect("observer channel");
+        let cancelled = Rc::new(Cell::new(0u32));
+        let counter = Rc::clone(&cancelled);
+        let listener = Closure::wrap(Box::new(move |event: MessageEvent| {
+            if let Ok(ChannelMessage::CancelQuery { .. }) =
+                serde_wasm_bindgen::from_value::<ChannelMessage>(event.data())
+            {
+                counter.set(counter.get() + 1);
+            }
+        }) as Box<dyn FnMut(MessageEvent)>);
+        observer.set_onmessage(Some(listener.as_ref().unchecked_ref()));
+        listener.forget();
+
+        follower.handle_channel_message(ChannelMessage::LeaderReady {
+            leader_id: "silent-leader".to_string(),
+        });
+        for request_id in 1..=3 {
+            follower.route_request(
+                request_id,
+                DbJobKind::Query {
+                    sql: "SELECT 1".to_string(),
+                    params: None,
+                    options: None,
+                },
+            );
+        }
+        assert_eq!(follower.follower_pending.borrow().len(), 3);
+
+        follower.handle_main_message(WorkerMessage::CancelAll);
+        sleep_ms(20).await;
+
+        assert!(follower.follower_pending.borrow().is_empty());
+        assert_eq!(
+            cancelled.get(),
+            3,
+            "the leader should be told to cancel each query"
+        );
+    }
+
+    #[wasm_bindgen_test(async)]
+    async fn follower_rejects_temp_objects_instead_of_forwarding_them() {
+        set_global_str("__SQLITE_DB_NAME", "testdb-follower-temp");
+        set_global_num("__SQLITE_QUERY_TIMEOUT_MS", 1000.0);
+        set_global_str("__SQLITE_EMBEDDED_WORKER", "");
+
+        let follower = CoordinatorState::new(worker_config_from_global().expect("config"))
+            .expect("follower state");
+        let channel_name = format!("sqlite-queries-{}", sanitize_identifier(&follower.db_name));
+        let observer = BroadcastChannel::new(&channel_name).expect("observer channel");
+        let forwarded = Rc::new(RefCell::new(Vec::new()));
+        let seen = Rc::clone(&forwarded);
+        let listener = Closure::wrap(Box::new(move |event: MessageEvent| {
+            if let Ok(ChannelMessage::QueryRequest { sql, .. }) =
+                serde_wasm_bindgen::from_value::<ChannelMessage>(event.data())
+            {
+                seen.borrow_mut().push(sql);
+            }
+        }) as Box<dyn FnMut(MessageEvent)>);
+        observer.set_onmessage(Some(listener.as_ref().unchecked_ref()));
+        listener.forget();
+
+        follower.handle_channel_message(ChannelMessage::LeaderReady {
+            leader_id: "silent-leader".to_string(),
+        });
+        for (request_id, sql) in [
+            (1, "CREATE TEMP TABLE scratch (a)"),
+            (2, "CREATE TABLE IF NOT EXISTS temp.scratch (a)"),
+            (3, "CREATE TABLE kept (a)"),
+        ] {
+            follower.route_request(
+                request_id,
+                DbJobKind::Query {
+                    sql: sql.to_string(),
+                    params: None,
+                    options: None,
+                },
+            );
+        }
+        follower.route_request(
+            4,
+            DbJobKind::Operation(DbOperation::Transaction {
+                statements: vec![
+                    crate::messages::BatchStatement {
+                        sql: "CREATE TABLE kept (a)".to_string(),
+                        params: None,
+                    },
+                    crate::messages::BatchStatement {
+                        sql: "CREATE TABLE \"temp\".scratch (a)".to_string(),
+                        params: None,
+                    },
+                ],
+                chunk_size: None,
+            }),
+        );
+        sleep_ms(20).await;
+
+        assert_eq!(follower.follower_pending.borrow().len(), 1);
+        assert_eq!(
+            *forwarded.borrow(),
+            vec!["CREATE TABLE kept (a)".to_string()]
+        );
+    }
+
+    #[wasm_bindgen_test(async)]
+    async fn follower_resends_pending_queries_to_a_new_leader() {
+        set_global_str("__SQLITE_DB_NAME", "testdb-follower-retry");
+        set_global_num("__SQLITE_FOLLOWER_TIMEOUT_MS", 1000.0);
+        set_global_num("__SQLITE_QUERY_TIMEOUT_MS", 1000.0);
+        set_global_str(
+            "__SQLITE_EMBEDDED_WORKER",
+            "self.postMessage({type:'worker-ready'}); self.onmessage = ev => { const d = ev.data || {}; if (d.type === 'execute-query') { self.postMessage({type:'query-result', requestId:d.requestId, result:'done', error:null}); } };",
+        );
+
+        let follower = CoordinatorState::new(worker_config_from_global().expect("config"))
+            .expect("follower state");
+        let new_leader = CoordinatorState::new(worker_config_from_global().expect("config"))
+            .expect("leader state");
+        follower
+            .setup_channel_listener()
+            .expect("follower listener");
+        new_leader
+            .setup_channel_listener()
+            .expect("leader listener");
+
+        let channel_name = format!("sqlite-queries-{}", sanitize_identifier(&follower.db_name));
+        let observer = BroadcastChannel::new(&channel_name).expect("observer channel");
+        let received: Rc<RefCell<Vec<ChannelMessage>>> = Rc::new(RefCell::new(Vec::new()));
+        let recv_clone = Rc::clone(&received);
+        let listener = Closure::wrap(Box::new(move |event: MessageEvent| {
+            if let Ok(msg) = serde_wasm_bindgen::from_value::<ChannelMessage>(event.data()) {
+                recv_clone.borrow_mut().push(msg);
+            }
+        }) as Box<dyn FnMut(MessageEvent)>);
+        observer.set_onmessage(Some(listener.as_ref().unchecked_ref()));
+        listener.forget();
+
+        // The query goes to a leader that disappears without answering.
+        follower.handle_channel_message(ChannelMessage::LeaderReady {
+            leader_id: "vanished-leader".to_string(),
+        });
+        follower.route_request(
+            7,
+            DbJobKind::Query {
+                sql: "INSERT INTO t VALUES (1)".to_string(),
+                params: None,
+                options: None,
+            },
+        );
+        let query_id = follower
+            .follower_pending
+            .borrow()
+            .keys()
+            .next()
+            .cloned()
+            .expect("query is pending");
+
+        new_leader.on_lock_granted();
+        sleep_ms(200).await;
+
+        assert!(follower.follower_pending.borrow().is_empty());
+        let received = received.borrow();
+        let sent = received
+            .iter()
+            .filter(|msg| {
+                matches!(msg, ChannelMessage::QueryRequest { query_id: id, .. } if *id == query_id)
+            })
+            .count();
+        assert_eq!(sent, 2, "the query is resent once under the same id");
+        assert!(received.iter().any(|msg| matches!(
+            msg,
+            ChannelMessage::QueryResponse { query_id: id, result: Some(result), .. }
+                if *id == query_id && result == "done"
+        )));
+        observer.set_onmessage(None);
+    }
+
+    #[wasm_bindgen_test(async)]
+    async fn leader_replays_answers_to_repeated_query_requests() {
+        set_global_str("__SQLITE_DB_NAME", "testdb-replay");
+        set_global_num("__SQLITE_FOLLOWER_TIMEOUT_MS", 100.0);
+        set_global_num("__SQLITE_QUERY_TIMEOUT_MS", 1000.0);
+        // The stub counts the inserts it runs.
+        set_global_str(
+            "__SQLITE_EMBEDDED_WORKER",
+            "let rows = 0; self.postMessage({type:'worker-ready'}); self.onmessage = ev => { const d = ev.data || {}; if (d.type === 'execute-query') { rows += 1; self.postMessage({type:'query-result', requestId:d.requestId, result:'rows: ' + rows, error:null}); } };",
+        );
+
+        let state =
+            CoordinatorState::new(worker_config_from_global().expect("config")).expect("state");
+        state.on_lock_granted();
+        sleep_ms(150).await;
+        assert!(*state.db_worker_ready.borrow());
+
+        let channel_name = format!("sqlite-queries-{}", sanitize_identifier(&state.db_name));
+        let observer = BroadcastChannel::new(&channel_name).expect("observer channel");
+        let received: Rc<RefCell<Vec<ChannelMessage>>> = Rc::new(RefCell::new(Vec::new()));
+        let recv_clone = Rc::clone(&received);
+        let listener = Closure::wrap(Box::new(move |event: MessageEvent| {
+            if let Ok(msg) = serde_wasm_bindgen::from_value::<ChannelMessage>(event.data()) {
+                recv_clone.borrow_mut().push(msg);
+            }
+        }) as Box<dyn FnMut(MessageEvent)>);
+        observer.set_onmessage(Some(listener.as_ref().unchecked_ref()));
+        listener.forget();
+
+        let insert = || ChannelMessage::QueryRequest {
+            query_id: "insert-once".to_string(),
+            sql: "INSERT INTO t VALUES (1)".to_string(),
+            params: None,
+            options: None,
+        };
+        state.handle_channel_message(insert());
+        sleep_ms(50).await;
+        state.handle_channel_message(insert());
+        sleep_ms(50).await;
+
+        let results = received
+            .borrow()
+            .iter()
+            .filter_map(|msg| match msg {
+                ChannelMessage::QueryResponse {
+                    query_id, result, ..
+                } if query_id == "insert-once" => result.clone(),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(results, vec!["rows: 1".to_string(), "rows: 1".to_string()]);
+        observer.set_onmessage(None);
+    }
+
+    #[wasm_bindgen_test]
+    fn answered_query_cache_is_capped_by_bytes() {
+        set_global_str("__SQLITE_DB_NAME", "testdb-answer-cache");
+        let state =
+            CoordinatorState::new(worker_config_from_global().expect("config")).expect("state");
+        let answer = |query_id: &str, bytes: usize| AnsweredQuery {
+            query_id: query_id.to_string(),
+            answered_at_ms: js_sys::Date::now(),
+            result: Some("x".repeat(bytes)),
+            error: None,
+            sqlite: None,
+            timing: None,
+        };
+        let half = ANSWERED_QUERY_CACHE_BYTES / 2;
+
+        state.remember_answer(answer("too-big", ANSWERED_QUERY_CACHE_BYTES + 1));
+        assert!(state.answered_response("too-big").is_none());
+
+        state.remember_answer(answer("first", half));
+        state.remember_answer(answer("second", half));
+        assert!(state.answered_response("first").is_none());
+        assert!(state.answered_response("second").is_some());
+        assert!(state.answered_bytes.get() <= ANSWERED_QUERY_CACHE_BYTES);
+    }
+
+    #[wasm_bindgen_test(async)]
+    async fn per_query_timeout_overrides_the_forwarded_query_deadline() {
+        set_global_str("__SQLITE_DB_NAME", "testdb-per-query-timeout");
+        set_global_num("__SQLITE_FOLLOWER_TIMEOUT_MS", 5000.0);
+        set_global_num("__SQLITE_QUERY_TIMEOUT_MS", 50.0);
+
+        let follower =
+            CoordinatorState::new(worker_config_from_global().expect("config")).expect("state");
+        assert_eq!(follower.follower_timeout_ms, 5000.0);
+        // A leader that never answers.
+        follower.handle_channel_message(ChannelMessage::LeaderReady {
+            leader_id: "silent-leader".to_string(),
+        });
+
+        let query = |timeout_ms: Option<f64>| DbJobKind::Query {
+            sql: "SELECT 1".to_string(),
+            params: None,
+            options: timeout_ms.map(|ms| QueryOptions {
+                timeout_ms: Some(ms),
+                ..Default::default()
+            }),
+        };
+        follower.route_request(1, query(None));
+        follower.route_request(2, query(Some(400.0)));
+        sleep_ms(150).await;
+
+        let pending = follower
+            .follower_pending
+            .borrow()
+            .values()
+            .map(|request| request.request_id)
+            .collect::<Vec<_>>();
+        assert_eq!(pending, vec![2], "only the default deadline has passed");
+
+        sleep_ms(350).await;
+        assert!(follower.follower_pending.borrow().is_empty());
+    }
+
+    #[wasm_bindgen_test(async)]
+    async fn binary_results_are_relayed_to_followers() {
+        set_global_str("__SQLITE_DB_NAME", "testdb-bytes-relay");
+        set_global_str("__SQLITE_EMBEDDED_WORKER", "");
+
+        let state =
+            CoordinatorState::new(worker_config_from_global().expect("config")).expect("state");
+        *state.role.borrow_mut() = LeadershipRole::Leader;
+        state.db_pending.borrow_mut().insert(
+            4,
+            DbRequestOrigin::Forwarded {
+                query_id: "q-bytes".to_string(),
+            },
+        );
+
+        let channel_name = format!("sqlite-queries-{}", sanitize_identifier(&state.db_name));
+        let observer = BroadcastChannel::new(&channel_name).expect("observer channel");
+        let received: Rc<RefCell<Vec<JsValue>>> = Rc::new(RefCell::new(Vec::new()));
+        let recv_clone = Rc::clone(&received);
+        let listener = Closure::wrap(Box::new(move |event: MessageEvent| {
+            recv_clone.borrow_mut().push(event.data());
+        }) as Box<dyn FnMut(MessageEvent)>);
+        observer.set_onmessage(Some(listener.as_ref().unchecked_ref()));
+        listener.forget();
+
+        let bytes = Uint8Array::from(&[1u8, 2, 3][..]);
+        let message = make_bytes_result_message(4, &bytes).expect("message");
+        state.handle_db_worker_value(message.into());
+        sleep_ms(50).await;
+
+        assert!(state.db_pending.borrow().is_empty());
+        let received = received.borrow();
+        assert_eq!(received.len(), 1);
+        let query_id = Reflect::get(&received[0], &JsValue::from_str("queryId"))
+            .unwrap()
+            .as_string();
+        assert_eq!(query_id.as_deref(), Some("q-bytes"));
+        let relayed = bytes_payload(&received[0]).expect("bytes are relayed");
+        assert_eq!(relayed.to_vec(), vec![1u8, 2, 3]);
+    }
+
+    #[wasm_bindgen_test(async)]
+    async fn follower_query_round_trips_through_a_real_leader_database() {
+        set_global_str("__SQLITE_DB_NAME", "testdb-follower-e2e");
+        set_global_num("__SQLITE_FOLLOWER_TIMEOUT_MS", 1000.0);
+        set_global_num("__SQLITE_QUERY_TIMEOUT_MS", 1000.0);
+        // The leader's DB worker only bridges messages to a `DbWorkerState`
+        // in this context, which runs them on a real in-memory database.
+        set_global_str(
+            "__SQLITE_EMBEDDED_WORKER",
+            "const bridge = new BroadcastChannel('testdb-follower-e2e-bridge'); bridge.onmessage = ev => self.postMessage(ev.data); self.onmessage = ev => bridge.postMessage(ev.data); self.postMessage({type:'worker-ready'});",
+        );
+
+        let config = worker_config_from_global().expect("config");
+        let mut database = SQLiteDatabase::open_in_memory(&config.open_options)
+            .await
+            .expect("in-memory database");
+        database
+            .exec(
+                "CREATE TABLE e2e_users (id INTEGER PRIMARY KEY, name TEXT, score REAL);
+                 INSERT INTO e2e_users (name, score) VALUES
+                     ('alice', 72.5), ('bob', 31.0), ('carol', 88.0), ('dave', 50.0);",
+            )
+            .await
+            .expect("seed");
+
+        let bridge = BroadcastChannel::new("testdb-follower-e2e-bridge").expect("bridge");
+        let deliver_bridge = bridge.clone();
+        let db_state = DbWorkerState::new_with_hooks(
+            config,
+            DbWorkerHooks::new(
+                Rc::new(|db, sql, params, options| Box::pin(exec_on_db(db, sql, params, options))),
+                Rc::new(move |obj: &js_sys::Object| {
+                    let _ = deliver_bridge.post_message(obj.as_ref());
+                }),
+            ),
+        );
+        *db_state.db.borrow_mut() = Some(database);
+        let db_state_clone = Rc::clone(&db_state);
+        let bridge_listener = Closure::wrap(Box::new(move |event: MessageEvent| {
+            if let Ok(msg) = serde_wasm_bindgen::from_value::<WorkerMessage>(event.data()) {
+                db_state_clone.handle_message(msg);
+            }
+        }) as Box<dyn FnMut(MessageEvent)>);
+        bridge.set_onmessage(Some(bridge_listener.as_ref().unchecked_ref()));
+        bridge_listener.forget();
+
+        let follower = CoordinatorState::new(worker_config_from_global().expect("config"))
+            .expect("follower state");
+        let leader = CoordinatorState::new(worker_config_from_global().expect("config"))
+            .expect("leader state");
+        follower
+            .setup_channel_listener()
+            .expect("follower listener");
+        leader.setup_channel_listener().expect("leader listener");
+
+        let channel_name = format!("sqlite-queries-{}", sanitize_identifier(&follower.db_name));
+        let observer = BroadcastChannel::new(&channel_name).expect("observer channel");
+        let received: Rc<RefCell<Vec<ChannelMessage>>> = Rc::new(RefCell::new(Vec::new()));
+        let recv_clone = Rc::clone(&received);
+        let listener = Closure::wrap(Box::new(move |event: MessageEvent| {
+            if let Ok(msg) = serde_wasm_bindgen::from_value::<ChannelMessage>(event.data()) {
+                recv_clone.borrow_mut().push(msg);
+            }
+        }) as Box<dyn FnMut(MessageEvent)>);
+        observer.set_onmessage(Some(listener.as_ref().unchecked_ref()));
+        listener.forget();
+
+        leader.on_lock_granted();
+        sleep_ms(200).await;
+        assert!(*leader.db_worker_ready.borrow());
+        assert!(
+            *follower.leader_ready.borrow(),
+            "the follower hears the leader is ready"
+        );
+
+        follower.route_request(
+            9,
+            DbJobKind::Query {
+                sql: "SELECT id, name FROM e2e_users WHERE score >= ? AND name <> ? ORDER BY id"
+                    .to_string(),
+                params: Some(vec![serde_json::json!(50), serde_json::json!("carol")]),
+                options: None,
+            },
+        );
+        let query_id = follower
+            .follower_pending
+            .borrow()
+            .keys()
+            .next()
+            .cloned()
+            .expect("query is forwarded");
+        sleep_ms(200).await;
+
+        assert!(
+            follower.follower_pending.borrow().is_empty(),
+            "the follower resolves the forwarded query"
+        );
+        let received = received.borrow();
+        let response = received
+            .iter()
+            .find_map(|msg| match msg {
+                ChannelMessage::QueryResponse {
+                    query_id: id,
+                    result,
+                    error,
+                    ..
+                } if *id == query_id => Some((result.clone(), error.clone())),
+                _ => None,
+            })
+            .expect("the leader answers on the channel");
+        assert_eq!(response.1, None, "unexpected error");
+        let rows: serde_json::Value =
+            serde_json::from_str(&response.0.expect("rows")).expect("rows are JSON");
+        assert_eq!(
+            rows,
+            serde_json::json!([
+                { "id": 1, "name": "alice" },
+                { "id": 4, "name": "dave" }
+            ])
+        );
+        observer.set_onmessage(None);
+        bridge.set_onmessage(None);
+    }
+
+    #[wasm_bindgen_test(async)]
+    async fn db_worker_failure_resets_and_reports() {
+        set_global_str("__SQLITE_DB_NAME", "testdb-db-failure");
         set_global_num("__SQLITE_FOLLOWER_TIMEOUT_MS", 50.0);
         set_global_num("__SQLITE_QUERY_TIMEOUT_MS", 50.0);
         set_global_str("__SQLITE_EMBEDDED_WORKER", "");
@@ -1201,8 +3784,8 @@ mod tests {
                 msg,
                 ChannelMessage::QueryResponse {
                     query_id: qid,
-                    result: _,
                     error: Some(err),
+                    ..
                 } if qid == &query_id && err == "boom"
             )
         });
@@ -1244,7 +3827,7 @@ mod tests {
         let hooks = DbWorkerHooks::new(
             {
                 let busy_flag = Rc::clone(&busy_flag);
-                Rc::new(move |_db, _sql, _params| {
+                Rc::new(move |_db, _sql, _params, _options| {
                     let busy_flag = Rc::clone(&busy_flag);
                     Box::pin(async move {
                         if busy_flag.replace(true) {
@@ -1269,6 +3852,11 @@ mod tests {
                 db_name: "testdb-fake".to_string(),
                 follower_timeout_ms: 10.0,
                 query_timeout_ms: 10.0,
+                max_follower_queries: DEFAULT_MAX_FOLLOWER_QUERIES,
+                max_follower_retries: DEFAULT_FOLLOWER_RETRIES,
+                max_queue_depth: DEFAULT_MAX_QUEUE_DEPTH,
+                announce_interval_ms: 0.0,
+                open_options: OpenOptions::default(),
             },
             hooks,
         );
@@ -1277,11 +3865,13 @@ mod tests {
             request_id: 1,
             sql: "SELECT 1".to_string(),
             params: None,
+            options: None,
         });
         state.handle_message(WorkerMessage::ExecuteQuery {
             request_id: 2,
             sql: "SELECT 2".to_string(),
             params: None,
+            options: None,
         });
 
         sleep_ms(30).await;
@@ -1300,4 +3890,551 @@ mod tests {
             assert!(error.is_none(), "no error expected");
         }
     }
+
+    #[wasm_bindgen_test(async)]
+    async fn db_worker_rejects_jobs_beyond_the_queue_depth() {
+        let results = Rc::new(Array::new());
+        let hooks = DbWorkerHooks::new(
+            Rc::new(|_db, _sql, _params, _options| {
+                Box::pin(async {
+                    sleep_ms(5).await;
+                    Ok("fake-db-ok".to_string())
+                })
+            }),
+            {
+                let results = Rc::clone(&results);
+                Rc::new(move |obj: &js_sys::Object| {
+                    results.push(obj.as_ref());
+                })
+            },
+        );
+        let state = DbWorkerState::new_with_hooks(
+            WorkerConfig {
+                db_name: "testdb-queue-depth".to_string(),
+                follower_timeout_ms: 10.0,
+                query_timeout_ms: 10.0,
+                max_follower_queries: DEFAULT_MAX_FOLLOWER_QUERIES,
+                max_follower_retries: DEFAULT_FOLLOWER_RETRIES,
+                max_queue_depth: 2,
+                announce_interval_ms: 0.0,
+                open_options: OpenOptions::default(),
+            },
+            hooks,
+        );
+
+        for request_id in 1..=4 {
+            state.handle_message(WorkerMessage::ExecuteQuery {
+                request_id,
+                sql: format!("SELECT {request_id}"),
+                params: None,
+                options: None,
+            });
+        }
+        state.handle_message(WorkerMessage::RunOperation {
+            request_id: 5,
+            operation: DbOperation::QueueDepth,
+        });
+        sleep_ms(50).await;
+
+        let outcome = |entry: JsValue| {
+            let request_id = Reflect::get(&entry, &JsValue::from_str("requestId"))
+                .ok()
+                .and_then(|v| v.as_f64())
+                .unwrap_or_default() as u32;
+            let error_type = Reflect::get(&entry, &JsValue::from_str("error"))
+                .ok()
+                .filter(|v| !v.is_null() && !v.is_undefined())
+                .and_then(|error| Reflect::get(&error, &JsValue::from_str("type")).ok())
+                .and_then(|v| v.as_string());
+            let result = Reflect::get(&entry, &JsValue::from_str("result"))
+                .ok()
+                .and_then(|v| v.as_string());
+            (request_id, error_type, result)
+        };
+        let outcomes: Vec<_> = results.iter().map(outcome).collect();
+        let busy = || Some(WORKER_ERROR_TYPE_SERVER_BUSY.to_string());
+        let ok = || Some("fake-db-ok".to_string());
+        assert_eq!(
+            outcomes,
+            vec![
+                (3, busy(), None),
+                (4, busy(), None),
+                (5, None, Some("2".to_string())),
+                (1, None, ok()),
+                (2, None, ok()),
+            ]
+        );
+    }
+
+    #[wasm_bindgen_test(async)]
+    async fn db_worker_answers_ping_without_touching_the_database() {
+        let results = Rc::new(Array::new());
+        let hooks = DbWorkerHooks::new(
+            Rc::new(|_db, _sql, _params, _options| {
+                Box::pin(async { Err("ping must not reach SQLite".to_string()) })
+            }),
+            {
+                let results = Rc::clone(&results);
+                Rc::new(move |obj: &js_sys::Object| {
+                    results.push(obj.as_ref());
+                })
+            },
+        );
+        // Never started, so there is no database to run anything on.
+        let state = DbWorkerState::new_with_hooks(
+            WorkerConfig {
+                db_name: "testdb-ping".to_string(),
+                follower_timeout_ms: 10.0,
+                query_timeout_ms: 10.0,
+                max_follower_queries: DEFAULT_MAX_FOLLOWER_QUERIES,
+                max_follower_retries: DEFAULT_FOLLOWER_RETRIES,
+                max_queue_depth: DEFAULT_MAX_QUEUE_DEPTH,
+                announce_interval_ms: 0.0,
+                open_options: OpenOptions::default(),
+            },
+            hooks,
+        );
+
+        state.handle_message(WorkerMessage::RunOperation {
+            request_id: 3,
+            operation: DbOperation::Ping,
+        });
+
+        assert_eq!(results.length(), 1);
+        let reply = results.get(0);
+        let result = Reflect::get(&reply, &JsValue::from_str("result"))
+            .ok()
+            .and_then(|v| v.as_string());
+        assert_eq!(result.as_deref(), Some("pong"));
+        let error = Reflect::get(&reply, &JsValue::from_str("error"))
+            .ok()
+            .filter(|v| !v.is_null() && !v.is_undefined());
+        assert!(error.is_none(), "ping should not fail");
+    }
+
+    #[wasm_bindgen_test(async)]
+    async fn memory_fallback_opens_in_memory_when_opfs_fails() {
+        let opfs_unavailable =
+            || async { Err::<CString, JsValue>(JsValue::from_str("Failed to install OPFS VFS")) };
+
+        let strict = OpenOptions::default();
+        assert!(
+            open_database("testdb-fallback", &strict, opfs_unavailable())
+                .await
+                .is_err(),
+            "without the option an OPFS failure still fails opening"
+        );
+
+        let options = OpenOptions {
+            allow_memory_fallback: true,
+            ..Default::default()
+        };
+        let Ok((mut db, memory_only)) =
+            open_database("testdb-fallback", &options, opfs_unavailable()).await
+        else {
+            panic!("the memory fallback should open");
+        };
+        assert!(memory_only);
+        db.exec("CREATE TABLE fallback (v TEXT)")
+            .await
+            .expect("Create failed");
+        db.exec("INSERT INTO fallback (v) VALUES ('kept')")
+            .await
+            .expect("Insert failed");
+        let rows = db
+            .exec("SELECT v FROM fallback")
+            .await
+            .expect("Select failed");
+        let parsed: serde_json::Value = serde_json::from_str(&rows).expect("Invalid JSON");
+        assert_eq!(parsed, serde_json::json!([{ "v": "kept" }]));
+    }
+
+    #[wasm_bindgen_test(async)]
+    async fn db_worker_reports_timing_only_when_profiling() {
+        let results = Rc::new(Array::new());
+        let hooks = DbWorkerHooks::new(
+            Rc::new(|_db, _sql, _params, _options| {
+                Box::pin(async {
+                    sleep_ms(5).await;
+                    Ok(r#"{"result":[]}"#.to_string())
+                })
+            }),
+            {
+                let results = Rc::clone(&results);
+                Rc::new(move |obj: &js_sys::Object| {
+                    results.push(obj.as_ref());
+                })
+            },
+        );
+        let state = DbWorkerState::new_with_hooks(
+            WorkerConfig {
+                db_name: "testdb-profile".to_string(),
+                follower_timeout_ms: 10.0,
+                query_timeout_ms: 10.0,
+                max_follower_queries: DEFAULT_MAX_FOLLOWER_QUERIES,
+                max_follower_retries: DEFAULT_FOLLOWER_RETRIES,
+                max_queue_depth: DEFAULT_MAX_QUEUE_DEPTH,
+                announce_interval_ms: 0.0,
+                open_options: OpenOptions::default(),
+            },
+            hooks,
+        );
+
+        state.handle_message(WorkerMessage::ExecuteQuery {
+            request_id: 1,
+            sql: "SELECT 1".to_string(),
+            params: None,
+            options: None,
+        });
+        state.handle_message(WorkerMessage::ExecuteQuery {
+            request_id: 2,
+            sql: "SELECT 2".to_string(),
+            params: None,
+            options: Some(QueryOptions {
+                profile: Some(true),
+                ..Default::default()
+            }),
+        });
+        sleep_ms(50).await;
+
+        assert_eq!(results.length(), 2);
+        let field = |index: u32, name: &str| {
+            Reflect::get(&results.get(index), &JsValue::from_str(name)).unwrap()
+        };
+        assert_eq!(field(0, "result").as_string().unwrap(), r#"{"result":[]}"#);
+        assert!(field(0, "timing").is_undefined());
+
+        // The result text is passed on untouched, with the timing beside it.
+        assert_eq!(field(1, "result").as_string().unwrap(), r#"{"result":[]}"#);
+        let timing: QueryTiming =
+            serde_wasm_bindgen::from_value(field(1, "timing")).expect("timing");
+        let queued = timing.queued_ms;
+        let exec = timing.exec_ms;
+        assert!(queued >= 0.0, "queued {queued}ms");
+        assert!(exec >= 0.0, "exec {exec}ms");
+    }
+
+    #[wasm_bindgen_test]
+    fn query_results_over_max_result_bytes_become_errors() {
+        let result = "[{\"n\":1}]".to_string();
+        let fits = make_query_result_message(1, Ok(result.clone()), Some(64)).unwrap();
+        let value = Reflect::get(&fits, &JsValue::from_str("result")).unwrap();
+        assert_eq!(value.as_string(), Some(result.clone()));
+
+        let too_large = make_query_result_message(2, Ok(result), Some(4)).unwrap();
+        let value = Reflect::get(&too_large, &JsValue::from_str("result")).unwrap();
+        assert!(value.is_null());
+        let error = Reflect::get(&too_large, &JsValue::from_str("error")).unwrap();
+        let message = Reflect::get(&error, &JsValue::from_str("message"))
+            .unwrap()
+            .as_string()
+            .unwrap_or_default();
+        assert!(message.contains("maxResultBytes (4)"), "{message}");
+        assert!(message.contains("queryStream"), "{message}");
+    }
+
+    #[wasm_bindgen_test]
+    fn sqlite_errors_are_attached_to_generic_query_errors_only() {
+        let detail = SqliteErrorDetail {
+            message: "near \"SELEC\": syntax error".to_string(),
+            code: 1,
+            offset: Some(0),
+        };
+        let failed =
+            make_query_result_message(1, Err("Failed to prepare".to_string()), None).unwrap();
+        attach_sqlite_error(&failed, Some(&detail)).unwrap();
+        let error = Reflect::get(&failed, &JsValue::from_str("error")).unwrap();
+        let attached: SqliteErrorDetail = serde_wasm_bindgen::from_value(
+            Reflect::get(&error, &JsValue::from_str("sqlite")).unwrap(),
+        )
+        .expect("sqlite detail");
+        assert_eq!(attached, detail);
+
+        let timed_out =
+            make_query_result_message(2, Err(WORKER_ERROR_TYPE_QUERY_TIMEOUT.to_string()), None)
+                .unwrap();
+        attach_sqlite_error(&timed_out, Some(&detail)).unwrap();
+        let error = Reflect::get(&timed_out, &JsValue::from_str("error")).unwrap();
+        assert!(Reflect::get(&error, &JsValue::from_str("sqlite"))
+            .unwrap()
+            .is_undefined());
+
+        let succeeded = make_query_result_message(3, Ok("[]".to_string()), None).unwrap();
+        attach_sqlite_error(&succeeded, Some(&detail)).expect("no error to attach to");
+    }
+
+    #[wasm_bindgen_test]
+    fn db_worker_rejects_unknown_messages_by_request_id() {
+        let results = Rc::new(Array::new());
+        let hooks = DbWorkerHooks::new(
+            Rc::new(|_db, _sql, _params, _options| {
+                Box::pin(async { Ok("fake-db-ok".to_string()) })
+            }),
+            {
+                let results = Rc::clone(&results);
+                Rc::new(move |obj: &js_sys::Object| {
+                    results.push(obj.as_ref());
+                })
+            },
+        );
+        let state = DbWorkerState::new_with_hooks(
+            WorkerConfig {
+                db_name: "testdb-unknown-message".to_string(),
+                follower_timeout_ms: 10.0,
+                query_timeout_ms: 10.0,
+                max_follower_queries: DEFAULT_MAX_FOLLOWER_QUERIES,
+                max_follower_retries: DEFAULT_FOLLOWER_RETRIES,
+                max_queue_depth: DEFAULT_MAX_QUEUE_DEPTH,
+                announce_interval_ms: 0.0,
+                open_options: OpenOptions::default(),
+            },
+            hooks,
+        );
+
+        let message = js_sys::Object::new();
+        set_js_property(&message, "type", &JsValue::from_str("vacuum-into")).unwrap();
+        set_js_property(&message, "requestId", &JsValue::from_f64(7.0)).unwrap();
+        assert!(state.handle_unknown_message(&message));
+        assert!(
+            !state.handle_unknown_message(&js_sys::Object::new()),
+            "messages without a request id have nobody to answer"
+        );
+
+        assert_eq!(results.length(), 1);
+        let response = results.get(0);
+        let request_id = Reflect::get(&response, &JsValue::from_str("requestId")).unwrap();
+        assert_eq!(request_id.as_f64(), Some(7.0));
+        let error = Reflect::get(&response, &JsValue::from_str("error")).unwrap();
+        let error_type = Reflect::get(&error, &JsValue::from_str("type")).unwrap();
+        assert_eq!(
+            error_type.as_string().as_deref(),
+            Some(WORKER_ERROR_TYPE_UNSUPPORTED_OPERATION)
+        );
+        let message = Reflect::get(&error, &JsValue::from_str("message")).unwrap();
+        assert_eq!(
+            message.as_string().as_deref(),
+            Some("UnsupportedOperation: vacuum-into")
+        );
+    }
+
+    #[wasm_bindgen_test(async)]
+    async fn db_worker_export_is_a_snapshot_between_queued_writes() {
+        let Ok(mut database) = SQLiteDatabase::initialize_opfs("testdb-export-snapshot").await
+        else {
+            return;
+        };
+        database
+            .exec("CREATE TABLE IF NOT EXISTS snapshot_test (id INTEGER PRIMARY KEY)")
+            .await
+            .expect("Create failed");
+        database
+            .exec("DELETE FROM snapshot_test")
+            .await
+            .expect("Delete failed");
+
+        let results = Rc::new(Array::new());
+        let hooks = DbWorkerHooks {
+            deliver: {
+                let results = Rc::clone(&results);
+                Rc::new(move |obj: &js_sys::Object| {
+                    results.push(obj.as_ref());
+                })
+            },
+            ..DbWorkerHooks::default()
+        };
+        let state = DbWorkerState::new_with_hooks(
+            WorkerConfig {
+                db_name: "testdb-export-snapshot".to_string(),
+                follower_timeout_ms: 10.0,
+                query_timeout_ms: 10.0,
+                max_follower_queries: DEFAULT_MAX_FOLLOWER_QUERIES,
+                max_follower_retries: DEFAULT_FOLLOWER_RETRIES,
+                max_queue_depth: DEFAULT_MAX_QUEUE_DEPTH,
+                announce_interval_ms: 0.0,
+                open_options: OpenOptions::default(),
+            },
+            hooks,
+        );
+        *state.db.borrow_mut() = Some(database);
+
+        // Writes queued on both sides of the export, all in the same tick.
+        let insert = |request_id: u32| WorkerMessage::ExecuteQuery {
+            request_id,
+            sql: "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 100) \
+                  INSERT INTO snapshot_test (id) SELECT NULL FROM n"
+                .to_string(),
+            params: None,
+            options: None,
+        };
+        state.handle_message(insert(1));
+        state.handle_message(insert(2));
+        state.handle_message(WorkerMessage::RunOperation {
+            request_id: 3,
+            operation: DbOperation::Export,
+        });
+        state.handle_message(insert(4));
+
+        for _ in 0..100 {
+            if results.length() == 4 {
+                break;
+            }
+            sleep_ms(10).await;
+        }
+        assert_eq!(results.length(), 4, "every request should be answered");
+
+        let export = results
+            .iter()
+            .find(|entry| {
+                Reflect::get(entry, &JsValue::from_str("requestId"))
+                    .ok()
+                    .and_then(|v| v.as_f64())
+                    == Some(3.0)
+            })
+            .expect("export result");
+        let bytes = Reflect::get(&export, &JsValue::from_str("bytes"))
+            .expect("bytes")
+            .dyn_into::<Uint8Array>()
+            .expect("export should return bytes")
+            .to_vec();
+        let mut snapshot = SQLiteDatabase::open_image(&bytes).expect("valid image");
+        let count = snapshot
+            .exec("SELECT COUNT(*) AS n FROM snapshot_test")
+            .await
+            .expect("Count failed");
+        let parsed: serde_json::Value = serde_json::from_str(&count).expect("Invalid JSON");
+        assert_eq!(parsed[0]["n"], serde_json::json!(200));
+    }
+
+    #[wasm_bindgen_test(async)]
+    async fn db_worker_cancel_drops_queued_job_before_execution() {
+        let results = Rc::new(Array::new());
+        let executed: Rc<RefCell<Vec<String>>> = Rc::new(RefCell::new(Vec::new()));
+        let hooks = DbWorkerHooks::new(
+            {
+                let executed = Rc::clone(&executed);
+                Rc::new(move |_db, sql, _params, _options| {
+                    executed.borrow_mut().push(sql);
+                    Box::pin(async move {
+                        sleep_ms(5).await;
+                        Ok("fake-db-ok".to_string())
+                    })
+                })
+            },
+            {
+                let results = Rc::clone(&results);
+                Rc::new(move |obj: &js_sys::Object| {
+                    results.push(obj.as_ref());
+                })
+            },
+        );
+
+        let state = DbWorkerState::new_with_hooks(
+            WorkerConfig {
+                db_name: "testdb-cancel".to_string(),
+                follower_timeout_ms: 10.0,
+                query_timeout_ms: 10.0,
+                max_follower_queries: DEFAULT_MAX_FOLLOWER_QUERIES,
+                max_follower_retries: DEFAULT_FOLLOWER_RETRIES,
+                max_queue_depth: DEFAULT_MAX_QUEUE_DEPTH,
+                announce_interval_ms: 0.0,
+                open_options: OpenOptions::default(),
+            },
+            hooks,
+        );
+
+        state.handle_message(WorkerMessage::ExecuteQuery {
+            request_id: 1,
+            sql: "SELECT 1".to_string(),
+            params: None,
+            options: None,
+        });
+        state.handle_message(WorkerMessage::ExecuteQuery {
+            request_id: 2,
+            sql: "SELECT 2".to_string(),
+            params: None,
+            options: None,
+        });
+        state.handle_message(WorkerMessage::CancelQuery { request_id: 2 });
+        // Cancelling an unknown request is a no-op.
+        state.handle_message(WorkerMessage::CancelQuery { request_id: 99 });
+
+        sleep_ms(30).await;
+
+        assert_eq!(*executed.borrow(), vec!["SELECT 1".to_string()]);
+        assert_eq!(results.length(), 2, "every request should be answered once");
+
+        let cancelled = results
+            .iter()
+            .find(|entry| {
+                Reflect::get(entry, &JsValue::from_str("requestId"))
+                    .ok()
+                    .and_then(|v| v.as_f64())
+                    == Some(2.0)
+            })
+            .expect("cancelled request should be answered");
+        let error = Reflect::get(&cancelled, &JsValue::from_str("error")).expect("error");
+        let error_type = Reflect::get(&error, &JsValue::from_str("type"))
+            .ok()
+            .and_then(|v| v.as_string());
+        assert_eq!(error_type.as_deref(), Some(WORKER_ERROR_TYPE_QUERY_ABORTED));
+    }
+
+    #[wasm_bindgen_test(async)]
+    async fn db_worker_skips_jobs_cancelled_through_the_slots() {
+        let results = Rc::new(Array::new());
+        let executed: Rc<RefCell<Vec<String>>> = Rc::new(RefCell::new(Vec::new()));
+        let hooks = DbWorkerHooks::new(
+            {
+                let executed = Rc::clone(&executed);
+                Rc::new(move |_db, sql, _params, _options| {
+                    executed.borrow_mut().push(sql);
+                    Box::pin(async move { Ok("fake-db-ok".to_string()) })
+                })
+            },
+            {
+                let results = Rc::clone(&results);
+                Rc::new(move |obj: &js_sys::Object| {
+                    results.push(obj.as_ref());
+                })
+            },
+        );
+
+        let state = DbWorkerState::new_with_hooks(
+            WorkerConfig {
+                db_name: "testdb-cancel-slots".to_string(),
+                follower_timeout_ms: 10.0,
+                query_timeout_ms: 10.0,
+                max_follower_queries: DEFAULT_MAX_FOLLOWER_QUERIES,
+                max_follower_retries: DEFAULT_FOLLOWER_RETRIES,
+                max_queue_depth: DEFAULT_MAX_QUEUE_DEPTH,
+                announce_interval_ms: 0.0,
+                open_options: OpenOptions::default(),
+            },
+            hooks,
+        );
+        let slots = Int32Array::new_with_length(CANCEL_SIGNAL_SLOTS);
+        js_sys::Atomics::store(&slots, 0, 2).expect("store");
+        *state.cancel_slots.borrow_mut() = Some(slots);
+
+        for request_id in [1, 2] {
+            state.handle_message(WorkerMessage::ExecuteQuery {
+                request_id,
+                sql: format!("SELECT {request_id}"),
+                params: None,
+                options: None,
+            });
+        }
+
+        sleep_ms(10).await;
+
+        assert_eq!(*executed.borrow(), vec!["SELECT 1".to_string()]);
+        assert_eq!(results.length(), 2, "every request should be answered once");
+        let cancelled = results.get(1);
+        assert_eq!(request_id_of(&cancelled), Some(2));
+        let error = Reflect::get(&cancelled, &JsValue::from_str("error")).expect("error");
+        let error_type = Reflect::get(&error, &JsValue::from_str("type"))
+            .ok()
+            .and_then(|v| v.as_string());
+        assert_eq!(error_type.as_deref(), Some(WORKER_ERROR_TYPE_QUERY_ABORTED));
+    }
 }
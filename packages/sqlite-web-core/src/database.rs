@@ -1,20 +1,405 @@
 use crate::database_functions::register_custom_functions;
-use crate::util::sanitize_db_filename;
+pub use crate::database_functions::FunctionSelection;
+use crate::messages::{
+    BatchStatement, HeaderPragma, QueryOptions, RowChange, SqliteErrorDetail, WorkerEvent,
+    WORKER_ERROR_TYPE_QUERY_TIMEOUT,
+};
+use crate::util::{encode_namespace, now_ms, sanitize_db_filename};
 use base64::Engine;
 use sqlite_wasm_rs::export::{install_opfs_sahpool, *};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::ffi::{CStr, CString};
-use std::os::raw::c_void;
+use std::os::raw::{c_char, c_int, c_uint, c_void};
 use wasm_bindgen::prelude::*;
 
+/// A cursor not read for this long is finalized by `close_idle_cursors`, so
+/// an abandoned `queryStream()` does not hold its read transaction forever.
+pub const CURSOR_IDLE_TIMEOUT_MS: f64 = 30_000.0;
+/// VM instructions between deadline checks while a statement runs.
+const DEADLINE_CHECK_INTERVAL_OPS: c_int = 1000;
+/// Name of the OPFS pool VFS used when no namespace is configured.
+const DEFAULT_SAHPOOL_VFS: &str = "opfs-sahpool";
+/// Magic string at the start of every SQLite database file.
+const SQLITE_HEADER_MAGIC: &[u8] = b"SQLite format 3\0";
+
+/// Settings applied when a connection is opened.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OpenOptions {
+    /// Which of the FLOAT_* and BIGINT_* SQL functions to register.
+    pub custom_functions: FunctionSelection,
+    /// Keeps the database in its own OPFS pool, apart from connections
+    /// opened under another namespace or none.
+    pub namespace: Option<String>,
+    /// Pragmas such as `"foreign_keys = ON"`, each run as `PRAGMA <entry>`
+    /// in order once the database is open. Opening fails if any of them do.
+    pub pragmas: Vec<String>,
+    /// Open an in-memory database when the OPFS VFS cannot be installed,
+    /// instead of failing.
+    pub allow_memory_fallback: bool,
+    /// Open a private in-memory database without installing the OPFS VFS at
+    /// all, e.g. for a probe that must not leave anything behind.
+    pub in_memory: bool,
+}
+
 // Real SQLite database using sqlite-wasm-rs FFI
 pub struct SQLiteDatabase {
     db: *mut sqlite3,
     in_transaction: bool,
+    hooks: Box<ConnectionHooks>,
+    query_options: QueryOptions,
+    /// Statements opened by `open_cursor`, stepped a chunk at a time.
+    cursors: HashMap<u32, Cursor>,
+    next_cursor_id: u32,
+    /// Code of the last `sqlite3_step` that finished a statement.
+    last_step_code: Cell<c_int>,
+    /// How many of the recorded changes are committed: those recorded
+    /// before the running query, or before a statement it started outside
+    /// a transaction. A failure discards only the changes after this.
+    durable_changes: Cell<usize>,
+    /// Each statement run with its bound values inlined, collected only
+    /// when requested.
+    expanded_sql: RefCell<Option<Vec<String>>>,
+    /// `{ statementIndex, rowsAffected, isQuery }` for each statement run,
+    /// collected only when requested.
+    statement_counts: RefCell<Option<Vec<serde_json::Value>>>,
+    /// What SQLite reported for the last query that failed, cleared when
+    /// the next query starts.
+    last_error: RefCell<Option<LastError>>,
+}
+
+/// A failure as SQLite reported it, read back with `last_error`.
+struct LastError {
+    message: String,
+    code: c_int,
+    /// Byte offset of the token SQLite blamed, when it named one.
+    offset: Option<usize>,
+    /// Length of the SQL from the failing statement to the end, until
+    /// `rebase` turns `offset` into a position in the whole query.
+    statement_len: Option<usize>,
+}
+
+impl LastError {
+    /// Make a statement-relative `offset` relative to the start of `sql`,
+    /// the query the failing statement was cut from. Trailing whitespace is
+    /// ignored on both sides since the single-statement path trims it.
+    fn rebase(&mut self, sql: &str) {
+        if let (Some(offset), Some(statement_len)) = (self.offset, self.statement_len.take()) {
+            let start = sql.trim_end().len().saturating_sub(statement_len);
+            self.offset = Some(start + offset);
+        }
+    }
 }
 
 unsafe impl Send for SQLiteDatabase {}
 unsafe impl Sync for SQLiteDatabase {}
 
+/// Raw connection handle used to interrupt running statements while the
+/// database itself is borrowed by the executing job.
+#[derive(Clone, Copy)]
+pub struct InterruptHandle {
+    db: *mut sqlite3,
+}
+
+impl InterruptHandle {
+    pub fn interrupt(&self) {
+        if !self.db.is_null() {
+            unsafe { sqlite3_interrupt(self.db) };
+        }
+    }
+}
+
+/// `sql` past any leading whitespace, comments and `;` of empty
+/// statements, or `None` if a block comment is never closed.
+fn skip_sql_trivia(sql: &[u8]) -> Option<&[u8]> {
+    let mut remaining = sql;
+    loop {
+        match remaining {
+            [b' ' | b'\t' | b'\r' | b'\n' | b';', tail @ ..] => {
+                remaining = tail;
+            }
+            [b'-', b'-', tail @ ..] => {
+                // line comment -- ... until newline
+                match tail.iter().position(|&byte| byte == b'\n') {
+                    Some(pos) => remaining = &tail[pos..],
+                    None => return Some(&[]), // comment to end of input
+                }
+            }
+            [b'/', b'*', tail @ ..] => {
+                // block comment /* ... */
+                match tail.windows(2).position(|window| window == b"*/") {
+                    Some(pos) => remaining = &tail[pos + 2..],
+                    None => return None, // unterminated comment
+                }
+            }
+            _ => return Some(remaining),
+        }
+    }
+}
+
+/// State shared with the SQLite callbacks registered on the connection.
+/// Boxed so the pointer handed to SQLite stays valid while the connection lives.
+#[derive(Default)]
+struct ConnectionHooks {
+    /// Wall-clock deadline for the running query, read by the progress handler.
+    deadline_ms: Cell<Option<f64>>,
+    /// Set when the progress handler interrupted the query for its deadline,
+    /// so only that failure is reported as a timeout.
+    deadline_hit: Cell<bool>,
+    /// Cancel slots and request id watched by the progress handler, so a
+    /// cancel written from another thread stops the running statement.
+    cancel_watch: RefCell<Option<(js_sys::Int32Array, u32)>>,
+    /// Rowids touched by the running query, collected only when requested.
+    rowids: RefCell<Option<Vec<i64>>>,
+    /// Rows written since the last `take_events`, buffered because the update
+    /// hook fires mid-statement.
+    changes: RefCell<Vec<RowChange>>,
+    /// Commits and rollbacks since the last `take_events`, in order.
+    transactions: RefCell<Vec<WorkerEvent>>,
+    /// Notices from the trace hook, collected only when requested.
+    notices: RefCell<Option<Vec<String>>>,
+}
+
+unsafe extern "C" fn deadline_progress_handler(arg: *mut c_void) -> c_int {
+    let hooks = &*(arg as *const ConnectionHooks);
+    if let Some((slots, request_id)) = hooks.cancel_watch.borrow().as_ref() {
+        if cancel_requested(slots, *request_id) {
+            return 1;
+        }
+    }
+    match hooks.deadline_ms.get() {
+        Some(at_ms) if js_sys::Date::now() >= at_ms => {
+            hooks.deadline_hit.set(true);
+            1
+        }
+        _ => 0,
+    }
+}
+
+/// Whether `request_id` has been written to the cancel slots. The slots are
+/// usually backed by a `SharedArrayBuffer` that another worker writes to, so
+/// they are read atomically.
+pub fn cancel_requested(slots: &js_sys::Int32Array, request_id: u32) -> bool {
+    (0..slots.length()).any(|index| {
+        js_sys::Atomics::load(slots, index).is_ok_and(|value| value == request_id as i32)
+    })
+}
+
+unsafe extern "C" fn update_hook(
+    arg: *mut c_void,
+    op: c_int,
+    _db_name: *const c_char,
+    table: *const c_char,
+    rowid: sqlite3_int64,
+) {
+    let hooks = &*(arg as *const ConnectionHooks);
+    if let Some(rowids) = hooks.rowids.borrow_mut().as_mut() {
+        rowids.push(rowid);
+    }
+    let op = match op {
+        SQLITE_INSERT => "insert",
+        SQLITE_UPDATE => "update",
+        SQLITE_DELETE => "delete",
+        _ => return,
+    };
+    hooks.changes.borrow_mut().push(RowChange {
+        op: op.to_string(),
+        table: CStr::from_ptr(table).to_string_lossy().into_owned(),
+        rowid,
+    });
+}
+
+/// Records planner notices while a query asks for them: statements that
+/// built an automatic index, and trigger programs, which SQLite traces as
+/// a `-- TRIGGER name` comment.
+unsafe extern "C" fn trace_hook(
+    event: c_uint,
+    arg: *mut c_void,
+    p: *mut c_void,
+    x: *mut c_void,
+) -> c_int {
+    let hooks = &*(arg as *const ConnectionHooks);
+    let mut notices = hooks.notices.borrow_mut();
+    let Some(notices) = notices.as_mut() else {
+        return 0;
+    };
+    match event {
+        SQLITE_TRACE_STMT => {
+            let text = CStr::from_ptr(x as *const c_char).to_string_lossy();
+            if let Some(comment) = text.strip_prefix("-- ") {
+                notices.push(comment.to_string());
+            }
+        }
+        SQLITE_TRACE_PROFILE => {
+            let stmt = p as *mut sqlite3_stmt;
+            let rows = sqlite3_stmt_status(stmt, SQLITE_STMTSTATUS_AUTOINDEX, 0);
+            let sql = sqlite3_sql(stmt);
+            if rows > 0 && !sql.is_null() {
+                notices.push(format!(
+                    "automatic index: {rows} rows indexed while running {}",
+                    CStr::from_ptr(sql).to_string_lossy()
+                ));
+            }
+        }
+        _ => {}
+    }
+    0
+}
+
+unsafe extern "C" fn commit_hook(arg: *mut c_void) -> c_int {
+    let hooks = &*(arg as *const ConnectionHooks);
+    hooks
+        .transactions
+        .borrow_mut()
+        .push(WorkerEvent::TransactionCommitted);
+    // Zero lets the commit proceed.
+    0
+}
+
+unsafe extern "C" fn rollback_hook(arg: *mut c_void) {
+    let hooks = &*(arg as *const ConnectionHooks);
+    hooks
+        .transactions
+        .borrow_mut()
+        .push(WorkerEvent::TransactionRolledBack);
+}
+
+/// What a query produced, before it is rendered into the result string.
+enum QueryOutput {
+    Rows(Vec<serde_json::Value>),
+    Affected(i32),
+    NoStatements,
+}
+
+impl QueryOutput {
+    fn from_statement((rows, affected): (Option<Vec<serde_json::Value>>, i32)) -> Self {
+        match rows {
+            Some(rows) => QueryOutput::Rows(rows),
+            None => QueryOutput::Affected(affected),
+        }
+    }
+
+    fn render(self) -> Result<String, String> {
+        match self {
+            QueryOutput::Rows(rows) => serde_json::to_string_pretty(&rows)
+                .map_err(|e| format!("JSON serialization error: {e}")),
+            QueryOutput::Affected(affected) => Ok(format!(
+                "Query executed successfully. Rows affected: {affected}"
+            )),
+            QueryOutput::NoStatements => Ok("No statements to execute.".to_string()),
+        }
+    }
+
+    /// The rows as a JSON array, or the summary message as a JSON string.
+    fn into_json(self) -> Result<serde_json::Value, String> {
+        match self {
+            QueryOutput::Rows(rows) => Ok(serde_json::Value::Array(rows)),
+            other => other.render().map(serde_json::Value::String),
+        }
+    }
+}
+
+/// Pretty-prints `value` like `serde_json::to_string_pretty`, but gives up
+/// once `budget_ms` has passed so a huge result cannot hold the worker's
+/// event loop for seconds.
+fn to_json_within<T: serde::Serialize>(
+    value: &T,
+    budget_ms: Option<f64>,
+) -> Result<String, String> {
+    let Some(budget_ms) = budget_ms else {
+        return serde_json::to_string_pretty(value)
+            .map_err(|e| format!("JSON serialization error: {e}"));
+    };
+    let formatter = DeadlineFormatter {
+        inner: serde_json::ser::PrettyFormatter::new(),
+        deadline_ms: js_sys::Date::now() + budget_ms,
+    };
+    let mut buffer = Vec::new();
+    let mut serializer = serde_json::Serializer::with_formatter(&mut buffer, formatter);
+    match value.serialize(&mut serializer) {
+        Ok(()) => String::from_utf8(buffer).map_err(|e| format!("JSON serialization error: {e}")),
+        // Writing into a Vec cannot fail, so an I/O error is the deadline.
+        Err(e) if e.is_io() => Err(format!(
+            "Result too large to serialize within {budget_ms:.0}ms"
+        )),
+        Err(e) => Err(format!("JSON serialization error: {e}")),
+    }
+}
+
+/// A `PrettyFormatter` that checks the clock before every array element.
+struct DeadlineFormatter {
+    inner: serde_json::ser::PrettyFormatter<'static>,
+    deadline_ms: f64,
+}
+
+impl serde_json::ser::Formatter for DeadlineFormatter {
+    fn begin_array<W: ?Sized + std::io::Write>(&mut self, writer: &mut W) -> std::io::Result<()> {
+        self.inner.begin_array(writer)
+    }
+
+    fn end_array<W: ?Sized + std::io::Write>(&mut self, writer: &mut W) -> std::io::Result<()> {
+        self.inner.end_array(writer)
+    }
+
+    fn begin_array_value<W: ?Sized + std::io::Write>(
+        &mut self,
+        writer: &mut W,
+        first: bool,
+    ) -> std::io::Result<()> {
+        if js_sys::Date::now() >= self.deadline_ms {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                "serialization budget exceeded",
+            ));
+        }
+        self.inner.begin_array_value(writer, first)
+    }
+
+    fn end_array_value<W: ?Sized + std::io::Write>(
+        &mut self,
+        writer: &mut W,
+    ) -> std::io::Result<()> {
+        self.inner.end_array_value(writer)
+    }
+
+    fn begin_object<W: ?Sized + std::io::Write>(&mut self, writer: &mut W) -> std::io::Result<()> {
+        self.inner.begin_object(writer)
+    }
+
+    fn end_object<W: ?Sized + std::io::Write>(&mut self, writer: &mut W) -> std::io::Result<()> {
+        self.inner.end_object(writer)
+    }
+
+    fn begin_object_key<W: ?Sized + std::io::Write>(
+        &mut self,
+        writer: &mut W,
+        first: bool,
+    ) -> std::io::Result<()> {
+        self.inner.begin_object_key(writer, first)
+    }
+
+    fn begin_object_value<W: ?Sized + std::io::Write>(
+        &mut self,
+        writer: &mut W,
+    ) -> std::io::Result<()> {
+        self.inner.begin_object_value(writer)
+    }
+
+    fn end_object_value<W: ?Sized + std::io::Write>(
+        &mut self,
+        writer: &mut W,
+    ) -> std::io::Result<()> {
+        self.inner.end_object_value(writer)
+    }
+}
+
+fn is_plain_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[derive(Default)]
 struct BoundBuffers {
     _texts: Vec<CString>,
     _blobs: Vec<Vec<u8>>,
@@ -45,6 +430,19 @@ impl Drop for StmtGuard {
     }
 }
 
+/// A statement kept open between `query_chunk` calls. Dropping it
+/// finalizes the statement.
+struct Cursor {
+    stmt: StmtGuard,
+    chunk_size: usize,
+    /// Resolved on the first row, like `exec_prepared_statement` does.
+    columns: Option<Vec<String>>,
+    max_cell_bytes: Option<usize>,
+    /// When the cursor was opened or last read.
+    last_used_ms: f64,
+    _buffers: BoundBuffers,
+}
+
 // Placeholder detection mode used during parameter binding
 enum PlaceholderMode {
     Plain {
@@ -67,6 +465,32 @@ enum ParamKind {
 }
 
 impl SQLiteDatabase {
+    /// The handle must not be used after this connection is dropped.
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        InterruptHandle { db: self.db }
+    }
+
+    /// Interrupt whatever runs until the next call once `request_id` appears
+    /// in `slots`. The progress handler checks them between VM steps, so a
+    /// statement stops mid-step rather than after it finishes. `None` stops
+    /// watching.
+    pub fn watch_cancel(&self, watch: Option<(js_sys::Int32Array, u32)>) {
+        self.hooks.cancel_watch.replace(watch);
+    }
+
+    /// Drain the notifications recorded by the connection hooks since the
+    /// last call: a `DbChange` with the rows written, in the order SQLite
+    /// reported them, followed by any commits and rollbacks.
+    pub fn take_events(&self) -> Vec<WorkerEvent> {
+        let changes = self.hooks.changes.take();
+        let mut events = Vec::new();
+        if !changes.is_empty() {
+            events.push(WorkerEvent::DbChange { changes });
+        }
+        events.extend(self.hooks.transactions.take());
+        events
+    }
+
     fn refresh_transaction_state(&mut self) {
         self.in_transaction = unsafe { sqlite3_get_autocommit(self.db) } == 0;
     }
@@ -93,8 +517,12 @@ impl SQLiteDatabase {
                 }
             }
             let msg = self.sqlite_errmsg();
+            let statement = unsafe { CStr::from_ptr(ptr) }.to_string_lossy();
+            self.record_error(Some(statement.trim_end().len()));
             let detail = if msg == "Unknown SQLite error" {
                 format!("SQLite error code: {ret}")
+            } else if msg == "too many SQL variables" {
+                Self::too_many_variables_error(None, self.variable_limit())
             } else {
                 msg
             };
@@ -102,6 +530,23 @@ impl SQLiteDatabase {
         }
         Ok((if stmt.is_null() { None } else { Some(stmt) }, tail))
     }
+
+    /// Most bound parameters one statement may use
+    /// (`SQLITE_LIMIT_VARIABLE_NUMBER`).
+    fn variable_limit(&self) -> usize {
+        unsafe { sqlite3_limit(self.db, SQLITE_LIMIT_VARIABLE_NUMBER, -1) }.max(0) as usize
+    }
+
+    fn too_many_variables_error(count: Option<usize>, limit: usize) -> String {
+        let problem = match count {
+            Some(count) => format!("{count} bound parameters but SQLite allows at most {limit}"),
+            None => format!("more than the {limit} bound parameters SQLite allows"),
+        };
+        format!(
+            "Statement uses {problem}. Split the values into chunks of at most {limit} \
+             and run one statement per chunk, or use insertMany for bulk inserts."
+        )
+    }
     fn sqlite_errmsg(&self) -> String {
         unsafe {
             let p = sqlite3_errmsg(self.db);
@@ -114,6 +559,41 @@ impl SQLiteDatabase {
         }
     }
 
+    /// Keep SQLite's message, code and error offset for `last_error`.
+    /// `statement_len` is given for prepare failures, whose offset counts
+    /// from the start of the statement being prepared.
+    fn record_error(&self, statement_len: Option<usize>) {
+        let offset = unsafe { sqlite3_error_offset(self.db) };
+        self.last_error.replace(Some(LastError {
+            message: self.sqlite_errmsg(),
+            code: unsafe { sqlite3_errcode(self.db) },
+            offset: usize::try_from(offset).ok(),
+            statement_len,
+        }));
+    }
+
+    /// The last failure SQLite reported, as JSON
+    /// `{ message, code, offset? }`, or `null` when the most recent query
+    /// succeeded. `offset` is the byte position in that query's SQL of the
+    /// token SQLite blamed, given for syntax errors and unknown names.
+    pub fn last_error(&self) -> Result<String, String> {
+        serde_json::to_string(&self.last_error_detail())
+            .map_err(|e| format!("JSON serialization error: {e}"))
+    }
+
+    /// The failure `last_error` reports, sent along with the error of the
+    /// query that caused it.
+    pub fn last_error_detail(&self) -> Option<SqliteErrorDetail> {
+        self.last_error
+            .borrow()
+            .as_ref()
+            .map(|error| SqliteErrorDetail {
+                message: error.message.clone(),
+                code: error.code,
+                offset: error.offset.and_then(|offset| u32::try_from(offset).ok()),
+            })
+    }
+
     fn collect_column_names(stmt: *mut sqlite3_stmt) -> Vec<String> {
         let col_count = unsafe { sqlite3_column_count(stmt) };
         let mut names = Vec::with_capacity(col_count as usize);
@@ -131,11 +611,49 @@ impl SQLiteDatabase {
         names
     }
 
-    fn read_column_value(stmt: *mut sqlite3_stmt, i: i32) -> serde_json::Value {
+    /// Result keys for a statement's columns. Names are taken from SQLite as-is,
+    /// so `SELECT count(*)` yields `count(*)` and `AS` aliases are used verbatim.
+    /// With `dealias_columns` set, names that are not plain identifiers are
+    /// replaced by `col_<index>`.
+    fn column_names(&self, stmt: *mut sqlite3_stmt) -> Vec<String> {
+        let names = Self::collect_column_names(stmt);
+        if !self.query_options.dealias_columns.unwrap_or(false) {
+            return names;
+        }
+        names
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| {
+                if is_plain_identifier(&name) {
+                    name
+                } else {
+                    format!("col_{i}")
+                }
+            })
+            .collect()
+    }
+
+    /// Read column `i` of the current row. With `coerce_affinity` an integer
+    /// read through a `REAL` column comes back as a float.
+    fn read_column_value(
+        stmt: *mut sqlite3_stmt,
+        i: i32,
+        max_cell_bytes: Option<usize>,
+        coerce_affinity: bool,
+    ) -> serde_json::Value {
         let col_type = unsafe { sqlite3_column_type(stmt, i) };
+        let as_real = coerce_affinity && Self::has_real_affinity(stmt, i);
         match col_type {
+            SQLITE_INTEGER if as_real => {
+                let val = unsafe { sqlite3_column_double(stmt, i) };
+                // Safe to unwrap: a converted integer is always finite
+                serde_json::Value::Number(serde_json::Number::from_f64(val).unwrap())
+            }
             SQLITE_INTEGER => {
                 let val = unsafe { sqlite3_column_int64(stmt, i) };
+                if (val == 0 || val == 1) && Self::is_boolean_column(stmt, i) {
+                    return serde_json::Value::Bool(val == 1);
+                }
                 serde_json::Value::Number(serde_json::Number::from(val))
             }
             SQLITE_FLOAT => {
@@ -149,25 +667,96 @@ impl SQLiteDatabase {
             }
             SQLITE_TEXT => {
                 let ptr = unsafe { sqlite3_column_text(stmt, i) };
-                if !ptr.is_null() {
-                    let text = unsafe {
-                        CStr::from_ptr(ptr as *const i8)
-                            .to_string_lossy()
-                            .into_owned()
-                    };
-                    serde_json::Value::String(text)
-                } else {
-                    serde_json::Value::Null
+                if ptr.is_null() {
+                    return serde_json::Value::Null;
+                }
+                let bytes = unsafe { CStr::from_ptr(ptr as *const c_char) }.to_bytes();
+                let len = bytes.len();
+                match max_cell_bytes {
+                    Some(max) if len > max => {
+                        // Cut on a character boundary so the preview stays valid UTF-8.
+                        let mut cut = max;
+                        while cut > 0 && (bytes[cut] & 0xC0) == 0x80 {
+                            cut -= 1;
+                        }
+                        let preview = String::from_utf8_lossy(&bytes[..cut]).into_owned();
+                        Self::truncated_cell(preview, len)
+                    }
+                    _ => serde_json::Value::String(String::from_utf8_lossy(bytes).into_owned()),
                 }
             }
             SQLITE_BLOB => {
                 let len = unsafe { sqlite3_column_bytes(stmt, i) };
-                serde_json::Value::String(format!("<blob {len} bytes>"))
+                let placeholder = format!("<blob {len} bytes>");
+                match max_cell_bytes {
+                    // Blob contents are never sent, so the placeholder is the preview.
+                    Some(max) if len as usize > max => {
+                        Self::truncated_cell(placeholder, len as usize)
+                    }
+                    _ => serde_json::Value::String(placeholder),
+                }
             }
             _ => serde_json::Value::Null,
         }
     }
 
+    /// Whether result column `i` reads a table column declared `BOOLEAN` (or
+    /// `BOOL`), whose 0/1 values are returned as JSON booleans. Expressions
+    /// have no declared type, so `SELECT flag + 0` stays numeric.
+    fn is_boolean_column(stmt: *mut sqlite3_stmt, i: i32) -> bool {
+        let Some(decltype) = Self::declared_type(stmt, i) else {
+            return false;
+        };
+        let decltype = decltype.trim();
+        decltype.eq_ignore_ascii_case("BOOLEAN") || decltype.eq_ignore_ascii_case("BOOL")
+    }
+
+    /// Whether result column `i` reads a table column whose declared type
+    /// has REAL affinity by SQLite's rules: no `INT`, `CHAR`, `CLOB`, `TEXT`
+    /// or `BLOB`, which take precedence, and one of `REAL`, `FLOA` or `DOUB`.
+    fn has_real_affinity(stmt: *mut sqlite3_stmt, i: i32) -> bool {
+        let Some(decltype) = Self::declared_type(stmt, i) else {
+            return false;
+        };
+        let decltype = decltype.to_ascii_uppercase();
+        let has = |words: &[&str]| words.iter().any(|word| decltype.contains(word));
+        !has(&["INT", "CHAR", "CLOB", "TEXT", "BLOB"]) && has(&["REAL", "FLOA", "DOUB"])
+    }
+
+    /// Declared type of the table column behind result column `i`, if any.
+    fn declared_type(stmt: *mut sqlite3_stmt, i: i32) -> Option<String> {
+        let ptr = unsafe { sqlite3_column_decltype(stmt, i) };
+        if ptr.is_null() {
+            return None;
+        }
+        Some(
+            unsafe { CStr::from_ptr(ptr) }
+                .to_string_lossy()
+                .into_owned(),
+        )
+    }
+
+    /// Storage class of column `i` in the current row, as reported by
+    /// `sqlite3_column_type`.
+    fn column_type_name(stmt: *mut sqlite3_stmt, i: i32) -> &'static str {
+        match unsafe { sqlite3_column_type(stmt, i) } {
+            SQLITE_INTEGER => "integer",
+            SQLITE_FLOAT => "float",
+            SQLITE_TEXT => "text",
+            SQLITE_BLOB => "blob",
+            _ => "null",
+        }
+    }
+
+    /// Marker returned in place of a value longer than `maxCellBytes`.
+    fn truncated_cell(preview: String, total_bytes: usize) -> serde_json::Value {
+        serde_json::json!({
+            "__truncated": true,
+            "preview": preview,
+            "totalBytes": total_bytes,
+        })
+    }
+
     fn detect_placeholder_mode(&self, stmt: *mut sqlite3_stmt) -> Result<PlaceholderMode, String> {
         let param_count = unsafe { sqlite3_bind_parameter_count(stmt) } as usize;
 
@@ -355,6 +944,37 @@ impl SQLiteDatabase {
                     .map_err(|_| format!("BigInt out of i64 range at index {}.", idx0 + 1))?;
                 Ok(ParamKind::I64(v))
             }
+            "json" => {
+                let s = map
+                    .get("value")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| format!("Invalid json parameter at index {}", idx0 + 1))?;
+                Self::parse_string_param(idx0, s)
+            }
+            "text_blob" => {
+                let s = map
+                    .get("value")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| format!("Invalid text_blob parameter at index {}", idx0 + 1))?;
+                Ok(ParamKind::Blob(s.as_bytes().to_vec()))
+            }
+            "int" => {
+                let value = map.get("value");
+                let parsed = match value {
+                    Some(serde_json::Value::String(s)) => s.trim().parse::<i64>().ok(),
+                    Some(serde_json::Value::Number(n)) => n.as_i64(),
+                    _ => None,
+                };
+                parsed
+                    .map(ParamKind::I64)
+                    .ok_or_else(|| format!("Invalid int parameter at index {}", idx0 + 1))
+            }
+            "double" => map
+                .get("value")
+                .and_then(|v| v.as_f64())
+                .filter(|v| v.is_finite())
+                .map(ParamKind::F64)
+                .ok_or_else(|| format!("Invalid double parameter at index {}", idx0 + 1)),
             _ => Err(format!(
                 "Unsupported extended param type '{}' at index {}",
                 t,
@@ -474,39 +1094,17 @@ impl SQLiteDatabase {
             ParamKind::Blob(bytes) => self.bind_blob(stmt, i, bytes, buffers),
         }
     }
+
+    /// Whether the text after a parameterized statement holds nothing to
+    /// run: whitespace, comments, and the `;` of empty statements, so a
+    /// query may end with a semicolon (or several) either way.
     fn is_trivia_tail_only(tail: *const i8) -> bool {
         if tail.is_null() {
             return true;
         }
         // Safe because we created the input as a NUL-terminated CString and SQLite returns a pointer into it
         let rest_c = unsafe { CStr::from_ptr(tail) };
-        let rest = rest_c.to_bytes();
-
-        // Simple scanner: skip whitespace and comments only
-        let mut remaining = rest;
-        loop {
-            match remaining {
-                [] => return true,
-                [b' ' | b'\t' | b'\r' | b'\n', tail @ ..] => {
-                    remaining = tail;
-                }
-                [b'-', b'-', tail @ ..] => {
-                    // line comment -- ... until newline
-                    match tail.iter().position(|&byte| byte == b'\n') {
-                        Some(pos) => remaining = &tail[pos..],
-                        None => return true, // comment to end of input
-                    }
-                }
-                [b'/', b'*', tail @ ..] => {
-                    // block comment /* ... */
-                    match tail.windows(2).position(|window| window == b"*/") {
-                        Some(pos) => remaining = &tail[pos + 2..],
-                        None => return false, // unterminated comment
-                    }
-                }
-                _ => return false,
-            }
-        }
+        matches!(skip_sql_trivia(rest_c.to_bytes()), Some([]))
     }
 
     fn bind_params_for_stmt(
@@ -514,31 +1112,33 @@ impl SQLiteDatabase {
         stmt: *mut sqlite3_stmt,
         params: &[serde_json::Value],
     ) -> Result<BoundBuffers, String> {
+        let limit = self.variable_limit();
+        let param_count = unsafe { sqlite3_bind_parameter_count(stmt) } as usize;
+        if params.len().max(param_count) > limit {
+            return Err(Self::too_many_variables_error(
+                Some(params.len().max(param_count)),
+                limit,
+            ));
+        }
+
         // Derive placeholder mode, validate with provided params, and build mapping
         let mode = self.detect_placeholder_mode(stmt)?;
         self.validate_params_against_mode(&mode, params.len())?;
         let param_map = self.build_param_map(stmt, &mode)?;
 
         // Keep owned buffers alive for text/blob while the statement executes
-        let param_count = unsafe { sqlite3_bind_parameter_count(stmt) } as usize;
-        (1..=param_count as i32).try_fold(
-            BoundBuffers {
-                _texts: Vec::new(),
-                _blobs: Vec::new(),
-            },
-            |mut buffers, param_index| {
-                let target_index = param_map[(param_index - 1) as usize];
-                let val = params.get(target_index).ok_or_else(|| {
-                    format!(
-                        "Missing parameter value at index {} (0-based {target_index})",
-                        target_index + 1
-                    )
-                })?;
-                let kind = self.parse_json_param(target_index, val)?;
-                self.bind_param(stmt, param_index, &kind, &mut buffers)?;
-                Ok(buffers)
-            },
-        )
+        (1..=param_count as i32).try_fold(BoundBuffers::default(), |mut buffers, param_index| {
+            let target_index = param_map[(param_index - 1) as usize];
+            let val = params.get(target_index).ok_or_else(|| {
+                format!(
+                    "Missing parameter value at index {} (0-based {target_index})",
+                    target_index + 1
+                )
+            })?;
+            let kind = self.parse_json_param(target_index, val)?;
+            self.bind_param(stmt, param_index, &kind, &mut buffers)?;
+            Ok(buffers)
+        })
     }
 
     async fn exec_single_statement_with_params(
@@ -547,8 +1147,21 @@ impl SQLiteDatabase {
         params: Vec<serde_json::Value>,
     ) -> Result<(Option<Vec<serde_json::Value>>, i32), String> {
         let sql_cstr = CString::new(sql).map_err(|e| format!("Invalid SQL string: {e}"))?;
-        let ptr = sql_cstr.as_ptr();
-        let (stmt_opt, tail) = self.prepare_one(ptr)?;
+        let Some((mut stmt_guard, _buffers_guard)) = self.prepare_bound(&sql_cstr, &params)? else {
+            return Ok((None, 0));
+        };
+        self.exec_prepared_statement(stmt_guard.take())
+    }
+
+    /// Prepare `sql` as a single statement and bind `params` to it, or
+    /// `None` when `sql` holds no statement. The returned buffers back the
+    /// bound text and blob values and must outlive the statement.
+    fn prepare_bound(
+        &self,
+        sql: &CStr,
+        params: &[serde_json::Value],
+    ) -> Result<Option<(StmtGuard, BoundBuffers)>, String> {
+        let (stmt_opt, tail) = self.prepare_one(sql.as_ptr())?;
         let Some(stmt) = stmt_opt else {
             if !Self::is_trivia_tail_only(tail) {
                 return Err("Parameterized queries must contain a single statement.".to_string());
@@ -559,9 +1172,9 @@ impl SQLiteDatabase {
                     params_len = params.len()
                 ));
             }
-            return Ok((None, 0));
+            return Ok(None);
         };
-        let mut stmt_guard = StmtGuard::new(stmt);
+        let stmt_guard = StmtGuard::new(stmt);
         if !Self::is_trivia_tail_only(tail) {
             return Err("Parameterized queries must contain a single statement.".to_string());
         }
@@ -573,19 +1186,51 @@ impl SQLiteDatabase {
                     params_len = params.len()
                 ));
             }
-            return self.exec_prepared_statement(stmt_guard.take());
+            return Ok(Some((stmt_guard, BoundBuffers::default())));
         }
-        let _buffers_guard = self.bind_params_for_stmt(stmt, &params)?;
-        self.exec_prepared_statement(stmt_guard.take())
+        let buffers = self.bind_params_for_stmt(stmt, params)?;
+        Ok(Some((stmt_guard, buffers)))
     }
 
     pub async fn initialize_opfs(db_name: &str) -> Result<Self, JsValue> {
-        // Install OPFS VFS and set as default
-        install_opfs_sahpool(None, true)
+        Self::initialize_opfs_with_options(db_name, &OpenOptions::default()).await
+    }
+
+    /// Open `db_name` like `initialize_opfs`, applying `options`.
+    pub async fn initialize_opfs_with_options(
+        db_name: &str,
+        options: &OpenOptions,
+    ) -> Result<Self, JsValue> {
+        let vfs = Self::install_opfs_vfs(options).await?;
+        Self::open_opfs(db_name, &vfs, options).await
+    }
+
+    /// Install the OPFS pool `options` selects and return its VFS name. The
+    /// default pool is also made the default VFS; a namespace gets its own
+    /// pool in its own OPFS directory.
+    pub async fn install_opfs_vfs(options: &OpenOptions) -> Result<CString, JsValue> {
+        let vfs_name = match options.namespace.as_deref() {
+            None => DEFAULT_SAHPOOL_VFS.to_string(),
+            Some(namespace) => format!("{DEFAULT_SAHPOOL_VFS}-{}", encode_namespace(namespace)),
+        };
+        let pool_config = options.namespace.as_ref().map(|_| {
+            OpfsSAHPoolCfgBuilder::new()
+                .vfs_name(&vfs_name)
+                .directory(&format!(".{vfs_name}"))
+                .build()
+        });
+        install_opfs_sahpool(pool_config.as_ref(), pool_config.is_none())
             .await
             .map_err(|e| JsValue::from_str(&format!("Failed to install OPFS VFS: {e:?}")))?;
+        CString::new(vfs_name).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
 
-        // Open database with OPFS
+    /// Open `db_name` in the pool `install_opfs_vfs` installed as `vfs`.
+    pub async fn open_opfs(
+        db_name: &str,
+        vfs: &CStr,
+        options: &OpenOptions,
+    ) -> Result<Self, JsValue> {
         let mut db: *mut sqlite3 = std::ptr::null_mut();
         let sanitized = sanitize_db_filename(db_name);
         let open_uri = format!("opfs-sahpool:{}", sanitized);
@@ -600,7 +1245,7 @@ impl SQLiteDatabase {
                 db_name.as_ptr(),
                 &mut db as *mut _,
                 SQLITE_OPEN_READWRITE | SQLITE_OPEN_CREATE,
-                std::ptr::null(),
+                vfs.as_ptr(),
             )
         };
 
@@ -625,16 +1270,78 @@ impl SQLiteDatabase {
             )));
         }
 
+        Self::from_open_handle(db, options).await
+    }
+
+    /// Open a private in-memory database, set up like `initialize_opfs_with_options`
+    /// but with nothing written to OPFS.
+    pub async fn open_in_memory(options: &OpenOptions) -> Result<Self, JsValue> {
+        let memory = CString::new(":memory:").expect("static filename");
+        let mut db: *mut sqlite3 = std::ptr::null_mut();
+        let ret = unsafe {
+            sqlite3_open_v2(
+                memory.as_ptr(),
+                &mut db as *mut _,
+                SQLITE_OPEN_READWRITE | SQLITE_OPEN_CREATE,
+                std::ptr::null(),
+            )
+        };
+        if ret != SQLITE_OK {
+            if !db.is_null() {
+                unsafe { sqlite3_close(db) };
+            }
+            return Err(JsValue::from_str(&format!(
+                "Failed to open in-memory database: SQLite error code {ret}"
+            )));
+        }
+        Self::from_open_handle(db, options).await
+    }
+
+    /// Register functions and hooks on a freshly opened `db` and run the
+    /// configured pragmas. `db` is closed if any step fails.
+    async fn from_open_handle(db: *mut sqlite3, options: &OpenOptions) -> Result<Self, JsValue> {
         // Register custom functions; close DB on failure to avoid leaks
-        if let Err(e) = register_custom_functions(db) {
+        if let Err(e) = register_custom_functions(db, &options.custom_functions) {
             unsafe { sqlite3_close(db) };
             return Err(JsValue::from_str(&e));
         }
 
-        Ok(SQLiteDatabase {
+        let hooks = Box::<ConnectionHooks>::default();
+        let hooks_ptr = &*hooks as *const ConnectionHooks as *mut c_void;
+        unsafe {
+            sqlite3_progress_handler(
+                db,
+                DEADLINE_CHECK_INTERVAL_OPS,
+                Some(deadline_progress_handler),
+                hooks_ptr,
+            );
+            sqlite3_update_hook(db, Some(update_hook), hooks_ptr);
+            sqlite3_commit_hook(db, Some(commit_hook), hooks_ptr);
+            sqlite3_rollback_hook(db, Some(rollback_hook), hooks_ptr);
+        }
+
+        let mut database = SQLiteDatabase {
             db,
             in_transaction: false,
-        })
+            hooks,
+            query_options: QueryOptions::default(),
+            cursors: HashMap::new(),
+            next_cursor_id: 0,
+            last_step_code: Cell::new(SQLITE_OK),
+            durable_changes: Cell::new(0),
+            expanded_sql: RefCell::new(None),
+            statement_counts: RefCell::new(None),
+            last_error: RefCell::new(None),
+        };
+        // Dropping `database` on failure closes the connection.
+        for pragma in &options.pragmas {
+            database
+                .exec_single_statement(&format!("PRAGMA {pragma}"))
+                .await
+                .map_err(|e| JsValue::from_str(&format!("Failed to apply PRAGMA {pragma}: {e}")))?;
+        }
+        database.refresh_transaction_state();
+        Ok(database)
     }
 
     /// Execute a prepared statement, collecting any result rows and the affected row count.
@@ -645,42 +1352,62 @@ impl SQLiteDatabase {
     ) -> Result<(Option<Vec<serde_json::Value>>, i32), String> {
         let guard = StmtGuard::new(stmt);
         let stmt = guard.stmt;
+        self.record_expanded_sql(stmt);
+        if unsafe { sqlite3_get_autocommit(self.db) } != 0 {
+            self.durable_changes.set(self.hooks.changes.borrow().len());
+        }
 
         let col_count = unsafe { sqlite3_column_count(stmt) };
         let is_query = col_count > 0;
 
         let mut results = Vec::new();
         let mut column_names: Option<Vec<String>> = None;
+        let max_cell_bytes = self.query_options.max_cell_bytes.map(|max| max as usize);
+        let typed_cells = self.query_options.typed_cells.unwrap_or(false);
+        let coerce_affinity = self.query_options.coerce_affinity.unwrap_or(false);
+        let total_changes_before = unsafe { sqlite3_total_changes(self.db) };
 
         loop {
             let step_result = unsafe { sqlite3_step(stmt) };
             match step_result {
+                // A statement without result columns can still step rows
+                // (some pragmas do); there is nothing to read from them.
+                SQLITE_ROW if !is_query => {}
                 SQLITE_ROW => {
                     if column_names.is_none() {
-                        column_names = Some(Self::collect_column_names(stmt));
+                        column_names = Some(self.column_names(stmt));
                     }
                     let names = column_names.as_ref().unwrap();
-                    let mut row_obj = std::collections::BTreeMap::new();
-                    for i in 0..col_count {
-                        let value = Self::read_column_value(stmt, i);
-                        if let Some(col_name) = names.get(i as usize) {
-                            row_obj.insert(col_name.clone(), value);
-                        }
-                    }
-                    results.push(serde_json::Value::Object(row_obj.into_iter().collect()));
+                    results.push(Self::read_row(
+                        stmt,
+                        names,
+                        max_cell_bytes,
+                        typed_cells,
+                        coerce_affinity,
+                    ));
                 }
-                SQLITE_DONE => break,
-                other => {
-                    return Err(format!("Query execution failed: {}", self.sqlite_errmsg())
-                        .replace(
-                            "Unknown SQLite error",
-                            &format!("SQLite error code: {other}"),
-                        ));
+                SQLITE_DONE => {
+                    self.last_step_code.set(SQLITE_DONE);
+                    break;
                 }
+                other => return Err(self.step_error(other)),
             }
         }
 
-        let changes = unsafe { sqlite3_changes(self.db) };
+        // `sqlite3_changes` still reports the last INSERT/UPDATE/DELETE when
+        // this statement changed nothing, e.g. a PRAGMA or ANALYZE.
+        let changes = if unsafe { sqlite3_total_changes(self.db) } == total_changes_before {
+            0
+        } else {
+            unsafe { sqlite3_changes(self.db) }
+        };
+        if let Some(counts) = self.statement_counts.borrow_mut().as_mut() {
+            counts.push(serde_json::json!({
+                "statementIndex": counts.len(),
+                "rowsAffected": changes,
+                "isQuery": is_query,
+            }));
+        }
         if is_query {
             Ok((Some(results), changes))
         } else {
@@ -688,6 +1415,62 @@ impl SQLiteDatabase {
         }
     }
 
+    /// Remember the text of `stmt` with its bound parameters substituted,
+    /// when the running query asked for `expandedSql`.
+    fn record_expanded_sql(&self, stmt: *mut sqlite3_stmt) {
+        let mut expanded = self.expanded_sql.borrow_mut();
+        let Some(expanded) = expanded.as_mut() else {
+            return;
+        };
+        let ptr = unsafe { sqlite3_expanded_sql(stmt) };
+        if ptr.is_null() {
+            return;
+        }
+        expanded.push(
+            unsafe { CStr::from_ptr(ptr) }
+                .to_string_lossy()
+                .into_owned(),
+        );
+        unsafe { sqlite3_free(ptr as *mut c_void) };
+    }
+
+    /// Read the current row of `stmt` as an object keyed by `names`. With
+    /// `typed_cells` each value is wrapped as `{ type, value }`.
+    fn read_row(
+        stmt: *mut sqlite3_stmt,
+        names: &[String],
+        max_cell_bytes: Option<usize>,
+        typed_cells: bool,
+        coerce_affinity: bool,
+    ) -> serde_json::Value {
+        let col_count = unsafe { sqlite3_column_count(stmt) };
+        let mut row_obj = std::collections::BTreeMap::new();
+        for i in 0..col_count {
+            let value = if typed_cells {
+                // Read the type first: it is undefined once a value was converted.
+                let type_name = Self::column_type_name(stmt, i);
+                serde_json::json!({
+                    "type": type_name,
+                    "value": Self::read_column_value(stmt, i, max_cell_bytes, coerce_affinity),
+                })
+            } else {
+                Self::read_column_value(stmt, i, max_cell_bytes, coerce_affinity)
+            };
+            if let Some(col_name) = names.get(i as usize) {
+                row_obj.insert(col_name.clone(), value);
+            }
+        }
+        serde_json::Value::Object(row_obj.into_iter().collect())
+    }
+
+    fn step_error(&self, code: c_int) -> String {
+        self.record_error(None);
+        format!("Query execution failed: {}", self.sqlite_errmsg()).replace(
+            "Unknown SQLite error",
+            &format!("SQLite error code: {code}"),
+        )
+    }
+
     /// Execute a single SQL statement and return the result
     async fn exec_single_statement(
         &self,
@@ -712,25 +1495,33 @@ impl SQLiteDatabase {
 
     /// Execute potentially multiple SQL statements
     pub async fn exec(&mut self, sql: &str) -> Result<String, String> {
+        self.run_statements(sql).await?.render()
+    }
+
+    async fn run_statements(&mut self, sql: &str) -> Result<QueryOutput, String> {
         let trimmed = sql.trim();
 
+        match self.query_options.allow_multiple {
+            Some(false) => self.ensure_single_statement(sql)?,
+            Some(true) => return self.run_all_statements(sql).await,
+            None => {}
+        }
+
         // Single-statement mode: execute only the first statement, ignore tail
         if !trimmed.ends_with(';') {
-            let (results, affected) = self.exec_single_statement(trimmed).await?;
+            let output = self.exec_single_statement(trimmed).await?;
 
             self.refresh_transaction_state();
 
-            return if let Some(results) = results {
-                serde_json::to_string_pretty(&results)
-                    .map_err(|e| format!("JSON serialization error: {e}"))
-            } else {
-                Ok(format!(
-                    "Query executed successfully. Rows affected: {affected}"
-                ))
-            };
+            return Ok(QueryOutput::from_statement(output));
         }
 
-        // Multi-statement mode: use SQLite parser with tail pointer
+        self.run_all_statements(sql).await
+    }
+
+    /// Multi-statement mode: run every statement, using SQLite's parser and
+    /// tail pointer to find where each one ends.
+    async fn run_all_statements(&mut self, sql: &str) -> Result<QueryOutput, String> {
         let sql_cstr = CString::new(sql).map_err(|e| format!("Invalid SQL string: {e}"))?;
         let mut ptr = sql_cstr.as_ptr();
 
@@ -785,16 +1576,40 @@ impl SQLiteDatabase {
         self.refresh_transaction_state();
 
         if !executed_any {
-            return Ok("No statements to execute.".to_string());
+            return Ok(QueryOutput::NoStatements);
         }
 
-        if let Some(results) = select_results {
-            serde_json::to_string_pretty(&results)
-                .map_err(|e| format!("JSON serialization error: {e}"))
-        } else {
-            Ok(format!(
-                "Query executed successfully. Rows affected: {total_affected_rows}"
-            ))
+        Ok(QueryOutput::from_statement((
+            select_results,
+            total_affected_rows,
+        )))
+    }
+
+    /// Fail unless SQLite's parser finds at most one statement in `sql`.
+    /// Stray semicolons and comments around it are allowed.
+    fn ensure_single_statement(&self, sql: &str) -> Result<(), String> {
+        const MULTIPLE: &str = "Multiple statements are not allowed when allowMultiple is false.";
+        let sql_cstr = CString::new(sql).map_err(|e| format!("Invalid SQL string: {e}"))?;
+        let mut ptr = sql_cstr.as_ptr();
+        let mut seen_statement = false;
+        loop {
+            let (stmt_opt, tail) = match self.prepare_one(ptr) {
+                Ok(prepared) => prepared,
+                // Whatever follows the first statement, it is not trivia.
+                Err(_) if seen_statement => return Err(MULTIPLE.to_string()),
+                Err(err) => return Err(err),
+            };
+            if let Some(stmt) = stmt_opt {
+                drop(StmtGuard::new(stmt));
+                if seen_statement {
+                    return Err(MULTIPLE.to_string());
+                }
+                seen_statement = true;
+            }
+            if tail.is_null() || tail == ptr {
+                return Ok(());
+            }
+            ptr = tail;
         }
     }
 
@@ -804,651 +1619,3808 @@ impl SQLiteDatabase {
         sql: &str,
         params: Vec<serde_json::Value>,
     ) -> Result<String, String> {
-        let (results, affected) = self.exec_single_statement_with_params(sql, params).await?;
+        self.run_with_params(sql, params).await?.render()
+    }
+
+    async fn run_with_params(
+        &mut self,
+        sql: &str,
+        params: Vec<serde_json::Value>,
+    ) -> Result<QueryOutput, String> {
+        if self.query_options.allow_multiple == Some(true) {
+            return self.run_all_statements_with_params(sql, &params).await;
+        }
+
+        let output = self.exec_single_statement_with_params(sql, params).await?;
 
         self.refresh_transaction_state();
 
-        if let Some(results) = results {
-            serde_json::to_string_pretty(&results)
-                .map_err(|e| format!("JSON serialization error: {e}"))
-        } else {
-            Ok(format!(
-                "Query executed successfully. Rows affected: {affected}"
-            ))
-        }
+        Ok(QueryOutput::from_statement(output))
     }
-}
 
-impl Drop for SQLiteDatabase {
-    fn drop(&mut self) {
-        if !self.db.is_null() {
-            unsafe {
-                sqlite3_close(self.db);
+    /// Multi-statement mode with parameters. Every statement binds from the
+    /// start of the same `params`: one with `?` placeholders takes as many
+    /// values as it has placeholders, and one with `?N` placeholders takes
+    /// values up to its highest `N`, so `?1` names the same value in every
+    /// statement. Each value must be used by some statement. The statements
+    /// run inside a savepoint, so a failure undoes all of them and leaves a
+    /// transaction that was already open as it was; otherwise they commit
+    /// together, or with the open transaction. SQL that begins or ends a
+    /// transaction itself runs without the savepoint, as it would without
+    /// params, and a failure rolls back whatever transaction is open.
+    async fn run_all_statements_with_params(
+        &mut self,
+        sql: &str,
+        params: &[serde_json::Value],
+    ) -> Result<QueryOutput, String> {
+        self.check_shared_params(sql, params)?;
+        if Self::has_transaction_control(sql) {
+            let finished = self.bind_and_run_each_statement(sql, params);
+            if finished.is_err() {
+                self.rollback_if_in_transaction().await;
             }
+            self.refresh_transaction_state();
+            return finished;
         }
+        self.exec_single_statement("SAVEPOINT sqlite_web_shared_params")
+            .await?;
+        let finished = match self.bind_and_run_each_statement(sql, params) {
+            Ok(output) => self
+                .exec_single_statement("RELEASE sqlite_web_shared_params")
+                .await
+                .map(|_| output),
+            Err(e) => {
+                let _ = self
+                    .exec_single_statement("ROLLBACK TO sqlite_web_shared_params")
+                    .await;
+                let _ = self
+                    .exec_single_statement("RELEASE sqlite_web_shared_params")
+                    .await;
+                Err(e)
+            }
+        };
+        self.refresh_transaction_state();
+        finished
     }
-}
-
-#[cfg(all(test, target_family = "wasm"))]
-mod tests {
-    use super::*;
-    use serde_json::json;
-    use wasm_bindgen_test::*;
 
-    wasm_bindgen_test_configure!(run_in_browser);
+    /// Check `params` against the placeholders of every statement in `sql`
+    /// before any of them runs. A statement that cannot be prepared yet,
+    /// such as one using a table an earlier statement creates, ends the
+    /// check; `bind_and_run_each_statement` checks the rest as it runs them.
+    fn check_shared_params(&self, sql: &str, params: &[serde_json::Value]) -> Result<(), String> {
+        let sql_cstr = CString::new(sql).map_err(|e| format!("Invalid SQL string: {e}"))?;
+        let mut ptr = sql_cstr.as_ptr();
+        let mut stmt_index: usize = 0;
+        let mut widest = 0;
 
-    #[wasm_bindgen_test]
-    async fn test_opfs_initialization_success() {
-        let result = SQLiteDatabase::initialize_opfs("testdb").await;
-        if result.is_err() {
-            return;
+        loop {
+            let Ok((stmt_opt, tail)) = self.prepare_one(ptr) else {
+                return Ok(());
+            };
+            if let Some(stmt) = stmt_opt {
+                stmt_index += 1;
+                let _guard = StmtGuard::new(stmt);
+                let needed = self
+                    .placeholders_needed(stmt)
+                    .map_err(|e| format!("Statement {stmt_index} failed: {e}"))?;
+                if needed > params.len() {
+                    return Err(format!(
+                        "Statement {stmt_index} failed: Expected {needed} parameters but got {}.",
+                        params.len()
+                    ));
+                }
+                widest = widest.max(needed);
+            }
+            if tail.is_null() || tail == ptr {
+                break;
+            }
+            ptr = tail;
         }
-        assert!(
-            result.is_ok(),
-            "OPFS database initialization should succeed"
-        );
-        let db = result.unwrap();
-        assert!(
-            !db.db.is_null(),
-            "Database pointer should not be null after initialization"
-        );
-    }
 
-    async fn get_test_db() -> Option<SQLiteDatabase> {
-        (SQLiteDatabase::initialize_opfs("testdb").await).ok()
+        if widest != params.len() {
+            return Err(format!(
+                "Expected {widest} parameters but got {}.",
+                params.len()
+            ));
+        }
+        Ok(())
     }
 
-    #[wasm_bindgen_test]
-    async fn test_create_table_and_insert() {
-        let Some(mut db) = get_test_db().await else {
-            return;
+    /// Whether any statement in `sql` is `BEGIN`, `COMMIT`, `END`,
+    /// `ROLLBACK`, `SAVEPOINT` or `RELEASE`. Statements are split where
+    /// `sqlite3_complete` sees one end, so a `;` inside a string or a
+    /// trigger body does not split them and none needs to prepare.
+    fn has_transaction_control(sql: &str) -> bool {
+        const KEYWORDS: &[&[u8]] = &[
+            b"BEGIN",
+            b"COMMIT",
+            b"END",
+            b"ROLLBACK",
+            b"SAVEPOINT",
+            b"RELEASE",
+        ];
+        let controls_transaction = |statement: &str| {
+            let Some(text) = skip_sql_trivia(statement.as_bytes()) else {
+                return false;
+            };
+            let word_len = text
+                .iter()
+                .position(|byte| !byte.is_ascii_alphabetic())
+                .unwrap_or(text.len());
+            KEYWORDS
+                .iter()
+                .any(|keyword| keyword.eq_ignore_ascii_case(&text[..word_len]))
         };
 
-        let create_result = db
-            .exec("CREATE TABLE test_users (id INTEGER PRIMARY KEY, name TEXT, age INTEGER)")
-            .await;
-        assert!(
-            create_result.is_ok(),
-            "CREATE TABLE should execute successfully"
-        );
-        assert!(
-            create_result.unwrap().contains("Rows affected: 0"),
-            "CREATE TABLE should report 0 rows affected"
-        );
+        let mut start = 0;
+        for (end, _) in sql.match_indices(';') {
+            let Ok(candidate) = CString::new(&sql[start..=end]) else {
+                return false;
+            };
+            if unsafe { sqlite3_complete(candidate.as_ptr()) } == 0 {
+                continue;
+            }
+            if controls_transaction(&sql[start..=end]) {
+                return true;
+            }
+            start = end + 1;
+        }
+        controls_transaction(&sql[start..])
+    }
 
-        let insert_result = db
-            .exec("INSERT INTO test_users (name, age) VALUES ('Alice', 25)")
-            .await;
-        assert!(
-            insert_result.is_ok(),
-            "INSERT statement should execute successfully"
-        );
-        assert!(
-            insert_result.unwrap().contains("Rows affected: 1"),
-            "INSERT should report 1 row affected"
-        );
+    /// How many of the shared params `stmt` binds: one per `?`, or up to
+    /// its highest `?N`.
+    fn placeholders_needed(&self, stmt: *mut sqlite3_stmt) -> Result<usize, String> {
+        Ok(match self.detect_placeholder_mode(stmt)? {
+            PlaceholderMode::Plain { count } => count,
+            PlaceholderMode::Numbered { max, .. } => max,
+        })
     }
 
-    #[wasm_bindgen_test]
-    async fn test_select_query_with_results() {
-        let Some(mut db) = get_test_db().await else {
-            return;
-        };
+    fn bind_and_run_each_statement(
+        &self,
+        sql: &str,
+        params: &[serde_json::Value],
+    ) -> Result<QueryOutput, String> {
+        let sql_cstr = CString::new(sql).map_err(|e| format!("Invalid SQL string: {e}"))?;
+        let mut ptr = sql_cstr.as_ptr();
 
-        db.exec("CREATE TABLE test_products (id INTEGER PRIMARY KEY, name TEXT, price REAL)")
-            .await
-            .expect("Create failed");
-        db.exec("INSERT INTO test_products (name, price) VALUES ('Laptop', 999.99)")
-            .await
-            .expect("Insert failed");
-        db.exec("INSERT INTO test_products (name, price) VALUES ('Mouse', 25.50)")
-            .await
-            .expect("Insert failed");
+        let mut select_results: Option<Vec<serde_json::Value>> = None;
+        let mut total_affected_rows = 0;
+        let mut stmt_index: usize = 0;
+        let mut widest = 0;
 
-        let result = db.exec("SELECT * FROM test_products ORDER BY id").await;
-        assert!(result.is_ok(), "SELECT query should execute successfully");
+        loop {
+            let (stmt_opt, tail) = self
+                .prepare_one(ptr)
+                .map_err(|e| format!("Statement {} failed: {}", stmt_index + 1, e))?;
 
-        let json_str = result.unwrap();
-        let parsed: serde_json::Value = serde_json::from_str(&json_str).expect("Invalid JSON");
-        let array = parsed.as_array().expect("Should be array");
-        assert_eq!(array.len(), 2, "Should return exactly 2 rows");
+            if let Some(stmt) = stmt_opt {
+                stmt_index += 1;
+                let failed = |e: String| format!("Statement {stmt_index} failed: {e}");
+                let mut stmt_guard = StmtGuard::new(stmt);
+                let needed = self.placeholders_needed(stmt).map_err(failed)?;
+                widest = widest.max(needed);
+                let bound = params.get(..needed).ok_or_else(|| {
+                    failed(format!(
+                        "Expected {needed} parameters but got {}.",
+                        params.len()
+                    ))
+                })?;
+                // Keep the bound text and blob buffers alive until the step
+                let _buffers = if needed > 0 {
+                    self.bind_params_for_stmt(stmt, bound).map_err(failed)?
+                } else {
+                    BoundBuffers::default()
+                };
+                let (rows_opt, affected) = self
+                    .exec_prepared_statement(stmt_guard.take())
+                    .map_err(failed)?;
+                if rows_opt.is_some() && select_results.is_none() {
+                    select_results = rows_opt;
+                }
+                total_affected_rows += affected;
+            }
 
-        let first = &array[0];
-        assert_eq!(
-            first["name"].as_str().unwrap(),
-            "Laptop",
-            "First product name should be 'Laptop'"
-        );
-        assert_eq!(
-            first["price"].as_f64().unwrap(),
-            999.99,
-            "First product price should be 999.99"
-        );
+            if tail.is_null() || tail == ptr {
+                break;
+            }
+            ptr = tail;
+        }
 
-        let second = &array[1];
-        assert_eq!(
-            second["name"].as_str().unwrap(),
-            "Mouse",
-            "Second product name should be 'Mouse'"
-        );
-        assert_eq!(
-            second["price"].as_f64().unwrap(),
-            25.50,
-            "Second product price should be 25.50"
-        );
+        if widest != params.len() {
+            return Err(format!(
+                "Expected {widest} parameters but got {}.",
+                params.len()
+            ));
+        }
+        if stmt_index == 0 {
+            return Ok(QueryOutput::NoStatements);
+        }
+        Ok(QueryOutput::from_statement((
+            select_results,
+            total_affected_rows,
+        )))
     }
 
-    #[wasm_bindgen_test]
-    async fn test_select_empty_result() {
-        let Some(mut db) = get_test_db().await else {
-            return;
+    /// Execute a query with its per-query options applied.
+    ///
+    /// Options only last for this call. With `timeout_ms` set, the progress
+    /// handler interrupts the statement once the deadline passes and that
+    /// failure is reported as a timeout; any other failure keeps its own
+    /// error even if the deadline has passed by then. The deadline is
+    /// cleared before returning so it never affects the next query.
+    ///
+    /// With `return_rowids` set, the result is a JSON object whose `result`
+    /// holds the usual rows or summary message and whose `rowids` lists every
+    /// rowid the update hook reported, including those of rows written by
+    /// triggers and foreign key actions in other tables. Rows removed by the
+    /// truncate optimization (`DELETE` without `WHERE`) and `WITHOUT ROWID`
+    /// tables are not reported.
+    /// `return_result_code` and `return_notices` produce the same object,
+    /// adding `resultCode`, the code of the last step that finished a
+    /// statement (`SQLITE_DONE`, or `SQLITE_OK` when nothing ran), and
+    /// `notices`, the automatic indexes and triggers the trace hook saw while
+    /// the query ran. `return_expanded_sql` adds `expandedSql`, the text of
+    /// every statement run with its bound values written in as literals.
+    /// `return_statement_counts` adds `statementCounts`, one
+    /// `{ statementIndex, rowsAffected, isQuery }` entry per statement run.
+    /// `serialize_budget_ms` bounds the time spent rendering the result.
+    pub async fn exec_with_options(
+        &mut self,
+        sql: &str,
+        params: Option<Vec<serde_json::Value>>,
+        options: &QueryOptions,
+    ) -> Result<String, String> {
+        self.hooks
+            .deadline_ms
+            .set(options.timeout_ms.map(|ms| js_sys::Date::now() + ms));
+        self.hooks.deadline_hit.set(false);
+        if options.return_rowids.unwrap_or(false) {
+            self.hooks.rowids.borrow_mut().replace(Vec::new());
+        }
+        if options.return_notices.unwrap_or(false) {
+            self.hooks.notices.borrow_mut().replace(Vec::new());
+            self.set_trace_hook(true);
+        }
+        if options.return_expanded_sql.unwrap_or(false) {
+            self.expanded_sql.borrow_mut().replace(Vec::new());
+        }
+        if options.return_statement_counts.unwrap_or(false) {
+            self.statement_counts.borrow_mut().replace(Vec::new());
+        }
+        self.last_step_code.set(SQLITE_OK);
+        self.last_error.replace(None);
+        self.durable_changes.set(self.hooks.changes.borrow().len());
+        self.query_options = options.clone();
+
+        let result = match params {
+            Some(params) => self.run_with_params(sql, params).await,
+            None => self.run_statements(sql).await,
         };
 
-        db.exec("CREATE TABLE empty_table (id INTEGER)")
-            .await
-            .expect("Create failed");
+        self.hooks.deadline_ms.take();
+        let timed_out = self.hooks.deadline_hit.take();
+        let rowids = self.hooks.rowids.borrow_mut().take();
+        let notices = self.hooks.notices.borrow_mut().take();
+        if notices.is_some() {
+            self.set_trace_hook(false);
+        }
+        let expanded_sql = self.expanded_sql.borrow_mut().take();
+        let statement_counts = self.statement_counts.borrow_mut().take();
+        self.query_options = QueryOptions::default();
+        if result.is_err() {
+            // SQLite rolled back the failing statement's writes, along with
+            // any transaction the failure ended; earlier statements that ran
+            // outside a transaction stay committed.
+            self.hooks
+                .changes
+                .borrow_mut()
+                .truncate(self.durable_changes.get());
+            if let Some(error) = self.last_error.borrow_mut().as_mut() {
+                error.rebase(sql);
+            }
+        }
+        let output = match result {
+            Err(_) if timed_out => return Err(WORKER_ERROR_TYPE_QUERY_TIMEOUT.to_string()),
+            result => result?,
+        };
 
-        let result = db.exec("SELECT * FROM empty_table").await;
-        assert!(
-            result.is_ok(),
-            "SELECT from empty table should execute successfully"
-        );
-        assert_eq!(
-            result.unwrap(),
-            "[]",
-            "Empty SELECT should return empty JSON array"
-        );
+        let result_code = options
+            .return_result_code
+            .unwrap_or(false)
+            .then(|| self.last_step_code.get());
+        let budget_ms = options.serialize_budget_ms;
+        // A profiled result is an object so the DB worker can add `timing`.
+        let profile = options.profile.unwrap_or(false);
+        if rowids.is_none()
+            && result_code.is_none()
+            && notices.is_none()
+            && expanded_sql.is_none()
+            && statement_counts.is_none()
+            && !profile
+        {
+            return match output {
+                QueryOutput::Rows(rows) => to_json_within(&rows, budget_ms),
+                other => other.render(),
+            };
+        }
+        let mut envelope = serde_json::Map::new();
+        envelope.insert("result".to_string(), output.into_json()?);
+        if let Some(rowids) = rowids {
+            envelope.insert("rowids".to_string(), rowids.into());
+        }
+        if let Some(code) = result_code {
+            envelope.insert("resultCode".to_string(), code.into());
+        }
+        if let Some(notices) = notices {
+            envelope.insert("notices".to_string(), notices.into());
+        }
+        if let Some(expanded_sql) = expanded_sql {
+            envelope.insert("expandedSql".to_string(), expanded_sql.into());
+        }
+        if let Some(counts) = statement_counts {
+            envelope.insert("statementCounts".to_string(), counts.into());
+        }
+        to_json_within(&envelope, budget_ms)
     }
 
-    #[wasm_bindgen_test]
-    async fn test_integer_column_handling() {
-        let Some(mut db) = get_test_db().await else {
-            return;
-        };
-
-        db.exec("CREATE TABLE test_ints (small_int INTEGER, big_int INTEGER)")
-            .await
-            .expect("Create failed");
-        db.exec("INSERT INTO test_ints VALUES (42, 9223372036854775807)")
-            .await
-            .expect("Insert failed");
+    /// Register the trace hook feeding `notices`, or remove it. It is only
+    /// attached while a query collects notices so other queries pay nothing.
+    fn set_trace_hook(&self, enabled: bool) {
+        let hooks_ptr = &*self.hooks as *const ConnectionHooks as *mut c_void;
+        unsafe {
+            if enabled {
+                sqlite3_trace_v2(
+                    self.db,
+                    SQLITE_TRACE_STMT | SQLITE_TRACE_PROFILE,
+                    Some(trace_hook),
+                    hooks_ptr,
+                );
+            } else {
+                sqlite3_trace_v2(self.db, 0, None, std::ptr::null_mut());
+            }
+        }
+    }
 
-        let result = db
-            .exec("SELECT * FROM test_ints")
-            .await
-            .expect("Select failed");
-        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
-        let array = parsed.as_array().expect("Should be array");
-        let row = &array[0];
+    /// Prepare a single row-returning statement and keep it open so its rows
+    /// can be read `chunk_size` at a time with `query_chunk`. Returns
+    /// `{"cursor": id}`. Cells are truncated to `max_cell_bytes` like
+    /// `QueryOptions::max_cell_bytes` does. The open statement holds a read
+    /// transaction until the cursor is exhausted, closed or idle for longer
+    /// than `CURSOR_IDLE_TIMEOUT_MS`.
+    pub fn open_cursor(
+        &mut self,
+        sql: &str,
+        params: Option<Vec<serde_json::Value>>,
+        chunk_size: u32,
+        max_cell_bytes: Option<u32>,
+    ) -> Result<String, String> {
+        let sql_cstr = CString::new(sql).map_err(|e| format!("Invalid SQL string: {e}"))?;
+        let params = params.unwrap_or_default();
+        let Some((stmt, buffers)) = self.prepare_bound(&sql_cstr, &params)? else {
+            return Err("Cannot stream an empty statement.".to_string());
+        };
+        if unsafe { sqlite3_column_count(stmt.stmt) } == 0 {
+            return Err("Only statements that return rows can be streamed.".to_string());
+        }
 
-        assert_eq!(
-            row["small_int"].as_i64().unwrap(),
-            42,
-            "Small integer should be 42"
-        );
-        assert_eq!(
-            row["big_int"].as_i64().unwrap(),
-            9223372036854775807,
-            "Large integer should be max i64 value"
+        let id = self.next_cursor_id;
+        self.next_cursor_id = self.next_cursor_id.wrapping_add(1);
+        self.cursors.insert(
+            id,
+            Cursor {
+                stmt,
+                chunk_size: chunk_size.max(1) as usize,
+                columns: None,
+                max_cell_bytes: max_cell_bytes.map(|max| max as usize),
+                last_used_ms: now_ms(),
+                _buffers: buffers,
+            },
         );
+        Ok(serde_json::json!({ "cursor": id }).to_string())
     }
 
-    #[wasm_bindgen_test]
+    /// Step an open cursor for its next chunk of rows, returned as
+    /// `{"rows": [...], "done": bool}`. The cursor is finalized once `done`
+    /// is reported or stepping fails.
+    pub fn query_chunk(&mut self, cursor: u32) -> Result<String, String> {
+        let Some(mut open) = self.cursors.remove(&cursor) else {
+            return Err(format!("Unknown cursor: {cursor}"));
+        };
+        let stmt = open.stmt.stmt;
+
+        let mut rows = Vec::with_capacity(open.chunk_size);
+        let mut done = false;
+        while rows.len() < open.chunk_size {
+            match unsafe { sqlite3_step(stmt) } {
+                SQLITE_ROW => {
+                    let names = open.columns.get_or_insert_with(|| self.column_names(stmt));
+                    rows.push(Self::read_row(
+                        stmt,
+                        names,
+                        open.max_cell_bytes,
+                        false,
+                        false,
+                    ));
+                }
+                SQLITE_DONE => {
+                    done = true;
+                    break;
+                }
+                other => return Err(self.step_error(other)),
+            }
+        }
+
+        if !done {
+            open.last_used_ms = now_ms();
+            self.cursors.insert(cursor, open);
+        }
+        let chunk = serde_json::json!({ "rows": rows, "done": done });
+        serde_json::to_string(&chunk).map_err(|e| format!("JSON serialization error: {e}"))
+    }
+
+    /// Finalize a cursor before it is exhausted. Closing one that already
+    /// finished is a no-op.
+    pub fn close_cursor(&mut self, cursor: u32) -> Result<String, String> {
+        self.cursors.remove(&cursor);
+        Ok("Cursor closed.".to_string())
+    }
+
+    /// Finalize every cursor not read for `idle_ms` and return how many were
+    /// closed.
+    pub fn close_idle_cursors(&mut self, idle_ms: f64) -> usize {
+        let now = now_ms();
+        let before = self.cursors.len();
+        self.cursors
+            .retain(|_, cursor| now - cursor.last_used_ms < idle_ms);
+        before - self.cursors.len()
+    }
+
+    /// Make every committed write durable in OPFS.
+    ///
+    /// Commits are synced by the VFS as they happen, so this only has work to
+    /// do in WAL mode, where the log is checkpointed back into the database file.
+    pub async fn flush(&mut self) -> Result<String, String> {
+        self.refresh_transaction_state();
+        if self.in_transaction {
+            return Err("Cannot flush while a transaction is open.".to_string());
+        }
+
+        let (rows, _) = self.exec_single_statement("PRAGMA journal_mode").await?;
+        let journal_mode = rows
+            .as_ref()
+            .and_then(|rows| rows.first())
+            .and_then(|row| row.get("journal_mode"))
+            .and_then(|mode| mode.as_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if journal_mode == "wal" {
+            self.exec_single_statement("PRAGMA wal_checkpoint(TRUNCATE)")
+                .await?;
+        }
+
+        Ok("Flush completed.".to_string())
+    }
+
+    /// Read a single integer pragma such as `page_count`.
+    pub(crate) async fn pragma_i64(&self, name: &str) -> Result<i64, String> {
+        let (rows, _) = self
+            .exec_single_statement(&format!("PRAGMA {name}"))
+            .await?;
+        rows.as_ref()
+            .and_then(|rows| rows.first())
+            .and_then(|row| row.get(name))
+            .and_then(|value| value.as_i64())
+            .ok_or_else(|| format!("PRAGMA {name} returned no value"))
+    }
+
+    /// Read `PRAGMA page_size`, first setting it to `value` when given. The
+    /// size can only change before the first table is created; a file that
+    /// already has pages is rebuilt with `VACUUM` so the new size applies.
+    pub async fn page_size(&mut self, value: Option<u32>) -> Result<String, String> {
+        if let Some(value) = value {
+            if !value.is_power_of_two() || !(512..=65536).contains(&value) {
+                return Err("page_size must be a power of two from 512 to 65536".to_string());
+            }
+            self.refresh_transaction_state();
+            if self.in_transaction {
+                return Err("Cannot change page_size while a transaction is open.".to_string());
+            }
+            let (rows, _) = self
+                .exec_single_statement("SELECT count(*) AS objects FROM sqlite_schema")
+                .await?;
+            let objects = rows
+                .as_ref()
+                .and_then(|rows| rows.first())
+                .and_then(|row| row.get("objects"))
+                .and_then(|value| value.as_i64())
+                .unwrap_or_default();
+            if objects > 0 {
+                return Err("page_size must be set before the first table is created".to_string());
+            }
+            self.exec_single_statement(&format!("PRAGMA page_size = {value}"))
+                .await?;
+            if self.pragma_i64("page_count").await? > 0 {
+                self.exec_single_statement("VACUUM").await?;
+            }
+        }
+        Ok(self.pragma_i64("page_size").await?.to_string())
+    }
+
+    /// Read a header pragma such as `user_version`, first setting it to
+    /// `value` when given. The write runs in its own transaction unless one
+    /// is already open, in which case it commits or rolls back with it.
+    pub async fn header_value(
+        &mut self,
+        pragma: HeaderPragma,
+        value: Option<i32>,
+    ) -> Result<String, String> {
+        let name = pragma.name();
+        if let Some(value) = value {
+            self.refresh_transaction_state();
+            let own_transaction = !self.in_transaction;
+            if own_transaction {
+                self.exec_single_statement("BEGIN IMMEDIATE").await?;
+            }
+            let set = self
+                .exec_single_statement(&format!("PRAGMA {name} = {value}"))
+                .await;
+            let finished = match set {
+                Ok(_) if own_transaction => self.exec_single_statement("COMMIT").await.map(|_| ()),
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    if own_transaction {
+                        let _ = self.exec_single_statement("ROLLBACK").await;
+                    }
+                    Err(e)
+                }
+            };
+            self.refresh_transaction_state();
+            finished?;
+        }
+        Ok(self.pragma_i64(name).await?.to_string())
+    }
+
+    /// Rebuild the database file with `VACUUM` so pages freed by deletes are
+    /// returned to OPFS. Reports the page counts before and after along with
+    /// an estimate of the bytes freed.
+    pub async fn vacuum(&mut self) -> Result<String, String> {
+        self.refresh_transaction_state();
+        if self.in_transaction {
+            return Err("Cannot vacuum while a transaction is open.".to_string());
+        }
+
+        let size_before = self.pragma_i64("page_size").await?;
+        let pages_before = self.pragma_i64("page_count").await?;
+        self.exec_single_statement("VACUUM").await?;
+        let size_after = self.pragma_i64("page_size").await?;
+        let pages_after = self.pragma_i64("page_count").await?;
+
+        let freed = (pages_before * size_before - pages_after * size_after).max(0);
+        let report = serde_json::json!({
+            "pageCountBefore": pages_before,
+            "pageCountAfter": pages_after,
+            "bytesFreed": freed,
+        });
+        serde_json::to_string(&report).map_err(|e| format!("JSON serialization error: {e}"))
+    }
+
+    /// Size of the database file in bytes, `page_count * page_size`. Pages
+    /// freed by deletes still count until `VACUUM` returns them.
+    pub async fn database_size(&self) -> Result<String, String> {
+        let page_size = self.pragma_i64("page_size").await?;
+        let page_count = self.pragma_i64("page_count").await?;
+        Ok((page_size * page_count).to_string())
+    }
+
+    /// Run `statements` in order between `BEGIN IMMEDIATE` and `COMMIT`. The
+    /// first failure rolls the whole batch back and names the statement's
+    /// index; on success the result is a JSON array holding each statement's
+    /// rows or summary message.
+    ///
+    /// With `chunk_size`, the transaction is committed and reopened after
+    /// every that many statements, so a failure only rolls back the chunk
+    /// it happened in and the error reports how many were committed.
+    pub async fn transaction(
+        &mut self,
+        statements: Vec<BatchStatement>,
+        chunk_size: Option<u32>,
+    ) -> Result<String, String> {
+        self.refresh_transaction_state();
+        if self.in_transaction {
+            return Err("Cannot start a transaction while one is open.".to_string());
+        }
+        let chunk_size = chunk_size.map(|size| size.max(1) as usize);
+
+        self.exec_single_statement("BEGIN IMMEDIATE").await?;
+        let mut results = Vec::with_capacity(statements.len());
+        let mut committed = 0;
+        // Changes up to here are durable: recorded before this call, or by
+        // chunks already committed. A failure only discards the rest.
+        let mut durable_changes = self.hooks.changes.borrow().len();
+        for (index, statement) in statements.into_iter().enumerate() {
+            let mut chunk = Ok(());
+            if chunk_size.is_some_and(|size| index > 0 && index % size == 0) {
+                chunk = self.commit_chunk().await;
+                if chunk.is_ok() {
+                    committed = index;
+                    durable_changes = self.hooks.changes.borrow().len();
+                }
+            }
+            let output = match chunk {
+                Ok(()) => match statement.params {
+                    Some(params) => self.run_with_params(&statement.sql, params).await,
+                    None => self.run_statements(&statement.sql).await,
+                },
+                Err(e) => Err(e),
+            };
+            match output.and_then(QueryOutput::into_json) {
+                Ok(value) => results.push(value),
+                Err(e) => {
+                    self.rollback_if_in_transaction().await;
+                    self.refresh_transaction_state();
+                    self.hooks.changes.borrow_mut().truncate(durable_changes);
+                    let position = index + 1;
+                    return Err(match chunk_size {
+                        Some(_) => format!(
+                            "Statement {position} failed after {committed} statements were committed: {e}"
+                        ),
+                        None => format!("Statement {position} failed: {e}"),
+                    });
+                }
+            }
+        }
+        let committed = self.exec_single_statement("COMMIT").await;
+        if committed.is_err() {
+            self.rollback_if_in_transaction().await;
+            self.hooks.changes.borrow_mut().truncate(durable_changes);
+        }
+        self.refresh_transaction_state();
+        committed?;
+        serde_json::to_string_pretty(&results).map_err(|e| format!("JSON serialization error: {e}"))
+    }
+
+    /// Commit the open chunk of a chunked `transaction` and start the next.
+    async fn commit_chunk(&self) -> Result<(), String> {
+        self.exec_single_statement("COMMIT").await?;
+        self.exec_single_statement("BEGIN IMMEDIATE").await?;
+        Ok(())
+    }
+
+    /// Report the plan SQLite picks for the first statement in `sql` as
+    /// `[{ id, parent, detail }]`. Only the `EXPLAIN QUERY PLAN` form is
+    /// stepped, so the statement itself never runs.
+    pub async fn explain(
+        &self,
+        sql: &str,
+        params: Option<Vec<serde_json::Value>>,
+    ) -> Result<String, String> {
+        let plan_sql = format!("EXPLAIN QUERY PLAN {}", sql.trim());
+        let (rows, _) = match params {
+            Some(params) if !params.is_empty() => {
+                self.exec_single_statement_with_params(&plan_sql, params)
+                    .await?
+            }
+            _ => self.exec_single_statement(&plan_sql).await?,
+        };
+        let steps: Vec<serde_json::Value> = rows
+            .unwrap_or_default()
+            .iter()
+            .map(|row| {
+                serde_json::json!({
+                    "id": row["id"],
+                    "parent": row["parent"],
+                    "detail": row["detail"],
+                })
+            })
+            .collect();
+        serde_json::to_string(&steps).map_err(|e| format!("JSON serialization error: {e}"))
+    }
+
+    /// Prepare each statement in `sql` and finalize it straight away,
+    /// reporting `{ valid: true }` or `{ valid: false, error, statementIndex }`
+    /// for the first one SQLite rejects. Nothing is stepped, so statements
+    /// are checked against the current schema: one naming a table that an
+    /// earlier statement in `sql` would create is reported as invalid.
+    pub fn validate(&self, sql: &str) -> Result<String, String> {
+        let sql_cstr = CString::new(sql).map_err(|e| format!("Invalid SQL string: {e}"))?;
+        let mut ptr = sql_cstr.as_ptr();
+        let mut statement_index: usize = 0;
+        let report = loop {
+            let (stmt_opt, tail) = match self.prepare_one(ptr) {
+                Ok(v) => v,
+                Err(error) => {
+                    if let Some(last_error) = self.last_error.borrow_mut().as_mut() {
+                        last_error.rebase(sql);
+                    }
+                    break serde_json::json!({
+                        "valid": false,
+                        "error": error,
+                        "statementIndex": statement_index,
+                    });
+                }
+            };
+            if let Some(stmt) = stmt_opt {
+                drop(StmtGuard::new(stmt));
+                statement_index += 1;
+            }
+            if tail.is_null() || tail == ptr || Self::is_trivia_tail_only(tail) {
+                break serde_json::json!({ "valid": true });
+            }
+            ptr = tail;
+        };
+        serde_json::to_string(&report).map_err(|e| format!("JSON serialization error: {e}"))
+    }
+
+    /// Run `PRAGMA integrity_check` and report `{ ok, problems }`, where
+    /// `problems` lists every message SQLite returned other than "ok".
+    pub async fn integrity_check(&self) -> Result<String, String> {
+        let (rows, _) = self.exec_single_statement("PRAGMA integrity_check").await?;
+        let problems: Vec<String> = rows
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|row| row.get("integrity_check")?.as_str().map(str::to_string))
+            .filter(|message| message != "ok")
+            .collect();
+        let report = serde_json::json!({
+            "ok": problems.is_empty(),
+            "problems": problems,
+        });
+        serde_json::to_string(&report).map_err(|e| format!("JSON serialization error: {e}"))
+    }
+
+    /// Write a compacted copy of the database with `VACUUM INTO`, to the OPFS
+    /// file a connection opened with `name` uses. SQLite refuses to overwrite
+    /// an existing non-empty file or to run inside a transaction.
+    pub async fn backup_into(&self, name: &str) -> Result<String, String> {
+        let target = format!("opfs-sahpool:{}", sanitize_db_filename(name));
+        self.exec_single_statement_with_params(
+            "VACUUM INTO ?",
+            vec![serde_json::Value::String(target)],
+        )
+        .await?;
+        Ok("Backup completed.".to_string())
+    }
+
+    /// Replace the database contents with a SQLite file image. The image is
+    /// loaded and checked in memory first, so an invalid one leaves the
+    /// database untouched.
+    pub async fn import(&mut self, image: &[u8]) -> Result<String, String> {
+        if !image.starts_with(SQLITE_HEADER_MAGIC) {
+            return Err("Invalid database image: missing SQLite header".to_string());
+        }
+        self.refresh_transaction_state();
+        if self.in_transaction {
+            return Err("Cannot import while a transaction is open.".to_string());
+        }
+
+        let source = Self::open_image(image)?;
+        let (rows, _) = source
+            .exec_single_statement("PRAGMA quick_check")
+            .await
+            .map_err(|e| format!("Invalid database image: {e}"))?;
+        let check = rows
+            .as_ref()
+            .and_then(|rows| rows.first())
+            .and_then(|row| row.get("quick_check"))
+            .and_then(|value| value.as_str());
+        if check != Some("ok") {
+            return Err(format!(
+                "Invalid database image: {}",
+                check.unwrap_or("integrity check failed")
+            ));
+        }
+
+        let main = CString::new("main").expect("static schema name");
+        let backup =
+            unsafe { sqlite3_backup_init(self.db, main.as_ptr(), source.db, main.as_ptr()) };
+        if backup.is_null() {
+            return Err(format!(
+                "Failed to import database: {}",
+                self.sqlite_errmsg()
+            ));
+        }
+        let rc = unsafe { sqlite3_backup_step(backup, -1) };
+        unsafe { sqlite3_backup_finish(backup) };
+        if rc != SQLITE_DONE {
+            return Err(format!(
+                "Failed to import database: {}",
+                self.sqlite_errmsg()
+            ));
+        }
+        Ok("Import completed.".to_string())
+    }
+
+    /// Open a private in-memory connection over a copy of `image`.
+    pub(crate) fn open_image(image: &[u8]) -> Result<SQLiteDatabase, String> {
+        let mut db: *mut sqlite3 = std::ptr::null_mut();
+        let memory = CString::new(":memory:").expect("static filename");
+        let ret = unsafe {
+            sqlite3_open_v2(
+                memory.as_ptr(),
+                &mut db as *mut _,
+                SQLITE_OPEN_READWRITE | SQLITE_OPEN_CREATE,
+                std::ptr::null(),
+            )
+        };
+        // Wrap the handle first so it is closed on every error path.
+        let source = SQLiteDatabase {
+            db,
+            in_transaction: false,
+            hooks: Box::default(),
+            query_options: QueryOptions::default(),
+            cursors: HashMap::new(),
+            next_cursor_id: 0,
+            last_step_code: Cell::new(SQLITE_OK),
+            durable_changes: Cell::new(0),
+            expanded_sql: RefCell::new(None),
+            statement_counts: RefCell::new(None),
+            last_error: RefCell::new(None),
+        };
+        if ret != SQLITE_OK {
+            return Err(format!(
+                "Failed to open import buffer: {}",
+                source.sqlite_errmsg()
+            ));
+        }
+
+        let size = image.len() as sqlite3_int64;
+        let buffer = unsafe { sqlite3_malloc64(image.len() as sqlite3_uint64) } as *mut u8;
+        if buffer.is_null() {
+            return Err("Failed to allocate import buffer".to_string());
+        }
+        unsafe { std::ptr::copy_nonoverlapping(image.as_ptr(), buffer, image.len()) };
+        let schema = CString::new("main").expect("static schema name");
+        // SQLite owns the buffer from here on, even when deserialize fails.
+        let rc = unsafe {
+            sqlite3_deserialize(
+                source.db,
+                schema.as_ptr(),
+                buffer,
+                size,
+                size,
+                SQLITE_DESERIALIZE_FREEONCLOSE | SQLITE_DESERIALIZE_RESIZEABLE,
+            )
+        };
+        if rc != SQLITE_OK {
+            return Err(format!(
+                "Invalid database image: {}",
+                source.sqlite_errmsg()
+            ));
+        }
+        Ok(source)
+    }
+
+    /// Serialize the main database into the same bytes as its file on disk.
+    ///
+    /// The image is a snapshot of committed data: it is copied in one
+    /// synchronous call, so no queued write can land part-way through, and
+    /// it is refused while a transaction is open rather than capture
+    /// uncommitted changes.
+    pub fn export(&self) -> Result<Vec<u8>, String> {
+        if unsafe { sqlite3_get_autocommit(self.db) } == 0 {
+            return Err("Cannot export while a transaction is open.".to_string());
+        }
+        let schema = CString::new("main").expect("static schema name");
+        let mut size: sqlite3_int64 = 0;
+        let ptr = unsafe { sqlite3_serialize(self.db, schema.as_ptr(), &mut size, 0) };
+        if ptr.is_null() {
+            // An empty database has no pages to copy.
+            if size == 0 {
+                return Ok(Vec::new());
+            }
+            return Err(format!(
+                "Failed to export database: {}",
+                self.sqlite_errmsg()
+            ));
+        }
+        let bytes = unsafe { std::slice::from_raw_parts(ptr, size as usize) }.to_vec();
+        unsafe { sqlite3_free(ptr as *mut c_void) };
+        Ok(bytes)
+    }
+}
+
+impl Drop for SQLiteDatabase {
+    fn drop(&mut self) {
+        // Finalize open cursors so the connection can close.
+        self.cursors.clear();
+        if !self.db.is_null() {
+            unsafe {
+                // Detach the hooks first so nothing reaches `self.hooks` while
+                // the connection shuts down.
+                sqlite3_progress_handler(self.db, 0, None, std::ptr::null_mut());
+                sqlite3_update_hook(self.db, None, std::ptr::null_mut());
+                sqlite3_commit_hook(self.db, None, std::ptr::null_mut());
+                sqlite3_rollback_hook(self.db, None, std::ptr::null_mut());
+                sqlite3_trace_v2(self.db, 0, None, std::ptr::null_mut());
+                sqlite3_close(self.db);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, target_family = "wasm"))]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    async fn test_opfs_initialization_success() {
+        let result = SQLiteDatabase::initialize_opfs("testdb").await;
+        if result.is_err() {
+            return;
+        }
+        assert!(
+            result.is_ok(),
+            "OPFS database initialization should succeed"
+        );
+        let db = result.unwrap();
+        assert!(
+            !db.db.is_null(),
+            "Database pointer should not be null after initialization"
+        );
+    }
+
+    async fn get_test_db() -> Option<SQLiteDatabase> {
+        (SQLiteDatabase::initialize_opfs("testdb").await).ok()
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_create_table_and_insert() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        let create_result = db
+            .exec("CREATE TABLE test_users (id INTEGER PRIMARY KEY, name TEXT, age INTEGER)")
+            .await;
+        assert!(
+            create_result.is_ok(),
+            "CREATE TABLE should execute successfully"
+        );
+        assert!(
+            create_result.unwrap().contains("Rows affected: 0"),
+            "CREATE TABLE should report 0 rows affected"
+        );
+
+        let insert_result = db
+            .exec("INSERT INTO test_users (name, age) VALUES ('Alice', 25)")
+            .await;
+        assert!(
+            insert_result.is_ok(),
+            "INSERT statement should execute successfully"
+        );
+        assert!(
+            insert_result.unwrap().contains("Rows affected: 1"),
+            "INSERT should report 1 row affected"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_select_query_with_results() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        db.exec("CREATE TABLE test_products (id INTEGER PRIMARY KEY, name TEXT, price REAL)")
+            .await
+            .expect("Create failed");
+        db.exec("INSERT INTO test_products (name, price) VALUES ('Laptop', 999.99)")
+            .await
+            .expect("Insert failed");
+        db.exec("INSERT INTO test_products (name, price) VALUES ('Mouse', 25.50)")
+            .await
+            .expect("Insert failed");
+
+        let result = db.exec("SELECT * FROM test_products ORDER BY id").await;
+        assert!(result.is_ok(), "SELECT query should execute successfully");
+
+        let json_str = result.unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&json_str).expect("Invalid JSON");
+        let array = parsed.as_array().expect("Should be array");
+        assert_eq!(array.len(), 2, "Should return exactly 2 rows");
+
+        let first = &array[0];
+        assert_eq!(
+            first["name"].as_str().unwrap(),
+            "Laptop",
+            "First product name should be 'Laptop'"
+        );
+        assert_eq!(
+            first["price"].as_f64().unwrap(),
+            999.99,
+            "First product price should be 999.99"
+        );
+
+        let second = &array[1];
+        assert_eq!(
+            second["name"].as_str().unwrap(),
+            "Mouse",
+            "Second product name should be 'Mouse'"
+        );
+        assert_eq!(
+            second["price"].as_f64().unwrap(),
+            25.50,
+            "Second product price should be 25.50"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_select_empty_result() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        db.exec("CREATE TABLE empty_table (id INTEGER)")
+            .await
+            .expect("Create failed");
+
+        let result = db.exec("SELECT * FROM empty_table").await;
+        assert!(
+            result.is_ok(),
+            "SELECT from empty table should execute successfully"
+        );
+        assert_eq!(
+            result.unwrap(),
+            "[]",
+            "Empty SELECT should return empty JSON array"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_integer_column_handling() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        db.exec("CREATE TABLE test_ints (small_int INTEGER, big_int INTEGER)")
+            .await
+            .expect("Create failed");
+        db.exec("INSERT INTO test_ints VALUES (42, 9223372036854775807)")
+            .await
+            .expect("Insert failed");
+
+        let result = db
+            .exec("SELECT * FROM test_ints")
+            .await
+            .expect("Select failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        let array = parsed.as_array().expect("Should be array");
+        let row = &array[0];
+
+        assert_eq!(
+            row["small_int"].as_i64().unwrap(),
+            42,
+            "Small integer should be 42"
+        );
+        assert_eq!(
+            row["big_int"].as_i64().unwrap(),
+            9223372036854775807,
+            "Large integer should be max i64 value"
+        );
+    }
+
+    #[wasm_bindgen_test]
     async fn test_float_column_handling() {
         let Some(mut db) = get_test_db().await else {
             return;
         };
-
-        db.exec("CREATE TABLE test_floats (pi REAL, negative REAL)")
+
+        db.exec("CREATE TABLE test_floats (pi REAL, negative REAL)")
+            .await
+            .expect("Create failed");
+        db.exec("INSERT INTO test_floats VALUES (3.14159, -2.71828)")
+            .await
+            .expect("Insert failed");
+
+        let result = db
+            .exec("SELECT * FROM test_floats")
+            .await
+            .expect("Select failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        let array = parsed.as_array().expect("Should be array");
+        let row = &array[0];
+
+        assert!(
+            (row["pi"].as_f64().unwrap() - std::f64::consts::PI).abs() < 0.00001,
+            "Pi should be approximately 3.14159"
+        );
+        assert!(
+            (row["negative"].as_f64().unwrap() - (-std::f64::consts::E)).abs() < 0.00001,
+            "Negative float should be approximately -2.71828"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_text_column_handling() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        db.exec("CREATE TABLE test_text (message TEXT, empty TEXT, null_val TEXT)")
+            .await
+            .expect("Create failed");
+        db.exec("INSERT INTO test_text VALUES ('Hello World', '', NULL)")
+            .await
+            .expect("Insert failed");
+
+        let result = db
+            .exec("SELECT * FROM test_text")
+            .await
+            .expect("Select failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        let array = parsed.as_array().expect("Should be array");
+        let row = &array[0];
+
+        assert_eq!(
+            row["message"].as_str().unwrap(),
+            "Hello World",
+            "Text column should contain 'Hello World'"
+        );
+        assert_eq!(
+            row["empty"].as_str().unwrap(),
+            "",
+            "Empty text column should be empty string"
+        );
+        assert!(
+            row["null_val"].is_null(),
+            "NULL text column should be null in JSON"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_exec_with_params_accepts_a_trailing_semicolon() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+        let expected = db
+            .exec_with_params("SELECT ? AS v", vec![json!(7)])
+            .await
+            .expect("Query failed");
+        for sql in [
+            "SELECT ? AS v;",
+            "SELECT ? AS v ;",
+            "SELECT ? AS v;\n-- done\n",
+            "SELECT ? AS v;;",
+        ] {
+            let result = db.exec_with_params(sql, vec![json!(7)]).await;
+            assert_eq!(result.as_deref(), Ok(expected.as_str()), "{sql}");
+        }
+
+        let second = db
+            .exec_with_params("SELECT ? AS v; SELECT 2", vec![json!(7)])
+            .await;
+        assert!(second
+            .unwrap_err()
+            .contains("Parameterized queries must contain a single statement."));
+    }
+
+    // exec_with_params integration tests
+    // 1) Positional '?' bindings with multiple types
+    #[wasm_bindgen_test]
+    async fn test_exec_with_params_positional_multiple_types() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        db.exec(
+            "CREATE TABLE params_positional (
+                null_col TEXT,
+                bool_col INTEGER,
+                int_col INTEGER,
+                float_col REAL,
+                text_col TEXT
+            )",
+        )
+        .await
+        .expect("Create failed");
+
+        // Insert with plain positional placeholders
+        let insert_res = db
+            .exec_with_params(
+                "INSERT INTO params_positional (null_col, bool_col, int_col, float_col, text_col) VALUES (?, ?, ?, ?, ?)",
+                vec![json!(null), json!(true), json!(42), json!(3.5), json!("hello")],
+            )
+            .await;
+        assert!(insert_res.is_ok(), "INSERT with params should succeed");
+        assert!(
+            insert_res.unwrap().contains("Rows affected: 1"),
+            "INSERT should report 1 row affected"
+        );
+
+        // Select using positional binding as well
+        let select_json = db
+            .exec_with_params(
+                "SELECT null_col, bool_col, int_col, float_col, text_col FROM params_positional WHERE int_col = ?",
+                vec![json!(42)],
+            )
+            .await
+            .expect("SELECT with params failed");
+        let parsed: serde_json::Value = serde_json::from_str(&select_json).expect("Invalid JSON");
+        let rows = parsed.as_array().expect("Expected array JSON");
+        assert_eq!(rows.len(), 1, "Should return one matching row");
+        let row = &rows[0];
+        assert!(row["null_col"].is_null(), "Null round-trips as null");
+        assert_eq!(row["bool_col"].as_i64().unwrap(), 1, "true -> 1");
+        assert_eq!(row["int_col"].as_i64().unwrap(), 42);
+        assert!((row["float_col"].as_f64().unwrap() - 3.5).abs() < 1e-9);
+        assert_eq!(row["text_col"].as_str().unwrap(), "hello");
+    }
+
+    // 2) Numbered placeholders '?N' including gap detection and duplicates
+    #[wasm_bindgen_test]
+    async fn test_exec_with_params_numbered_duplicate_indices_allowed() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        db.exec("CREATE TABLE numbered_dup (a INTEGER, b INTEGER)")
+            .await
+            .expect("Create failed");
+
+        // Duplicate ?1 should reuse the same bound value
+        let res = db
+            .exec_with_params(
+                "INSERT INTO numbered_dup (a, b) VALUES (?1, ?1)",
+                vec![json!(7)],
+            )
+            .await;
+        assert!(res.is_ok(), "Duplicate numbered index should succeed");
+
+        let out = db
+            .exec("SELECT a, b FROM numbered_dup")
+            .await
+            .expect("Select failed");
+        let parsed: serde_json::Value = serde_json::from_str(&out).expect("Invalid JSON");
+        let rows = parsed.as_array().expect("Expected array JSON");
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["a"].as_i64().unwrap(), 7);
+        assert_eq!(rows[0]["b"].as_i64().unwrap(), 7);
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_exec_with_params_numbered_gap_error() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        // Use ?1 and ?3 with a missing ?2 to trigger continuity error
+        let err = db
+            .exec_with_params("SELECT ?1, ?3", vec![json!(10), json!(30)])
+            .await
+            .unwrap_err();
+        assert!(
+            err.contains("Missing parameter index ?2"),
+            "Should report missing index in numbered placeholders"
+        );
+    }
+
+    // 3) BLOB object and bigint-as-string handling
+    #[wasm_bindgen_test]
+    async fn test_exec_with_params_blob_and_bigint() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        db.exec("CREATE TABLE binint_test (b BLOB, bi INTEGER)")
+            .await
+            .expect("Create failed");
+
+        // Base64 for bytes: "Rust" -> 52 75 73 74
+        let blob_b64 = base64::engine::general_purpose::STANDARD.encode(b"Rust");
+        let big_str = "9223372036854775807"; // i64::MAX
+
+        let res = db
+            .exec_with_params(
+                "INSERT INTO binint_test (b, bi) VALUES (?, ?)",
+                vec![
+                    json!({"__type":"blob","base64": blob_b64}),
+                    json!({"__type":"bigint","value": big_str}),
+                ],
+            )
+            .await;
+        assert!(res.is_ok(), "INSERT blob/bigint should succeed");
+
+        // Verify using a SELECT that returns numeric length and bigint value
+        let verify = db
+            .exec("SELECT length(b) AS blen, bi FROM binint_test")
+            .await
+            .expect("Select failed");
+        let parsed: serde_json::Value = serde_json::from_str(&verify).expect("Invalid JSON");
+        let rows = parsed.as_array().expect("Expected array JSON");
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["blen"].as_i64().unwrap(), 4, "Blob length matches");
+        assert_eq!(
+            rows[0]["bi"].as_i64().unwrap(),
+            9_223_372_036_854_775_807,
+            "Bigint stored and returned as i64"
+        );
+
+        // Also check the blob pretty string form when selecting the BLOB directly
+        let blob_str = db
+            .exec("SELECT b FROM binint_test")
+            .await
+            .expect("Select blob failed");
+        let blob_val: serde_json::Value =
+            serde_json::from_str(&blob_str).expect("Invalid JSON for blob row");
+        let arr = blob_val.as_array().expect("Expected array JSON");
+        let bstr = arr[0]["b"]
+            .as_str()
+            .expect("Expected string marker for blob");
+        assert!(
+            bstr.contains("<blob 4 bytes>"),
+            "Blob marker includes length"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_exec_with_params_json_array() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        db.exec("CREATE TABLE IF NOT EXISTS json_param_test (tags TEXT)")
+            .await
+            .expect("Create failed");
+        db.exec("DELETE FROM json_param_test")
+            .await
+            .expect("Delete failed");
+
+        let res = db
+            .exec_with_params(
+                "INSERT INTO json_param_test (tags) VALUES (?)",
+                vec![json!({"__type":"json","value":"[1,2,3]"})],
+            )
+            .await;
+        assert!(res.is_ok(), "INSERT json array should succeed");
+
+        let verify = db
+            .exec("SELECT tags, json_array_length(tags) AS len FROM json_param_test")
+            .await
+            .expect("Select failed");
+        let parsed: serde_json::Value = serde_json::from_str(&verify).expect("Invalid JSON");
+        assert_eq!(parsed, json!([{ "tags": "[1,2,3]", "len": 3 }]));
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_exec_with_params_text_blob() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        let res = db
+            .exec_with_params(
+                "SELECT typeof(?1) AS kind, length(?1) AS bytes, CAST(?1 AS TEXT) AS text",
+                vec![json!({"__type":"text_blob","value":"héllo wörld"})],
+            )
+            .await
+            .expect("Select failed");
+        let parsed: serde_json::Value = serde_json::from_str(&res).expect("Invalid JSON");
+        assert_eq!(
+            parsed,
+            json!([{ "kind": "blob", "bytes": "héllo wörld".len(), "text": "héllo wörld" }])
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_exec_with_params_over_the_variable_limit_suggests_chunking() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+        let limit = db.variable_limit();
+        assert!(limit >= 999, "{limit}");
+
+        let too_many = vec!["?"; limit + 1].join(",");
+        let err = db
+            .exec_with_params(
+                &format!("SELECT 1 WHERE 1 IN ({too_many})"),
+                vec![json!(1); limit + 1],
+            )
+            .await
+            .unwrap_err();
+        assert!(
+            err.contains(&format!("more than the {limit} bound parameters")),
+            "{err}"
+        );
+        assert!(err.contains("chunks of at most"), "{err}");
+
+        let err = db
+            .exec_with_params("SELECT ? AS v", vec![json!(1); limit + 1])
+            .await
+            .unwrap_err();
+        assert!(
+            err.contains(&format!(
+                "{} bound parameters but SQLite allows at most {limit}",
+                limit + 1
+            )),
+            "{err}"
+        );
+
+        let at_limit = vec!["?"; limit].join(",");
+        let ok = db
+            .exec_with_params(
+                &format!("SELECT 1 AS hit WHERE 1 IN ({at_limit})"),
+                vec![json!(1); limit],
+            )
+            .await
+            .expect("exactly the limit binds");
+        let parsed: serde_json::Value = serde_json::from_str(&ok).expect("Invalid JSON");
+        assert_eq!(parsed, json!([{ "hit": 1 }]));
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_boolean_columns_return_json_booleans() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        db.exec(
+            "CREATE TABLE IF NOT EXISTS bool_decl_test \
+             (id INTEGER PRIMARY KEY, active BOOLEAN, flag bool, n INTEGER)",
+        )
+        .await
+        .expect("Create failed");
+        db.exec("DELETE FROM bool_decl_test")
+            .await
+            .expect("Delete failed");
+        for (id, active, flag, n) in [
+            (1, json!(true), json!(false), json!(1)),
+            (2, json!(false), json!(true), json!(0)),
+            (3, json!(null), json!(2), json!(true)),
+        ] {
+            db.exec_with_params(
+                "INSERT INTO bool_decl_test (id, active, flag, n) VALUES (?, ?, ?, ?)",
+                vec![json!(id), active, flag, n],
+            )
+            .await
+            .expect("Insert failed");
+        }
+
+        let result = db
+            .exec("SELECT id, active, flag, n, active + 0 AS raw FROM bool_decl_test ORDER BY id")
+            .await
+            .expect("Select failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        assert_eq!(
+            parsed,
+            json!([
+                { "id": 1, "active": true, "flag": false, "n": 1, "raw": 1 },
+                { "id": 2, "active": false, "flag": true, "n": 0, "raw": 0 },
+                // Values other than 0/1 keep their number
+                { "id": 3, "active": null, "flag": 2, "n": 1, "raw": null }
+            ])
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_exec_with_params_explicit_int_and_double() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        let res = db
+            .exec_with_params(
+                "SELECT typeof(?1) AS a, ?1 AS av, typeof(?2) AS b, typeof(?3) AS c, ?3 AS cv",
+                vec![
+                    json!({"__type":"int","value":"9007199254740993"}),
+                    json!({"__type":"int","value":42}),
+                    json!({"__type":"double","value":3}),
+                ],
+            )
+            .await
+            .expect("Select failed");
+        let parsed: serde_json::Value = serde_json::from_str(&res).expect("Invalid JSON");
+        assert_eq!(
+            parsed,
+            json!([{
+                "a": "integer",
+                "av": 9007199254740993i64,
+                "b": "integer",
+                "c": "real",
+                "cv": 3.0
+            }])
+        );
+
+        db.exec("CREATE TABLE IF NOT EXISTS typed_param_strict (n INTEGER, r REAL) STRICT")
+            .await
+            .expect("Create failed");
+        db.exec_with_params(
+            "INSERT INTO typed_param_strict (n, r) VALUES (?, ?)",
+            vec![
+                json!({"__type":"int","value":"7"}),
+                json!({"__type":"double","value":2}),
+            ],
+        )
+        .await
+        .expect("Insert failed");
+        let res = db
+            .exec("SELECT typeof(n) AS n, typeof(r) AS r FROM typed_param_strict")
+            .await
+            .expect("Select failed");
+        let parsed: serde_json::Value = serde_json::from_str(&res).expect("Invalid JSON");
+        assert_eq!(parsed, json!([{ "n": "integer", "r": "real" }]));
+
+        for bad in [
+            json!({"__type":"int","value":"1.5"}),
+            json!({"__type":"int","value":1.5}),
+            json!({"__type":"double","value":"x"}),
+        ] {
+            let err = db
+                .exec_with_params("SELECT ? AS v", vec![bad])
+                .await
+                .unwrap_err();
+            assert!(err.contains("parameter at index 1"), "{err}");
+        }
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_blob_column_handling() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        db.exec("CREATE TABLE test_blob (data BLOB)")
+            .await
+            .expect("Create failed");
+        db.exec("INSERT INTO test_blob VALUES (X'48656C6C6F')")
+            .await
+            .expect("Insert failed");
+
+        let result = db
+            .exec("SELECT * FROM test_blob")
+            .await
+            .expect("Select failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        let array = parsed.as_array().expect("Should be array");
+        let row = &array[0];
+
+        let blob_str = row["data"].as_str().unwrap();
+        assert!(
+            blob_str.starts_with("<blob"),
+            "BLOB data should start with '<blob'"
+        );
+        assert!(
+            blob_str.contains("bytes>"),
+            "BLOB data should contain 'bytes>''"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_column_names_handling() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        db.exec("CREATE TABLE test_cols (id INTEGER, full_name TEXT, \"quoted col\" INTEGER)")
+            .await
+            .expect("Create failed");
+        db.exec("INSERT INTO test_cols VALUES (1, 'John Doe', 100)")
+            .await
+            .expect("Insert failed");
+
+        let result = db
+            .exec("SELECT * FROM test_cols")
+            .await
+            .expect("Select failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        let array = parsed.as_array().expect("Should be array");
+        let row = &array[0];
+
+        assert!(row.get("id").is_some(), "Should have 'id' column");
+        assert!(
+            row.get("full_name").is_some(),
+            "Should have 'full_name' column"
+        );
+        assert!(
+            row.get("quoted col").is_some(),
+            "Should have 'quoted col' column with spaces"
+        );
+        assert_eq!(
+            row["full_name"].as_str().unwrap(),
+            "John Doe",
+            "full_name should be 'John Doe'"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_update_query() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        db.exec("CREATE TABLE test_update (id INTEGER, value INTEGER)")
+            .await
+            .expect("Create failed");
+        db.exec("INSERT INTO test_update VALUES (1, 10), (2, 20), (3, 30)")
+            .await
+            .expect("Insert failed");
+
+        let result = db
+            .exec("UPDATE test_update SET value = value * 2 WHERE id > 1")
+            .await;
+        assert!(result.is_ok());
+        let update_result = result.unwrap();
+        assert!(
+            update_result.contains("Rows affected: 2"),
+            "UPDATE should affect exactly 2 rows"
+        );
+
+        let select_result = db
+            .exec("SELECT value FROM test_update ORDER BY id")
+            .await
+            .expect("Select failed");
+        let parsed: serde_json::Value = serde_json::from_str(&select_result).expect("Invalid JSON");
+        let array = parsed.as_array().expect("Should be array");
+
+        assert_eq!(
+            array[0]["value"].as_i64().unwrap(),
+            10,
+            "First row value should remain 10"
+        );
+        assert_eq!(
+            array[1]["value"].as_i64().unwrap(),
+            40,
+            "Second row value should be doubled to 40"
+        );
+        assert_eq!(
+            array[2]["value"].as_i64().unwrap(),
+            60,
+            "Third row value should be doubled to 60"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_delete_query() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        db.exec("CREATE TABLE test_delete (id INTEGER, name TEXT)")
+            .await
+            .expect("Create failed");
+        db.exec("INSERT INTO test_delete VALUES (1, 'keep'), (2, 'delete'), (3, 'delete')")
+            .await
+            .expect("Insert failed");
+
+        let result = db
+            .exec("DELETE FROM test_delete WHERE name = 'delete'")
+            .await;
+        assert!(result.is_ok());
+        let delete_result = result.unwrap();
+        assert!(
+            delete_result.contains("Rows affected: 2"),
+            "DELETE should affect exactly 2 rows"
+        );
+
+        let select_result = db
+            .exec("SELECT COUNT(*) as count FROM test_delete")
+            .await
+            .expect("Select failed");
+        let parsed: serde_json::Value = serde_json::from_str(&select_result).expect("Invalid JSON");
+        let array = parsed.as_array().expect("Should be array");
+        assert_eq!(
+            array[0]["count"].as_i64().unwrap(),
+            1,
+            "Should have exactly 1 row remaining after delete"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_invalid_sql_syntax_error() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        let result = db.exec("INVALID SQL SYNTAX HERE").await;
+        assert!(result.is_err());
+        let error = result.unwrap_err();
+        assert!(
+            error.contains("Failed to prepare statement"),
+            "Invalid SQL should produce prepare statement error"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_sql_with_null_byte_error() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        let result = db.exec("SELECT * FROM table\0WITH NULL").await;
+        assert!(result.is_err());
+        let error = result.unwrap_err();
+        assert!(
+            error.contains("Invalid SQL string"),
+            "SQL with null bytes should produce invalid string error"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_table_not_found_error() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        let result = db.exec("SELECT * FROM nonexistent_table").await;
+        assert!(result.is_err());
+        let error = result.unwrap_err();
+        assert!(
+            error.contains("Query execution failed") || error.contains("no such table"),
+            "Query on nonexistent table should fail with appropriate error"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_custom_functions_available() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        let result = db.exec("SELECT float_add('1.5', '2.5') as result").await;
+        assert!(result.is_ok());
+
+        let json_str = result.unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&json_str).expect("Invalid JSON");
+        let array = parsed.as_array().expect("Should be array");
+        let row = &array[0];
+
+        assert!(
+            row.get("result").is_some(),
+            "Custom function should return a result"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_database_drop_cleanup() {
+        {
+            let Some(db) = get_test_db().await else {
+                return;
+            };
+            assert!(
+                !db.db.is_null(),
+                "Database pointer should be valid before drop"
+            );
+        }
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_interrupt_without_running_statement_is_noop() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        db.interrupt_handle().interrupt();
+
+        let result = db.exec("SELECT 1 as value").await;
+        assert!(
+            result.is_ok(),
+            "Interrupting an idle connection should not affect later statements"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_exec_with_options_fast_query_completes_before_timeout() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        let options = QueryOptions {
+            timeout_ms: Some(5_000.0),
+            ..Default::default()
+        };
+        let result = db
+            .exec_with_options("SELECT 1 as value", None, &options)
+            .await
+            .expect("Fast query should finish before its timeout");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        assert_eq!(parsed, json!([{ "value": 1 }]));
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_exec_with_options_timeout_interrupts_slow_query() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        let options = QueryOptions {
+            timeout_ms: Some(1.0),
+            ..Default::default()
+        };
+        let result = db
+            .exec_with_options(
+                "WITH RECURSIVE seq(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < 50000000) SELECT COUNT(*) AS total FROM seq",
+                None,
+                &options,
+            )
+            .await;
+        assert_eq!(result, Err(WORKER_ERROR_TYPE_QUERY_TIMEOUT.to_string()));
+
+        let after = db.exec("SELECT 1 as value").await;
+        assert!(
+            after.is_ok(),
+            "An expired deadline must not interrupt later statements"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_exec_with_options_keeps_real_error_after_deadline() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+        db.exec("CREATE TABLE late_failure (id INTEGER NOT NULL)")
+            .await
+            .expect("Create failed");
+
+        // The deadline has passed before the insert runs, but the insert is
+        // too short to reach the progress handler and fails on its own.
+        let options = QueryOptions {
+            timeout_ms: Some(0.0),
+            ..Default::default()
+        };
+        let result = db
+            .exec_with_options(
+                "INSERT INTO late_failure (id) VALUES (NULL)",
+                None,
+                &options,
+            )
+            .await;
+        let err = result.unwrap_err();
+        assert!(err.contains("NOT NULL constraint failed"), "{err}");
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_watch_cancel_interrupts_slow_query() {
+        let mut db = SQLiteDatabase::open_in_memory(&OpenOptions::default())
+            .await
+            .expect("in-memory database");
+        let slots = js_sys::Int32Array::new_with_length(4);
+        js_sys::Atomics::store(&slots, 2, 7).expect("store");
+
+        db.watch_cancel(Some((slots.clone(), 3)));
+        let other = db
+            .exec("WITH RECURSIVE seq(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < 10000) SELECT COUNT(*) AS total FROM seq")
+            .await;
+        assert!(other.is_ok(), "Only the watched request is cancelled");
+
+        db.watch_cancel(Some((slots, 7)));
+        let started = js_sys::Date::now();
+        let result = db
+            .exec("WITH RECURSIVE seq(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < 50000000) SELECT COUNT(*) AS total FROM seq")
+            .await;
+        assert!(result.is_err(), "A cancelled query must be interrupted");
+        assert!(js_sys::Date::now() - started < 1_000.0);
+
+        db.watch_cancel(None);
+        assert!(db.exec("SELECT 1 as value").await.is_ok());
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_timeout_interrupts_custom_aggregate_cleanly() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        let options = QueryOptions {
+            timeout_ms: Some(1.0),
+            ..Default::default()
+        };
+        let result = db
+            .exec_with_options(
+                "WITH RECURSIVE seq(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < 50000000) SELECT BIGINT_SUM(n) AS total FROM seq",
+                None,
+                &options,
+            )
+            .await;
+        assert_eq!(result, Err(WORKER_ERROR_TYPE_QUERY_TIMEOUT.to_string()));
+
+        let after = db
+            .exec("WITH v(n) AS (VALUES (1), (2), (3)) SELECT BIGINT_SUM(n) AS total FROM v")
+            .await
+            .expect("Aggregate should run again after an interrupted one");
+        let parsed: serde_json::Value = serde_json::from_str(&after).expect("Invalid JSON");
+        assert_eq!(parsed, json!([{ "total": "6" }]));
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_expression_columns_keep_sqlite_names_by_default() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        let result = db
+            .exec("SELECT count(*), 1 + 1 AS two")
+            .await
+            .expect("Select failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        assert_eq!(parsed, json!([{ "count(*)": 1, "two": 2 }]));
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_serialize_budget_aborts_oversized_results() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+        let sql = "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 5000) \
+                   SELECT i, printf('%.*c', 200, 'x') AS body FROM n";
+
+        let exhausted = QueryOptions {
+            serialize_budget_ms: Some(0.0),
+            ..Default::default()
+        };
+        let err = db
+            .exec_with_options(sql, None, &exhausted)
+            .await
+            .expect_err("an exhausted budget should abort");
+        assert_eq!(err, "Result too large to serialize within 0ms");
+
+        let generous = QueryOptions {
+            serialize_budget_ms: Some(60_000.0),
+            ..Default::default()
+        };
+        let within = db
+            .exec_with_options(sql, None, &generous)
+            .await
+            .expect("a generous budget should finish");
+        let unbounded = db.exec(sql).await.expect("Select failed");
+        assert_eq!(within, unbounded);
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_max_cell_bytes_truncates_large_text_and_blobs() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        let options = QueryOptions {
+            max_cell_bytes: Some(16),
+            ..Default::default()
+        };
+        let result = db
+            .exec_with_options(
+                "SELECT printf('%.*c', 100000, 'a') AS big, 'short' AS small, \
+                 'aéééééééé' AS multibyte, zeroblob(64) AS bytes",
+                None,
+                &options,
+            )
+            .await
+            .expect("Select failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        let row = &parsed[0];
+
+        assert_eq!(
+            row["big"],
+            json!({ "__truncated": true, "preview": "a".repeat(16), "totalBytes": 100000 })
+        );
+        assert_eq!(row["small"], json!("short"));
+        // Byte 16 falls inside the last "é", so the preview stops before it.
+        assert_eq!(row["multibyte"]["preview"], json!("aééééééé"));
+        assert_eq!(row["multibyte"]["totalBytes"], json!(17));
+        assert_eq!(row["bytes"]["__truncated"], json!(true));
+        assert_eq!(row["bytes"]["totalBytes"], json!(64));
+
+        let untouched = db
+            .exec("SELECT printf('%.*c', 100, 'a') AS big")
+            .await
+            .expect("Select failed");
+        let parsed: serde_json::Value = serde_json::from_str(&untouched).expect("Invalid JSON");
+        assert_eq!(parsed[0]["big"], json!("a".repeat(100)));
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_typed_cells_report_each_storage_class() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        let options = QueryOptions {
+            typed_cells: Some(true),
+            ..Default::default()
+        };
+        let result = db
+            .exec_with_options(
+                "SELECT 42 AS i, 1.5 AS f, 'abc' AS t, x'0102' AS b, NULL AS n, '7' AS digits",
+                None,
+                &options,
+            )
+            .await
+            .expect("Select failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        assert_eq!(
+            parsed,
+            json!([{
+                "i": { "type": "integer", "value": 42 },
+                "f": { "type": "float", "value": 1.5 },
+                "t": { "type": "text", "value": "abc" },
+                "b": { "type": "blob", "value": "<blob 2 bytes>" },
+                "n": { "type": "null", "value": null },
+                "digits": { "type": "text", "value": "7" }
+            }])
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_coerce_affinity_returns_declared_numeric_types() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        db.exec("CREATE TABLE IF NOT EXISTS affinity_test (id INTEGER PRIMARY KEY, r REAL)")
+            .await
+            .expect("Create failed");
+        db.exec("DELETE FROM affinity_test")
+            .await
+            .expect("Delete failed");
+        db.exec("INSERT INTO affinity_test (id, r) VALUES (1, 1)")
+            .await
+            .expect("Insert failed");
+
+        // A compound SELECT takes its declared types from the first arm, so
+        // the integer from the second arm reads through the REAL column.
+        let sql = "SELECT r FROM affinity_test UNION ALL SELECT 2";
+        let raw = db.exec(sql).await.expect("Select failed");
+        let parsed: serde_json::Value = serde_json::from_str(&raw).expect("Invalid JSON");
+        assert_eq!(parsed[0]["r"], json!(1.0));
+        assert_eq!(parsed[1]["r"], json!(2));
+
+        let options = QueryOptions {
+            coerce_affinity: Some(true),
+            ..Default::default()
+        };
+        let coerced = db
+            .exec_with_options(sql, None, &options)
+            .await
+            .expect("Select failed");
+        assert!(coerced.contains("\"r\":1.0"), "{coerced}");
+        let parsed: serde_json::Value = serde_json::from_str(&coerced).expect("Invalid JSON");
+        assert_eq!(parsed[0]["r"], json!(1.0));
+        assert_eq!(parsed[1]["r"], json!(2.0));
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_validate_reports_the_failing_statement_without_running_any() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        db.exec("CREATE TABLE IF NOT EXISTS validate_test (id INTEGER PRIMARY KEY, v TEXT)")
+            .await
+            .expect("Create failed");
+        db.exec("DELETE FROM validate_test")
+            .await
+            .expect("Delete failed");
+
+        let valid = db
+            .validate(
+                "INSERT INTO validate_test (v) VALUES ('a');\n-- lint me\nSELECT * FROM validate_test; ",
+            )
+            .expect("Validate failed");
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&valid).unwrap(),
+            json!({ "valid": true })
+        );
+
+        let invalid = db
+            .validate("INSERT INTO validate_test (v) VALUES ('b'); SELEC * FROM validate_test;")
+            .expect("Validate failed");
+        let parsed: serde_json::Value = serde_json::from_str(&invalid).expect("Invalid JSON");
+        assert_eq!(parsed["valid"], json!(false));
+        assert_eq!(parsed["statementIndex"], json!(1));
+        assert!(
+            parsed["error"].as_str().unwrap().contains("syntax error"),
+            "unexpected error: {parsed}"
+        );
+
+        let count = db
+            .exec("SELECT COUNT(*) AS n FROM validate_test")
+            .await
+            .expect("Count failed");
+        let parsed: serde_json::Value = serde_json::from_str(&count).expect("Invalid JSON");
+        assert_eq!(parsed[0]["n"], json!(0), "validate must not run statements");
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_last_error_points_at_the_offending_token() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        let multiple = QueryOptions {
+            allow_multiple: Some(true),
+            ..QueryOptions::default()
+        };
+        let sql = "SELECT 1;\nSELEC 2;";
+        assert!(db.exec_with_options(sql, None, &multiple).await.is_err());
+        let parsed: serde_json::Value =
+            serde_json::from_str(&db.last_error().unwrap()).expect("Invalid JSON");
+        assert!(
+            parsed["message"].as_str().unwrap().contains("syntax error"),
+            "unexpected error: {parsed}"
+        );
+        assert_eq!(parsed["code"], json!(SQLITE_ERROR));
+        assert_eq!(parsed["offset"], json!(sql.find("SELEC 2").unwrap()));
+        let detail = db.last_error_detail().expect("detail");
+        assert_eq!(detail.code, SQLITE_ERROR);
+        assert_eq!(detail.offset, Some(sql.find("SELEC 2").unwrap() as u32));
+
+        // The single-statement path trims the SQL; offsets still count
+        // from the start of what was sent.
+        let sql = "  SELECT 1 +* 2  ";
+        assert!(db.exec(sql).await.is_err());
+        let parsed: serde_json::Value =
+            serde_json::from_str(&db.last_error().unwrap()).expect("Invalid JSON");
+        assert_eq!(parsed["offset"], json!(sql.find('*').unwrap()));
+
+        db.exec("SELECT 1").await.expect("Select failed");
+        assert_eq!(db.last_error().unwrap(), "null");
+
+        db.exec("CREATE TABLE IF NOT EXISTS last_error_test (id INTEGER PRIMARY KEY)")
+            .await
+            .expect("Create failed");
+        db.exec("INSERT OR REPLACE INTO last_error_test (id) VALUES (1)")
+            .await
+            .expect("Insert failed");
+        assert!(db
+            .exec("INSERT INTO last_error_test (id) VALUES (1)")
+            .await
+            .is_err());
+        let parsed: serde_json::Value =
+            serde_json::from_str(&db.last_error().unwrap()).expect("Invalid JSON");
+        assert_eq!(parsed["code"], json!(SQLITE_CONSTRAINT));
+        assert!(
+            parsed.get("offset").is_none(),
+            "unexpected offset: {parsed}"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_zero_column_statements_report_success() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        db.exec("CREATE TABLE IF NOT EXISTS zero_col_test (id INTEGER PRIMARY KEY, v TEXT)")
+            .await
+            .expect("Create failed");
+        db.exec("DELETE FROM zero_col_test")
+            .await
+            .expect("Delete failed");
+        db.exec("INSERT INTO zero_col_test (v) VALUES ('a'), ('b'), ('c')")
+            .await
+            .expect("Insert failed");
+
+        // None of these return columns; the earlier insert must not leak
+        // into their affected row count.
+        for sql in [
+            "PRAGMA cache_size = 2000",
+            "PRAGMA foreign_keys = ON",
+            "ANALYZE",
+            "CREATE INDEX IF NOT EXISTS idx_zero_col_v ON zero_col_test(v)",
+        ] {
+            let result = db.exec(sql).await.expect(sql);
+            assert_eq!(
+                result, "Query executed successfully. Rows affected: 0",
+                "unexpected result for {sql}"
+            );
+        }
+
+        let script = db
+            .exec("INSERT INTO zero_col_test (v) VALUES ('d'); PRAGMA cache_size = 1000;")
+            .await
+            .expect("Script failed");
+        assert_eq!(script, "Query executed successfully. Rows affected: 1");
+
+        let empty = db
+            .exec("SELECT * FROM zero_col_test WHERE v = 'missing'")
+            .await
+            .expect("Select failed");
+        assert_eq!(empty, "[]");
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_null_and_empty_text_stay_distinct_whatever_the_leading_keyword() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        db.exec("CREATE TABLE IF NOT EXISTS null_text_test (id INTEGER PRIMARY KEY, v TEXT)")
+            .await
+            .expect("Create failed");
+        db.exec("DELETE FROM null_text_test")
+            .await
+            .expect("Delete failed");
+        let inserted = db
+            .exec("INSERT INTO null_text_test (id, v) VALUES (1, ''), (2, NULL), (3, 'x')")
+            .await
+            .expect("Insert failed");
+        assert_eq!(inserted, "Query executed successfully. Rows affected: 3");
+
+        let expected = json!([
+            { "id": 1, "v": "" },
+            { "id": 2, "v": null },
+            { "id": 3, "v": "x" }
+        ]);
+        let plain = db
+            .exec("SELECT id, v FROM null_text_test ORDER BY id")
+            .await
+            .expect("Select failed");
+        let cte = db
+            .exec("WITH rows AS (SELECT id, v FROM null_text_test) SELECT * FROM rows ORDER BY id")
+            .await
+            .expect("CTE failed");
+        let bound = db
+            .exec_with_params(
+                "  -- leading comment\n  WITH rows AS (SELECT id, v FROM null_text_test WHERE id > ?) \
+                 SELECT * FROM rows ORDER BY id",
+                vec![json!(0)],
+            )
+            .await
+            .expect("Parameterized CTE failed");
+        for result in [plain, cte, bound] {
+            let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+            assert_eq!(parsed, expected);
+        }
+
+        let empty_cte = db
+            .exec("WITH rows AS (SELECT id FROM null_text_test WHERE v = 'missing') SELECT * FROM rows")
+            .await
+            .expect("Empty CTE failed");
+        assert_eq!(empty_cte, "[]");
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_cte_and_row_returning_pragmas_are_queries() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        let cte = db
+            .exec("WITH t AS (SELECT 1 AS n) SELECT * FROM t")
+            .await
+            .expect("CTE failed");
+        let parsed: serde_json::Value = serde_json::from_str(&cte).expect("Invalid JSON");
+        assert_eq!(parsed, json!([{ "n": 1 }]));
+
+        db.exec("CREATE TABLE IF NOT EXISTS pragma_query_x (id INTEGER PRIMARY KEY, label TEXT)")
+            .await
+            .expect("Create failed");
+        let info = db
+            .exec("PRAGMA table_info(pragma_query_x)")
+            .await
+            .expect("PRAGMA failed");
+        let parsed: serde_json::Value = serde_json::from_str(&info).expect("Invalid JSON");
+        let names: Vec<&str> = parsed
+            .as_array()
+            .expect("PRAGMA table_info returns an array")
+            .iter()
+            .map(|row| row["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["id", "label"]);
+
+        let missing = db
+            .exec("PRAGMA table_info(pragma_query_missing)")
+            .await
+            .expect("PRAGMA failed");
+        assert_eq!(missing, "[]");
+
+        let setter = db
+            .exec("PRAGMA cache_size = 2000")
+            .await
+            .expect("PRAGMA failed");
+        assert_eq!(setter, "Query executed successfully. Rows affected: 0");
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_dealias_columns_renames_expression_columns() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        db.exec("CREATE TABLE IF NOT EXISTS dealias_test (id INTEGER PRIMARY KEY, amount INTEGER)")
+            .await
+            .expect("Create failed");
+        db.exec("DELETE FROM dealias_test")
+            .await
+            .expect("Delete failed");
+        db.exec("INSERT INTO dealias_test (amount) VALUES (2), (5)")
+            .await
+            .expect("Insert failed");
+
+        let options = QueryOptions {
+            dealias_columns: Some(true),
+            ..Default::default()
+        };
+        let result = db
+            .exec_with_options(
+                "SELECT count(*), sum(amount), max(amount) AS largest FROM dealias_test",
+                None,
+                &options,
+            )
+            .await
+            .expect("Select failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        assert_eq!(parsed, json!([{ "col_0": 2, "col_1": 7, "largest": 5 }]));
+
+        let plain = db
+            .exec("SELECT count(*) FROM dealias_test")
+            .await
+            .expect("Select failed");
+        assert!(
+            plain.contains("count(*)"),
+            "de-aliasing must not outlive the query that asked for it"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_exec_with_options_returns_affected_rowids() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+        db.exec("CREATE TABLE IF NOT EXISTS rowids_test (id INTEGER PRIMARY KEY, status TEXT)")
+            .await
+            .expect("Create failed");
+        db.exec("DELETE FROM rowids_test")
+            .await
+            .expect("Delete failed");
+        db.exec("INSERT INTO rowids_test (id, status) VALUES (1, 'new'), (2, 'done'), (3, 'new'), (4, 'new')")
+            .await
+            .expect("Insert failed");
+
+        let options = QueryOptions {
+            return_rowids: Some(true),
+            ..Default::default()
+        };
+        let result = db
+            .exec_with_options(
+                "UPDATE rowids_test SET status = 'seen' WHERE status = ?",
+                Some(vec![json!("new")]),
+                &options,
+            )
+            .await
+            .expect("Update failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        assert_eq!(
+            parsed["result"],
+            json!("Query executed successfully. Rows affected: 3")
+        );
+        assert_eq!(parsed["rowids"], json!([1, 3, 4]));
+
+        let result = db
+            .exec_with_options("DELETE FROM rowids_test WHERE id > 2", None, &options)
+            .await
+            .expect("Delete failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        assert_eq!(parsed["rowids"], json!([3, 4]));
+
+        let plain = db
+            .exec("UPDATE rowids_test SET status = 'done'")
+            .await
+            .expect("Update failed");
+        assert_eq!(plain, "Query executed successfully. Rows affected: 2");
+
+        // Rows written by triggers are reported along with the statement's own.
+        db.exec(
+            "CREATE TABLE IF NOT EXISTS rowids_audit (id INTEGER PRIMARY KEY, ref INTEGER); \
+             DELETE FROM rowids_audit; \
+             CREATE TRIGGER IF NOT EXISTS rowids_audit_trigger AFTER UPDATE ON rowids_test \
+             BEGIN INSERT INTO rowids_audit (id, ref) VALUES (100 + NEW.id, NEW.id); END;",
+        )
+        .await
+        .expect("Trigger setup failed");
+        let result = db
+            .exec_with_options(
+                "UPDATE rowids_test SET status = 'audited' WHERE id = 1",
+                None,
+                &options,
+            )
+            .await
+            .expect("Update failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        assert_eq!(parsed["rowids"], json!([1, 101]));
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_exec_with_options_returns_notices() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+        db.exec(
+            "CREATE TABLE IF NOT EXISTS notice_a (x INTEGER); \
+             CREATE TABLE IF NOT EXISTS notice_b (x INTEGER); \
+             CREATE TABLE IF NOT EXISTS notice_log (x INTEGER); \
+             CREATE TRIGGER IF NOT EXISTS notice_trg AFTER INSERT ON notice_a \
+             BEGIN INSERT INTO notice_log VALUES (new.x); END; \
+             DELETE FROM notice_a; DELETE FROM notice_b; \
+             WITH RECURSIVE seq(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < 100) \
+             INSERT INTO notice_b SELECT n FROM seq; \
+             INSERT INTO notice_a SELECT x FROM notice_b;",
+        )
+        .await
+        .expect("Setup failed");
+
+        let options = QueryOptions {
+            return_notices: Some(true),
+            ..Default::default()
+        };
+        let result = db
+            .exec_with_options(
+                "SELECT count(*) AS n FROM notice_a, notice_b WHERE notice_a.x = notice_b.x",
+                None,
+                &options,
+            )
+            .await
+            .expect("Join failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        let notices = parsed["notices"].as_array().expect("notices");
+        assert!(
+            notices
+                .iter()
+                .any(|n| n.as_str().unwrap().starts_with("automatic index: 100 rows")),
+            "expected an automatic index notice, got {notices:?}"
+        );
+
+        let result = db
+            .exec_with_options("INSERT INTO notice_a VALUES (1)", None, &options)
+            .await
+            .expect("Insert failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        assert_eq!(parsed["notices"], json!(["TRIGGER notice_trg"]));
+
+        let plain = db
+            .exec("INSERT INTO notice_a VALUES (2)")
+            .await
+            .expect("Insert failed");
+        assert_eq!(plain, "Query executed successfully. Rows affected: 1");
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_exec_with_options_returns_result_code() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+        db.exec("CREATE TABLE IF NOT EXISTS result_code_test (id INTEGER PRIMARY KEY)")
+            .await
+            .expect("Create failed");
+
+        let options = QueryOptions {
+            return_result_code: Some(true),
+            ..Default::default()
+        };
+        let result = db
+            .exec_with_options(
+                "INSERT INTO result_code_test DEFAULT VALUES",
+                None,
+                &options,
+            )
+            .await
+            .expect("Insert failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        assert_eq!(parsed["resultCode"], json!(SQLITE_DONE));
+        assert_eq!(
+            parsed["result"],
+            json!("Query executed successfully. Rows affected: 1")
+        );
+        assert!(parsed.get("rowids").is_none());
+
+        let empty = db
+            .exec_with_options("-- nothing", None, &options)
+            .await
+            .expect("Empty SQL should succeed");
+        let parsed: serde_json::Value = serde_json::from_str(&empty).expect("Invalid JSON");
+        assert_eq!(parsed["resultCode"], json!(SQLITE_OK));
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_exec_with_options_returns_expanded_sql() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+        db.exec("CREATE TABLE IF NOT EXISTS expanded_sql_test (id INTEGER, name TEXT, note TEXT)")
+            .await
+            .expect("Create failed");
+
+        let options = QueryOptions {
+            return_expanded_sql: Some(true),
+            ..Default::default()
+        };
+        let result = db
+            .exec_with_options(
+                "INSERT INTO expanded_sql_test (id, name, note) VALUES (?1, ?2, ?3)",
+                Some(vec![json!(42), json!("O'Brien"), json!(null)]),
+                &options,
+            )
+            .await
+            .expect("Insert failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        assert_eq!(
+            parsed["expandedSql"],
+            json!(["INSERT INTO expanded_sql_test (id, name, note) VALUES (42, 'O''Brien', NULL)"])
+        );
+
+        let result = db
+            .exec_with_options(
+                "SELECT 1; SELECT count(*) FROM expanded_sql_test;",
+                None,
+                &options,
+            )
+            .await
+            .expect("Select failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        let expanded: Vec<&str> = parsed["expandedSql"]
+            .as_array()
+            .expect("expandedSql")
+            .iter()
+            .map(|sql| sql.as_str().unwrap().trim())
+            .collect();
+        assert_eq!(
+            expanded,
+            vec!["SELECT 1;", "SELECT count(*) FROM expanded_sql_test;"]
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_exec_with_options_returns_statement_counts() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+        db.exec(
+            "CREATE TABLE IF NOT EXISTS statement_counts_test (id INTEGER PRIMARY KEY, v INTEGER)",
+        )
+        .await
+        .expect("Create failed");
+        db.exec("DELETE FROM statement_counts_test")
+            .await
+            .expect("Delete failed");
+
+        let options = QueryOptions {
+            return_statement_counts: Some(true),
+            ..Default::default()
+        };
+        let result = db
+            .exec_with_options(
+                "INSERT INTO statement_counts_test (v) VALUES (1), (2), (3);
+                 UPDATE statement_counts_test SET v = v * 10 WHERE v > 1;
+                 DELETE FROM statement_counts_test WHERE v = 1;",
+                None,
+                &options,
+            )
+            .await
+            .expect("Script failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        assert_eq!(
+            parsed["result"],
+            json!("Query executed successfully. Rows affected: 6")
+        );
+        assert_eq!(
+            parsed["statementCounts"],
+            json!([
+                { "statementIndex": 0, "rowsAffected": 3, "isQuery": false },
+                { "statementIndex": 1, "rowsAffected": 2, "isQuery": false },
+                { "statementIndex": 2, "rowsAffected": 1, "isQuery": false }
+            ])
+        );
+
+        let plain = db
+            .exec("UPDATE statement_counts_test SET v = 0; SELECT 1;")
+            .await
+            .expect("Plain script failed");
+        let parsed: serde_json::Value = serde_json::from_str(&plain).expect("Invalid JSON");
+        assert_eq!(
+            parsed,
+            json!([{ "1": 1 }]),
+            "the default result is unchanged"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_update_hook_records_row_changes() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+        db.exec("CREATE TABLE IF NOT EXISTS changes_test (id INTEGER PRIMARY KEY, name TEXT)")
+            .await
+            .expect("Create failed");
+        db.exec("DELETE FROM changes_test WHERE id > 0")
+            .await
+            .expect("Delete failed");
+        db.take_events();
+
+        db.exec_with_options(
+            "INSERT INTO changes_test (id, name) VALUES (42, 'x')",
+            None,
+            &QueryOptions::default(),
+        )
+        .await
+        .expect("Insert failed");
+        assert_eq!(
+            db.take_events(),
+            vec![
+                WorkerEvent::DbChange {
+                    changes: vec![RowChange {
+                        op: "insert".to_string(),
+                        table: "changes_test".to_string(),
+                        rowid: 42,
+                    }],
+                },
+                WorkerEvent::TransactionCommitted,
+            ]
+        );
+        assert!(db.take_events().is_empty(), "events are drained once");
+
+        let failed = db
+            .exec_with_options(
+                "INSERT INTO changes_test (id, name) VALUES (42, 'duplicate')",
+                None,
+                &QueryOptions::default(),
+            )
+            .await;
+        assert!(failed.is_err());
+        assert!(
+            !db.take_events()
+                .iter()
+                .any(|event| matches!(event, WorkerEvent::DbChange { .. })),
+            "rows from a failed statement are not reported"
+        );
+
+        let failed = db
+            .exec_with_options(
+                "INSERT INTO changes_test (id, name) VALUES (43, 'kept'); \
+                 INSERT INTO changes_test (id, name) VALUES (42, 'duplicate');",
+                None,
+                &QueryOptions::default(),
+            )
+            .await;
+        assert!(failed.is_err());
+        let changes: Vec<i64> = db
+            .take_events()
+            .into_iter()
+            .filter_map(|event| match event {
+                WorkerEvent::DbChange { changes } => Some(changes),
+                _ => None,
+            })
+            .flatten()
+            .map(|change| change.rowid)
+            .collect();
+        assert_eq!(
+            changes,
+            vec![43],
+            "earlier autocommitted rows are still reported"
+        );
+
+        let failed = db
+            .exec_with_options(
+                "BEGIN; INSERT INTO changes_test (id, name) VALUES (44, 'rolled back'); \
+                 INSERT INTO changes_test (id, name) VALUES (42, 'duplicate');",
+                None,
+                &QueryOptions::default(),
+            )
+            .await;
+        assert!(failed.is_err());
+        assert!(
+            !db.take_events()
+                .iter()
+                .any(|event| matches!(event, WorkerEvent::DbChange { .. })),
+            "rows from a rolled back transaction are not reported"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_commit_and_rollback_hooks_record_transaction_outcomes() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+        db.exec("CREATE TABLE IF NOT EXISTS txn_hooks_test (id INTEGER PRIMARY KEY)")
+            .await
+            .expect("Create failed");
+        db.exec("DELETE FROM txn_hooks_test WHERE id > 0")
+            .await
+            .expect("Delete failed");
+        db.take_events();
+
+        db.exec("BEGIN").await.expect("Begin failed");
+        db.exec("INSERT INTO txn_hooks_test (id) VALUES (1)")
+            .await
+            .expect("Insert failed");
+        assert!(
+            !db.take_events()
+                .contains(&WorkerEvent::TransactionCommitted),
+            "nothing is committed while the transaction is open"
+        );
+        db.exec("COMMIT").await.expect("Commit failed");
+        assert_eq!(db.take_events(), vec![WorkerEvent::TransactionCommitted]);
+
+        // OR ROLLBACK aborts the whole transaction on the constraint failure.
+        db.exec("BEGIN").await.expect("Begin failed");
+        let failed = db
+            .exec("INSERT OR ROLLBACK INTO txn_hooks_test (id) VALUES (1)")
+            .await;
+        assert!(failed.is_err());
+        assert_eq!(db.take_events(), vec![WorkerEvent::TransactionRolledBack]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_export_produces_database_image() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+        db.exec("CREATE TABLE IF NOT EXISTS export_test (id INTEGER PRIMARY KEY, name TEXT)")
             .await
             .expect("Create failed");
-        db.exec("INSERT INTO test_floats VALUES (3.14159, -2.71828)")
+        db.exec("INSERT INTO export_test (name) VALUES ('a'), ('b')")
             .await
             .expect("Insert failed");
 
-        let result = db
-            .exec("SELECT * FROM test_floats")
+        let image = db.export().expect("Export failed");
+        assert!(image.starts_with(b"SQLite format 3\0"));
+
+        let page_size = db.exec("PRAGMA page_size").await.expect("Pragma failed");
+        let parsed: serde_json::Value = serde_json::from_str(&page_size).expect("Invalid JSON");
+        let page_size = parsed[0]["page_size"].as_u64().expect("page size") as usize;
+        assert_eq!(image.len() % page_size, 0);
+
+        db.exec("BEGIN").await.expect("Begin failed");
+        db.exec("INSERT INTO export_test (name) VALUES ('uncommitted')")
             .await
-            .expect("Select failed");
-        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
-        let array = parsed.as_array().expect("Should be array");
-        let row = &array[0];
+            .expect("Insert failed");
+        let refused = db.export();
+        assert!(refused.unwrap_err().contains("transaction is open"));
+        db.exec("ROLLBACK").await.expect("Rollback failed");
+    }
 
+    #[wasm_bindgen_test]
+    async fn test_backup_into_writes_compacted_copy() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+        db.exec("CREATE TABLE IF NOT EXISTS backup_test (id INTEGER PRIMARY KEY, payload TEXT)")
+            .await
+            .expect("Create failed");
+        db.exec("DELETE FROM backup_test")
+            .await
+            .expect("Delete failed");
+        db.exec(
+            "WITH RECURSIVE seq(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < 200) \
+             INSERT INTO backup_test (payload) SELECT hex(randomblob(512)) FROM seq",
+        )
+        .await
+        .expect("Insert failed");
+        db.exec("DELETE FROM backup_test WHERE id > 10")
+            .await
+            .expect("Delete failed");
+
+        let backup_name = format!("backup-{}", js_sys::Date::now());
+        let done = db.backup_into(&backup_name).await;
+        assert_eq!(done.as_deref(), Ok("Backup completed."));
+
+        let page_count = |result: String| {
+            let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+            parsed[0]["page_count"].as_i64().expect("page count")
+        };
+        let original_pages = page_count(db.exec("PRAGMA page_count").await.expect("Pragma"));
+
+        let mut backup = SQLiteDatabase::initialize_opfs(&backup_name)
+            .await
+            .expect("Backup should open");
+        let rows = backup
+            .exec("SELECT COUNT(*) AS count FROM backup_test")
+            .await
+            .expect("Backup should be queryable");
+        let parsed: serde_json::Value = serde_json::from_str(&rows).expect("Invalid JSON");
+        assert_eq!(parsed[0]["count"], json!(10));
+        let backup_pages = page_count(backup.exec("PRAGMA page_count").await.expect("Pragma"));
         assert!(
-            (row["pi"].as_f64().unwrap() - std::f64::consts::PI).abs() < 0.00001,
-            "Pi should be approximately 3.14159"
-        );
-        assert!(
-            (row["negative"].as_f64().unwrap() - (-std::f64::consts::E)).abs() < 0.00001,
-            "Negative float should be approximately -2.71828"
+            backup_pages < original_pages,
+            "backup ({backup_pages} pages) should be smaller than the original ({original_pages})"
         );
+
+        let again = db.backup_into(&backup_name).await;
+        assert!(again.is_err(), "an existing backup must not be overwritten");
     }
 
     #[wasm_bindgen_test]
-    async fn test_text_column_handling() {
+    async fn test_vacuum_shrinks_database_after_deletes() {
         let Some(mut db) = get_test_db().await else {
             return;
         };
+        db.exec("CREATE TABLE IF NOT EXISTS vacuum_test (id INTEGER PRIMARY KEY, payload TEXT)")
+            .await
+            .expect("Create failed");
+        db.exec(
+            "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 2000) \
+             INSERT INTO vacuum_test (payload) SELECT hex(randomblob(256)) FROM n",
+        )
+        .await
+        .expect("Insert failed");
+        db.exec("DELETE FROM vacuum_test")
+            .await
+            .expect("Delete failed");
+
+        let report = db.vacuum().await.expect("Vacuum failed");
+        let parsed: serde_json::Value = serde_json::from_str(&report).expect("Invalid JSON");
+        let before = parsed["pageCountBefore"].as_i64().expect("pages before");
+        let after = parsed["pageCountAfter"].as_i64().expect("pages after");
+        assert!(after < before, "expected {after} < {before}");
+        assert!(parsed["bytesFreed"].as_i64().expect("bytes freed") > 0);
+
+        db.exec("BEGIN").await.expect("Begin failed");
+        let refused = db.vacuum().await;
+        assert!(refused.unwrap_err().contains("transaction is open"));
+        db.exec("ROLLBACK").await.expect("Rollback failed");
+    }
 
-        db.exec("CREATE TABLE test_text (message TEXT, empty TEXT, null_val TEXT)")
+    #[wasm_bindgen_test]
+    async fn test_database_size_tracks_inserts_and_vacuum() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+        let size = |text: String| text.parse::<i64>().expect("numeric size");
+        db.exec("CREATE TABLE IF NOT EXISTS size_test (id INTEGER PRIMARY KEY, payload TEXT)")
             .await
             .expect("Create failed");
-        db.exec("INSERT INTO test_text VALUES ('Hello World', '', NULL)")
+        let empty = size(db.database_size().await.expect("Size failed"));
+        assert!(empty > 0);
+
+        db.exec(
+            "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 2000) \
+             INSERT INTO size_test (payload) SELECT hex(randomblob(256)) FROM n",
+        )
+        .await
+        .expect("Insert failed");
+        let grown = size(db.database_size().await.expect("Size failed"));
+        assert!(grown > empty, "expected {grown} > {empty}");
+
+        db.exec("DELETE FROM size_test")
             .await
-            .expect("Insert failed");
+            .expect("Delete failed");
+        db.vacuum().await.expect("Vacuum failed");
+        let shrunk = size(db.database_size().await.expect("Size failed"));
+        assert!(shrunk < grown, "expected {shrunk} < {grown}");
+    }
 
-        let result = db
-            .exec("SELECT * FROM test_text")
+    #[wasm_bindgen_test]
+    async fn test_transaction_rolls_back_every_statement_on_failure() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+        db.exec("CREATE TABLE IF NOT EXISTS tx_batch (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
             .await
-            .expect("Select failed");
-        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
-        let array = parsed.as_array().expect("Should be array");
-        let row = &array[0];
+            .expect("Create failed");
+        db.exec("DELETE FROM tx_batch")
+            .await
+            .expect("Delete failed");
+        let statement = |sql: &str, params: Option<Vec<serde_json::Value>>| BatchStatement {
+            sql: sql.to_string(),
+            params,
+        };
 
-        assert_eq!(
-            row["message"].as_str().unwrap(),
-            "Hello World",
-            "Text column should contain 'Hello World'"
+        let failed = db
+            .transaction(
+                vec![
+                    statement(
+                        "INSERT INTO tx_batch (id, name) VALUES (?, ?)",
+                        Some(vec![serde_json::json!(1), serde_json::json!("a")]),
+                    ),
+                    statement("INSERT INTO tx_batch (id, name) VALUES (2, 'b')", None),
+                    statement("INSERT INTO tx_batch (id, name) VALUES (3, NULL)", None),
+                ],
+                None,
+            )
+            .await;
+        let message = failed.unwrap_err();
+        assert!(
+            message.starts_with("Statement 3 failed: "),
+            "unexpected error: {message}"
         );
+        assert!(!db.in_transaction);
+        let count = db
+            .exec("SELECT COUNT(*) AS n FROM tx_batch")
+            .await
+            .expect("Count failed");
+        assert!(count.contains("\"n\": 0"), "unexpected rows: {count}");
+
+        let done = db
+            .transaction(
+                vec![
+                    statement("INSERT INTO tx_batch (id, name) VALUES (1, 'a')", None),
+                    statement("SELECT name FROM tx_batch", None),
+                ],
+                None,
+            )
+            .await
+            .expect("Transaction failed");
+        let parsed: serde_json::Value = serde_json::from_str(&done).expect("Invalid JSON");
         assert_eq!(
-            row["empty"].as_str().unwrap(),
-            "",
-            "Empty text column should be empty string"
+            parsed[0],
+            serde_json::json!("Query executed successfully. Rows affected: 1")
         );
+        assert_eq!(parsed[1], serde_json::json!([{ "name": "a" }]));
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_chunked_transaction_keeps_committed_chunks() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+        db.exec(
+            "CREATE TABLE IF NOT EXISTS tx_chunks (id INTEGER PRIMARY KEY, n INTEGER NOT NULL)",
+        )
+        .await
+        .expect("Create failed");
+        db.exec("DELETE FROM tx_chunks")
+            .await
+            .expect("Delete failed");
+        let rows = |failing_at: Option<usize>| -> Vec<BatchStatement> {
+            (0..5000)
+                .map(|i| BatchStatement {
+                    sql: "INSERT INTO tx_chunks (n) VALUES (?)".to_string(),
+                    params: Some(vec![if Some(i) == failing_at {
+                        serde_json::Value::Null
+                    } else {
+                        serde_json::json!(i)
+                    }]),
+                })
+                .collect()
+        };
+        let count = |text: String| -> i64 {
+            let parsed: serde_json::Value = serde_json::from_str(&text).expect("Invalid JSON");
+            parsed[0]["n"].as_i64().expect("count")
+        };
+
+        db.take_events();
+        let failed = db.transaction(rows(Some(2500)), Some(1000)).await;
+        let message = failed.unwrap_err();
         assert!(
-            row["null_val"].is_null(),
-            "NULL text column should be null in JSON"
+            message.starts_with("Statement 2501 failed after 2000 statements were committed"),
+            "unexpected error: {message}"
         );
+        assert!(!db.in_transaction);
+        let inserted: usize = db
+            .take_events()
+            .into_iter()
+            .map(|event| match event {
+                WorkerEvent::DbChange { changes } => changes.len(),
+                _ => 0,
+            })
+            .sum();
+        assert_eq!(inserted, 2000, "committed chunks keep their change events");
+        let persisted = db
+            .exec("SELECT COUNT(*) AS n FROM tx_chunks")
+            .await
+            .expect("Count failed");
+        assert_eq!(count(persisted), 2000);
+
+        db.exec("DELETE FROM tx_chunks")
+            .await
+            .expect("Delete failed");
+        db.transaction(rows(None), Some(1000))
+            .await
+            .expect("Chunked transaction failed");
+        let persisted = db
+            .exec("SELECT COUNT(*) AS n FROM tx_chunks")
+            .await
+            .expect("Count failed");
+        assert_eq!(count(persisted), 5000);
     }
 
-    // exec_with_params integration tests
-    // 1) Positional '?' bindings with multiple types
     #[wasm_bindgen_test]
-    async fn test_exec_with_params_positional_multiple_types() {
+    async fn test_page_count_grows_and_page_size_is_fixed_by_tables() {
         let Some(mut db) = get_test_db().await else {
             return;
         };
-
+        db.exec("CREATE TABLE IF NOT EXISTS page_test (id INTEGER PRIMARY KEY, payload TEXT)")
+            .await
+            .expect("Create failed");
+        let before = db.pragma_i64("page_count").await.expect("page_count");
         db.exec(
-            "CREATE TABLE params_positional (
-                null_col TEXT,
-                bool_col INTEGER,
-                int_col INTEGER,
-                float_col REAL,
-                text_col TEXT
-            )",
+            "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 500) \
+             INSERT INTO page_test (payload) SELECT hex(randomblob(256)) FROM n",
         )
         .await
-        .expect("Create failed");
+        .expect("Insert failed");
+        let after = db.pragma_i64("page_count").await.expect("page_count");
+        assert!(after > before, "expected {after} > {before}");
+
+        let current = db.page_size(None).await.expect("page_size");
+        let err = db
+            .page_size(Some(8192))
+            .await
+            .expect_err("tables already exist");
+        assert!(err.contains("before the first table"), "{err}");
+        assert_eq!(db.page_size(None).await.expect("page_size"), current);
+        assert!(db.page_size(Some(1000)).await.is_err());
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_page_size_applies_to_an_empty_database() {
+        let Ok(mut db) = SQLiteDatabase::initialize_opfs("testdb-page-size").await else {
+            return;
+        };
+        let size = db.page_size(Some(8192)).await.expect("page_size");
+        assert_eq!(size, "8192");
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_explain_reports_index_use_without_running_the_statement() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+        db.exec("CREATE TABLE IF NOT EXISTS explain_test (id INTEGER PRIMARY KEY, indexed_col INTEGER, other TEXT)")
+            .await
+            .expect("Create failed");
+        db.exec("CREATE INDEX IF NOT EXISTS explain_test_indexed ON explain_test (indexed_col)")
+            .await
+            .expect("Create index failed");
+        db.exec("DELETE FROM explain_test")
+            .await
+            .expect("Delete failed");
+
+        let plan = db
+            .explain("SELECT * FROM explain_test WHERE indexed_col = 1", None)
+            .await
+            .expect("Explain failed");
+        let steps: serde_json::Value = serde_json::from_str(&plan).expect("Invalid JSON");
+        let steps = steps.as_array().expect("plan is an array");
+        assert!(!steps.is_empty());
+        assert!(steps.iter().all(|step| step["id"].is_i64()
+            && step["parent"].is_i64()
+            && step["detail"].is_string()));
+        assert!(
+            steps.iter().any(|step| step["detail"]
+                .as_str()
+                .is_some_and(|detail| detail.contains("INDEX explain_test_indexed"))),
+            "plan should use the index: {steps:?}"
+        );
+
+        db.explain(
+            "INSERT INTO explain_test (indexed_col) VALUES (?)",
+            Some(vec![json!(1)]),
+        )
+        .await
+        .expect("Explain with params failed");
+        let count = db
+            .exec("SELECT COUNT(*) AS n FROM explain_test")
+            .await
+            .expect("Count failed");
+        let parsed: serde_json::Value = serde_json::from_str(&count).expect("Invalid JSON");
+        assert_eq!(
+            parsed,
+            json!([{ "n": 0 }]),
+            "explain must not run the insert"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_integrity_check_reports_ok() {
+        let Some(db) = get_test_db().await else {
+            return;
+        };
+        let report = db.integrity_check().await.expect("Integrity check failed");
+        let parsed: serde_json::Value = serde_json::from_str(&report).expect("Invalid JSON");
+        assert_eq!(parsed, json!({ "ok": true, "problems": [] }));
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_namespaces_keep_same_named_databases_apart() {
+        let open = |namespace: &str| OpenOptions {
+            namespace: Some(namespace.to_string()),
+            ..Default::default()
+        };
+        let Ok(mut first) =
+            SQLiteDatabase::initialize_opfs_with_options("testdb-shared", &open("app-one")).await
+        else {
+            return;
+        };
+        let Ok(mut second) =
+            SQLiteDatabase::initialize_opfs_with_options("testdb-shared", &open("app-two")).await
+        else {
+            return;
+        };
+
+        first
+            .exec("CREATE TABLE IF NOT EXISTS ns_items (name TEXT); DELETE FROM ns_items; INSERT INTO ns_items VALUES ('one')")
+            .await
+            .expect("Write to first namespace failed");
+        let missing = second.exec("SELECT name FROM ns_items").await;
+        assert!(
+            missing.unwrap_err().contains("no such table"),
+            "The second namespace should not see the first one's tables"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_open_applies_configured_pragmas_in_order() {
+        let options = OpenOptions {
+            pragmas: vec![
+                "foreign_keys = ON".to_string(),
+                "recursive_triggers = 1".to_string(),
+                "cache_size = -4096".to_string(),
+            ],
+            ..Default::default()
+        };
+        let Ok(mut db) =
+            SQLiteDatabase::initialize_opfs_with_options("testdb-pragmas", &options).await
+        else {
+            return;
+        };
+
+        let result = db
+            .exec(
+                "SELECT (SELECT foreign_keys FROM pragma_foreign_keys) AS fk, \
+                 (SELECT recursive_triggers FROM pragma_recursive_triggers) AS rt, \
+                 (SELECT cache_size FROM pragma_cache_size) AS cache",
+            )
+            .await
+            .expect("Query failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        assert_eq!(parsed, json!([{ "fk": 1, "rt": 1, "cache": -4096 }]));
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_open_fails_when_a_configured_pragma_fails() {
+        if get_test_db().await.is_none() {
+            return;
+        }
+        let options = OpenOptions {
+            pragmas: vec!["foreign_keys = ON".to_string(), "table_info(".to_string()],
+            ..Default::default()
+        };
+        let Err(err) =
+            SQLiteDatabase::initialize_opfs_with_options("testdb-bad-pragma", &options).await
+        else {
+            panic!("a failing pragma should abort opening the database");
+        };
+        let message = err.as_string().unwrap_or_default();
+        assert!(
+            message.starts_with("Failed to apply PRAGMA table_info(:"),
+            "unexpected error: {message}"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_custom_functions_can_be_disabled() {
+        let options = OpenOptions {
+            custom_functions: FunctionSelection::Only(vec![]),
+            ..Default::default()
+        };
+        let Ok(mut db) =
+            SQLiteDatabase::initialize_opfs_with_options("testdb-no-functions", &options).await
+        else {
+            return;
+        };
+
+        let missing = db.exec("SELECT FLOAT_SUM('0x0') AS total").await;
+        assert!(
+            missing.unwrap_err().contains("no such function: FLOAT_SUM"),
+            "FLOAT_SUM should not be registered"
+        );
+
+        let result = db
+            .exec("SELECT sum(n) AS total FROM (SELECT 1 AS n UNION ALL SELECT 2)")
+            .await
+            .expect("Built-in SQL should still work");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        assert_eq!(parsed, json!([{ "total": 3 }]));
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_custom_functions_can_be_selected() {
+        let options = OpenOptions {
+            custom_functions: FunctionSelection::Only(vec!["BIGINT_SUM".to_string()]),
+            ..Default::default()
+        };
+        let Ok(mut db) =
+            SQLiteDatabase::initialize_opfs_with_options("testdb-some-functions", &options).await
+        else {
+            return;
+        };
+
+        let result = db
+            .exec("SELECT BIGINT_SUM(n) AS total FROM (SELECT 1 AS n UNION ALL SELECT 2)")
+            .await
+            .expect("BIGINT_SUM should be registered");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        assert_eq!(parsed, json!([{ "total": "3" }]));
+
+        for excluded in [
+            "FLOAT_SUM('0x0')",
+            "FLOAT_ZERO_HEX()",
+            "FLOAT_IS_ZERO('0x0')",
+        ] {
+            let missing = db.exec(&format!("SELECT {excluded} AS value")).await;
+            assert!(
+                missing.unwrap_err().contains("no such function"),
+                "{excluded} should not be registered"
+            );
+        }
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_float_count_nonzero_counts_per_group() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+        db.exec("CREATE TABLE IF NOT EXISTS float_nonzero_test (grp TEXT, amount TEXT)")
+            .await
+            .expect("Create failed");
+        db.exec("DELETE FROM float_nonzero_test")
+            .await
+            .expect("Delete failed");
+        let hex = |d: &str| {
+            rain_math_float::Float::parse(d.to_string())
+                .unwrap()
+                .as_hex()
+        };
+        for (grp, value) in [
+            ("mixed", "0"),
+            ("mixed", "2.5"),
+            ("mixed", "-1"),
+            ("zeros", "0"),
+            ("zeros", "0"),
+        ] {
+            db.exec_with_params(
+                "INSERT INTO float_nonzero_test (grp, amount) VALUES (?, ?)",
+                vec![json!(grp), json!(hex(value))],
+            )
+            .await
+            .expect("Insert failed");
+        }
+
+        let result = db
+            .exec(
+                "SELECT grp, FLOAT_COUNT_NONZERO(amount) AS n FROM float_nonzero_test \
+                 GROUP BY grp ORDER BY grp",
+            )
+            .await
+            .expect("Query failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        assert_eq!(
+            parsed,
+            json!([{ "grp": "mixed", "n": 2 }, { "grp": "zeros", "n": 0 }])
+        );
+
+        db.exec("INSERT INTO float_nonzero_test (grp, amount) VALUES ('mixed', 'garbage')")
+            .await
+            .expect("Insert failed");
+        let invalid = db
+            .exec("SELECT FLOAT_COUNT_NONZERO(amount) AS n FROM float_nonzero_test")
+            .await;
+        assert!(invalid.is_err(), "invalid hex should fail the aggregate");
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_builtin_json_functions_build_and_extract_objects() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+        db.exec("CREATE TABLE IF NOT EXISTS json_fn_test (id INTEGER PRIMARY KEY, name TEXT)")
+            .await
+            .expect("Create failed");
+        db.exec("DELETE FROM json_fn_test")
+            .await
+            .expect("Delete failed");
+        db.exec("INSERT INTO json_fn_test (id, name) VALUES (1, 'alice'), (2, 'bob')")
+            .await
+            .expect("Insert failed");
 
-        // Insert with plain positional placeholders
-        let insert_res = db
-            .exec_with_params(
-                "INSERT INTO params_positional (null_col, bool_col, int_col, float_col, text_col) VALUES (?, ?, ?, ?, ?)",
-                vec![json!(null), json!(true), json!(42), json!(3.5), json!("hello")],
+        let result = db
+            .exec(
+                "SELECT json_group_array(json_object('id', id, 'name', name)) AS rows \
+                 FROM (SELECT * FROM json_fn_test ORDER BY id)",
             )
-            .await;
-        assert!(insert_res.is_ok(), "INSERT with params should succeed");
-        assert!(
-            insert_res.unwrap().contains("Rows affected: 1"),
-            "INSERT should report 1 row affected"
+            .await
+            .expect("json_group_array failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        let rows: serde_json::Value =
+            serde_json::from_str(parsed[0]["rows"].as_str().expect("TEXT column"))
+                .expect("column holds JSON");
+        assert_eq!(
+            rows,
+            json!([{ "id": 1, "name": "alice" }, { "id": 2, "name": "bob" }])
         );
 
-        // Select using positional binding as well
-        let select_json = db
+        let result = db
             .exec_with_params(
-                "SELECT null_col, bool_col, int_col, float_col, text_col FROM params_positional WHERE int_col = ?",
-                vec![json!(42)],
+                "SELECT json_extract(?, '$.user.name') AS name, \
+                 json_extract(?, '$.user.tags[1]') AS tag",
+                vec![
+                    json!(r#"{"user":{"name":"carol","tags":["a","b"]}}"#),
+                    json!(r#"{"user":{"name":"carol","tags":["a","b"]}}"#),
+                ],
             )
             .await
-            .expect("SELECT with params failed");
-        let parsed: serde_json::Value = serde_json::from_str(&select_json).expect("Invalid JSON");
-        let rows = parsed.as_array().expect("Expected array JSON");
-        assert_eq!(rows.len(), 1, "Should return one matching row");
-        let row = &rows[0];
-        assert!(row["null_col"].is_null(), "Null round-trips as null");
-        assert_eq!(row["bool_col"].as_i64().unwrap(), 1, "true -> 1");
-        assert_eq!(row["int_col"].as_i64().unwrap(), 42);
-        assert!((row["float_col"].as_f64().unwrap() - 3.5).abs() < 1e-9);
-        assert_eq!(row["text_col"].as_str().unwrap(), "hello");
+            .expect("json_extract failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        assert_eq!(parsed, json!([{ "name": "carol", "tag": "b" }]));
     }
 
-    // 2) Numbered placeholders '?N' including gap detection and duplicates
     #[wasm_bindgen_test]
-    async fn test_exec_with_params_numbered_duplicate_indices_allowed() {
+    async fn test_float_sum_saturating_caps_an_overflowing_sum() {
         let Some(mut db) = get_test_db().await else {
             return;
         };
-
-        db.exec("CREATE TABLE numbered_dup (a INTEGER, b INTEGER)")
+        db.exec("CREATE TABLE IF NOT EXISTS float_saturate_test (amount TEXT)")
             .await
             .expect("Create failed");
-
-        // Duplicate ?1 should reuse the same bound value
-        let res = db
-            .exec_with_params(
-                "INSERT INTO numbered_dup (a, b) VALUES (?1, ?1)",
-                vec![json!(7)],
+        db.exec("DELETE FROM float_saturate_test")
+            .await
+            .expect("Delete failed");
+        let max = "0x7fffffff7fffffffffffffffffffffffffffffffffffffffffffffffffffffff";
+        for _ in 0..2 {
+            db.exec_with_params(
+                "INSERT INTO float_saturate_test (amount) VALUES (?)",
+                vec![json!(max)],
             )
+            .await
+            .expect("Insert failed");
+        }
+
+        let failed = db
+            .exec("SELECT FLOAT_SUM(amount) AS total FROM float_saturate_test")
             .await;
-        assert!(res.is_ok(), "Duplicate numbered index should succeed");
+        assert!(
+            failed.unwrap_err().contains("Float overflow"),
+            "FLOAT_SUM keeps failing on overflow"
+        );
 
-        let out = db
-            .exec("SELECT a, b FROM numbered_dup")
+        let result = db
+            .exec("SELECT FLOAT_SUM_SATURATING(amount) AS total FROM float_saturate_test")
             .await
-            .expect("Select failed");
-        let parsed: serde_json::Value = serde_json::from_str(&out).expect("Invalid JSON");
-        let rows = parsed.as_array().expect("Expected array JSON");
-        assert_eq!(rows.len(), 1);
-        assert_eq!(rows[0]["a"].as_i64().unwrap(), 7);
-        assert_eq!(rows[0]["b"].as_i64().unwrap(), 7);
+            .expect("Query failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        let total = parsed[0]["total"].as_str().expect("hex total");
+        assert_eq!(
+            rain_math_float::Float::from_hex(total)
+                .unwrap()
+                .format()
+                .unwrap(),
+            rain_math_float::Float::from_hex(max)
+                .unwrap()
+                .format()
+                .unwrap()
+        );
     }
 
     #[wasm_bindgen_test]
-    async fn test_exec_with_params_numbered_gap_error() {
+    async fn test_float_validate_flags_bad_rows() {
         let Some(mut db) = get_test_db().await else {
             return;
         };
+        db.exec("CREATE TABLE IF NOT EXISTS float_validate_test (id INTEGER, amount TEXT)")
+            .await
+            .expect("Create failed");
+        db.exec("DELETE FROM float_validate_test")
+            .await
+            .expect("Delete failed");
+        let valid = rain_math_float::Float::parse("3.25".to_string())
+            .unwrap()
+            .as_hex();
+        for (id, amount) in [
+            (1, json!(valid)),
+            (2, json!("garbage")),
+            (3, json!("")),
+            (4, serde_json::Value::Null),
+        ] {
+            db.exec_with_params(
+                "INSERT INTO float_validate_test (id, amount) VALUES (?, ?)",
+                vec![json!(id), amount],
+            )
+            .await
+            .expect("Insert failed");
+        }
 
-        // Use ?1 and ?3 with a missing ?2 to trigger continuity error
-        let err = db
-            .exec_with_params("SELECT ?1, ?3", vec![json!(10), json!(30)])
+        let result = db
+            .exec("SELECT id, FLOAT_VALIDATE(amount) AS ok FROM float_validate_test ORDER BY id")
             .await
-            .unwrap_err();
-        assert!(
-            err.contains("Missing parameter index ?2"),
-            "Should report missing index in numbered placeholders"
+            .expect("Query failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        assert_eq!(
+            parsed,
+            json!([
+                { "id": 1, "ok": 1 },
+                { "id": 2, "ok": 0 },
+                { "id": 3, "ok": 0 },
+                { "id": 4, "ok": null }
+            ])
         );
+
+        let bad = db
+            .exec("SELECT id FROM float_validate_test WHERE NOT FLOAT_VALIDATE(amount) ORDER BY id")
+            .await
+            .expect("Query failed");
+        let parsed: serde_json::Value = serde_json::from_str(&bad).expect("Invalid JSON");
+        assert_eq!(parsed, json!([{ "id": 2 }, { "id": 3 }]));
     }
 
-    // 3) BLOB object and bigint-as-string handling
     #[wasm_bindgen_test]
-    async fn test_exec_with_params_blob_and_bigint() {
+    async fn test_regexp_operator_filters_rows() {
         let Some(mut db) = get_test_db().await else {
             return;
         };
-
-        db.exec("CREATE TABLE binint_test (b BLOB, bi INTEGER)")
+        db.exec("CREATE TABLE IF NOT EXISTS regexp_test (id INTEGER, email TEXT)")
             .await
             .expect("Create failed");
+        db.exec("DELETE FROM regexp_test")
+            .await
+            .expect("Delete failed");
+        for (id, email) in [
+            (1, json!("alice@example.com")),
+            (2, json!("bob@example.org")),
+            (3, json!("not-an-email")),
+            (4, serde_json::Value::Null),
+        ] {
+            db.exec_with_params(
+                "INSERT INTO regexp_test (id, email) VALUES (?, ?)",
+                vec![json!(id), email],
+            )
+            .await
+            .expect("Insert failed");
+        }
 
-        // Base64 for bytes: "Rust" -> 52 75 73 74
-        let blob_b64 = base64::engine::general_purpose::STANDARD.encode(b"Rust");
-        let big_str = "9223372036854775807"; // i64::MAX
-
-        let res = db
-            .exec_with_params(
-                "INSERT INTO binint_test (b, bi) VALUES (?, ?)",
-                vec![
-                    json!({"__type":"blob","base64": blob_b64}),
-                    json!({"__type":"bigint","value": big_str}),
-                ],
+        let result = db
+            .exec(
+                "SELECT id FROM regexp_test WHERE email REGEXP '@example\\.(com|org)$' ORDER BY id",
             )
-            .await;
-        assert!(res.is_ok(), "INSERT blob/bigint should succeed");
+            .await
+            .expect("Query failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        assert_eq!(parsed, json!([{ "id": 1 }, { "id": 2 }]));
 
-        // Verify using a SELECT that returns numeric length and bigint value
-        let verify = db
-            .exec("SELECT length(b) AS blen, bi FROM binint_test")
+        let result = db
+            .exec("SELECT id, REGEXP('^[a-z]+@', email) AS ok FROM regexp_test ORDER BY id")
             .await
-            .expect("Select failed");
-        let parsed: serde_json::Value = serde_json::from_str(&verify).expect("Invalid JSON");
-        let rows = parsed.as_array().expect("Expected array JSON");
-        assert_eq!(rows.len(), 1);
-        assert_eq!(rows[0]["blen"].as_i64().unwrap(), 4, "Blob length matches");
+            .expect("Query failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
         assert_eq!(
-            rows[0]["bi"].as_i64().unwrap(),
-            9_223_372_036_854_775_807,
-            "Bigint stored and returned as i64"
+            parsed,
+            json!([
+                { "id": 1, "ok": 1 },
+                { "id": 2, "ok": 1 },
+                { "id": 3, "ok": 0 },
+                { "id": 4, "ok": null }
+            ])
         );
 
-        // Also check the blob pretty string form when selecting the BLOB directly
-        let blob_str = db
-            .exec("SELECT b FROM binint_test")
+        let err = db
+            .exec("SELECT id FROM regexp_test WHERE email REGEXP '(unclosed'")
             .await
-            .expect("Select blob failed");
-        let blob_val: serde_json::Value =
-            serde_json::from_str(&blob_str).expect("Invalid JSON for blob row");
-        let arr = blob_val.as_array().expect("Expected array JSON");
-        let bstr = arr[0]["b"]
-            .as_str()
-            .expect("Expected string marker for blob");
-        assert!(
-            bstr.contains("<blob 4 bytes>"),
-            "Blob marker includes length"
-        );
+            .unwrap_err();
+        assert!(err.contains("REGEXP: invalid pattern"), "{err}");
     }
 
     #[wasm_bindgen_test]
-    async fn test_blob_column_handling() {
+    async fn test_unicode_case_functions_fold_non_ascii_text() {
         let Some(mut db) = get_test_db().await else {
             return;
         };
+        let result = db
+            .exec(
+                "SELECT upper('café') AS ascii_upper, \
+                        UNICODE_UPPER('café') AS upper, \
+                        UNICODE_LOWER('ÉCOLE Ñandú') AS lower, \
+                        UNICODE_UPPER('Grüße, Κόσμε и мир') AS mixed, \
+                        UNICODE_LOWER(NULL) AS missing",
+            )
+            .await
+            .expect("Query failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        assert_eq!(
+            parsed,
+            json!([{
+                "ascii_upper": "CAFé",
+                "upper": "CAFÉ",
+                "lower": "école ñandú",
+                "mixed": "GRÜSSE, ΚΌΣΜΕ И МИР",
+                "missing": null
+            }])
+        );
 
-        db.exec("CREATE TABLE test_blob (data BLOB)")
+        db.exec("CREATE TABLE IF NOT EXISTS unicode_case_test (name TEXT)")
             .await
             .expect("Create failed");
-        db.exec("INSERT INTO test_blob VALUES (X'48656C6C6F')")
+        db.exec("DELETE FROM unicode_case_test")
+            .await
+            .expect("Delete failed");
+        db.exec("INSERT INTO unicode_case_test (name) VALUES ('Émile'), ('émile'), ('Emile')")
             .await
             .expect("Insert failed");
-
         let result = db
-            .exec("SELECT * FROM test_blob")
+            .exec(
+                "SELECT COUNT(*) AS n FROM unicode_case_test \
+                 WHERE UNICODE_LOWER(name) = UNICODE_LOWER('ÉMILE')",
+            )
             .await
-            .expect("Select failed");
+            .expect("Query failed");
         let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
-        let array = parsed.as_array().expect("Should be array");
-        let row = &array[0];
-
-        let blob_str = row["data"].as_str().unwrap();
-        assert!(
-            blob_str.starts_with("<blob"),
-            "BLOB data should start with '<blob'"
-        );
-        assert!(
-            blob_str.contains("bytes>"),
-            "BLOB data should contain 'bytes>''"
-        );
+        assert_eq!(parsed, json!([{ "n": 2 }]));
     }
 
     #[wasm_bindgen_test]
-    async fn test_column_names_handling() {
+    async fn test_uuid_v4_default_gives_each_row_a_distinct_key() {
         let Some(mut db) = get_test_db().await else {
             return;
         };
-
-        db.exec("CREATE TABLE test_cols (id INTEGER, full_name TEXT, \"quoted col\" INTEGER)")
+        db.exec(
+            "CREATE TABLE IF NOT EXISTS uuid_v4_test \
+             (id TEXT PRIMARY KEY DEFAULT (UUID_V4()), label TEXT)",
+        )
+        .await
+        .expect("Create failed");
+        db.exec("DELETE FROM uuid_v4_test")
             .await
-            .expect("Create failed");
-        db.exec("INSERT INTO test_cols VALUES (1, 'John Doe', 100)")
+            .expect("Delete failed");
+        db.exec("INSERT INTO uuid_v4_test (label) VALUES ('a'), ('b'), ('c'), ('d'), ('e')")
             .await
             .expect("Insert failed");
+        db.exec("INSERT INTO uuid_v4_test (label) SELECT label FROM uuid_v4_test")
+            .await
+            .expect("Insert select failed");
 
         let result = db
-            .exec("SELECT * FROM test_cols")
+            .exec("SELECT COUNT(*) AS n, COUNT(DISTINCT id) AS distinct_ids FROM uuid_v4_test")
             .await
-            .expect("Select failed");
+            .expect("Query failed");
         let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
-        let array = parsed.as_array().expect("Should be array");
-        let row = &array[0];
+        assert_eq!(parsed, json!([{ "n": 10, "distinct_ids": 10 }]));
 
-        assert!(row.get("id").is_some(), "Should have 'id' column");
-        assert!(
-            row.get("full_name").is_some(),
-            "Should have 'full_name' column"
-        );
-        assert!(
-            row.get("quoted col").is_some(),
-            "Should have 'quoted col' column with spaces"
-        );
+        let result = db
+            .exec("SELECT id FROM uuid_v4_test")
+            .await
+            .expect("Query failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        for row in parsed.as_array().unwrap() {
+            let id = row["id"].as_str().expect("text id");
+            let uuid = uuid::Uuid::parse_str(id).expect("canonical UUID");
+            assert_eq!(uuid.get_version_num(), 4);
+            assert_eq!(id, uuid.hyphenated().to_string());
+        }
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_now_ms_reads_the_worker_clock_in_milliseconds() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+        let before = js_sys::Date::now() as i64;
+        let first = db
+            .exec("SELECT NOW_MS() AS t, typeof(NOW_MS()) AS kind")
+            .await
+            .expect("Query failed");
+        let second = db.exec("SELECT NOW_MS() AS t").await.expect("Query failed");
+        let after = js_sys::Date::now() as i64;
+
+        let first: serde_json::Value = serde_json::from_str(&first).expect("Invalid JSON");
+        let second: serde_json::Value = serde_json::from_str(&second).expect("Invalid JSON");
+        assert_eq!(first[0]["kind"], json!("integer"));
+        let first = first[0]["t"].as_i64().expect("integer timestamp");
+        let second = second[0]["t"].as_i64().expect("integer timestamp");
+        // 2024-01-01T00:00:00Z, well before any run of this test.
+        assert!(first > 1_704_067_200_000, "{first}");
+        assert!(before <= first && first <= second && second <= after);
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_bigint_validate_passes_null_through() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+        let result = db
+            .exec(
+                "SELECT BIGINT_VALIDATE('123') AS valid, BIGINT_VALIDATE('1e5') AS garbage, \
+                 BIGINT_VALIDATE(NULL) AS missing",
+            )
+            .await
+            .expect("Query failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
         assert_eq!(
-            row["full_name"].as_str().unwrap(),
-            "John Doe",
-            "full_name should be 'John Doe'"
+            parsed,
+            json!([{ "valid": 1, "garbage": 0, "missing": null }])
         );
     }
 
     #[wasm_bindgen_test]
-    async fn test_update_query() {
+    async fn test_float_coll_orders_hex_floats_numerically() {
         let Some(mut db) = get_test_db().await else {
             return;
         };
-
-        db.exec("CREATE TABLE test_update (id INTEGER, value INTEGER)")
+        db.exec("CREATE TABLE IF NOT EXISTS float_coll_test (amount TEXT)")
             .await
             .expect("Create failed");
-        db.exec("INSERT INTO test_update VALUES (1, 10), (2, 20), (3, 30)")
+        db.exec("DELETE FROM float_coll_test")
+            .await
+            .expect("Delete failed");
+        let hex = |d: &str| {
+            rain_math_float::Float::parse(d.to_string())
+                .unwrap()
+                .as_hex()
+        };
+        for value in ["2", "-7.5", "0.125", "-0.5", "100"] {
+            db.exec_with_params(
+                "INSERT INTO float_coll_test (amount) VALUES (?)",
+                vec![json!(hex(value))],
+            )
+            .await
+            .expect("Insert failed");
+        }
+        db.exec("INSERT INTO float_coll_test (amount) VALUES ('garbage'), (NULL)")
             .await
             .expect("Insert failed");
 
         let result = db
-            .exec("UPDATE test_update SET value = value * 2 WHERE id > 1")
-            .await;
-        assert!(result.is_ok());
-        let update_result = result.unwrap();
-        assert!(
-            update_result.contains("Rows affected: 2"),
-            "UPDATE should affect exactly 2 rows"
-        );
-
-        let select_result = db
-            .exec("SELECT value FROM test_update ORDER BY id")
+            .exec("SELECT amount FROM float_coll_test ORDER BY amount COLLATE FLOAT_COLL")
             .await
-            .expect("Select failed");
-        let parsed: serde_json::Value = serde_json::from_str(&select_result).expect("Invalid JSON");
-        let array = parsed.as_array().expect("Should be array");
-
-        assert_eq!(
-            array[0]["value"].as_i64().unwrap(),
-            10,
-            "First row value should remain 10"
-        );
-        assert_eq!(
-            array[1]["value"].as_i64().unwrap(),
-            40,
-            "Second row value should be doubled to 40"
-        );
-        assert_eq!(
-            array[2]["value"].as_i64().unwrap(),
-            60,
-            "Third row value should be doubled to 60"
-        );
+            .expect("Query failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        let expected: Vec<serde_json::Value> = std::iter::once(serde_json::Value::Null)
+            .chain(
+                ["-7.5", "-0.5", "0.125", "2", "100"]
+                    .iter()
+                    .map(|d| json!(hex(d))),
+            )
+            .chain(std::iter::once(json!("garbage")))
+            .collect();
+        let amounts: Vec<serde_json::Value> = parsed
+            .as_array()
+            .expect("rows")
+            .iter()
+            .map(|row| row["amount"].clone())
+            .collect();
+        assert_eq!(amounts, expected);
     }
 
     #[wasm_bindgen_test]
-    async fn test_delete_query() {
+    async fn test_import_round_trips_an_export() {
         let Some(mut db) = get_test_db().await else {
             return;
         };
-
-        db.exec("CREATE TABLE test_delete (id INTEGER, name TEXT)")
+        db.exec("CREATE TABLE IF NOT EXISTS import_test (id INTEGER PRIMARY KEY, name TEXT)")
             .await
             .expect("Create failed");
-        db.exec("INSERT INTO test_delete VALUES (1, 'keep'), (2, 'delete'), (3, 'delete')")
+        db.exec("DELETE FROM import_test")
+            .await
+            .expect("Delete failed");
+        db.exec("INSERT INTO import_test (name) VALUES ('alpha'), ('beta'), ('gamma')")
             .await
             .expect("Insert failed");
+        let image = db.export().expect("Export failed");
 
-        let result = db
-            .exec("DELETE FROM test_delete WHERE name = 'delete'")
-            .await;
-        assert!(result.is_ok());
-        let delete_result = result.unwrap();
-        assert!(
-            delete_result.contains("Rows affected: 2"),
-            "DELETE should affect exactly 2 rows"
-        );
+        db.exec("DELETE FROM import_test")
+            .await
+            .expect("Delete failed");
+        let done = db.import(&image).await;
+        assert_eq!(done.as_deref(), Ok("Import completed."));
 
-        let select_result = db
-            .exec("SELECT COUNT(*) as count FROM test_delete")
+        let rows = db
+            .exec("SELECT name FROM import_test ORDER BY id")
             .await
             .expect("Select failed");
-        let parsed: serde_json::Value = serde_json::from_str(&select_result).expect("Invalid JSON");
-        let array = parsed.as_array().expect("Should be array");
+        let parsed: serde_json::Value = serde_json::from_str(&rows).expect("Invalid JSON");
         assert_eq!(
-            array[0]["count"].as_i64().unwrap(),
-            1,
-            "Should have exactly 1 row remaining after delete"
+            parsed,
+            json!([{ "name": "alpha" }, { "name": "beta" }, { "name": "gamma" }])
         );
     }
 
     #[wasm_bindgen_test]
-    async fn test_invalid_sql_syntax_error() {
+    async fn test_import_rejects_corrupt_images_without_clobbering() {
         let Some(mut db) = get_test_db().await else {
             return;
         };
+        db.exec("CREATE TABLE IF NOT EXISTS import_guard (id INTEGER PRIMARY KEY)")
+            .await
+            .expect("Create failed");
+        db.exec("INSERT OR IGNORE INTO import_guard (id) VALUES (1)")
+            .await
+            .expect("Insert failed");
 
-        let result = db.exec("INVALID SQL SYNTAX HERE").await;
-        assert!(result.is_err());
-        let error = result.unwrap_err();
-        assert!(
-            error.contains("Failed to prepare statement"),
-            "Invalid SQL should produce prepare statement error"
-        );
+        let not_sqlite = db.import(b"definitely not a database").await;
+        assert!(not_sqlite.unwrap_err().contains("missing SQLite header"));
+
+        let mut truncated = db.export().expect("Export failed");
+        truncated.truncate(100);
+        truncated[20..].fill(0xff);
+        assert!(db.import(&truncated).await.is_err());
+
+        let rows = db
+            .exec("SELECT COUNT(*) AS count FROM import_guard")
+            .await
+            .expect("Existing data must survive a failed import");
+        let parsed: serde_json::Value = serde_json::from_str(&rows).expect("Invalid JSON");
+        assert_eq!(parsed[0]["count"], json!(1));
     }
 
     #[wasm_bindgen_test]
-    async fn test_sql_with_null_byte_error() {
-        let Some(mut db) = get_test_db().await else {
+    async fn test_flush_persists_writes_across_reopen() {
+        {
+            let Some(mut db) = get_test_db().await else {
+                return;
+            };
+            db.exec("CREATE TABLE IF NOT EXISTS flush_test (id INTEGER PRIMARY KEY, value TEXT)")
+                .await
+                .expect("Create failed");
+            db.exec("DELETE FROM flush_test")
+                .await
+                .expect("Delete failed");
+            db.exec("INSERT INTO flush_test (value) VALUES ('durable')")
+                .await
+                .expect("Insert failed");
+
+            let flushed = db.flush().await;
+            assert_eq!(flushed.as_deref(), Ok("Flush completed."));
+        }
+
+        let Some(mut reopened) = get_test_db().await else {
             return;
         };
-
-        let result = db.exec("SELECT * FROM table\0WITH NULL").await;
-        assert!(result.is_err());
-        let error = result.unwrap_err();
-        assert!(
-            error.contains("Invalid SQL string"),
-            "SQL with null bytes should produce invalid string error"
-        );
+        let rows = reopened
+            .exec("SELECT value FROM flush_test")
+            .await
+            .expect("Select failed");
+        let parsed: serde_json::Value = serde_json::from_str(&rows).expect("Invalid JSON");
+        let array = parsed.as_array().expect("Should be array");
+        assert_eq!(array.len(), 1);
+        assert_eq!(array[0]["value"].as_str().unwrap(), "durable");
     }
 
     #[wasm_bindgen_test]
-    async fn test_table_not_found_error() {
-        let Some(mut db) = get_test_db().await else {
+    async fn test_header_values_persist_across_reopen() {
+        {
+            let Some(mut db) = get_test_db().await else {
+                return;
+            };
+            let set = db.header_value(HeaderPragma::UserVersion, Some(7)).await;
+            assert_eq!(set.as_deref(), Ok("7"));
+            let set = db
+                .header_value(HeaderPragma::ApplicationId, Some(0x5157_4542))
+                .await;
+            assert_eq!(set.as_deref(), Ok("1364673858"));
+            assert!(!db.in_transaction);
+        }
+
+        let Some(mut reopened) = get_test_db().await else {
             return;
         };
+        let user_version = reopened.header_value(HeaderPragma::UserVersion, None).await;
+        assert_eq!(user_version.as_deref(), Ok("7"));
+        let application_id = reopened
+            .header_value(HeaderPragma::ApplicationId, None)
+            .await;
+        assert_eq!(application_id.as_deref(), Ok("1364673858"));
 
-        let result = db.exec("SELECT * FROM nonexistent_table").await;
-        assert!(result.is_err());
-        let error = result.unwrap_err();
-        assert!(
-            error.contains("Query execution failed") || error.contains("no such table"),
-            "Query on nonexistent table should fail with appropriate error"
-        );
+        reopened.exec("BEGIN").await.expect("Begin failed");
+        let _ = reopened
+            .header_value(HeaderPragma::UserVersion, Some(9))
+            .await;
+        reopened.exec("ROLLBACK").await.expect("Rollback failed");
+        let rolled_back = reopened.header_value(HeaderPragma::UserVersion, None).await;
+        assert_eq!(rolled_back.as_deref(), Ok("7"));
+
+        let _ = reopened
+            .header_value(HeaderPragma::UserVersion, Some(0))
+            .await;
+        let _ = reopened
+            .header_value(HeaderPragma::ApplicationId, Some(0))
+            .await;
     }
 
     #[wasm_bindgen_test]
-    async fn test_custom_functions_available() {
+    async fn test_flush_rejects_open_transaction() {
         let Some(mut db) = get_test_db().await else {
             return;
         };
 
-        let result = db.exec("SELECT float_add('1.5', '2.5') as result").await;
-        assert!(result.is_ok());
-
-        let json_str = result.unwrap();
-        let parsed: serde_json::Value = serde_json::from_str(&json_str).expect("Invalid JSON");
-        let array = parsed.as_array().expect("Should be array");
-        let row = &array[0];
+        db.exec("BEGIN TRANSACTION").await.expect("Begin failed");
+        let result = db.flush().await;
+        db.exec("ROLLBACK").await.expect("Rollback failed");
 
         assert!(
-            row.get("result").is_some(),
-            "Custom function should return a result"
+            result.is_err(),
+            "Flushing with uncommitted writes should fail"
         );
     }
 
-    #[wasm_bindgen_test]
-    async fn test_database_drop_cleanup() {
-        {
-            let Some(db) = get_test_db().await else {
-                return;
-            };
-            assert!(
-                !db.db.is_null(),
-                "Database pointer should be valid before drop"
-            );
-        }
-    }
-
     #[wasm_bindgen_test]
     async fn test_multiple_statements_handling() {
         let Some(mut db) = get_test_db().await else {
@@ -1782,6 +5754,252 @@ mod tests {
         assert_eq!(array[1]["name"].as_str().unwrap(), "c");
     }
 
+    #[wasm_bindgen_test]
+    async fn test_count_returns_one_row_with_or_without_trailing_semicolon() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        db.exec("CREATE TABLE count_dispatch_test (id INTEGER)")
+            .await
+            .expect("Create failed");
+
+        let expected = json!([{ "count": 0 }]);
+        for sql in [
+            "SELECT COUNT(*) AS count FROM count_dispatch_test",
+            "SELECT COUNT(*) AS count FROM count_dispatch_test;",
+            "  SELECT COUNT(*) AS count FROM count_dispatch_test ;  \n",
+            "SELECT COUNT(*) AS count FROM count_dispatch_test; -- trailing comment",
+            "SELECT COUNT(*) AS count FROM count_dispatch_test WHERE id > 100;",
+        ] {
+            let result = db.exec(sql).await.expect("Count failed");
+            let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+            assert_eq!(parsed, expected, "unexpected result for {sql:?}");
+        }
+
+        // In a batch, the count row is returned even after DML statements.
+        let result = db
+            .exec(
+                "INSERT INTO count_dispatch_test VALUES (1); INSERT INTO count_dispatch_test VALUES (2); \
+                 SELECT COUNT(*) AS count FROM count_dispatch_test;",
+            )
+            .await
+            .expect("Batch failed");
+        let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+        assert_eq!(parsed, json!([{ "count": 2 }]));
+
+        let params = db
+            .exec_with_params(
+                "SELECT COUNT(*) AS count FROM count_dispatch_test WHERE id > ?",
+                vec![json!(1)],
+            )
+            .await
+            .expect("Parameterized count failed");
+        let parsed: serde_json::Value = serde_json::from_str(&params).expect("Invalid JSON");
+        assert_eq!(parsed, json!([{ "count": 1 }]));
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_allow_multiple_false_rejects_extra_statements() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+        db.exec(
+            "CREATE TABLE single_only_test (id INTEGER); INSERT INTO single_only_test VALUES (1);",
+        )
+        .await
+        .expect("Setup failed");
+
+        let options = QueryOptions {
+            allow_multiple: Some(false),
+            ..Default::default()
+        };
+        for sql in [
+            "SELECT 1; DROP TABLE single_only_test",
+            "SELECT 1; DROP TABLE single_only_test;",
+            "SELECT 1; not even valid sql",
+        ] {
+            let err = db
+                .exec_with_options(sql, None, &options)
+                .await
+                .expect_err("Extra statements should be rejected");
+            assert!(
+                err.contains("allowMultiple"),
+                "unexpected error for {sql:?}: {err}"
+            );
+        }
+
+        for sql in [
+            "SELECT COUNT(*) AS n FROM single_only_test",
+            "SELECT COUNT(*) AS n FROM single_only_test; -- done",
+            ";; SELECT COUNT(*) AS n FROM single_only_test;;",
+        ] {
+            let result = db
+                .exec_with_options(sql, None, &options)
+                .await
+                .expect("A single statement should run");
+            let parsed: serde_json::Value = serde_json::from_str(&result).expect("Invalid JSON");
+            assert_eq!(parsed, json!([{ "n": 1 }]), "unexpected result for {sql:?}");
+        }
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_allow_multiple_true_runs_statements_without_trailing_semicolon() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+        db.exec("CREATE TABLE multi_allowed_test (id INTEGER)")
+            .await
+            .expect("Create failed");
+
+        let options = QueryOptions {
+            allow_multiple: Some(true),
+            ..Default::default()
+        };
+        let result = db
+            .exec_with_options(
+                "INSERT INTO multi_allowed_test VALUES (1); INSERT INTO multi_allowed_test VALUES (2)",
+                None,
+                &options,
+            )
+            .await
+            .expect("Both inserts should run");
+        assert_eq!(result, "Query executed successfully. Rows affected: 2");
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_allow_multiple_binds_shared_params_across_statements() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+        db.exec("CREATE TABLE multi_params_a (id INTEGER, label TEXT)")
+            .await
+            .expect("Create failed");
+        db.exec("CREATE TABLE multi_params_b (id INTEGER)")
+            .await
+            .expect("Create failed");
+
+        let options = QueryOptions {
+            allow_multiple: Some(true),
+            ..Default::default()
+        };
+        let result = db
+            .exec_with_options(
+                "INSERT INTO multi_params_a (id, label) VALUES (?1, ?2);
+                 INSERT INTO multi_params_b (id) VALUES (?1);
+                 DELETE FROM multi_params_b WHERE id <> ?;",
+                Some(vec![json!(7), json!("seven")]),
+                &options,
+            )
+            .await
+            .expect("Script should run");
+        assert_eq!(result, "Query executed successfully. Rows affected: 2");
+
+        let rows = db
+            .exec(
+                "SELECT a.id, a.label, (SELECT COUNT(*) FROM multi_params_b WHERE id = a.id) AS b \
+                 FROM multi_params_a a",
+            )
+            .await
+            .expect("Select failed");
+        let parsed: serde_json::Value = serde_json::from_str(&rows).expect("Invalid JSON");
+        assert_eq!(parsed, json!([{ "id": 7, "label": "seven", "b": 1 }]));
+
+        // The second statement needs more values than given: nothing is kept.
+        let err = db
+            .exec_with_options(
+                "INSERT INTO multi_params_b (id) VALUES (?1); \
+                 INSERT INTO multi_params_a (id, label) VALUES (?1, ?2);",
+                Some(vec![json!(8)]),
+                &options,
+            )
+            .await
+            .unwrap_err();
+        assert!(
+            err.contains("Statement 2 failed: Expected 2 parameters but got 1."),
+            "{err}"
+        );
+        // A value no statement uses is rejected too.
+        let err = db
+            .exec_with_options(
+                "INSERT INTO multi_params_b (id) VALUES (?); SELECT 1;",
+                Some(vec![json!(9), json!(10)]),
+                &options,
+            )
+            .await
+            .unwrap_err();
+        assert!(err.contains("Expected 1 parameters but got 2."), "{err}");
+
+        let count = db
+            .exec("SELECT COUNT(*) AS n FROM multi_params_b")
+            .await
+            .expect("Count failed");
+        let parsed: serde_json::Value = serde_json::from_str(&count).expect("Invalid JSON");
+        assert_eq!(parsed, json!([{ "n": 1 }]));
+
+        // Inside an open transaction a failure undoes only the script.
+        db.exec("BEGIN").await.expect("Begin failed");
+        db.exec("INSERT INTO multi_params_b (id) VALUES (20)")
+            .await
+            .expect("Insert failed");
+        let err = db
+            .exec_with_options(
+                "INSERT INTO multi_params_b (id) VALUES (?1); \
+                 INSERT INTO missing_table (id) VALUES (?1);",
+                Some(vec![json!(21)]),
+                &options,
+            )
+            .await
+            .unwrap_err();
+        assert!(err.contains("Statement 2 failed"), "{err}");
+        db.exec("COMMIT").await.expect("Commit failed");
+        let ids = db
+            .exec("SELECT id FROM multi_params_b ORDER BY id")
+            .await
+            .expect("Select failed");
+        let parsed: serde_json::Value = serde_json::from_str(&ids).expect("Invalid JSON");
+        assert_eq!(parsed, json!([{ "id": 7 }, { "id": 20 }]));
+
+        // A block with its own BEGIN ... COMMIT runs without the savepoint.
+        let result = db
+            .exec_with_options(
+                "BEGIN; \
+                 INSERT INTO multi_params_b (id) VALUES (?1); \
+                 INSERT INTO multi_params_b (id) VALUES (?2); \
+                 COMMIT;",
+                Some(vec![json!(30), json!(31)]),
+                &options,
+            )
+            .await
+            .expect("Migration with its own transaction should run");
+        assert_eq!(result, "Query executed successfully. Rows affected: 2");
+        assert!(!db.in_transaction);
+
+        // A failure part-way through rolls back the block's transaction.
+        let err = db
+            .exec_with_options(
+                "BEGIN; \
+                 INSERT INTO multi_params_b (id) VALUES (?1); \
+                 INSERT INTO missing_table (id) VALUES (?1); \
+                 COMMIT;",
+                Some(vec![json!(40)]),
+                &options,
+            )
+            .await
+            .unwrap_err();
+        assert!(err.contains("Statement 3 failed"), "{err}");
+        assert!(!db.in_transaction);
+        let ids = db
+            .exec("SELECT id FROM multi_params_b ORDER BY id")
+            .await
+            .expect("Select failed");
+        let parsed: serde_json::Value = serde_json::from_str(&ids).expect("Invalid JSON");
+        assert_eq!(
+            parsed,
+            json!([{ "id": 7 }, { "id": 20 }, { "id": 30 }, { "id": 31 }])
+        );
+    }
+
     #[wasm_bindgen_test]
     async fn test_semicolons_in_comments_do_not_split() {
         let Some(mut db) = get_test_db().await else {
@@ -1841,4 +6059,116 @@ mod tests {
         assert_eq!(array[0]["msg"].as_str().unwrap(), "insert; happened");
         assert_eq!(array[1]["msg"].as_str().unwrap(), "second; line");
     }
+
+    #[wasm_bindgen_test]
+    async fn test_cursor_streams_rows_in_chunks_and_finalizes() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        db.exec(
+            "CREATE TABLE stream_rows (n INTEGER); \
+             WITH RECURSIVE seq(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < 5000) \
+             INSERT INTO stream_rows SELECT n FROM seq;",
+        )
+        .await
+        .expect("Seed failed");
+
+        let opened = db
+            .open_cursor(
+                "SELECT n FROM stream_rows WHERE n > ? ORDER BY n",
+                Some(vec![json!(0)]),
+                500,
+                None,
+            )
+            .expect("Open cursor failed");
+        let opened: serde_json::Value = serde_json::from_str(&opened).unwrap();
+        let cursor = opened["cursor"].as_u64().unwrap() as u32;
+
+        let mut total = 0;
+        let mut chunks = 0;
+        loop {
+            let chunk = db.query_chunk(cursor).expect("Chunk failed");
+            let chunk: serde_json::Value = serde_json::from_str(&chunk).unwrap();
+            let rows = chunk["rows"].as_array().unwrap();
+            assert!(rows.len() <= 500);
+            if let Some(first) = rows.first() {
+                assert_eq!(first["n"].as_i64().unwrap(), total as i64 + 1);
+            }
+            total += rows.len();
+            chunks += 1;
+            if chunk["done"].as_bool().unwrap() {
+                break;
+            }
+        }
+
+        assert_eq!(total, 5000);
+        assert_eq!(chunks, 11, "ten full chunks, then an empty final one");
+        assert!(unsafe { sqlite3_next_stmt(db.db, std::ptr::null_mut()) }.is_null());
+        assert!(db.query_chunk(cursor).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_closing_cursor_early_finalizes_statement() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        let opened = db
+            .open_cursor("SELECT 1 AS one UNION ALL SELECT 2", None, 1, None)
+            .expect("Open cursor failed");
+        let cursor = serde_json::from_str::<serde_json::Value>(&opened).unwrap()["cursor"]
+            .as_u64()
+            .unwrap() as u32;
+        db.query_chunk(cursor).expect("Chunk failed");
+        assert!(!unsafe { sqlite3_next_stmt(db.db, std::ptr::null_mut()) }.is_null());
+
+        db.close_cursor(cursor).expect("Close failed");
+        assert!(unsafe { sqlite3_next_stmt(db.db, std::ptr::null_mut()) }.is_null());
+
+        let err = db
+            .open_cursor("CREATE TABLE not_streamed (x)", None, 10, None)
+            .unwrap_err();
+        assert!(err.contains("return rows"));
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_idle_cursors_are_closed() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        let opened = db
+            .open_cursor("SELECT 1 AS one UNION ALL SELECT 2", None, 1, None)
+            .expect("Open cursor failed");
+        let cursor = serde_json::from_str::<serde_json::Value>(&opened).unwrap()["cursor"]
+            .as_u64()
+            .unwrap() as u32;
+        db.query_chunk(cursor).expect("Chunk failed");
+
+        assert_eq!(db.close_idle_cursors(CURSOR_IDLE_TIMEOUT_MS), 0);
+        assert_eq!(db.close_idle_cursors(0.0), 1);
+        assert!(unsafe { sqlite3_next_stmt(db.db, std::ptr::null_mut()) }.is_null());
+        assert!(db.query_chunk(cursor).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_cursor_truncates_cells_to_max_cell_bytes() {
+        let Some(mut db) = get_test_db().await else {
+            return;
+        };
+
+        let opened = db
+            .open_cursor("SELECT 'abcdefghij' AS text", None, 10, Some(4))
+            .expect("Open cursor failed");
+        let cursor = serde_json::from_str::<serde_json::Value>(&opened).unwrap()["cursor"]
+            .as_u64()
+            .unwrap() as u32;
+        let chunk = db.query_chunk(cursor).expect("Chunk failed");
+        let chunk: serde_json::Value = serde_json::from_str(&chunk).unwrap();
+        assert_eq!(
+            chunk["rows"][0]["text"],
+            json!({ "__truncated": true, "preview": "abcd", "totalBytes": 10 })
+        );
+    }
 }
@@ -0,0 +1,75 @@
+use super::*;
+use std::cmp::Ordering;
+
+// Numeric order for valid I256 integers, which all sort before invalid
+// text. Two invalid values fall back to byte order so the collation stays a
+// total order.
+fn compare_bigint_text(a: &[u8], b: &[u8]) -> Ordering {
+    let parse = |text: &[u8]| parse_bigint(std::str::from_utf8(text).ok()?).ok();
+    match (parse(a), parse(b)) {
+        (Some(x), Some(y)) => x.cmp(&y),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a.cmp(b),
+    }
+}
+
+// SQLite collation: ORDER BY balance COLLATE BIGINT_COLL
+pub(crate) unsafe extern "C" fn bigint_coll(
+    _arg: *mut c_void,
+    len_a: c_int,
+    a: *const c_void,
+    len_b: c_int,
+    b: *const c_void,
+) -> c_int {
+    ordering_to_c(compare_bigint_text(
+        collation_bytes(a, len_a),
+        collation_bytes(b, len_b),
+    ))
+}
+
+#[cfg(all(test, target_family = "wasm"))]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    fn sorted(values: &[&str]) -> Vec<String> {
+        let mut values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+        values.sort_by(|a, b| compare_bigint_text(a.as_bytes(), b.as_bytes()));
+        values
+    }
+
+    #[wasm_bindgen_test]
+    fn test_compare_bigint_text_orders_negatives_numerically() {
+        assert_eq!(
+            sorted(&["100", "-5", "99", "-100", "0", "7"]),
+            ["-100", "-5", "0", "7", "99", "100"]
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn test_compare_bigint_text_handles_values_beyond_i64() {
+        let huge = "57896044618658097711785492504343953926634992332820282019728792003956564819967";
+        let big_negative = "-123456789012345678901234567890";
+        assert_eq!(
+            sorted(&[
+                huge,
+                "9223372036854775807",
+                big_negative,
+                "-9223372036854775808"
+            ]),
+            [
+                big_negative,
+                "-9223372036854775808",
+                "9223372036854775807",
+                huge
+            ]
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn test_compare_bigint_text_sorts_invalid_values_last() {
+        assert_eq!(sorted(&["abc", "10", "-", "2"]), ["2", "10", "-", "abc"]);
+        assert_eq!(compare_bigint_text(b" 42 ", b"0x2a"), Ordering::Equal);
+    }
+}
@@ -6,6 +6,31 @@ const BIGINT_ARG_ERROR_MESSAGE: &[u8] = b"BIGINT_SUM() requires exactly 1 argume
 const BIGINT_CONTEXT_ERROR_MESSAGE: &[u8] = b"Failed to allocate aggregate context\0";
 const BIGINT_ZERO_RESULT_BYTES: &[u8] = b"0\0";
 
+/// Parse a decimal or `0x`-prefixed hex integer as an `I256`, ignoring
+/// surrounding whitespace.
+pub(crate) fn parse_bigint(value_str: &str) -> Result<I256, String> {
+    let trimmed = value_str.trim();
+
+    if trimmed.is_empty() {
+        return Err("Empty string is not a valid number".to_string());
+    }
+
+    if trimmed == "-" {
+        return Err("Invalid negative number format".to_string());
+    }
+
+    if trimmed.starts_with("0x") || trimmed.starts_with("0X") {
+        let hex_part = &trimmed[2..];
+        if hex_part.is_empty() {
+            return Err("Incomplete hex number: missing digits after 0x".to_string());
+        }
+        I256::from_hex_str(hex_part)
+            .map_err(|e| format!("Failed to parse hex number '{}': {}", trimmed, e))
+    } else {
+        I256::from_str(trimmed).map_err(|e| format!("Failed to parse number '{}': {}", trimmed, e))
+    }
+}
+
 // Context structure for BIGINT_SUM aggregate function
 pub struct BigIntSumContext {
     total: I256,
@@ -17,27 +42,7 @@ impl BigIntSumContext {
     }
 
     fn add_value(&mut self, value_str: &str) -> Result<(), String> {
-        let trimmed = value_str.trim();
-
-        if trimmed.is_empty() {
-            return Err("Empty string is not a valid number".to_string());
-        }
-
-        if trimmed == "-" {
-            return Err("Invalid negative number format".to_string());
-        }
-
-        let num = if trimmed.starts_with("0x") || trimmed.starts_with("0X") {
-            let hex_part = &trimmed[2..];
-            if hex_part.is_empty() {
-                return Err("Incomplete hex number: missing digits after 0x".to_string());
-            }
-            I256::from_hex_str(hex_part)
-                .map_err(|e| format!("Failed to parse hex number '{}': {}", trimmed, e))?
-        } else {
-            I256::from_str(trimmed)
-                .map_err(|e| format!("Failed to parse number '{}': {}", trimmed, e))?
-        };
+        let num = parse_bigint(value_str)?;
 
         self.total = self.total.checked_add(num).ok_or_else(|| {
             format!(
@@ -76,41 +81,17 @@ pub unsafe extern "C" fn bigint_sum_step(
 
     let value_str = CStr::from_ptr(value_ptr as *const c_char).to_string_lossy();
 
-    // Get or create the aggregate context
-    let aggregate_context =
-        sqlite3_aggregate_context(context, std::mem::size_of::<BigIntSumContext>() as c_int);
-    if aggregate_context.is_null() {
+    let Some(sum_context) = aggregate_state(context, BigIntSumContext::new) else {
         sqlite3_result_error(
             context,
             BIGINT_CONTEXT_ERROR_MESSAGE.as_ptr() as *const c_char,
             -1,
         );
         return;
-    }
-
-    // Cast to our context type
-    let sum_context = aggregate_context as *mut BigIntSumContext;
-
-    // SQLite's sqlite3_aggregate_context allocates zeroed memory on first call
-    // We can determine if this is the first call by checking if the memory is all zeros
-    let mut is_uninitialized = true;
-    let bytes = std::slice::from_raw_parts(
-        aggregate_context as *const u8,
-        std::mem::size_of::<BigIntSumContext>(),
-    );
-    for &byte in bytes {
-        if byte != 0 {
-            is_uninitialized = false;
-            break;
-        }
-    }
-
-    if is_uninitialized {
-        std::ptr::write(sum_context, BigIntSumContext::new());
-    }
+    };
 
     // Add this value to the running total
-    if let Err(e) = (*sum_context).add_value(&value_str) {
+    if let Err(e) = sum_context.add_value(&value_str) {
         let error_msg = format!("{}\0", e);
         sqlite3_result_error(context, error_msg.as_ptr() as *const c_char, -1);
     }
@@ -118,9 +99,7 @@ pub unsafe extern "C" fn bigint_sum_step(
 
 // Aggregate function final - called to return the final result
 pub unsafe extern "C" fn bigint_sum_final(context: *mut sqlite3_context) {
-    let aggregate_context = sqlite3_aggregate_context(context, 0);
-
-    if aggregate_context.is_null() {
+    let Some(sum_context) = take_aggregate_state::<BigIntSumContext>(context) else {
         sqlite3_result_text(
             context,
             BIGINT_ZERO_RESULT_BYTES.as_ptr() as *const c_char,
@@ -131,17 +110,15 @@ pub unsafe extern "C" fn bigint_sum_final(context: *mut sqlite3_context) {
             >(-1isize)),
         );
         return;
-    }
+    };
 
-    let sum_context = aggregate_context as *mut BigIntSumContext;
-    let result_str = (*sum_context).get_result();
+    let result_str = sum_context.get_result();
 
     let result_cstring = match CString::new(result_str) {
         Ok(s) => s,
         Err(e) => {
             let error_msg = format!("Failed to create result string: {}\0", e);
             sqlite3_result_error(context, error_msg.as_ptr() as *const c_char, -1);
-            std::ptr::drop_in_place(sum_context);
             return;
         }
     };
@@ -155,8 +132,6 @@ pub unsafe extern "C" fn bigint_sum_final(context: *mut sqlite3_context) {
             unsafe extern "C" fn(*mut std::ffi::c_void),
         >(-1isize)), // SQLITE_TRANSIENT
     );
-
-    std::ptr::drop_in_place(sum_context);
 }
 
 #[cfg(all(test, target_family = "wasm"))]
@@ -0,0 +1,76 @@
+use super::*;
+
+const BIGINT_VALIDATE_ARG_ERROR_MESSAGE: &[u8] = b"BIGINT_VALIDATE() requires exactly 1 argument\0";
+
+// Accepts exactly what BIGINT_SUM accepts: a decimal or `0x` hex integer
+// that fits in an I256.
+fn is_valid_bigint(value_str: &str) -> bool {
+    parse_bigint(value_str).is_ok()
+}
+
+// SQLite scalar function wrapper: BIGINT_VALIDATE(value). Returns 1 when the
+// value parses as an I256 and 0 otherwise, never raising an error.
+pub unsafe extern "C" fn bigint_validate(
+    context: *mut sqlite3_context,
+    argc: c_int,
+    argv: *mut *mut sqlite3_value,
+) {
+    if argc != 1 {
+        sqlite3_result_error(
+            context,
+            BIGINT_VALIDATE_ARG_ERROR_MESSAGE.as_ptr() as *const c_char,
+            -1,
+        );
+        return;
+    }
+
+    if sqlite3_value_type(*argv) == SQLITE_NULL {
+        sqlite3_result_null(context);
+        return;
+    }
+    let value_ptr = sqlite3_value_text(*argv);
+    if value_ptr.is_null() {
+        sqlite3_result_error_nomem(context);
+        return;
+    }
+
+    let valid = CStr::from_ptr(value_ptr as *const c_char)
+        .to_str()
+        .is_ok_and(is_valid_bigint);
+    sqlite3_result_int(context, if valid { 1 } else { 0 });
+}
+
+#[cfg(all(test, target_family = "wasm"))]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test]
+    fn test_is_valid_bigint_accepts_256_bit_values() {
+        assert!(is_valid_bigint("0"));
+        assert!(is_valid_bigint("-42"));
+        assert!(is_valid_bigint(
+            "57896044618658097711785492504343953926634992332820282019728792003956564819967"
+        ));
+        assert!(is_valid_bigint(
+            "-57896044618658097711785492504343953926634992332820282019728792003956564819967"
+        ));
+        assert!(is_valid_bigint("0xff"));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_is_valid_bigint_rejects_overflowing_numbers() {
+        assert!(!is_valid_bigint(
+            "57896044618658097711785492504343953926634992332820282019728792003956564819968"
+        ));
+        assert!(!is_valid_bigint(&"9".repeat(100)));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_is_valid_bigint_rejects_garbage() {
+        assert!(!is_valid_bigint("abc"));
+        assert!(!is_valid_bigint("12.5"));
+        assert!(!is_valid_bigint("-"));
+        assert!(!is_valid_bigint(""));
+    }
+}
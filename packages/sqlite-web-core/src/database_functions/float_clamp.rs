@@ -0,0 +1,99 @@
+use super::*;
+
+const FLOAT_CLAMP_ARG_ERROR_MESSAGE: &[u8] = b"FLOAT_CLAMP() requires exactly 3 arguments\0";
+
+// Bound `value` to `[lo, hi]`, returning whichever of the three applies.
+fn float_clamp_hex(value_hex: &str, lo_hex: &str, hi_hex: &str) -> Result<String, String> {
+    let value = parse_float_arg(value_hex)?;
+    let lo = parse_float_arg(lo_hex)?;
+    let hi = parse_float_arg(hi_hex)?;
+    let compare_error = |e| format!("Failed to compare Float values: {e}");
+
+    if hi.lt(lo).map_err(compare_error)? {
+        return Err("FLOAT_CLAMP() lower bound is greater than upper bound".to_string());
+    }
+
+    let clamped = if value.lt(lo).map_err(compare_error)? {
+        lo
+    } else if value.gt(hi).map_err(compare_error)? {
+        hi
+    } else {
+        value
+    };
+    Ok(clamped.as_hex())
+}
+
+// SQLite scalar function wrapper: FLOAT_CLAMP(value_hex, lo_hex, hi_hex)
+pub unsafe extern "C" fn float_clamp(
+    context: *mut sqlite3_context,
+    argc: c_int,
+    argv: *mut *mut sqlite3_value,
+) {
+    if argc != 3 {
+        sqlite3_result_error(
+            context,
+            FLOAT_CLAMP_ARG_ERROR_MESSAGE.as_ptr() as *const c_char,
+            -1,
+        );
+        return;
+    }
+
+    let mut args = [""; 3];
+    for (index, arg) in args.iter_mut().enumerate() {
+        match text_arg(argv, index) {
+            Ok(Some(text)) => *arg = text,
+            Ok(None) => {
+                sqlite3_result_null(context);
+                return;
+            }
+            Err(e) => {
+                set_text_result(context, Err(e));
+                return;
+            }
+        }
+    }
+
+    set_text_result(context, float_clamp_hex(args[0], args[1], args[2]));
+}
+
+#[cfg(all(test, target_family = "wasm"))]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    fn hex(decimal: &str) -> String {
+        Float::parse(decimal.to_string()).unwrap().as_hex()
+    }
+
+    fn clamp(value: &str, lo: &str, hi: &str) -> Result<String, String> {
+        float_clamp_hex(&hex(value), &hex(lo), &hex(hi))
+    }
+
+    #[wasm_bindgen_test]
+    fn test_float_clamp_hex_below_range_returns_lower_bound() {
+        assert_eq!(clamp("-5", "0", "10").unwrap(), hex("0"));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_float_clamp_hex_within_range_returns_value() {
+        assert_eq!(clamp("2.5", "0", "10").unwrap(), hex("2.5"));
+        assert_eq!(clamp("10", "0", "10").unwrap(), hex("10"));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_float_clamp_hex_above_range_returns_upper_bound() {
+        assert_eq!(clamp("10.5", "0", "10").unwrap(), hex("10"));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_float_clamp_hex_inverted_bounds_error() {
+        let err = clamp("1", "10", "0").unwrap_err();
+        assert_eq!(err, "FLOAT_CLAMP() lower bound is greater than upper bound");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_float_clamp_hex_invalid_input() {
+        assert!(float_clamp_hex("", &hex("0"), &hex("1")).is_err());
+        assert!(float_clamp_hex(&hex("0"), "not_hex", &hex("1")).is_err());
+    }
+}
@@ -0,0 +1,83 @@
+use super::*;
+use std::cmp::Ordering;
+
+fn parse_float_hex(text: &[u8]) -> Option<Float> {
+    let trimmed = std::str::from_utf8(text).ok()?.trim();
+    Float::from_hex(trimmed).ok()
+}
+
+fn float_ordering(a: Float, b: Float) -> Option<Ordering> {
+    if a.lt(b).ok()? {
+        Some(Ordering::Less)
+    } else if a.eq(b).ok()? {
+        Some(Ordering::Equal)
+    } else {
+        Some(Ordering::Greater)
+    }
+}
+
+// Numeric order for valid hex Floats, which all sort before invalid text.
+// Invalid values, and any pair the comparison fails on, fall back to byte
+// order so the collation stays a total order.
+fn compare_float_hex(a: &[u8], b: &[u8]) -> Ordering {
+    match (parse_float_hex(a), parse_float_hex(b)) {
+        (Some(x), Some(y)) => float_ordering(x, y).unwrap_or_else(|| a.cmp(b)),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a.cmp(b),
+    }
+}
+
+// SQLite collation: ORDER BY amount COLLATE FLOAT_COLL
+pub(crate) unsafe extern "C" fn float_coll(
+    _arg: *mut c_void,
+    len_a: c_int,
+    a: *const c_void,
+    len_b: c_int,
+    b: *const c_void,
+) -> c_int {
+    ordering_to_c(compare_float_hex(
+        collation_bytes(a, len_a),
+        collation_bytes(b, len_b),
+    ))
+}
+
+#[cfg(all(test, target_family = "wasm"))]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    fn hex(decimal: &str) -> String {
+        Float::parse(decimal.to_string()).unwrap().as_hex()
+    }
+
+    #[wasm_bindgen_test]
+    fn test_compare_float_hex_orders_mixed_signs_numerically() {
+        let decimals = ["10", "-2.5", "0", "0.75", "-100", "3"];
+        let mut values: Vec<String> = decimals.iter().map(|d| hex(d)).collect();
+        values.sort_by(|a, b| compare_float_hex(a.as_bytes(), b.as_bytes()));
+        let sorted: Vec<String> = values
+            .iter()
+            .map(|v| Float::from_hex(v).unwrap().format().unwrap())
+            .collect();
+        assert_eq!(sorted, ["-100", "-2.5", "0", "0.75", "3", "10"]);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_compare_float_hex_sorts_invalid_values_last() {
+        let one = hex("1");
+        assert_eq!(
+            compare_float_hex(one.as_bytes(), b"not hex"),
+            Ordering::Less
+        );
+        assert_eq!(
+            compare_float_hex(b"not hex", one.as_bytes()),
+            Ordering::Greater
+        );
+        assert_eq!(compare_float_hex(b"abc", b"abd"), Ordering::Less);
+        assert_eq!(
+            compare_float_hex(format!(" {one} ").as_bytes(), one.as_bytes()),
+            Ordering::Equal
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use super::*;
+
+const FLOAT_COUNT_NONZERO_ARG_ERROR_MESSAGE: &[u8] =
+    b"FLOAT_COUNT_NONZERO() requires exactly 1 argument\0";
+const FLOAT_COUNT_NONZERO_CONTEXT_ERROR_MESSAGE: &[u8] = b"Failed to allocate aggregate context\0";
+
+// Context structure for FLOAT_COUNT_NONZERO aggregate function
+pub struct FloatCountNonzeroContext {
+    count: i64,
+}
+
+impl FloatCountNonzeroContext {
+    fn new() -> Self {
+        Self { count: 0 }
+    }
+
+    fn add_value(&mut self, value_str: &str) -> Result<(), String> {
+        if !float_is_zero_hex(value_str)? {
+            self.count += 1;
+        }
+        Ok(())
+    }
+}
+
+// Aggregate function step - called for each row. NULLs are not counted;
+// text that is not a hex Float fails the query.
+pub(crate) unsafe extern "C" fn float_count_nonzero_step(
+    context: *mut sqlite3_context,
+    argc: c_int,
+    argv: *mut *mut sqlite3_value,
+) {
+    if argc != 1 {
+        sqlite3_result_error(
+            context,
+            FLOAT_COUNT_NONZERO_ARG_ERROR_MESSAGE.as_ptr() as *const c_char,
+            -1,
+        );
+        return;
+    }
+
+    let value_str = match text_arg(argv, 0) {
+        Ok(Some(value_str)) => value_str,
+        Ok(None) => return,
+        Err(e) => {
+            set_text_result(context, Err(e));
+            return;
+        }
+    };
+
+    let Some(count_context) = aggregate_state(context, FloatCountNonzeroContext::new) else {
+        sqlite3_result_error(
+            context,
+            FLOAT_COUNT_NONZERO_CONTEXT_ERROR_MESSAGE.as_ptr() as *const c_char,
+            -1,
+        );
+        return;
+    };
+
+    if let Err(e) = count_context.add_value(value_str) {
+        set_text_result(context, Err(e));
+    }
+}
+
+// Aggregate function final - an empty group counts 0
+pub(crate) unsafe extern "C" fn float_count_nonzero_final(context: *mut sqlite3_context) {
+    let count = take_aggregate_state::<FloatCountNonzeroContext>(context)
+        .map_or(0, |count_context| count_context.count);
+    sqlite3_result_int64(context, count);
+}
+
+#[cfg(all(test, target_family = "wasm"))]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    fn hex(decimal: &str) -> String {
+        Float::parse(decimal.to_string()).unwrap().as_hex()
+    }
+
+    #[wasm_bindgen_test]
+    fn test_count_nonzero_counts_mixed_values() {
+        let mut context = FloatCountNonzeroContext::new();
+        for value in ["0", "1.5", "-2", "0", "0.001"] {
+            context.add_value(&hex(value)).unwrap();
+        }
+        assert_eq!(context.count, 3);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_count_nonzero_all_zero_is_zero() {
+        let mut context = FloatCountNonzeroContext::new();
+        for _ in 0..4 {
+            context.add_value(&hex("0")).unwrap();
+        }
+        assert_eq!(context.count, 0);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_count_nonzero_rejects_invalid_hex() {
+        let mut context = FloatCountNonzeroContext::new();
+        assert!(context.add_value("not_hex").is_err());
+        assert!(context.add_value("").is_err());
+        assert_eq!(context.count, 0);
+    }
+}
@@ -2,7 +2,7 @@ use super::*;
 
 const FLOAT_IS_ZERO_ARG_ERROR_MESSAGE: &[u8] = b"FLOAT_IS_ZERO() requires exactly 1 argument\0";
 
-fn float_is_zero_hex(input_hex: &str) -> Result<bool, String> {
+pub(crate) fn float_is_zero_hex(input_hex: &str) -> Result<bool, String> {
     let trimmed = input_hex.trim();
 
     if trimmed.is_empty() {
@@ -0,0 +1,108 @@
+use super::*;
+
+const FLOAT_MOD_ARG_ERROR_MESSAGE: &[u8] = b"FLOAT_MOD() requires exactly 2 arguments\0";
+
+// Remainder of `dividend / divisor` truncated toward zero, so the result
+// takes the sign of the dividend like SQLite's `%`.
+fn float_mod_hex(dividend_hex: &str, divisor_hex: &str) -> Result<String, String> {
+    let dividend = parse_float_arg(dividend_hex)?;
+    let divisor = parse_float_arg(divisor_hex)?;
+
+    if divisor
+        .is_zero()
+        .map_err(|e| format!("Failed to evaluate Float zero state: {e}"))?
+    {
+        return Err("FLOAT_MOD() division by zero".to_string());
+    }
+
+    let quotient = (dividend / divisor).map_err(|e| format!("Failed to divide Floats: {e}"))?;
+    let fraction = quotient
+        .frac()
+        .map_err(|e| format!("Failed to truncate Float quotient: {e}"))?;
+    let whole =
+        (quotient - fraction).map_err(|e| format!("Failed to truncate Float quotient: {e}"))?;
+    let multiple = (divisor * whole).map_err(|e| format!("Failed to multiply Floats: {e}"))?;
+    let remainder = (dividend - multiple).map_err(|e| format!("Failed to subtract Floats: {e}"))?;
+
+    Ok(remainder.as_hex())
+}
+
+// SQLite scalar function wrapper: FLOAT_MOD(dividend_hex, divisor_hex)
+pub unsafe extern "C" fn float_mod(
+    context: *mut sqlite3_context,
+    argc: c_int,
+    argv: *mut *mut sqlite3_value,
+) {
+    if argc != 2 {
+        sqlite3_result_error(
+            context,
+            FLOAT_MOD_ARG_ERROR_MESSAGE.as_ptr() as *const c_char,
+            -1,
+        );
+        return;
+    }
+
+    let (dividend, divisor) = match (text_arg(argv, 0), text_arg(argv, 1)) {
+        (Ok(Some(dividend)), Ok(Some(divisor))) => (dividend, divisor),
+        (Err(e), _) | (_, Err(e)) => {
+            set_text_result(context, Err(e));
+            return;
+        }
+        _ => {
+            sqlite3_result_null(context);
+            return;
+        }
+    };
+
+    set_text_result(context, float_mod_hex(dividend, divisor));
+}
+
+#[cfg(all(test, target_family = "wasm"))]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    fn hex(decimal: &str) -> String {
+        Float::parse(decimal.to_string()).unwrap().as_hex()
+    }
+
+    fn assert_float_eq(actual_hex: &str, expected: &str) {
+        let actual = Float::from_hex(actual_hex).unwrap();
+        let expected_float = Float::parse(expected.to_string()).unwrap();
+        assert!(
+            actual.eq(expected_float).unwrap(),
+            "expected {expected}, got {}",
+            actual.format().unwrap()
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn test_float_mod_hex_whole_numbers() {
+        let out = float_mod_hex(&hex("5"), &hex("3")).unwrap();
+        assert_float_eq(&out, "2");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_float_mod_hex_negative_dividend_keeps_its_sign() {
+        let out = float_mod_hex(&hex("-5"), &hex("3")).unwrap();
+        assert_float_eq(&out, "-2");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_float_mod_hex_fractional_values() {
+        let out = float_mod_hex(&hex("7.5"), &hex("2")).unwrap();
+        assert_float_eq(&out, "1.5");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_float_mod_hex_zero_divisor_errors() {
+        let err = float_mod_hex(&hex("5"), &hex("0")).unwrap_err();
+        assert_eq!(err, "FLOAT_MOD() division by zero");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_float_mod_hex_invalid_input() {
+        assert!(float_mod_hex("", &hex("3")).is_err());
+        assert!(float_mod_hex(&hex("5"), "not_hex").is_err());
+    }
+}
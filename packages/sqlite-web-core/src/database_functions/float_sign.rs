@@ -0,0 +1,75 @@
+use super::*;
+
+const FLOAT_SIGN_ARG_ERROR_MESSAGE: &[u8] = b"FLOAT_SIGN() requires exactly 1 argument\0";
+
+// -1, 0 or 1 for a negative, zero or positive hex Float.
+fn float_sign_hex(input_hex: &str) -> Result<c_int, String> {
+    let value = parse_float_arg(input_hex)?;
+
+    if value
+        .is_zero()
+        .map_err(|e| format!("Failed to evaluate Float zero state: {e}"))?
+    {
+        return Ok(0);
+    }
+    let negative = value
+        .lt(Float::default())
+        .map_err(|e| format!("Failed to compare Float values: {e}"))?;
+    Ok(if negative { -1 } else { 1 })
+}
+
+// SQLite scalar function wrapper: FLOAT_SIGN(hex_text)
+pub unsafe extern "C" fn float_sign(
+    context: *mut sqlite3_context,
+    argc: c_int,
+    argv: *mut *mut sqlite3_value,
+) {
+    if argc != 1 {
+        sqlite3_result_error(
+            context,
+            FLOAT_SIGN_ARG_ERROR_MESSAGE.as_ptr() as *const c_char,
+            -1,
+        );
+        return;
+    }
+
+    match text_arg(argv, 0).and_then(|value| value.map(float_sign_hex).transpose()) {
+        Ok(Some(sign)) => sqlite3_result_int(context, sign),
+        Ok(None) => sqlite3_result_null(context),
+        Err(e) => set_text_result(context, Err(e)),
+    }
+}
+
+#[cfg(all(test, target_family = "wasm"))]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    fn hex(decimal: &str) -> String {
+        Float::parse(decimal.to_string()).unwrap().as_hex()
+    }
+
+    #[wasm_bindgen_test]
+    fn test_float_sign_hex_negative() {
+        assert_eq!(float_sign_hex(&hex("-3.5")).unwrap(), -1);
+        assert_eq!(float_sign_hex(&hex("-0.0001")).unwrap(), -1);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_float_sign_hex_zero() {
+        assert_eq!(float_sign_hex(&hex("0")).unwrap(), 0);
+        assert_eq!(float_sign_hex(&Float::default().as_hex()).unwrap(), 0);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_float_sign_hex_positive() {
+        assert_eq!(float_sign_hex(&hex("42")).unwrap(), 1);
+        assert_eq!(float_sign_hex(&hex("0.5")).unwrap(), 1);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_float_sign_hex_invalid_input() {
+        assert!(float_sign_hex("").is_err());
+        assert!(float_sign_hex("not_hex").is_err());
+    }
+}
@@ -2,17 +2,41 @@ use super::*;
 use rain_math_float::Float;
 
 const FLOAT_SUM_ARG_ERROR_MESSAGE: &[u8] = b"FLOAT_SUM() requires exactly 1 argument\0";
+const FLOAT_SUM_SATURATING_ARG_ERROR_MESSAGE: &[u8] =
+    b"FLOAT_SUM_SATURATING() requires exactly 1 argument\0";
 const FLOAT_SUM_CONTEXT_ERROR_MESSAGE: &[u8] = b"Failed to allocate aggregate context\0";
 const FLOAT_SUM_ZERO_HEX_ERROR_MESSAGE: &[u8] = b"Zero hex string contained interior NUL\0";
 
+// Largest and smallest values a packed Float can hold: the maximum
+// exponent with the largest positive and negative coefficients.
+const FLOAT_MAX_POSITIVE_HEX: &str =
+    "0x7fffffff7fffffffffffffffffffffffffffffffffffffffffffffffffffffff";
+const FLOAT_MIN_NEGATIVE_HEX: &str =
+    "0x7fffffff80000000000000000000000000000000000000000000000000000000";
+
+/// What a running sum does when an addition overflows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Fail the query.
+    Error,
+    /// Clamp the total to the largest or smallest Float.
+    Saturate,
+}
+
 pub struct FloatSumContext {
     total: Float,
+    policy: OverflowPolicy,
 }
 
 impl FloatSumContext {
     fn new() -> Self {
+        Self::with_policy(OverflowPolicy::Error)
+    }
+
+    fn with_policy(policy: OverflowPolicy) -> Self {
         Self {
             total: Float::default(),
+            policy,
         }
     }
 
@@ -26,16 +50,34 @@ impl FloatSumContext {
         let float_value = Float::from_hex(trimmed)
             .map_err(|e| format!("Failed to parse hex number '{}': {}", trimmed, e))?;
 
-        self.total = (self.total + float_value).map_err(|e| {
-            format!(
-                "Float overflow when adding {} to running total: {}",
-                trimmed, e
-            )
-        })?;
+        self.total = match (self.total + float_value, self.policy) {
+            (Ok(total), _) => total,
+            (Err(_), OverflowPolicy::Saturate) => Self::bound_towards(float_value)?,
+            (Err(e), OverflowPolicy::Error) => {
+                return Err(format!(
+                    "Float overflow when adding {} to running total: {}",
+                    trimmed, e
+                ))
+            }
+        };
 
         Ok(())
     }
 
+    // An addition only overflows when both operands share a sign, so the
+    // sign of the value added says which bound the total ran past.
+    fn bound_towards(value: Float) -> Result<Float, String> {
+        let negative = value
+            .lt(Float::default())
+            .map_err(|e| format!("Failed to compare Float values: {e}"))?;
+        let bound = if negative {
+            FLOAT_MIN_NEGATIVE_HEX
+        } else {
+            FLOAT_MAX_POSITIVE_HEX
+        };
+        Float::from_hex(bound).map_err(|e| format!("Failed to parse Float bound: {e}"))
+    }
+
     fn get_total_as_hex(&self) -> Result<String, String> {
         // Return the hex representation of the accumulated Float
         Ok(self.total.as_hex())
@@ -47,13 +89,41 @@ pub(crate) unsafe extern "C" fn float_sum_step(
     context: *mut sqlite3_context,
     argc: c_int,
     argv: *mut *mut sqlite3_value,
+) {
+    step_with_policy(
+        context,
+        argc,
+        argv,
+        OverflowPolicy::Error,
+        FLOAT_SUM_ARG_ERROR_MESSAGE,
+    )
+}
+
+// FLOAT_SUM_SATURATING step: like FLOAT_SUM, but overflow clamps the total
+// instead of failing the query. Shares `float_sum_final`.
+pub(crate) unsafe extern "C" fn float_sum_saturating_step(
+    context: *mut sqlite3_context,
+    argc: c_int,
+    argv: *mut *mut sqlite3_value,
+) {
+    step_with_policy(
+        context,
+        argc,
+        argv,
+        OverflowPolicy::Saturate,
+        FLOAT_SUM_SATURATING_ARG_ERROR_MESSAGE,
+    )
+}
+
+unsafe fn step_with_policy(
+    context: *mut sqlite3_context,
+    argc: c_int,
+    argv: *mut *mut sqlite3_value,
+    policy: OverflowPolicy,
+    arg_error_message: &[u8],
 ) {
     if argc != 1 {
-        sqlite3_result_error(
-            context,
-            FLOAT_SUM_ARG_ERROR_MESSAGE.as_ptr() as *const c_char,
-            -1,
-        );
+        sqlite3_result_error(context, arg_error_message.as_ptr() as *const c_char, -1);
         return;
     }
 
@@ -65,35 +135,18 @@ pub(crate) unsafe extern "C" fn float_sum_step(
 
     let value_str = CStr::from_ptr(value_ptr as *const c_char).to_string_lossy();
 
-    // Get or create the aggregate context
-    let aggregate_context =
-        sqlite3_aggregate_context(context, std::mem::size_of::<FloatSumContext>() as c_int);
-    if aggregate_context.is_null() {
+    let Some(sum_context) = aggregate_state(context, || FloatSumContext::with_policy(policy))
+    else {
         sqlite3_result_error(
             context,
             FLOAT_SUM_CONTEXT_ERROR_MESSAGE.as_ptr() as *const c_char,
             -1,
         );
         return;
-    }
-
-    // Cast to our context type
-    let sum_context = aggregate_context as *mut FloatSumContext;
-
-    // SQLite's sqlite3_aggregate_context allocates zeroed memory on first call
-    // We can determine if this is the first call by checking if the memory is all zeros
-    let bytes = std::slice::from_raw_parts(
-        aggregate_context as *const u8,
-        std::mem::size_of::<FloatSumContext>(),
-    );
-    let is_uninitialized = bytes.iter().all(|&b| b == 0);
-
-    if is_uninitialized {
-        std::ptr::write(sum_context, FloatSumContext::new());
-    }
+    };
 
     // Add this value to the running total
-    if let Err(e) = (*sum_context).add_value(&value_str) {
+    if let Err(e) = sum_context.add_value(&value_str) {
         let error_msg = format!("{}\0", e);
         sqlite3_result_error(context, error_msg.as_ptr() as *const c_char, -1)
     }
@@ -101,9 +154,7 @@ pub(crate) unsafe extern "C" fn float_sum_step(
 
 // Aggregate function final - called to return the final result
 pub(crate) unsafe extern "C" fn float_sum_final(context: *mut sqlite3_context) {
-    let aggregate_context = sqlite3_aggregate_context(context, 0);
-
-    if aggregate_context.is_null() {
+    let Some(sum_context) = take_aggregate_state::<FloatSumContext>(context) else {
         // No rows were processed; surface the canonical zero hex string derived from Float::default().
         let zero_hex = Float::default().as_hex();
         match CString::new(zero_hex) {
@@ -127,15 +178,13 @@ pub(crate) unsafe extern "C" fn float_sum_final(context: *mut sqlite3_context) {
             }
         }
         return;
-    }
+    };
 
-    let sum_context = aggregate_context as *mut FloatSumContext;
-    let result_str = match (*sum_context).get_total_as_hex() {
+    let result_str = match sum_context.get_total_as_hex() {
         Ok(s) => s,
         Err(e) => {
             let error_msg = format!("{}\0", e);
             sqlite3_result_error(context, error_msg.as_ptr() as *const c_char, -1);
-            std::ptr::drop_in_place(sum_context);
             return;
         }
     };
@@ -145,7 +194,6 @@ pub(crate) unsafe extern "C" fn float_sum_final(context: *mut sqlite3_context) {
         Err(e) => {
             let error_msg = format!("Failed to create result string: {}\0", e);
             sqlite3_result_error(context, error_msg.as_ptr() as *const c_char, -1);
-            std::ptr::drop_in_place(sum_context);
             return;
         }
     };
@@ -159,8 +207,6 @@ pub(crate) unsafe extern "C" fn float_sum_final(context: *mut sqlite3_context) {
             unsafe extern "C" fn(*mut std::ffi::c_void),
         >(-1isize)), // SQLITE_TRANSIENT
     );
-
-    std::ptr::drop_in_place(sum_context);
 }
 
 #[cfg(all(test, target_family = "wasm"))]
@@ -390,4 +436,38 @@ mod tests {
         let result_decimal = Float::from_hex(&result_hex).unwrap().format().unwrap();
         assert_eq!(result_decimal, "104.11111111011111111"); // 4.11111111011111111 + 100
     }
+
+    #[wasm_bindgen_test]
+    fn test_float_sum_overflow_errors_by_default() {
+        let mut context = FloatSumContext::new();
+        assert!(context.add_value(FLOAT_MAX_POSITIVE_HEX).is_ok());
+        let err = context.add_value(FLOAT_MAX_POSITIVE_HEX).unwrap_err();
+        assert!(err.contains("Float overflow"), "unexpected error: {err}");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_float_sum_saturating_clamps_to_the_bounds() {
+        let max = Float::from_hex(FLOAT_MAX_POSITIVE_HEX).unwrap();
+        let min = Float::from_hex(FLOAT_MIN_NEGATIVE_HEX).unwrap();
+
+        let mut context = FloatSumContext::with_policy(OverflowPolicy::Saturate);
+        assert!(context.add_value(FLOAT_MAX_POSITIVE_HEX).is_ok());
+        assert!(context.add_value(FLOAT_MAX_POSITIVE_HEX).is_ok());
+        assert_eq!(context.total.format().unwrap(), max.format().unwrap());
+
+        let mut context = FloatSumContext::with_policy(OverflowPolicy::Saturate);
+        assert!(context.add_value(FLOAT_MIN_NEGATIVE_HEX).is_ok());
+        assert!(context.add_value(FLOAT_MIN_NEGATIVE_HEX).is_ok());
+        assert_eq!(context.total.format().unwrap(), min.format().unwrap());
+
+        // Values that do not overflow sum exactly as with FLOAT_SUM.
+        let mut context = FloatSumContext::with_policy(OverflowPolicy::Saturate);
+        assert!(context
+            .add_value(Float::parse("1.5".to_string()).unwrap().as_hex().as_str())
+            .is_ok());
+        assert!(context
+            .add_value(Float::parse("2.5".to_string()).unwrap().as_hex().as_str())
+            .is_ok());
+        assert_eq!(context.total.format().unwrap(), "4");
+    }
 }
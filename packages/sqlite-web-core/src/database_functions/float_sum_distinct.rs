@@ -0,0 +1,146 @@
+use super::*;
+use std::collections::HashSet;
+
+const FLOAT_SUM_DISTINCT_ARG_ERROR_MESSAGE: &[u8] =
+    b"FLOAT_SUM_DISTINCT() requires exactly 1 argument\0";
+const FLOAT_SUM_DISTINCT_CONTEXT_ERROR_MESSAGE: &[u8] = b"Failed to allocate aggregate context\0";
+
+/// Distinct values one FLOAT_SUM_DISTINCT group may track. Every distinct
+/// value is kept as its decimal string until the group finishes, so memory
+/// grows with the number of distinct values; past this the query fails
+/// rather than grow without bound.
+const FLOAT_SUM_DISTINCT_MAX_VALUES: usize = 100_000;
+
+// Context structure for FLOAT_SUM_DISTINCT aggregate function
+pub struct FloatSumDistinctContext {
+    total: Float,
+    seen: HashSet<String>,
+}
+
+impl FloatSumDistinctContext {
+    fn new() -> Self {
+        Self {
+            total: Float::default(),
+            seen: HashSet::new(),
+        }
+    }
+
+    // Values are compared by their decimal `format()`, so different hex
+    // encodings of the same number are only added once.
+    fn add_value(&mut self, value_str: &str) -> Result<(), String> {
+        let float_value = parse_float_arg(value_str)?;
+        let canonical = float_value
+            .format()
+            .map_err(|e| format!("Failed to format Float value: {e}"))?;
+        if self.seen.contains(&canonical) {
+            return Ok(());
+        }
+        if self.seen.len() >= FLOAT_SUM_DISTINCT_MAX_VALUES {
+            return Err(format!(
+                "FLOAT_SUM_DISTINCT() supports at most {FLOAT_SUM_DISTINCT_MAX_VALUES} distinct values"
+            ));
+        }
+
+        self.total = (self.total + float_value).map_err(|e| {
+            format!(
+                "Float overflow when adding {} to running total: {}",
+                value_str.trim(),
+                e
+            )
+        })?;
+        self.seen.insert(canonical);
+        Ok(())
+    }
+}
+
+// Aggregate function step - called for each row
+pub(crate) unsafe extern "C" fn float_sum_distinct_step(
+    context: *mut sqlite3_context,
+    argc: c_int,
+    argv: *mut *mut sqlite3_value,
+) {
+    if argc != 1 {
+        sqlite3_result_error(
+            context,
+            FLOAT_SUM_DISTINCT_ARG_ERROR_MESSAGE.as_ptr() as *const c_char,
+            -1,
+        );
+        return;
+    }
+
+    let value_str = match text_arg(argv, 0) {
+        Ok(Some(value_str)) => value_str,
+        Ok(None) => return,
+        Err(e) => {
+            set_text_result(context, Err(e));
+            return;
+        }
+    };
+
+    let Some(sum_context) = aggregate_state(context, FloatSumDistinctContext::new) else {
+        sqlite3_result_error(
+            context,
+            FLOAT_SUM_DISTINCT_CONTEXT_ERROR_MESSAGE.as_ptr() as *const c_char,
+            -1,
+        );
+        return;
+    };
+
+    if let Err(e) = sum_context.add_value(value_str) {
+        set_text_result(context, Err(e));
+    }
+}
+
+// Aggregate function final - an empty group sums to zero like FLOAT_SUM
+pub(crate) unsafe extern "C" fn float_sum_distinct_final(context: *mut sqlite3_context) {
+    let total = take_aggregate_state::<FloatSumDistinctContext>(context)
+        .map_or_else(Float::default, |sum_context| sum_context.total);
+    set_text_result(context, Ok(total.as_hex()));
+}
+
+#[cfg(all(test, target_family = "wasm"))]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    fn hex(decimal: &str) -> String {
+        Float::parse(decimal.to_string()).unwrap().as_hex()
+    }
+
+    fn total_decimal(context: &FloatSumDistinctContext) -> String {
+        context.total.format().unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    fn test_sum_distinct_counts_equal_encodings_once() {
+        let three = Float::parse("3".to_string()).unwrap();
+        let two = Float::parse("2".to_string()).unwrap();
+        // 3 / 2 carries a different mantissa and exponent than parsing 1.5.
+        let divided = (three / two).unwrap().as_hex();
+
+        let mut context = FloatSumDistinctContext::new();
+        context.add_value(&hex("1.5")).unwrap();
+        context.add_value(&divided).unwrap();
+        context.add_value(&hex("1.50")).unwrap();
+        context.add_value(&hex("2")).unwrap();
+        assert_eq!(total_decimal(&context), "3.5");
+        assert_eq!(context.seen.len(), 2);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_sum_distinct_rejects_invalid_hex() {
+        let mut context = FloatSumDistinctContext::new();
+        assert!(context.add_value("not_hex").is_err());
+        assert!(context.seen.is_empty());
+    }
+
+    #[wasm_bindgen_test]
+    fn test_sum_distinct_bounds_tracked_values() {
+        let mut context = FloatSumDistinctContext::new();
+        context.seen = (0..FLOAT_SUM_DISTINCT_MAX_VALUES)
+            .map(|i| format!("seen-{i}"))
+            .collect();
+        let err = context.add_value(&hex("1")).unwrap_err();
+        assert!(err.contains("at most"), "unexpected error: {err}");
+    }
+}
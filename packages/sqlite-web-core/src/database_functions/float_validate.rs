@@ -0,0 +1,66 @@
+use super::*;
+
+const FLOAT_VALIDATE_ARG_ERROR_MESSAGE: &[u8] = b"FLOAT_VALIDATE() requires exactly 1 argument\0";
+
+fn is_valid_float_hex(input_hex: &str) -> bool {
+    parse_float_arg(input_hex).is_ok()
+}
+
+// SQLite scalar function wrapper: FLOAT_VALIDATE(value). Returns 1 when the
+// value parses as a hex Float and 0 otherwise, so bad rows can be filtered
+// instead of failing the query.
+pub unsafe extern "C" fn float_validate(
+    context: *mut sqlite3_context,
+    argc: c_int,
+    argv: *mut *mut sqlite3_value,
+) {
+    if argc != 1 {
+        sqlite3_result_error(
+            context,
+            FLOAT_VALIDATE_ARG_ERROR_MESSAGE.as_ptr() as *const c_char,
+            -1,
+        );
+        return;
+    }
+
+    if sqlite3_value_type(*argv) == SQLITE_NULL {
+        sqlite3_result_null(context);
+        return;
+    }
+    let value_ptr = sqlite3_value_text(*argv);
+    if value_ptr.is_null() {
+        sqlite3_result_error_nomem(context);
+        return;
+    }
+
+    let valid = CStr::from_ptr(value_ptr as *const c_char)
+        .to_str()
+        .is_ok_and(is_valid_float_hex);
+    sqlite3_result_int(context, if valid { 1 } else { 0 });
+}
+
+#[cfg(all(test, target_family = "wasm"))]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test]
+    fn test_is_valid_float_hex_accepts_formatted_floats() {
+        let hex = Float::parse("-12.5".to_string()).unwrap().as_hex();
+        assert!(is_valid_float_hex(&hex));
+        assert!(is_valid_float_hex(&format!("  {hex}  ")));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_is_valid_float_hex_rejects_garbage() {
+        assert!(!is_valid_float_hex("not_hex"));
+        assert!(!is_valid_float_hex("12.5"));
+        assert!(!is_valid_float_hex("0x"));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_is_valid_float_hex_rejects_empty() {
+        assert!(!is_valid_float_hex(""));
+        assert!(!is_valid_float_hex("   "));
+    }
+}
@@ -1,126 +1,434 @@
 use rain_math_float::Float;
 use sqlite_wasm_rs::export::*;
-use std::ffi::{c_int, CStr, CString};
+use std::cmp::Ordering;
+use std::ffi::{c_int, c_void, CStr, CString};
+use std::mem::MaybeUninit;
 use std::os::raw::c_char;
 
 // Import the individual function modules
+mod bigint_coll;
 mod bigint_sum;
+mod bigint_validate;
+mod float_clamp;
+mod float_coll;
+mod float_count_nonzero;
 mod float_is_zero;
+mod float_mod;
 mod float_negate;
+mod float_sign;
 mod float_sum;
+mod float_sum_distinct;
+mod float_validate;
 mod float_zero_hex;
+mod now_ms;
+mod regexp;
+mod unicode_case;
+mod uuid_v4;
 
+use bigint_coll::*;
 use bigint_sum::*;
+use bigint_validate::*;
+use float_clamp::*;
+use float_coll::*;
+use float_count_nonzero::*;
 use float_is_zero::*;
+use float_mod::*;
 use float_negate::*;
+use float_sign::*;
 use float_sum::*;
+use float_sum_distinct::*;
+use float_validate::*;
 use float_zero_hex::*;
+use now_ms::*;
+use regexp::*;
+use unicode_case::*;
+use uuid_v4::*;
 
-/// Register all custom functions with the SQLite database
-pub fn register_custom_functions(db: *mut sqlite3) -> Result<(), String> {
-    // Register BIGINT_SUM aggregate function
-    let bigint_sum_name = CString::new("BIGINT_SUM")
-        .map_err(|_| "Function name BIGINT_SUM contains interior NUL bytes".to_string())?;
-    let ret = unsafe {
-        sqlite3_create_function_v2(
-            db,
-            bigint_sum_name.as_ptr(),
-            1, // 1 argument
-            SQLITE_UTF8,
-            std::ptr::null_mut(),
-            None,                   // No xFunc for aggregate function
-            Some(bigint_sum_step),  // xStep callback
-            Some(bigint_sum_final), // xFinal callback
-            None,                   // No destructor
-        )
-    };
-
-    if ret != SQLITE_OK {
-        return Err("Failed to register BIGINT_SUM function".to_string());
-    }
-
-    // Register FLOAT_SUM aggregate function
-    let float_sum_name = CString::new("FLOAT_SUM")
-        .map_err(|_| "Function name FLOAT_SUM contains interior NUL bytes".to_string())?;
-    let ret = unsafe {
-        sqlite3_create_function_v2(
-            db,
-            float_sum_name.as_ptr(),
-            1, // 1 argument
-            SQLITE_UTF8,
-            std::ptr::null_mut(),
-            None,                  // No xFunc for aggregate function
-            Some(float_sum_step),  // xStep callback
-            Some(float_sum_final), // xFinal callback
-            None,                  // No destructor
-        )
-    };
-
-    if ret != SQLITE_OK {
-        return Err("Failed to register FLOAT_SUM function".to_string());
-    }
-
-    // Register FLOAT_ZERO_HEX scalar function
-    let float_zero_hex_name = CString::new("FLOAT_ZERO_HEX")
-        .map_err(|_| "Function name FLOAT_ZERO_HEX contains interior NUL bytes".to_string())?;
-    let ret = unsafe {
-        sqlite3_create_function_v2(
-            db,
-            float_zero_hex_name.as_ptr(),
-            0, // 0 arguments
-            SQLITE_UTF8 | SQLITE_DETERMINISTIC | SQLITE_INNOCUOUS,
-            std::ptr::null_mut(),
-            Some(float_zero_hex), // Scalar function
-            None,
-            None,
-            None,
-        )
-    };
-
-    if ret != SQLITE_OK {
-        return Err("Failed to register FLOAT_ZERO_HEX function".to_string());
-    }
-
-    // Register FLOAT_NEGATE scalar function
-    let float_negate_name = CString::new("FLOAT_NEGATE")
-        .map_err(|_| "Function name FLOAT_NEGATE contains interior NUL bytes".to_string())?;
-    let ret = unsafe {
-        sqlite3_create_function_v2(
-            db,
-            float_negate_name.as_ptr(),
-            1, // 1 argument
-            SQLITE_UTF8 | SQLITE_DETERMINISTIC | SQLITE_INNOCUOUS,
-            std::ptr::null_mut(),
-            Some(float_negate), // xFunc for scalar
-            None,               // No xStep
-            None,               // No xFinal
-            None,               // No destructor
-        )
-    };
-
-    if ret != SQLITE_OK {
-        return Err("Failed to register FLOAT_NEGATE function".to_string());
-    }
-
-    // Register FLOAT_IS_ZERO scalar function
-    let float_is_zero_name = CString::new("FLOAT_IS_ZERO")
-        .map_err(|_| "Function name FLOAT_IS_ZERO contains interior NUL bytes".to_string())?;
-    let ret = unsafe {
-        sqlite3_create_function_v2(
-            db,
-            float_is_zero_name.as_ptr(),
-            1, // 1 argument
-            SQLITE_UTF8 | SQLITE_DETERMINISTIC | SQLITE_INNOCUOUS,
-            std::ptr::null_mut(),
-            Some(float_is_zero), // xFunc for scalar
-            None,                // No xStep
-            None,                // No xFinal
-            None,                // No destructor
-        )
-    };
+/// Typed state kept in an aggregate function's SQLite-owned context.
+///
+/// SQLite zeroes the context when it is first allocated, so `initialized`
+/// starts out `false` whatever the bit pattern of `T`. SQLite calls xFinal
+/// once for every context xStep allocated, including when the statement is
+/// interrupted or fails part-way, so xFinal takes the state back out with
+/// `take_aggregate_state` and drops it there.
+#[repr(C)]
+struct AggregateSlot<T> {
+    initialized: bool,
+    value: MaybeUninit<T>,
+}
+
+/// The state for the current group, created with `init` on the first step.
+/// Returns `None` when SQLite cannot allocate the context.
+pub(crate) unsafe fn aggregate_state<'a, T>(
+    context: *mut sqlite3_context,
+    init: impl FnOnce() -> T,
+) -> Option<&'a mut T> {
+    let slot = sqlite3_aggregate_context(context, std::mem::size_of::<AggregateSlot<T>>() as c_int)
+        as *mut AggregateSlot<T>;
+    if slot.is_null() {
+        return None;
+    }
+    let slot = &mut *slot;
+    if !slot.initialized {
+        slot.value.write(init());
+        slot.initialized = true;
+    }
+    Some(slot.value.assume_init_mut())
+}
+
+/// Move the state out of the context in xFinal, or `None` when no row was
+/// stepped for this group.
+pub(crate) unsafe fn take_aggregate_state<T>(context: *mut sqlite3_context) -> Option<T> {
+    let slot = sqlite3_aggregate_context(context, 0) as *mut AggregateSlot<T>;
+    if slot.is_null() || !(*slot).initialized {
+        return None;
+    }
+    (*slot).initialized = false;
+    Some((*slot).value.assume_init_read())
+}
+
+type ValueFn = unsafe extern "C" fn(*mut sqlite3_context, c_int, *mut *mut sqlite3_value);
+type FinalFn = unsafe extern "C" fn(*mut sqlite3_context);
+
+/// A SQL function this crate registers on new connections.
+struct CustomFunction {
+    name: &'static str,
+    n_arg: c_int,
+    flags: c_int,
+    x_func: Option<ValueFn>,
+    x_step: Option<ValueFn>,
+    x_final: Option<FinalFn>,
+}
+
+const SCALAR_FLAGS: c_int = SQLITE_UTF8 | SQLITE_DETERMINISTIC | SQLITE_INNOCUOUS;
+/// Flags for scalars that may return a different value on every call.
+const VOLATILE_SCALAR_FLAGS: c_int = SQLITE_UTF8 | SQLITE_INNOCUOUS;
+
+const CUSTOM_FUNCTIONS: &[CustomFunction] = &[
+    CustomFunction {
+        name: "BIGINT_SUM",
+        n_arg: 1,
+        flags: SQLITE_UTF8,
+        x_func: None,
+        x_step: Some(bigint_sum_step),
+        x_final: Some(bigint_sum_final),
+    },
+    CustomFunction {
+        name: "FLOAT_SUM",
+        n_arg: 1,
+        flags: SQLITE_UTF8,
+        x_func: None,
+        x_step: Some(float_sum_step),
+        x_final: Some(float_sum_final),
+    },
+    CustomFunction {
+        name: "FLOAT_SUM_SATURATING",
+        n_arg: 1,
+        flags: SQLITE_UTF8,
+        x_func: None,
+        x_step: Some(float_sum_saturating_step),
+        x_final: Some(float_sum_final),
+    },
+    CustomFunction {
+        name: "FLOAT_SUM_DISTINCT",
+        n_arg: 1,
+        flags: SQLITE_UTF8,
+        x_func: None,
+        x_step: Some(float_sum_distinct_step),
+        x_final: Some(float_sum_distinct_final),
+    },
+    CustomFunction {
+        name: "FLOAT_ZERO_HEX",
+        n_arg: 0,
+        flags: SCALAR_FLAGS,
+        x_func: Some(float_zero_hex),
+        x_step: None,
+        x_final: None,
+    },
+    CustomFunction {
+        name: "FLOAT_NEGATE",
+        n_arg: 1,
+        flags: SCALAR_FLAGS,
+        x_func: Some(float_negate),
+        x_step: None,
+        x_final: None,
+    },
+    CustomFunction {
+        name: "FLOAT_IS_ZERO",
+        n_arg: 1,
+        flags: SCALAR_FLAGS,
+        x_func: Some(float_is_zero),
+        x_step: None,
+        x_final: None,
+    },
+    CustomFunction {
+        name: "FLOAT_SIGN",
+        n_arg: 1,
+        flags: SCALAR_FLAGS,
+        x_func: Some(float_sign),
+        x_step: None,
+        x_final: None,
+    },
+    CustomFunction {
+        name: "FLOAT_MOD",
+        n_arg: 2,
+        flags: SCALAR_FLAGS,
+        x_func: Some(float_mod),
+        x_step: None,
+        x_final: None,
+    },
+    CustomFunction {
+        name: "FLOAT_CLAMP",
+        n_arg: 3,
+        flags: SCALAR_FLAGS,
+        x_func: Some(float_clamp),
+        x_step: None,
+        x_final: None,
+    },
+    CustomFunction {
+        name: "FLOAT_VALIDATE",
+        n_arg: 1,
+        flags: SCALAR_FLAGS,
+        x_func: Some(float_validate),
+        x_step: None,
+        x_final: None,
+    },
+    CustomFunction {
+        name: "BIGINT_VALIDATE",
+        n_arg: 1,
+        flags: SCALAR_FLAGS,
+        x_func: Some(bigint_validate),
+        x_step: None,
+        x_final: None,
+    },
+    CustomFunction {
+        name: "FLOAT_COUNT_NONZERO",
+        n_arg: 1,
+        flags: SQLITE_UTF8,
+        x_func: None,
+        x_step: Some(float_count_nonzero_step),
+        x_final: Some(float_count_nonzero_final),
+    },
+    CustomFunction {
+        name: "REGEXP",
+        n_arg: 2,
+        flags: SCALAR_FLAGS,
+        x_func: Some(regexp),
+        x_step: None,
+        x_final: None,
+    },
+    CustomFunction {
+        name: "UNICODE_UPPER",
+        n_arg: 1,
+        flags: SCALAR_FLAGS,
+        x_func: Some(unicode_upper),
+        x_step: None,
+        x_final: None,
+    },
+    CustomFunction {
+        name: "UNICODE_LOWER",
+        n_arg: 1,
+        flags: SCALAR_FLAGS,
+        x_func: Some(unicode_lower),
+        x_step: None,
+        x_final: None,
+    },
+    CustomFunction {
+        name: "UUID_V4",
+        n_arg: 0,
+        flags: VOLATILE_SCALAR_FLAGS,
+        x_func: Some(uuid_v4),
+        x_step: None,
+        x_final: None,
+    },
+    CustomFunction {
+        name: "NOW_MS",
+        n_arg: 0,
+        flags: VOLATILE_SCALAR_FLAGS,
+        x_func: Some(now_ms),
+        x_step: None,
+        x_final: None,
+    },
+];
+
+/// Parse a hex `Float` argument, ignoring surrounding whitespace.
+pub(crate) fn parse_float_arg(input_hex: &str) -> Result<Float, String> {
+    let trimmed = input_hex.trim();
 
-    if ret != SQLITE_OK {
-        return Err("Failed to register FLOAT_IS_ZERO function".to_string());
+    if trimmed.is_empty() {
+        return Err("Empty string is not a valid hex number".to_string());
+    }
+
+    Float::from_hex(trimmed).map_err(|e| format!("Failed to parse Float hex: {e}"))
+}
+
+/// Argument `index` as UTF-8 text, or `None` when it is NULL.
+pub(crate) unsafe fn text_arg<'a>(
+    argv: *mut *mut sqlite3_value,
+    index: usize,
+) -> Result<Option<&'a str>, String> {
+    let value = *argv.add(index);
+    if sqlite3_value_type(value) == SQLITE_NULL {
+        return Ok(None);
+    }
+    let ptr = sqlite3_value_text(value);
+    if ptr.is_null() {
+        return Err("Out of memory reading argument".to_string());
+    }
+    CStr::from_ptr(ptr as *const c_char)
+        .to_str()
+        .map(Some)
+        .map_err(|_| "invalid UTF-8".to_string())
+}
+
+/// Report `result` as the function's text value or as its error.
+pub(crate) unsafe fn set_text_result(
+    context: *mut sqlite3_context,
+    result: Result<String, String>,
+) {
+    match result.and_then(|text| {
+        CString::new(text).map_err(|_| "Result contained interior NUL".to_string())
+    }) {
+        Ok(text) => sqlite3_result_text(
+            context,
+            text.as_ptr(),
+            text.as_bytes().len() as c_int,
+            SQLITE_TRANSIENT(),
+        ),
+        Err(e) => {
+            let message = CString::new(e)
+                .unwrap_or_else(|_| CString::new("Error message contained interior NUL").unwrap());
+            sqlite3_result_error(context, message.as_ptr(), -1);
+        }
+    }
+}
+
+type CompareFn =
+    unsafe extern "C" fn(*mut c_void, c_int, *const c_void, c_int, *const c_void) -> c_int;
+
+/// A collation this crate registers on new connections. Collations are
+/// selected by name alongside the SQL functions.
+struct CustomCollation {
+    name: &'static str,
+    x_compare: CompareFn,
+}
+
+const CUSTOM_COLLATIONS: &[CustomCollation] = &[
+    CustomCollation {
+        name: "FLOAT_COLL",
+        x_compare: float_coll,
+    },
+    CustomCollation {
+        name: "BIGINT_COLL",
+        x_compare: bigint_coll,
+    },
+];
+
+/// The bytes SQLite passes a collation, which are not NUL-terminated.
+pub(crate) unsafe fn collation_bytes<'a>(ptr: *const c_void, len: c_int) -> &'a [u8] {
+    if ptr.is_null() || len <= 0 {
+        return &[];
+    }
+    std::slice::from_raw_parts(ptr as *const u8, len as usize)
+}
+
+pub(crate) fn ordering_to_c(ordering: Ordering) -> c_int {
+    match ordering {
+        Ordering::Less => -1,
+        Ordering::Equal => 0,
+        Ordering::Greater => 1,
+    }
+}
+
+/// Which custom functions a connection registers.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum FunctionSelection {
+    /// Every custom function.
+    #[default]
+    All,
+    /// Only the named functions, matched case-insensitively. An empty list
+    /// leaves the connection with SQLite's built-in functions alone.
+    Only(Vec<String>),
+}
+
+impl FunctionSelection {
+    fn includes(&self, name: &str) -> bool {
+        match self {
+            FunctionSelection::All => true,
+            FunctionSelection::Only(names) => names.iter().any(|n| n.eq_ignore_ascii_case(name)),
+        }
+    }
+}
+
+/// Register the selected custom functions with the SQLite database
+pub fn register_custom_functions(
+    db: *mut sqlite3,
+    selection: &FunctionSelection,
+) -> Result<(), String> {
+    if let FunctionSelection::Only(names) = selection {
+        if let Some(unknown) = names.iter().find(|n| {
+            !CUSTOM_FUNCTIONS
+                .iter()
+                .any(|f| f.name.eq_ignore_ascii_case(n))
+                && !CUSTOM_COLLATIONS
+                    .iter()
+                    .any(|c| c.name.eq_ignore_ascii_case(n))
+        }) {
+            return Err(format!("Unknown custom function: {unknown}"));
+        }
+    }
+
+    for function in CUSTOM_FUNCTIONS {
+        if !selection.includes(function.name) {
+            continue;
+        }
+        let name = CString::new(function.name).map_err(|_| {
+            format!(
+                "Function name {} contains interior NUL bytes",
+                function.name
+            )
+        })?;
+        let ret = unsafe {
+            sqlite3_create_function_v2(
+                db,
+                name.as_ptr(),
+                function.n_arg,
+                function.flags,
+                std::ptr::null_mut(),
+                function.x_func,
+                function.x_step,
+                function.x_final,
+                None, // No destructor
+            )
+        };
+        if ret != SQLITE_OK {
+            return Err(format!("Failed to register {} function", function.name));
+        }
+    }
+
+    for collation in CUSTOM_COLLATIONS {
+        if !selection.includes(collation.name) {
+            continue;
+        }
+        let name = CString::new(collation.name).map_err(|_| {
+            format!(
+                "Collation name {} contains interior NUL bytes",
+                collation.name
+            )
+        })?;
+        let ret = unsafe {
+            sqlite3_create_collation_v2(
+                db,
+                name.as_ptr(),
+                SQLITE_UTF8,
+                std::ptr::null_mut(),
+                Some(collation.x_compare),
+                None,
+            )
+        };
+        if ret != SQLITE_OK {
+            return Err(format!("Failed to register {} collation", collation.name));
+        }
     }
 
     Ok(())
@@ -131,6 +439,102 @@ mod tests {
     use super::*;
     use wasm_bindgen_test::*;
 
+    thread_local! {
+        static LIVE_STATES: std::cell::Cell<i32> = const { std::cell::Cell::new(0) };
+        static PROGRESS_CALLS: std::cell::Cell<i32> = const { std::cell::Cell::new(0) };
+    }
+
+    struct TrackedState;
+
+    impl TrackedState {
+        fn new() -> Self {
+            LIVE_STATES.with(|live| live.set(live.get() + 1));
+            TrackedState
+        }
+    }
+
+    impl Drop for TrackedState {
+        fn drop(&mut self) {
+            LIVE_STATES.with(|live| live.set(live.get() - 1));
+        }
+    }
+
+    unsafe extern "C" fn tracked_step(
+        context: *mut sqlite3_context,
+        _argc: c_int,
+        _argv: *mut *mut sqlite3_value,
+    ) {
+        let _ = aggregate_state(context, TrackedState::new);
+    }
+
+    unsafe extern "C" fn tracked_final(context: *mut sqlite3_context) {
+        drop(take_aggregate_state::<TrackedState>(context));
+        sqlite3_result_null(context);
+    }
+
+    // Let a few thousand rows through, then interrupt mid-aggregation.
+    unsafe extern "C" fn interrupt_later(_: *mut std::ffi::c_void) -> c_int {
+        PROGRESS_CALLS.with(|calls| {
+            calls.set(calls.get() + 1);
+            (calls.get() > 10) as c_int
+        })
+    }
+
+    #[wasm_bindgen_test]
+    fn test_interrupted_aggregate_state_is_dropped() {
+        let mut db: *mut sqlite3 = std::ptr::null_mut();
+        let memory = CString::new(":memory:").unwrap();
+        let name = CString::new("TRACKED_AGG").unwrap();
+        let sql = CString::new(
+            "WITH RECURSIVE seq(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < 50000000) \
+             SELECT TRACKED_AGG(n) FROM seq",
+        )
+        .unwrap();
+
+        unsafe {
+            assert_eq!(sqlite3_open(memory.as_ptr(), &mut db), SQLITE_OK);
+            let ret = sqlite3_create_function_v2(
+                db,
+                name.as_ptr(),
+                1,
+                SQLITE_UTF8,
+                std::ptr::null_mut(),
+                None,
+                Some(tracked_step),
+                Some(tracked_final),
+                None,
+            );
+            assert_eq!(ret, SQLITE_OK);
+            sqlite3_progress_handler(db, 1000, Some(interrupt_later), std::ptr::null_mut());
+
+            let mut stmt: *mut sqlite3_stmt = std::ptr::null_mut();
+            let ret = sqlite3_prepare_v2(db, sql.as_ptr(), -1, &mut stmt, std::ptr::null_mut());
+            assert_eq!(ret, SQLITE_OK);
+            assert_eq!(sqlite3_step(stmt), SQLITE_INTERRUPT);
+            sqlite3_finalize(stmt);
+            sqlite3_close(db);
+        }
+
+        assert!(PROGRESS_CALLS.with(|calls| calls.get()) > 10);
+        assert_eq!(LIVE_STATES.with(|live| live.get()), 0);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_function_selection_matches_case_insensitively() {
+        assert!(FunctionSelection::All.includes("FLOAT_SUM"));
+        let only = FunctionSelection::Only(vec!["bigint_sum".to_string()]);
+        assert!(only.includes("BIGINT_SUM"));
+        assert!(!only.includes("FLOAT_SUM"));
+        assert!(!FunctionSelection::Only(vec![]).includes("FLOAT_SUM"));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_register_rejects_unknown_function_names() {
+        let selection = FunctionSelection::Only(vec!["NOT_A_FUNCTION".to_string()]);
+        let err = register_custom_functions(std::ptr::null_mut(), &selection).unwrap_err();
+        assert_eq!(err, "Unknown custom function: NOT_A_FUNCTION");
+    }
+
     #[wasm_bindgen_test]
     fn test_cstring_conversion() {
         let test_string = "test string with spaces and symbols!@#$%";
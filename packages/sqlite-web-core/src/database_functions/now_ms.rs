@@ -0,0 +1,23 @@
+use super::*;
+
+const NOW_MS_ARG_ERROR_MESSAGE: &[u8] = b"NOW_MS() does not take any arguments\0";
+
+// SQLite scalar function wrapper: NOW_MS(). Returns the Unix epoch in
+// milliseconds as an INTEGER, read from `Date.now()` in the worker that runs
+// the query, so it follows that worker's clock rather than any tab's.
+pub unsafe extern "C" fn now_ms(
+    context: *mut sqlite3_context,
+    argc: c_int,
+    _argv: *mut *mut sqlite3_value,
+) {
+    if argc != 0 {
+        sqlite3_result_error(
+            context,
+            NOW_MS_ARG_ERROR_MESSAGE.as_ptr() as *const c_char,
+            -1,
+        );
+        return;
+    }
+
+    sqlite3_result_int64(context, js_sys::Date::now() as i64);
+}
@@ -0,0 +1,91 @@
+use super::*;
+use regex::Regex;
+
+const REGEXP_ARG_ERROR_MESSAGE: &[u8] = b"REGEXP() requires exactly 2 arguments\0";
+
+fn compile_pattern(pattern: &str) -> Result<Regex, String> {
+    Regex::new(pattern).map_err(|e| format!("REGEXP: invalid pattern '{pattern}': {e}"))
+}
+
+unsafe extern "C" fn drop_cached_regex(regex: *mut c_void) {
+    drop(Box::from_raw(regex as *mut Regex));
+}
+
+// SQLite scalar function wrapper: REGEXP(pattern, text), which also backs
+// `text REGEXP pattern`. Returns 1 when the pattern matches anywhere in the
+// text and 0 otherwise; a NULL argument gives NULL. The compiled pattern is
+// cached on the pattern argument, so a constant pattern compiles once per
+// statement rather than once per row.
+pub unsafe extern "C" fn regexp(
+    context: *mut sqlite3_context,
+    argc: c_int,
+    argv: *mut *mut sqlite3_value,
+) {
+    if argc != 2 {
+        sqlite3_result_error(
+            context,
+            REGEXP_ARG_ERROR_MESSAGE.as_ptr() as *const c_char,
+            -1,
+        );
+        return;
+    }
+
+    let (pattern, text) = match (text_arg(argv, 0), text_arg(argv, 1)) {
+        (Ok(Some(pattern)), Ok(Some(text))) => (pattern, text),
+        (Ok(_), Ok(_)) => {
+            sqlite3_result_null(context);
+            return;
+        }
+        (Err(e), _) | (_, Err(e)) => {
+            set_text_result(context, Err(format!("REGEXP: {e}")));
+            return;
+        }
+    };
+
+    let cached = sqlite3_get_auxdata(context, 0) as *const Regex;
+    let matched = if cached.is_null() {
+        let regex = match compile_pattern(pattern) {
+            Ok(regex) => regex,
+            Err(e) => {
+                set_text_result(context, Err(e));
+                return;
+            }
+        };
+        let matched = regex.is_match(text);
+        // SQLite may drop the cache straight away; `regex` is not used after
+        // this call either way.
+        sqlite3_set_auxdata(
+            context,
+            0,
+            Box::into_raw(Box::new(regex)) as *mut c_void,
+            Some(drop_cached_regex),
+        );
+        matched
+    } else {
+        (*cached).is_match(text)
+    };
+    sqlite3_result_int(context, if matched { 1 } else { 0 });
+}
+
+#[cfg(all(test, target_family = "wasm"))]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test]
+    fn test_compile_pattern_matches_anywhere_in_the_text() {
+        let regex = compile_pattern("^foo").unwrap();
+        assert!(regex.is_match("foobar"));
+        assert!(!regex.is_match("barfoo"));
+        assert!(compile_pattern("o+b").unwrap().is_match("foobar"));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_compile_pattern_reports_invalid_patterns() {
+        let err = compile_pattern("(unclosed").unwrap_err();
+        assert!(
+            err.starts_with("REGEXP: invalid pattern '(unclosed'"),
+            "{err}"
+        );
+    }
+}
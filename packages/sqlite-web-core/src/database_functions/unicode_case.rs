@@ -0,0 +1,58 @@
+use super::*;
+
+const UNICODE_UPPER_ARG_ERROR_MESSAGE: &[u8] = b"UNICODE_UPPER() requires exactly 1 argument\0";
+const UNICODE_LOWER_ARG_ERROR_MESSAGE: &[u8] = b"UNICODE_LOWER() requires exactly 1 argument\0";
+
+// Shared body of UNICODE_UPPER/UNICODE_LOWER: NULL passes through, anything
+// else is read as UTF-8 text and mapped with `convert`.
+unsafe fn convert_case(
+    context: *mut sqlite3_context,
+    argc: c_int,
+    argv: *mut *mut sqlite3_value,
+    arg_error: &[u8],
+    convert: fn(&str) -> String,
+) {
+    if argc != 1 {
+        sqlite3_result_error(context, arg_error.as_ptr() as *const c_char, -1);
+        return;
+    }
+
+    match text_arg(argv, 0) {
+        Ok(None) => sqlite3_result_null(context),
+        Ok(Some(text)) => set_text_result(context, Ok(convert(text))),
+        Err(e) => set_text_result(context, Err(e)),
+    }
+}
+
+// SQLite scalar function wrapper: UNICODE_UPPER(text). Unlike the built-in
+// `upper`, which only folds ASCII, this applies the full Unicode mapping, so
+// 'é' becomes 'É' and 'ß' becomes 'SS'.
+pub unsafe extern "C" fn unicode_upper(
+    context: *mut sqlite3_context,
+    argc: c_int,
+    argv: *mut *mut sqlite3_value,
+) {
+    convert_case(
+        context,
+        argc,
+        argv,
+        UNICODE_UPPER_ARG_ERROR_MESSAGE,
+        str::to_uppercase,
+    );
+}
+
+// SQLite scalar function wrapper: UNICODE_LOWER(text), the Unicode-aware
+// counterpart of the built-in `lower`.
+pub unsafe extern "C" fn unicode_lower(
+    context: *mut sqlite3_context,
+    argc: c_int,
+    argv: *mut *mut sqlite3_value,
+) {
+    convert_case(
+        context,
+        argc,
+        argv,
+        UNICODE_LOWER_ARG_ERROR_MESSAGE,
+        str::to_lowercase,
+    );
+}
@@ -0,0 +1,24 @@
+use super::*;
+use uuid::Uuid;
+
+const UUID_V4_ARG_ERROR_MESSAGE: &[u8] = b"UUID_V4() does not take any arguments\0";
+
+// SQLite scalar function wrapper: UUID_V4(). Returns a fresh random UUID in
+// canonical hyphenated form. Registered without SQLITE_DETERMINISTIC so
+// SQLite calls it once per row instead of folding it into a constant.
+pub unsafe extern "C" fn uuid_v4(
+    context: *mut sqlite3_context,
+    argc: c_int,
+    _argv: *mut *mut sqlite3_value,
+) {
+    if argc != 0 {
+        sqlite3_result_error(
+            context,
+            UUID_V4_ARG_ERROR_MESSAGE.as_ptr() as *const c_char,
+            -1,
+        );
+        return;
+    }
+
+    set_text_result(context, Ok(Uuid::new_v4().to_string()));
+}
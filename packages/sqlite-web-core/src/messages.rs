@@ -3,6 +3,13 @@ use serde::{Deserialize, Serialize};
 
 pub const WORKER_ERROR_TYPE_GENERIC: &str = "WorkerError";
 pub const WORKER_ERROR_TYPE_INITIALIZATION_PENDING: &str = "InitializationPending";
+pub const WORKER_ERROR_TYPE_QUERY_ABORTED: &str = "QueryAborted";
+pub const WORKER_ERROR_TYPE_QUERY_TIMEOUT: &str = "QueryTimeout";
+/// The leader went away before answering; the request is safe to resend.
+pub const WORKER_ERROR_TYPE_LEADER_STEPDOWN: &str = "LeaderStepdown";
+pub const WORKER_ERROR_TYPE_UNSUPPORTED_OPERATION: &str = "UnsupportedOperation";
+pub const WORKER_ERROR_TYPE_LEADER_ELECTION_TIMEOUT: &str = "LeaderElectionTimeout";
+pub const WORKER_ERROR_TYPE_SERVER_BUSY: &str = "ServerBusy";
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct WorkerErrorPayload {
@@ -11,6 +18,248 @@ pub struct WorkerErrorPayload {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(default)]
     pub message: Option<String>,
+    /// What SQLite reported, when the query failed inside SQLite.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub sqlite: Option<SqliteErrorDetail>,
+}
+
+/// SQLite's message, result code and error offset for a failed query,
+/// sent along with that query's error.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SqliteErrorDetail {
+    /// `sqlite3_errmsg`, e.g. `near "SELEC": syntax error`.
+    pub message: String,
+    /// `sqlite3_errcode`, e.g. 1 for `SQLITE_ERROR`.
+    pub code: i32,
+    /// Byte offset in the query's SQL of the token SQLite blamed, when it
+    /// named one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub offset: Option<u32>,
+}
+
+/// Where a profiled query's time went, sent beside its result.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct QueryTiming {
+    /// Time spent waiting behind other queries on the leader.
+    #[serde(rename = "queuedMs")]
+    pub queued_ms: f64,
+    /// Time spent running the query.
+    #[serde(rename = "execMs")]
+    pub exec_ms: f64,
+}
+
+/// Per-query options sent alongside the SQL. Every field is optional so
+/// messages from older callers keep deserializing.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct QueryOptions {
+    #[serde(rename = "timeoutMs")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub timeout_ms: Option<f64>,
+    #[serde(rename = "dealiasColumns")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub dealias_columns: Option<bool>,
+    #[serde(rename = "returnRowids")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub return_rowids: Option<bool>,
+    /// `false` rejects SQL holding more than one statement; `true` runs
+    /// every statement even without a trailing semicolon.
+    #[serde(rename = "allowMultiple")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub allow_multiple: Option<bool>,
+    #[serde(rename = "returnNotices")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub return_notices: Option<bool>,
+    /// Report each statement run with its bound parameters substituted.
+    #[serde(rename = "returnExpandedSql")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub return_expanded_sql: Option<bool>,
+    /// Report the rows each statement changed, not only their total.
+    #[serde(rename = "returnStatementCounts")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub return_statement_counts: Option<bool>,
+    #[serde(rename = "returnResultCode")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub return_result_code: Option<bool>,
+    /// Text and blob values longer than this many bytes are replaced by a
+    /// `{ __truncated, preview, totalBytes }` marker.
+    #[serde(rename = "maxCellBytes")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub max_cell_bytes: Option<u32>,
+    /// Milliseconds the worker may spend turning the result into JSON before
+    /// it gives up with a "result too large" error.
+    #[serde(rename = "serializeBudgetMs")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub serialize_budget_ms: Option<f64>,
+    /// Fail a result whose JSON is longer than this many bytes instead of
+    /// posting it to the main thread.
+    #[serde(rename = "maxResultBytes")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub max_result_bytes: Option<u32>,
+    /// Report how long the query waited in the DB worker queue and how long
+    /// it ran, as `timing: { queuedMs, execMs }` in the result object.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub profile: Option<bool>,
+    /// Return each cell as `{ type, value }`, where `type` is the storage
+    /// class SQLite reported for it.
+    #[serde(rename = "typedCells")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub typed_cells: Option<bool>,
+    /// Return numbers with the type of their declared column's affinity
+    /// rather than the storage class they were read with.
+    #[serde(rename = "coerceAffinity")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub coerce_affinity: Option<bool>,
+}
+
+/// Leadership snapshot returned as the result of a `get-role` request.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RoleInfo {
+    pub role: String,
+    #[serde(rename = "leaderId")]
+    pub leader_id: Option<String>,
+}
+
+/// Integer fields of the database header exposed through pragmas.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum HeaderPragma {
+    UserVersion,
+    ApplicationId,
+}
+
+impl HeaderPragma {
+    pub fn name(self) -> &'static str {
+        match self {
+            HeaderPragma::UserVersion => "user_version",
+            HeaderPragma::ApplicationId => "application_id",
+        }
+    }
+}
+
+/// Database operations other than plain queries. They run on the DB worker
+/// queue in order with queries, so they observe every earlier request.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind")]
+pub enum DbOperation {
+    #[serde(rename = "flush")]
+    Flush,
+    #[serde(rename = "export")]
+    Export,
+    #[serde(rename = "vacuum")]
+    Vacuum,
+    #[serde(rename = "integrity-check")]
+    IntegrityCheck,
+    /// Size of the database file in bytes.
+    #[serde(rename = "database-size")]
+    DatabaseSize,
+    #[serde(rename = "page-count")]
+    PageCount,
+    /// Read the page size, first setting it when `value` is given.
+    #[serde(rename = "page-size")]
+    PageSize {
+        #[serde(default)]
+        value: Option<u32>,
+    },
+    /// Jobs waiting in the DB worker queue. Answered on arrival rather than
+    /// queued behind them.
+    #[serde(rename = "queue-depth")]
+    QueueDepth,
+    /// Answered `"pong"` by the DB worker on arrival, without touching
+    /// SQLite, to check the messaging path.
+    #[serde(rename = "ping")]
+    Ping,
+    /// Whether the database was opened in memory because OPFS could not be
+    /// installed. Answered on arrival like `Ping`.
+    #[serde(rename = "memory-only")]
+    MemoryOnly,
+    #[serde(rename = "backup-into")]
+    BackupInto { name: String },
+    /// Read a header pragma, first setting it when `value` is given.
+    #[serde(rename = "header-value")]
+    HeaderValue {
+        pragma: HeaderPragma,
+        #[serde(default)]
+        value: Option<i32>,
+    },
+    /// Replace the database with a SQLite file image. The `Uint8Array` is
+    /// kept as is through serialization so its buffer can be transferred
+    /// between workers instead of copied.
+    #[serde(rename = "import")]
+    Import {
+        #[serde(with = "serde_wasm_bindgen::preserve")]
+        bytes: js_sys::Uint8Array,
+    },
+    /// Prepare a row-returning statement to be read `chunk_size` rows at a
+    /// time with `QueryChunk`.
+    #[serde(rename = "open-cursor")]
+    OpenCursor {
+        sql: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(default)]
+        params: Option<Vec<serde_json::Value>>,
+        #[serde(rename = "chunkSize")]
+        chunk_size: u32,
+        #[serde(rename = "maxCellBytes")]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(default)]
+        max_cell_bytes: Option<u32>,
+    },
+    #[serde(rename = "query-chunk")]
+    QueryChunk { cursor: u32 },
+    #[serde(rename = "close-cursor")]
+    CloseCursor { cursor: u32 },
+    /// Run the statements in order inside one transaction, rolling back
+    /// everything on the first failure.
+    /// With `chunk_size`, commits after every that many statements and a
+    /// failure rolls back only the current chunk.
+    #[serde(rename = "transaction")]
+    Transaction {
+        statements: Vec<BatchStatement>,
+        #[serde(rename = "chunkSize")]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(default)]
+        chunk_size: Option<u32>,
+    },
+    /// Report the `EXPLAIN QUERY PLAN` steps for `sql` without running it.
+    #[serde(rename = "explain")]
+    Explain {
+        sql: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(default)]
+        params: Option<Vec<serde_json::Value>>,
+    },
+    /// Prepare every statement in `sql` without running any of them.
+    #[serde(rename = "validate")]
+    Validate { sql: String },
+    /// Report SQLite's message, code and error offset for the last query
+    /// that failed on the leader's connection.
+    #[serde(rename = "last-error")]
+    LastError,
+}
+
+/// One statement of a `Transaction` operation.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BatchStatement {
+    pub sql: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub params: Option<Vec<serde_json::Value>>,
 }
 
 // Message types for BroadcastChannel communication
@@ -35,6 +284,9 @@ pub enum ChannelMessage {
         #[serde(skip_serializing_if = "Option::is_none")]
         #[serde(default)]
         params: Option<Vec<serde_json::Value>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(default)]
+        options: Option<QueryOptions>,
     },
     #[serde(rename = "query-response")]
     QueryResponse {
@@ -42,12 +294,50 @@ pub enum ChannelMessage {
         query_id: String,
         result: Option<String>,
         error: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(default)]
+        sqlite: Option<SqliteErrorDetail>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(default)]
+        timing: Option<QueryTiming>,
     },
     #[serde(rename = "leader-ping")]
     LeaderPing {
         #[serde(rename = "requesterId")]
         requester_id: String,
     },
+    /// The leader is giving up the database because its tab is closing or
+    /// `close()` was called. Followers look for a new leader straight away.
+    #[serde(rename = "leader-stepdown")]
+    LeaderStepdown {
+        #[serde(rename = "leaderId")]
+        leader_id: String,
+    },
+    #[serde(rename = "leader-claim")]
+    LeaderClaim {
+        #[serde(rename = "candidateId")]
+        candidate_id: String,
+    },
+    #[serde(rename = "leader-claim-rejected")]
+    LeaderClaimRejected {
+        #[serde(rename = "candidateId")]
+        candidate_id: String,
+        #[serde(rename = "leaderId")]
+        leader_id: String,
+    },
+    #[serde(rename = "cancel-query")]
+    CancelQuery {
+        #[serde(rename = "queryId")]
+        query_id: String,
+    },
+    #[serde(rename = "worker-event")]
+    WorkerEvent { event: WorkerEvent },
+    #[serde(rename = "operation-request")]
+    OperationRequest {
+        #[serde(rename = "queryId")]
+        query_id: String,
+        operation: DbOperation,
+    },
 }
 
 // Messages from main thread
@@ -62,7 +352,33 @@ pub enum WorkerMessage {
         #[serde(skip_serializing_if = "Option::is_none")]
         #[serde(default)]
         params: Option<Vec<serde_json::Value>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(default)]
+        options: Option<QueryOptions>,
     },
+    #[serde(rename = "cancel-query")]
+    CancelQuery {
+        #[serde(rename = "requestId")]
+        request_id: u32,
+    },
+    /// Cancels every request the sending connection still has in flight.
+    #[serde(rename = "cancel-all")]
+    CancelAll,
+    #[serde(rename = "run-operation")]
+    RunOperation {
+        #[serde(rename = "requestId")]
+        request_id: u32,
+        operation: DbOperation,
+    },
+    #[serde(rename = "get-role")]
+    GetRole {
+        #[serde(rename = "requestId")]
+        request_id: u32,
+    },
+    /// Sent by the page when it unloads so a leading worker hands off
+    /// before the tab goes away.
+    #[serde(rename = "step-down")]
+    StepDown,
 }
 
 // Messages to main thread
@@ -75,9 +391,96 @@ pub enum MainThreadMessage {
         request_id: u32,
         result: Option<String>,
         error: Option<WorkerErrorPayload>,
+        /// Set for queries run with `profile: true`.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        #[serde(default)]
+        timing: Option<QueryTiming>,
     },
     #[serde(rename = "worker-ready")]
     WorkerReady,
+    #[serde(rename = "worker-event")]
+    WorkerEvent { event: WorkerEvent },
+    /// A diagnostic line from a worker, only sent when the connection was
+    /// opened with `debug: true`. `timestamp` is milliseconds since the
+    /// epoch.
+    #[serde(rename = "worker-log")]
+    WorkerLog {
+        level: LogLevel,
+        message: String,
+        timestamp: f64,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+/// Notifications pushed to the main thread outside of any request.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind")]
+pub enum WorkerEvent {
+    #[serde(rename = "leadership-change")]
+    LeadershipChange {
+        #[serde(rename = "leaderId")]
+        leader_id: String,
+        #[serde(rename = "isLeader")]
+        is_leader: bool,
+    },
+    #[serde(rename = "db-change")]
+    DbChange { changes: Vec<RowChange> },
+    #[serde(rename = "transaction-committed")]
+    TransactionCommitted,
+    #[serde(rename = "transaction-rolled-back")]
+    TransactionRolledBack,
+    /// A step of opening the connection has finished.
+    #[serde(rename = "init-progress")]
+    InitProgress { stage: InitStage },
+}
+
+impl WorkerEvent {
+    /// Whether the leader relays this event to follower tabs. Leadership
+    /// changes and initialization progress are observed by each tab itself;
+    /// data changes and transaction outcomes only happen on the leader.
+    pub fn is_shared(&self) -> bool {
+        !matches!(
+            self,
+            WorkerEvent::LeadershipChange { .. } | WorkerEvent::InitProgress { .. }
+        )
+    }
+}
+
+/// Milestones reported while a connection opens, in the order they happen.
+/// Only the leading tab installs OPFS and opens the database, so a follower
+/// stops at `LeaderElected`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum InitStage {
+    /// The coordinator worker is running.
+    WorkerSpawned,
+    /// This tab knows which worker leads the database.
+    LeaderElected,
+    /// The DB worker installed the OPFS VFS.
+    OpfsInstalled,
+    /// Installing OPFS failed and the DB worker opens an in-memory database
+    /// instead, which is lost when the worker stops. Replaces
+    /// `OpfsInstalled` when the connection allows the fallback.
+    MemoryFallback,
+    /// The DB worker opened the database and applied the pragmas.
+    DbOpen,
+}
+
+/// A row written by a query, as reported by SQLite's update hook.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RowChange {
+    /// `"insert"`, `"update"` or `"delete"`.
+    pub op: String,
+    pub table: String,
+    pub rowid: i64,
 }
 
 pub struct PendingQuery {
@@ -88,6 +491,8 @@ pub struct PendingQuery {
 #[cfg(all(test, target_family = "wasm"))]
 mod tests {
     use super::*;
+    use js_sys::Reflect;
+    use wasm_bindgen::JsValue;
     use wasm_bindgen_test::*;
 
     wasm_bindgen_test_configure!(run_in_browser);
@@ -120,6 +525,7 @@ mod tests {
             query_id: "query-456".to_string(),
             sql: "SELECT * FROM users".to_string(),
             params: None,
+            options: None,
         };
         assert_serialization_roundtrip(query_request, "query-request", |json| {
             assert!(json.contains("\"queryId\":\"query-456\""));
@@ -130,21 +536,35 @@ mod tests {
             query_id: "query-789".to_string(),
             result: Some("[{\"id\": 1, \"name\": \"test\"}]".to_string()),
             error: None,
+            sqlite: None,
+            timing: Some(QueryTiming {
+                queued_ms: 0.0,
+                exec_ms: 3.0,
+            }),
         };
         assert_serialization_roundtrip(query_success, "query-response", |json| {
             assert!(json.contains("\"queryId\":\"query-789\""));
             assert!(json.contains("\"result\":\""));
             assert!(json.contains("\"error\":null"));
+            assert!(json.contains("\"execMs\":3.0"));
         });
 
         let query_error = ChannelMessage::QueryResponse {
             query_id: "query-error".to_string(),
             result: None,
             error: Some("SQL syntax error".to_string()),
+            sqlite: Some(SqliteErrorDetail {
+                message: "near \"SELEC\": syntax error".to_string(),
+                code: 1,
+                offset: Some(0),
+            }),
+            timing: None,
         };
         assert_serialization_roundtrip(query_error, "query-response", |json| {
             assert!(json.contains("\"error\":\"SQL syntax error\""));
             assert!(json.contains("\"result\":null"));
+            assert!(json.contains("\"code\":1"));
+            assert!(json.contains("\"offset\":0"));
         });
 
         let leader_ping = ChannelMessage::LeaderPing {
@@ -161,6 +581,7 @@ mod tests {
             request_id: 42,
             sql: "INSERT INTO table VALUES (1, 'test')".to_string(),
             params: None,
+            options: None,
         };
 
         let json = serde_json::to_string(&msg).expect("Should serialize");
@@ -175,20 +596,343 @@ mod tests {
                 assert_eq!(sql, "INSERT INTO table VALUES (1, 'test')");
                 assert_eq!(request_id, 42);
             }
+            other => panic!("expected ExecuteQuery, got {other:?}"),
         }
     }
 
+    #[wasm_bindgen_test]
+    fn test_get_role_serialization() {
+        let request = WorkerMessage::GetRole { request_id: 4 };
+        assert_serialization_roundtrip(request, "get-role", |json| {
+            assert!(json.contains("\"requestId\":4"));
+        });
+
+        let info = RoleInfo {
+            role: "leader".to_string(),
+            leader_id: Some("worker-1".to_string()),
+        };
+        let json = serde_json::to_string(&info).expect("Should serialize");
+        assert_eq!(json, r#"{"role":"leader","leaderId":"worker-1"}"#);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_leader_claim_messages_serialization() {
+        let claim = ChannelMessage::LeaderClaim {
+            candidate_id: "worker-a".to_string(),
+        };
+        assert_serialization_roundtrip(claim, "leader-claim", |json| {
+            assert!(json.contains("\"candidateId\":\"worker-a\""));
+        });
+
+        let rejected = ChannelMessage::LeaderClaimRejected {
+            candidate_id: "worker-a".to_string(),
+            leader_id: "worker-b".to_string(),
+        };
+        assert_serialization_roundtrip(rejected, "leader-claim-rejected", |json| {
+            assert!(json.contains("\"candidateId\":\"worker-a\""));
+            assert!(json.contains("\"leaderId\":\"worker-b\""));
+        });
+    }
+
+    #[wasm_bindgen_test]
+    fn test_stepdown_messages_serialization() {
+        let stepdown = ChannelMessage::LeaderStepdown {
+            leader_id: "worker-a".to_string(),
+        };
+        assert_serialization_roundtrip(stepdown, "leader-stepdown", |json| {
+            assert!(json.contains("\"leaderId\":\"worker-a\""));
+        });
+
+        assert_serialization_roundtrip(WorkerMessage::StepDown, "step-down", |json| {
+            assert_eq!(json, r#"{"type":"step-down"}"#);
+        });
+    }
+
+    #[wasm_bindgen_test]
+    fn test_cancel_query_messages_serialization() {
+        let cancel = WorkerMessage::CancelQuery { request_id: 9 };
+        assert_serialization_roundtrip(cancel, "cancel-query", |json| {
+            assert!(json.contains("\"requestId\":9"));
+        });
+
+        assert_serialization_roundtrip(WorkerMessage::CancelAll, "cancel-all", |json| {
+            assert_eq!(json, r#"{"type":"cancel-all"}"#);
+        });
+
+        let forwarded = ChannelMessage::CancelQuery {
+            query_id: "query-9".to_string(),
+        };
+        assert_serialization_roundtrip(forwarded, "cancel-query", |json| {
+            assert!(json.contains("\"queryId\":\"query-9\""));
+        });
+    }
+
+    #[wasm_bindgen_test]
+    fn test_query_options_serialization() {
+        let msg = WorkerMessage::ExecuteQuery {
+            request_id: 5,
+            sql: "SELECT 1".to_string(),
+            params: None,
+            options: Some(QueryOptions {
+                timeout_ms: Some(250.0),
+                ..Default::default()
+            }),
+        };
+        assert_serialization_roundtrip(msg, "execute-query", |json| {
+            assert!(json.contains("\"options\":{\"timeoutMs\":250.0}"));
+        });
+
+        let truncated = WorkerMessage::ExecuteQuery {
+            request_id: 6,
+            sql: "SELECT body FROM docs".to_string(),
+            params: None,
+            options: Some(QueryOptions {
+                max_cell_bytes: Some(1024),
+                ..Default::default()
+            }),
+        };
+        assert_serialization_roundtrip(truncated, "execute-query", |json| {
+            assert!(json.contains("\"options\":{\"maxCellBytes\":1024}"));
+        });
+
+        let legacy: WorkerMessage =
+            serde_json::from_str(r#"{"type":"execute-query","requestId":1,"sql":"SELECT 1"}"#)
+                .expect("options should be optional");
+        match legacy {
+            WorkerMessage::ExecuteQuery { options, .. } => assert_eq!(options, None),
+            other => panic!("expected ExecuteQuery, got {other:?}"),
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn test_operation_messages_serialization() {
+        let run = WorkerMessage::RunOperation {
+            request_id: 3,
+            operation: DbOperation::Flush,
+        };
+        assert_serialization_roundtrip(run, "run-operation", |json| {
+            assert!(json.contains("\"requestId\":3"));
+            assert!(json.contains("\"operation\":{\"kind\":\"flush\"}"));
+        });
+
+        let export = WorkerMessage::RunOperation {
+            request_id: 4,
+            operation: DbOperation::Export,
+        };
+        assert_serialization_roundtrip(export, "run-operation", |json| {
+            assert!(json.contains("\"operation\":{\"kind\":\"export\"}"));
+        });
+
+        let backup = ChannelMessage::OperationRequest {
+            query_id: "query-2".to_string(),
+            operation: DbOperation::BackupInto {
+                name: "backup".to_string(),
+            },
+        };
+        assert_serialization_roundtrip(backup, "operation-request", |json| {
+            assert!(json.contains("\"operation\":{\"kind\":\"backup-into\",\"name\":\"backup\"}"));
+        });
+
+        let check = WorkerMessage::RunOperation {
+            request_id: 6,
+            operation: DbOperation::IntegrityCheck,
+        };
+        assert_serialization_roundtrip(check, "run-operation", |json| {
+            assert!(json.contains("\"operation\":{\"kind\":\"integrity-check\"}"));
+        });
+
+        let size = WorkerMessage::RunOperation {
+            request_id: 8,
+            operation: DbOperation::DatabaseSize,
+        };
+        assert_serialization_roundtrip(size, "run-operation", |json| {
+            assert!(json.contains("\"operation\":{\"kind\":\"database-size\"}"));
+        });
+
+        let depth = WorkerMessage::RunOperation {
+            request_id: 9,
+            operation: DbOperation::QueueDepth,
+        };
+        assert_serialization_roundtrip(depth, "run-operation", |json| {
+            assert!(json.contains("\"operation\":{\"kind\":\"queue-depth\"}"));
+        });
+
+        let ping = WorkerMessage::RunOperation {
+            request_id: 9,
+            operation: DbOperation::Ping,
+        };
+        assert_serialization_roundtrip(ping, "run-operation", |json| {
+            assert!(json.contains("\"operation\":{\"kind\":\"ping\"}"));
+        });
+
+        let memory_only = WorkerMessage::RunOperation {
+            request_id: 9,
+            operation: DbOperation::MemoryOnly,
+        };
+        assert_serialization_roundtrip(memory_only, "run-operation", |json| {
+            assert!(json.contains("\"operation\":{\"kind\":\"memory-only\"}"));
+        });
+
+        let page_size = WorkerMessage::RunOperation {
+            request_id: 10,
+            operation: DbOperation::PageSize { value: Some(8192) },
+        };
+        assert_serialization_roundtrip(page_size, "run-operation", |json| {
+            assert!(json.contains("\"operation\":{\"kind\":\"page-size\",\"value\":8192}"));
+        });
+
+        let header = WorkerMessage::RunOperation {
+            request_id: 7,
+            operation: DbOperation::HeaderValue {
+                pragma: HeaderPragma::ApplicationId,
+                value: Some(42),
+            },
+        };
+        assert_serialization_roundtrip(header, "run-operation", |json| {
+            assert!(json.contains("\"kind\":\"header-value\""));
+            assert!(json.contains("\"pragma\":\"application_id\""));
+            assert!(json.contains("\"value\":42"));
+        });
+
+        // The image only round-trips through JS values, which keep the
+        // `Uint8Array` itself.
+        let bytes = js_sys::Uint8Array::from(&b"SQL"[..]);
+        let import = WorkerMessage::RunOperation {
+            request_id: 5,
+            operation: DbOperation::Import {
+                bytes: bytes.clone(),
+            },
+        };
+        let value = serde_wasm_bindgen::to_value(&import).expect("Should serialize");
+        let operation = Reflect::get(&value, &JsValue::from_str("operation")).expect("operation");
+        let sent = Reflect::get(&operation, &JsValue::from_str("bytes")).expect("bytes");
+        assert_eq!(sent, JsValue::from(bytes));
+        let decoded: WorkerMessage =
+            serde_wasm_bindgen::from_value(value).expect("Should deserialize");
+        assert_eq!(decoded, import);
+
+        let open = WorkerMessage::RunOperation {
+            request_id: 8,
+            operation: DbOperation::OpenCursor {
+                sql: "SELECT n FROM t WHERE n > ?".to_string(),
+                params: Some(vec![serde_json::json!(1)]),
+                chunk_size: 500,
+                max_cell_bytes: Some(64),
+            },
+        };
+        assert_serialization_roundtrip(open, "run-operation", |json| {
+            assert!(json.contains("\"kind\":\"open-cursor\""));
+            assert!(json.contains("\"params\":[1]"));
+            assert!(json.contains("\"chunkSize\":500"));
+            assert!(json.contains("\"maxCellBytes\":64"));
+        });
+
+        let chunk = ChannelMessage::OperationRequest {
+            query_id: "op-2".to_string(),
+            operation: DbOperation::QueryChunk { cursor: 3 },
+        };
+        assert_serialization_roundtrip(chunk, "operation-request", |json| {
+            assert!(json.contains("\"operation\":{\"kind\":\"query-chunk\",\"cursor\":3}"));
+        });
+
+        let close = WorkerMessage::RunOperation {
+            request_id: 9,
+            operation: DbOperation::CloseCursor { cursor: 3 },
+        };
+        assert_serialization_roundtrip(close, "run-operation", |json| {
+            assert!(json.contains("\"operation\":{\"kind\":\"close-cursor\",\"cursor\":3}"));
+        });
+
+        let batch = WorkerMessage::RunOperation {
+            request_id: 10,
+            operation: DbOperation::Transaction {
+                statements: vec![
+                    BatchStatement {
+                        sql: "INSERT INTO t VALUES (?)".to_string(),
+                        params: Some(vec![serde_json::json!(1)]),
+                    },
+                    BatchStatement {
+                        sql: "DELETE FROM u".to_string(),
+                        params: None,
+                    },
+                ],
+                chunk_size: Some(1000),
+            },
+        };
+        assert_serialization_roundtrip(batch, "run-operation", |json| {
+            assert!(json.contains("\"kind\":\"transaction\""));
+            assert!(json.contains("\"params\":[1]"));
+            assert!(json.contains("{\"sql\":\"DELETE FROM u\"}"));
+            assert!(json.contains("\"chunkSize\":1000"));
+        });
+
+        let explain = WorkerMessage::RunOperation {
+            request_id: 11,
+            operation: DbOperation::Explain {
+                sql: "SELECT * FROM t WHERE a = ?".to_string(),
+                params: Some(vec![serde_json::json!(1)]),
+            },
+        };
+        assert_serialization_roundtrip(explain, "run-operation", |json| {
+            assert!(json.contains("\"kind\":\"explain\""));
+            assert!(json.contains("\"sql\":\"SELECT * FROM t WHERE a = ?\""));
+        });
+
+        let validate = WorkerMessage::RunOperation {
+            request_id: 12,
+            operation: DbOperation::Validate {
+                sql: "SELECT 1; SELECT 2".to_string(),
+            },
+        };
+        assert_serialization_roundtrip(validate, "run-operation", |json| {
+            assert!(json.contains("\"kind\":\"validate\""));
+            assert!(json.contains("\"sql\":\"SELECT 1; SELECT 2\""));
+        });
+
+        let last_error = WorkerMessage::RunOperation {
+            request_id: 13,
+            operation: DbOperation::LastError,
+        };
+        assert_serialization_roundtrip(last_error, "run-operation", |json| {
+            assert!(json.contains("\"kind\":\"last-error\""));
+        });
+
+        let forwarded = ChannelMessage::OperationRequest {
+            query_id: "op-1".to_string(),
+            operation: DbOperation::Flush,
+        };
+        assert_serialization_roundtrip(forwarded, "operation-request", |json| {
+            assert!(json.contains("\"queryId\":\"op-1\""));
+            assert!(json.contains("\"kind\":\"flush\""));
+        });
+    }
+
     #[wasm_bindgen_test]
     fn test_main_thread_messages_serialization() {
         let success_result = MainThreadMessage::QueryResult {
             request_id: 7,
             result: Some("Success".to_string()),
             error: None,
+            timing: None,
         };
         assert_serialization_roundtrip(success_result, "query-result", |json| {
             assert!(json.contains("\"result\":\"Success\""));
             assert!(json.contains("\"error\":null"));
             assert!(json.contains("\"requestId\":7"));
+            assert!(!json.contains("timing"));
+        });
+
+        let profiled_result = MainThreadMessage::QueryResult {
+            request_id: 9,
+            result: Some("[]".to_string()),
+            error: None,
+            timing: Some(QueryTiming {
+                queued_ms: 1.5,
+                exec_ms: 2.0,
+            }),
+        };
+        assert_serialization_roundtrip(profiled_result, "query-result", |json| {
+            assert!(json.contains("\"timing\":{\"queuedMs\":1.5,\"execMs\":2.0}"));
         });
 
         let error_result = MainThreadMessage::QueryResult {
@@ -197,7 +941,9 @@ mod tests {
             error: Some(WorkerErrorPayload {
                 error_type: WORKER_ERROR_TYPE_GENERIC.to_string(),
                 message: Some("Database error".to_string()),
+                sqlite: None,
             }),
+            timing: None,
         };
         assert_serialization_roundtrip(error_result, "query-result", |json| {
             assert!(json.contains("\"type\":\"WorkerError\""));
@@ -208,6 +954,100 @@ mod tests {
 
         let worker_ready = MainThreadMessage::WorkerReady;
         assert_serialization_roundtrip(worker_ready, "worker-ready", |_| {});
+
+        let leadership = MainThreadMessage::WorkerEvent {
+            event: WorkerEvent::LeadershipChange {
+                leader_id: "leader-2".to_string(),
+                is_leader: false,
+            },
+        };
+        assert_serialization_roundtrip(leadership, "worker-event", |json| {
+            assert!(json.contains("\"kind\":\"leadership-change\""));
+            assert!(json.contains("\"leaderId\":\"leader-2\""));
+            assert!(json.contains("\"isLeader\":false"));
+        });
+
+        let log = MainThreadMessage::WorkerLog {
+            level: LogLevel::Warn,
+            message: "DB worker restarted".to_string(),
+            timestamp: 1_700_000_000_000.0,
+        };
+        assert_serialization_roundtrip(log, "worker-log", |json| {
+            assert!(json.contains("\"level\":\"warn\""));
+            assert!(json.contains("\"message\":\"DB worker restarted\""));
+            assert!(json.contains("\"timestamp\":1700000000000"));
+        });
+    }
+
+    #[wasm_bindgen_test]
+    fn test_db_change_event_serialization() {
+        let event = WorkerEvent::DbChange {
+            changes: vec![RowChange {
+                op: "insert".to_string(),
+                table: "users".to_string(),
+                rowid: 7,
+            }],
+        };
+        assert!(event.is_shared());
+        assert_serialization_roundtrip(
+            ChannelMessage::WorkerEvent {
+                event: event.clone(),
+            },
+            "worker-event",
+            |json| {
+                assert!(json.contains("\"kind\":\"db-change\""));
+                assert!(json
+                    .contains("\"changes\":[{\"op\":\"insert\",\"table\":\"users\",\"rowid\":7}]"));
+            },
+        );
+
+        let leadership = WorkerEvent::LeadershipChange {
+            leader_id: "leader-1".to_string(),
+            is_leader: true,
+        };
+        assert!(!leadership.is_shared());
+    }
+
+    #[wasm_bindgen_test]
+    fn test_transaction_event_serialization() {
+        for (event, kind) in [
+            (WorkerEvent::TransactionCommitted, "transaction-committed"),
+            (
+                WorkerEvent::TransactionRolledBack,
+                "transaction-rolled-back",
+            ),
+        ] {
+            assert!(event.is_shared());
+            assert_serialization_roundtrip(
+                MainThreadMessage::WorkerEvent { event },
+                "worker-event",
+                |json| {
+                    assert!(json.contains(&format!("\"event\":{{\"kind\":\"{kind}\"}}")));
+                },
+            );
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn test_init_progress_event_serialization() {
+        for (stage, name) in [
+            (InitStage::WorkerSpawned, "worker-spawned"),
+            (InitStage::LeaderElected, "leader-elected"),
+            (InitStage::OpfsInstalled, "opfs-installed"),
+            (InitStage::DbOpen, "db-open"),
+        ] {
+            let event = WorkerEvent::InitProgress { stage };
+            assert!(!event.is_shared());
+            assert_serialization_roundtrip(
+                MainThreadMessage::WorkerEvent { event },
+                "worker-event",
+                |json| {
+                    assert!(json.contains(&format!(
+                        "\"event\":{{\"kind\":\"init-progress\",\"stage\":\"{name}\"}}"
+                    )));
+                },
+            );
+        }
     }
 
     #[wasm_bindgen_test]
@@ -230,6 +1070,7 @@ mod tests {
             query_id: "test".to_string(),
             sql: String::new(),
             params: None,
+            options: None,
         };
         assert_serialization_roundtrip(empty_sql, "query-request", |json| {
             assert!(json.contains("\"sql\":\"\""));
@@ -239,6 +1080,7 @@ mod tests {
             query_id: "query\"with\"quotes".to_string(),
             sql: "SELECT 'test\nwith\nnewlines'".to_string(),
             params: None,
+            options: None,
         };
         assert_serialization_roundtrip(special_chars, "query-request", |_| {});
     }
@@ -18,6 +18,35 @@ pub fn sanitize_identifier(name: &str) -> String {
     }
 }
 
+/// `namespace` made safe for channel, lock and OPFS directory names. Letters,
+/// digits, `.` and `-` are kept and every other byte becomes `_` and two hex
+/// digits, so unlike `sanitize_identifier` two namespaces never share a name.
+pub fn encode_namespace(namespace: &str) -> String {
+    let mut encoded = String::with_capacity(namespace.len());
+    for byte in namespace.bytes() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'.' | b'-' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("_{byte:02x}")),
+        }
+    }
+    encoded
+}
+
+/// `name` sanitized and, with a namespace, prefixed by the encoded
+/// namespace. Names are sanitized like the file they open, so names sharing
+/// a file also share the identifier; the `:` separator never appears in
+/// either part, so different namespaces never do.
+pub fn namespaced_identifier(namespace: Option<&str>, name: &str) -> String {
+    match namespace {
+        Some(namespace) => format!(
+            "{}:{}",
+            encode_namespace(namespace),
+            sanitize_identifier(name)
+        ),
+        None => sanitize_identifier(name),
+    }
+}
+
 pub fn sanitize_db_filename(name: &str) -> String {
     let mut id = sanitize_identifier(name);
     if !id.ends_with(".db") {
@@ -26,6 +55,19 @@ pub fn sanitize_db_filename(name: &str) -> String {
     id
 }
 
+/// `performance.now()`, or `Date.now()` where the global has no
+/// `performance`.
+pub fn now_ms() -> f64 {
+    let performance = Reflect::get(&js_sys::global(), &JsValue::from_str("performance"))
+        .unwrap_or(JsValue::UNDEFINED);
+    Reflect::get(&performance, &JsValue::from_str("now"))
+        .ok()
+        .and_then(|now| now.dyn_into::<js_sys::Function>().ok())
+        .and_then(|now| now.call0(&performance).ok())
+        .and_then(|value| value.as_f64())
+        .unwrap_or_else(js_sys::Date::now)
+}
+
 pub fn set_js_property(target: &JsValue, key: &str, value: &JsValue) -> Result<(), JsValue> {
     match Reflect::set(target, &JsValue::from_str(key), value) {
         Ok(true) => Ok(()),
@@ -59,6 +101,82 @@ pub fn js_value_to_string(value: &JsValue) -> String {
     }
 }
 
+/// Whether `sql` creates a temporary table, view, index or trigger, either
+/// with `TEMP`/`TEMPORARY` or by naming the `temp` schema, quoted or not.
+/// String literals, other quoted identifiers and comments are skipped.
+pub fn creates_temp_object(sql: &str) -> bool {
+    let tokens = sql_tokens(sql);
+    tokens.split(|token| token == ";").any(|statement| {
+        let mut words = statement.iter().map(String::as_str);
+        if words.next() != Some("CREATE") {
+            return false;
+        }
+        let mut rest = words.skip_while(|word| *word == "UNIQUE").peekable();
+        if matches!(rest.peek(), Some(&"TEMP") | Some(&"TEMPORARY")) {
+            return true;
+        }
+        let mut rest = rest
+            .skip_while(|word| matches!(*word, "TABLE" | "VIEW" | "INDEX" | "TRIGGER"))
+            .skip_while(|word| matches!(*word, "IF" | "NOT" | "EXISTS"));
+        rest.next() == Some("TEMP") && rest.next() == Some(".")
+    })
+}
+
+/// Upper-cased words and single punctuation characters of `sql`, without
+/// comments. Literals and quoted identifiers become `?`, except a quoted
+/// `temp`, which names the temp schema like the bare word.
+fn sql_tokens(sql: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = sql.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' | '`' | '[' => {
+                let close = if c == '[' { ']' } else { c };
+                let mut quoted = String::new();
+                // A doubled quote stands for one inside the literal.
+                while let Some(next) = chars.next() {
+                    if next == close && (close == ']' || chars.next_if_eq(&close).is_none()) {
+                        break;
+                    }
+                    quoted.push(next);
+                }
+                if c != '\'' && quoted.eq_ignore_ascii_case("temp") {
+                    tokens.push("TEMP".to_string());
+                } else {
+                    tokens.push("?".to_string());
+                }
+            }
+            '-' if chars.next_if_eq(&'-').is_some() => {
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.next_if_eq(&'*').is_some() => {
+                let mut prev = ' ';
+                for next in chars.by_ref() {
+                    if prev == '*' && next == '/' {
+                        break;
+                    }
+                    prev = next;
+                }
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut word = c.to_ascii_uppercase().to_string();
+                while let Some(next) = chars.next_if(|next| next.is_alphanumeric() || *next == '_')
+                {
+                    word.push(next.to_ascii_uppercase());
+                }
+                tokens.push(word);
+            }
+            c if c.is_whitespace() => {}
+            c => tokens.push(c.to_string()),
+        }
+    }
+    tokens
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -71,6 +189,56 @@ mod tests {
         assert_eq!(sanitize_identifier(""), "db");
     }
 
+    #[test]
+    fn test_namespaced_identifier() {
+        assert_eq!(namespaced_identifier(None, "my db"), "my_db");
+        assert_eq!(namespaced_identifier(Some("app"), "my db"), "app:my_db");
+        assert_ne!(
+            namespaced_identifier(Some("a-b"), "c"),
+            namespaced_identifier(Some("a"), "b-c")
+        );
+        assert_ne!(
+            namespaced_identifier(Some("app one"), "db"),
+            namespaced_identifier(Some("app_one"), "db")
+        );
+    }
+
+    #[test]
+    fn test_encode_namespace() {
+        // Keep in sync with `namespace_encoding_matches_the_core_crate` in
+        // the main crate's opfs.rs.
+        assert_eq!(encode_namespace("app-one.v2"), "app-one.v2");
+        assert_eq!(encode_namespace("app one"), "app_20one");
+        assert_eq!(encode_namespace("app_one"), "app_5fone");
+        assert_eq!(encode_namespace("é"), "_c3_a9");
+    }
+
+    #[test]
+    fn test_creates_temp_object() {
+        assert!(creates_temp_object("CREATE TEMP TABLE t (a)"));
+        assert!(creates_temp_object("create temporary view v AS SELECT 1"));
+        assert!(creates_temp_object("CREATE TABLE IF NOT EXISTS temp.t (a)"));
+        assert!(creates_temp_object("CREATE UNIQUE INDEX temp.i ON t (a)"));
+        assert!(creates_temp_object(
+            "SELECT 1; CREATE TEMP TRIGGER tr AFTER INSERT ON t BEGIN SELECT 1; END"
+        ));
+        assert!(!creates_temp_object("CREATE TABLE temps (a)"));
+        assert!(!creates_temp_object("CREATE TABLE t (temp TEXT)"));
+        assert!(!creates_temp_object(
+            "INSERT INTO t VALUES ('CREATE TEMP TABLE x (a)')"
+        ));
+        assert!(!creates_temp_object("-- CREATE TEMP TABLE x (a)\nSELECT 1"));
+        assert!(!creates_temp_object(
+            "/* CREATE TEMP TABLE x */ CREATE TABLE y (a)"
+        ));
+        assert!(!creates_temp_object("SELECT * FROM temp.t"));
+        assert!(creates_temp_object("CREATE TABLE \"temp\".t (a)"));
+        assert!(creates_temp_object("CREATE VIEW [TEMP].v AS SELECT 1"));
+        assert!(creates_temp_object("CREATE INDEX `temp`.i ON t (a)"));
+        assert!(!creates_temp_object("CREATE TABLE \"temp\" (a)"));
+        assert!(!creates_temp_object("CREATE TABLE 'temp'.t (a)"));
+    }
+
     #[test]
     fn test_sanitize_db_filename() {
         assert_eq!(sanitize_db_filename("mydb"), "mydb.db");
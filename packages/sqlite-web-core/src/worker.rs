@@ -6,9 +6,10 @@ use wasm_bindgen::JsCast;
 use web_sys::{DedicatedWorkerGlobalScope, MessageEvent};
 
 use crate::coordination::{
-    send_worker_error, worker_config_from_global, CoordinatorState, DbWorkerState, WorkerConfig,
+    debug_logging_from_global, send_init_progress, send_worker_error, send_worker_log,
+    set_debug_logging, worker_config_from_global, CoordinatorState, DbWorkerState, WorkerConfig,
 };
-use crate::messages::WorkerMessage;
+use crate::messages::{InitStage, LogLevel, WorkerMessage};
 
 enum WorkerRuntime {
     Coordinator(Rc<CoordinatorState>),
@@ -43,9 +44,16 @@ fn install_main_thread_handler() {
                 });
             }
             Err(err) => {
-                let _ = send_worker_error(JsValue::from_str(&format!(
-                    "Invalid worker message: {err:?}"
-                )));
+                let data = event.data();
+                let answered = RUNTIME.with(|runtime| match runtime.borrow().as_ref() {
+                    Some(WorkerRuntime::DbOnly(db)) => db.handle_unknown_message(&data),
+                    _ => false,
+                });
+                if !answered {
+                    let _ = send_worker_error(JsValue::from_str(&format!(
+                        "Invalid worker message: {err:?}"
+                    )));
+                }
             }
         }
     }) as Box<dyn FnMut(MessageEvent)>);
@@ -56,6 +64,7 @@ fn install_main_thread_handler() {
 
 fn start_coordinator_runtime(config: WorkerConfig) -> Result<(), JsValue> {
     let state = CoordinatorState::new(config)?;
+    send_init_progress(InitStage::WorkerSpawned);
     state.setup_channel_listener()?;
     state.start_leader_probe();
     state.try_become_leader();
@@ -78,10 +87,24 @@ fn start_db_only_runtime(config: WorkerConfig) -> Result<(), JsValue> {
     Ok(())
 }
 
-/// Entry point for the worker - called from the blob
+/// Entry point for the worker - called from the blob. Calling it again once
+/// a runtime is running does nothing, so a worker never ends up with two
+/// channel listeners or two elections.
 pub fn main() -> Result<(), JsValue> {
     console_error_panic_hook::set_once();
+    if RUNTIME.with(|runtime| runtime.borrow().is_some()) {
+        return Ok(());
+    }
     let config = worker_config_from_global()?;
+    set_debug_logging(debug_logging_from_global());
+    send_worker_log(LogLevel::Debug, || {
+        let mode = if is_db_only_mode() {
+            "DB"
+        } else {
+            "coordinator"
+        };
+        format!("Starting {mode} worker for {}", config.db_name)
+    });
 
     if is_db_only_mode() {
         start_db_only_runtime(config)
@@ -104,6 +127,35 @@ mod tests {
         assert!(!is_db_only_mode());
     }
 
+    fn running_coordinator() -> Option<Rc<CoordinatorState>> {
+        RUNTIME.with(|runtime| match runtime.borrow().as_ref() {
+            Some(WorkerRuntime::Coordinator(state)) => Some(Rc::clone(state)),
+            _ => None,
+        })
+    }
+
+    #[wasm_bindgen_test]
+    fn main_starts_the_runtime_once() {
+        let global = js_sys::global();
+        let _ = Reflect::delete_property(&global, &JsValue::from_str("__SQLITE_DB_ONLY"));
+        Reflect::set(
+            &global,
+            &JsValue::from_str("__SQLITE_DB_NAME"),
+            &JsValue::from_str("testdb-main-once"),
+        )
+        .unwrap();
+
+        main().expect("first start");
+        let first = running_coordinator().expect("coordinator runtime");
+        main().expect("second start");
+        let second = running_coordinator().expect("coordinator runtime");
+
+        assert!(
+            Rc::ptr_eq(&first, &second),
+            "the second call should keep the running coordinator"
+        );
+    }
+
     #[wasm_bindgen_test]
     fn db_only_mode_reads_flag() {
         Reflect::set(
@@ -0,0 +1,262 @@
+use js_sys::{Function, Int32Array, Object, Reflect};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{AbortSignal, Worker};
+
+use crate::messages::{
+    WORKER_ERROR_TYPE_QUERY_ABORTED, WORKER_ERROR_TYPE_QUERY_TIMEOUT,
+    WORKER_ERROR_TYPE_WORKER_RESTARTED,
+};
+use crate::worker::PendingQueries;
+
+/// Rejects a pending request when its `AbortSignal` fires and asks the worker
+/// to cancel it. The listener is removed when this guard is dropped, so an
+/// abort after the request settled does nothing.
+pub(crate) struct AbortListener {
+    signal: AbortSignal,
+    callback: Closure<dyn FnMut()>,
+}
+
+impl AbortListener {
+    pub(crate) fn attach(
+        signal: &AbortSignal,
+        request_id: u32,
+        worker: Rc<RefCell<Worker>>,
+        pending_queries: PendingQueries,
+        cancel_signal: CancelSignal,
+    ) -> Self {
+        let callback = Closure::wrap(Box::new(move || {
+            cancel_pending(
+                request_id,
+                &worker,
+                &pending_queries,
+                &cancel_signal,
+                make_aborted_error(),
+            );
+        }) as Box<dyn FnMut()>);
+        let _ = signal.add_event_listener_with_callback("abort", callback.as_ref().unchecked_ref());
+        Self {
+            signal: signal.clone(),
+            callback,
+        }
+    }
+}
+
+impl Drop for AbortListener {
+    fn drop(&mut self) {
+        let _ = self
+            .signal
+            .remove_event_listener_with_callback("abort", self.callback.as_ref().unchecked_ref());
+    }
+}
+
+/// Rejects a pending request with a timeout error once `timeoutMs` elapses and
+/// asks the worker to cancel it. Dropping the guard clears the timer, so a
+/// request that settles in time is never rejected afterwards. The timer is
+/// set on the global scope, so it also runs when the library is used from a
+/// worker.
+pub(crate) struct TimeoutGuard {
+    handle: JsValue,
+    _callback: Closure<dyn FnMut()>,
+}
+
+impl TimeoutGuard {
+    pub(crate) fn start(
+        timeout_ms: f64,
+        request_id: u32,
+        worker: Rc<RefCell<Worker>>,
+        pending_queries: PendingQueries,
+        cancel_signal: CancelSignal,
+    ) -> Option<Self> {
+        let set_timeout = global_function("setTimeout")?;
+        let callback = Closure::wrap(Box::new(move || {
+            cancel_pending(
+                request_id,
+                &worker,
+                &pending_queries,
+                &cancel_signal,
+                make_timeout_error(),
+            );
+        }) as Box<dyn FnMut()>);
+        let handle = set_timeout
+            .call2(
+                &js_sys::global(),
+                callback.as_ref(),
+                &JsValue::from_f64(timeout_ms.ceil().min(i32::MAX as f64)),
+            )
+            .ok()?;
+        Some(Self {
+            handle,
+            _callback: callback,
+        })
+    }
+}
+
+impl Drop for TimeoutGuard {
+    fn drop(&mut self) {
+        if let Some(clear_timeout) = global_function("clearTimeout") {
+            let _ = clear_timeout.call1(&js_sys::global(), &self.handle);
+        }
+    }
+}
+
+/// A function of the global scope, whether that is a window or a worker.
+fn global_function(name: &str) -> Option<Function> {
+    Reflect::get(&js_sys::global(), &JsValue::from_str(name))
+        .ok()?
+        .dyn_into::<Function>()
+        .ok()
+}
+
+fn cancel_pending(
+    request_id: u32,
+    worker: &Rc<RefCell<Worker>>,
+    pending_queries: &PendingQueries,
+    cancel_signal: &CancelSignal,
+    error: JsValue,
+) {
+    let Some((_, reject)) = pending_queries.borrow_mut().remove(&request_id) else {
+        return;
+    };
+    let _ = reject.call1(&JsValue::NULL, &error);
+    cancel_signal.cancel(request_id);
+    let _ = post_cancel_message(&worker.borrow(), request_id);
+}
+
+/// Cancel slots handed over by a worker that runs the database itself, as an
+/// unshared connection's does. That worker is blocked while a statement
+/// runs, so it reads a cancel message only afterwards; a request id written
+/// here stops the statement straight away. Empty for shared connections,
+/// whose coordinator writes to its own DB worker's slots, and without
+/// `SharedArrayBuffer`.
+#[derive(Clone, Default)]
+pub(crate) struct CancelSignal(Rc<RefCell<Option<(Int32Array, Cell<u32>)>>>);
+
+impl CancelSignal {
+    /// Take the slots from a `worker-ready` message, replacing those of any
+    /// previous worker. Other messages are ignored.
+    pub(crate) fn adopt(&self, message: &JsValue) {
+        let message_type = Reflect::get(message, &JsValue::from_str("type"))
+            .ok()
+            .and_then(|value| value.as_string());
+        if message_type.as_deref() != Some("worker-ready") {
+            return;
+        }
+        let slots = Reflect::get(message, &JsValue::from_str("cancelSlots"))
+            .ok()
+            .and_then(|slots| slots.dyn_into::<Int32Array>().ok());
+        self.0.replace(slots.map(|slots| (slots, Cell::new(0))));
+    }
+
+    pub(crate) fn clear(&self) {
+        self.0.replace(None);
+    }
+
+    pub(crate) fn cancel(&self, request_id: u32) {
+        if let Some((slots, next)) = self.0.borrow().as_ref() {
+            let index = next.get();
+            next.set((index + 1) % slots.length().max(1));
+            let _ = js_sys::Atomics::store(slots, index, request_id as i32);
+        }
+    }
+}
+
+pub(crate) fn make_aborted_error() -> JsValue {
+    make_structured_error(WORKER_ERROR_TYPE_QUERY_ABORTED, "Query aborted")
+}
+
+pub(crate) fn make_timeout_error() -> JsValue {
+    make_structured_error(WORKER_ERROR_TYPE_QUERY_TIMEOUT, "Query timed out")
+}
+
+pub(crate) fn make_worker_restarted_error() -> JsValue {
+    make_structured_error(
+        WORKER_ERROR_TYPE_WORKER_RESTARTED,
+        "Worker restarted before answering",
+    )
+}
+
+fn make_structured_error(error_type: &str, message: &str) -> JsValue {
+    let error = Object::new();
+    let _ = Reflect::set(
+        &error,
+        &JsValue::from_str("type"),
+        &JsValue::from_str(error_type),
+    );
+    let _ = Reflect::set(
+        &error,
+        &JsValue::from_str("message"),
+        &JsValue::from_str(message),
+    );
+    error.into()
+}
+
+pub(crate) fn post_cancel_message(worker: &Worker, request_id: u32) -> Result<(), JsValue> {
+    let message = Object::new();
+    Reflect::set(
+        &message,
+        &JsValue::from_str("type"),
+        &JsValue::from_str("cancel-query"),
+    )?;
+    Reflect::set(
+        &message,
+        &JsValue::from_str("requestId"),
+        &JsValue::from_f64(request_id as f64),
+    )?;
+    worker.post_message(&message)
+}
+
+#[cfg(all(test, target_family = "wasm"))]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn aborted_error_is_structured() {
+        let error = make_aborted_error();
+        let error_type = Reflect::get(&error, &JsValue::from_str("type"))
+            .unwrap()
+            .as_string();
+        assert_eq!(error_type.as_deref(), Some(WORKER_ERROR_TYPE_QUERY_ABORTED));
+    }
+
+    #[wasm_bindgen_test]
+    fn cancel_signal_writes_ids_round_robin() {
+        let slots = Int32Array::new_with_length(2);
+        let ready = Object::new();
+        Reflect::set(&ready, &JsValue::from_str("type"), &"worker-ready".into()).unwrap();
+        Reflect::set(&ready, &JsValue::from_str("cancelSlots"), &slots).unwrap();
+        let signal = CancelSignal::default();
+        signal.adopt(&ready);
+
+        for request_id in [4, 5, 6] {
+            signal.cancel(request_id);
+        }
+        assert_eq!(slots.to_vec(), vec![6, 5]);
+
+        signal.clear();
+        signal.cancel(7);
+        assert_eq!(slots.to_vec(), vec![6, 5]);
+    }
+
+    #[wasm_bindgen_test]
+    fn timers_come_from_the_global_scope() {
+        assert!(global_function("setTimeout").is_some());
+        assert!(global_function("clearTimeout").is_some());
+        assert!(global_function("noSuchTimer").is_none());
+    }
+
+    #[wasm_bindgen_test]
+    fn timeout_error_is_structured() {
+        let error = make_timeout_error();
+        let error_type = Reflect::get(&error, &JsValue::from_str("type"))
+            .unwrap()
+            .as_string();
+        assert_eq!(error_type.as_deref(), Some(WORKER_ERROR_TYPE_QUERY_TIMEOUT));
+    }
+}
@@ -1,30 +1,69 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::rc::Rc;
 
 use js_sys::{Array, Reflect};
 use serde::Serialize;
 use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::JsFuture;
 use wasm_bindgen_utils::prelude::*;
 use web_sys::Worker;
 
+use crate::abort::{
+    make_aborted_error, make_worker_restarted_error, post_cancel_message, AbortListener,
+    CancelSignal, TimeoutGuard,
+};
+use crate::diagnostics::{workers_available, SelfTestReport, SELF_TEST_DB_NAME};
 use crate::errors::SQLiteWasmDatabaseError;
-use crate::messages::WORKER_ERROR_TYPE_INITIALIZATION_PENDING;
-use crate::opfs::delete_opfs_sahpool_directory;
-use crate::params::normalize_params_js;
+use crate::events::EventListeners;
+use crate::expand::expand_named_params;
+use crate::image::{image_has_sqlite_header, DatabaseImage};
+use crate::maintenance::{IntegrityReport, VacuumReport};
+use crate::messages::{
+    EVENT_DB_CHANGE, EVENT_INIT_PROGRESS, EVENT_LEADERSHIP_CHANGE, EVENT_TRANSACTION_COMMITTED,
+    EVENT_TRANSACTION_ROLLED_BACK, EVENT_WORKER_LOG, WORKER_ERROR_TYPE_INITIALIZATION_PENDING,
+    WORKER_ERROR_TYPE_LEADER_ELECTION_TIMEOUT, WORKER_ERROR_TYPE_LEADER_STEPDOWN,
+    WORKER_ERROR_TYPE_QUERY_ABORTED, WORKER_ERROR_TYPE_QUERY_TIMEOUT,
+    WORKER_ERROR_TYPE_SERVER_BUSY, WORKER_ERROR_TYPE_WORKER_RESTARTED,
+};
+use crate::opfs::{
+    delete_opfs_sahpool_directory, get_opfs_root, hold_pool_lock, sweep_stale_private_pools,
+    PoolLock,
+};
+use crate::options::{
+    ConnectionOptions, InsertOptions, QueryOptions, TableExistsOptions, TransactionOptions,
+};
+use crate::params::{normalize_params_js, normalize_scalar_or_params_js};
+use crate::plan::QueryPlanStep;
 use crate::ready::{InitializationState, ReadySignal};
+use crate::role::LeadershipInfo;
 use crate::utils::describe_js_value;
-use crate::worker::{create_worker_from_code, install_onmessage_handler};
+use crate::validation::{LastError, SqlValidation};
+use crate::worker::{
+    create_worker_from_code, install_onmessage_handler, PagehideListener, PendingQueries,
+};
 use crate::worker_template::generate_self_contained_worker;
 
+/// How long `close()` waits for the worker to hand off leadership before
+/// stopping it anyway.
+const CLOSE_HANDOFF_TIMEOUT_MS: f64 = 1000.0;
+
 #[wasm_bindgen]
+#[derive(Clone)]
 pub struct SQLiteWasmDatabase {
     worker: Rc<RefCell<Worker>>,
     db_name: String,
-    pending_queries: Rc<RefCell<HashMap<u32, (js_sys::Function, js_sys::Function)>>>,
+    options: ConnectionOptions,
+    pending_queries: PendingQueries,
     next_request_id: Rc<RefCell<u32>>,
     ready_signal: ReadySignal,
+    listeners: EventListeners,
+    cancel_signal: CancelSignal,
+    pagehide: Rc<RefCell<Option<PagehideListener>>>,
+    closed: Rc<Cell<bool>>,
+    /// Held by a `shared: false` connection until it closes.
+    pool_lock: Rc<RefCell<Option<PoolLock>>>,
 }
 
 impl Serialize for SQLiteWasmDatabase {
@@ -41,48 +80,266 @@ impl Serialize for SQLiteWasmDatabase {
 #[wasm_export]
 impl SQLiteWasmDatabase {
     /// Create a new database connection with fully embedded worker
+    ///
+    /// `options.customFunctions: false` opens the database without the
+    /// custom SQL functions (FLOAT_*, BIGINT_*, REGEXP, NOW_MS and the rest);
+    /// an array such as `["BIGINT_SUM"]` registers only the named ones.
+    /// Options apply to the worker that leads the database, so every tab
+    /// sharing it should pass the same ones.
+    /// `options.pragmas` lists pragmas such as `"foreign_keys = ON"` to run
+    /// in order when the database is opened; if one fails, opening fails.
+    /// `options.synchronous` (`"OFF"`, `"NORMAL"`, `"FULL"` or `"EXTRA"`)
+    /// sets `PRAGMA synchronous` before them. The OPFS pool flushes each
+    /// file on sync, so `NORMAL` skips some of those flushes and is faster;
+    /// committed writes survive closing the tab, but a browser or OS crash
+    /// may lose the last transactions. `OFF` never flushes and can lose
+    /// more. The default, `FULL`, flushes on every commit.
+    /// `options.namespace` (letters, digits, `.`, `_` and `-`) isolates the
+    /// connection from others on the same origin: tabs only coordinate
+    /// with, and `wipeAndRecreate()` only deletes the storage of,
+    /// connections in the same namespace, so two apps can use the same
+    /// database name without sharing data.
+    /// `options.maxPendingQueries` is an exception: it only limits this
+    /// connection, rejecting new requests while that many await a reply.
+    /// `options.announceIntervalMs` also only affects this tab: it answers
+    /// other tabs' leader pings at most once per interval, so many tabs
+    /// opening together cause less channel traffic.
+    /// `options.followerRetries` (default 2) is how many times a follower
+    /// tab resends a request whose leader went away without answering. A
+    /// leader that answers a resend it is still running, or has just
+    /// answered, does not run it twice unless that answer was over 1 MiB,
+    /// but a write the old leader committed before it went away is run
+    /// again by the new one. Use `0` to fail
+    /// such requests instead when writes are not safe to repeat.
+    /// `options.maxFollowerQueries` (default 64) caps how many other tabs'
+    /// requests this tab runs at once while it leads; requests beyond it
+    /// fail with "Too many concurrent requests".
+    /// `options.maxQueueDepth` (default 1024) caps the requests, from every
+    /// tab, waiting to run on the database while this tab leads; beyond it
+    /// new ones are rejected as busy.
+    /// `options.debug: true` has the workers send their log lines, such as
+    /// leader elections and the database opening, to `onLog` callbacks;
+    /// without it nothing is logged.
+    /// `options.onProgress` is called with `{ kind: "init-progress", stage }`
+    /// as opening proceeds, e.g. to drive a loading indicator. `stage` is
+    /// `"worker-spawned"`, `"leader-elected"`, `"opfs-installed"` and then
+    /// `"db-open"`, all before `new()` resolves; a tab that follows another
+    /// one's leader stops at `"leader-elected"`. It is called again when
+    /// `reconnect()` restarts the worker.
+    /// `options.allowMemoryFallback: true` keeps a browser without OPFS
+    /// working: if the OPFS pool cannot be installed the database is opened
+    /// in memory instead of failing, `onProgress` reports
+    /// `"memory-fallback"` in place of `"opfs-installed"`, and
+    /// `isMemoryOnly()` resolves to `true`. Nothing written is persisted
+    /// then, and the data is gone once the leading tab closes.
+    /// `options.shared: false` opens a database only this connection uses:
+    /// its worker opens it directly, without electing a leader, in an OPFS
+    /// pool of its own, so two such connections with the same name never
+    /// see each other's writes. The pool is named afresh for every
+    /// connection, so its data cannot be reopened later: `close()` deletes
+    /// it, and one left behind by a tab that closed without calling
+    /// `close()` is deleted when the next `shared: false` connection opens.
+    /// Such a connection always reports itself as leader and only sends the
+    /// `"opfs-installed"` and `"db-open"` progress stages.
     #[wasm_export(js_name = "new", preserve_js_class)]
-    pub async fn new(db_name: &str) -> Result<SQLiteWasmDatabase, SQLiteWasmDatabaseError> {
+    pub async fn new(
+        db_name: &str,
+        options: Option<js_sys::Object>,
+    ) -> Result<SQLiteWasmDatabase, SQLiteWasmDatabaseError> {
         let db_name = db_name.trim();
         if db_name.is_empty() {
             return Err(SQLiteWasmDatabaseError::JsError(JsValue::from_str(
                 "Database name is required",
             )));
         }
-        let db = Self::construct(db_name)?;
+        let options = ConnectionOptions::from_js(options.as_ref())?;
+        let pool_lock = Self::lock_private_pool(&options).await;
+        let db = Self::construct(db_name, options)?;
+        db.pool_lock.replace(pool_lock);
         db.wait_until_ready().await?;
         Ok(db)
     }
 
-    fn construct(db_name: &str) -> Result<SQLiteWasmDatabase, SQLiteWasmDatabaseError> {
-        let worker_code = generate_self_contained_worker(db_name);
+    /// For a `shared: false` connection, lock its pool before the worker
+    /// creates it, then delete the pools of connections that are gone.
+    /// Without Web Locks nothing is locked, and nothing is swept either.
+    async fn lock_private_pool(options: &ConnectionOptions) -> Option<PoolLock> {
+        if !options.isolated || options.in_memory {
+            return None;
+        }
+        let lock = hold_pool_lock(options.namespace.as_deref()?).await.ok()?;
+        // A pool that cannot be deleted now is tried again on the next open.
+        let _ = sweep_stale_private_pools().await;
+        Some(lock)
+    }
+
+    /// Open `db_name` and replace its contents with a SQLite file image,
+    /// such as one produced by `export()`. The image is validated before
+    /// anything is written, so a corrupt image leaves an existing database
+    /// untouched. `bytes` is copied once and the copy transferred to the
+    /// worker, so the caller's array stays usable.
+    #[wasm_export(js_name = "import", preserve_js_class)]
+    pub async fn import(
+        db_name: &str,
+        bytes: js_sys::Uint8Array,
+    ) -> Result<SQLiteWasmDatabase, SQLiteWasmDatabaseError> {
+        if !image_has_sqlite_header(&bytes) {
+            return Err(SQLiteWasmDatabaseError::JsError(JsValue::from_str(
+                "Invalid database image: missing SQLite header",
+            )));
+        }
+        let image = bytes.slice(0, bytes.length());
+        let db = Self::new(db_name, None).await?;
+        let operation = Self::operation("import")?;
+        js_sys::Reflect::set(&operation, &JsValue::from_str("bytes"), &image)
+            .map_err(SQLiteWasmDatabaseError::JsError)?;
+        let transfer = Array::of1(&image.buffer());
+        if let Err(e) = db.run_operation_transferring(operation, &transfer).await {
+            // Release the worker and its leader lock rather than leak them.
+            db.worker.borrow().terminate();
+            return Err(e);
+        }
+        Ok(db)
+    }
+
+    /// Probe whether this environment can run the database: OPFS and
+    /// workers are checked, then a private in-memory connection runs a
+    /// create/insert/select against a TEMP table and calls a custom
+    /// function. Failed checks are
+    /// listed in `problems` rather than thrown, so the report can be pasted
+    /// into a bug report as is.
+    #[wasm_export(
+        js_name = "selfTest",
+        unchecked_return_type = "{ ok: boolean; opfsAvailable: boolean; workersAvailable: boolean; queryOk: boolean; customFunctionsOk: boolean; version: string; sqliteVersion: string | null; problems: string[] }"
+    )]
+    pub async fn self_test() -> Result<SelfTestReport, SQLiteWasmDatabaseError> {
+        let mut report = SelfTestReport::new();
+        match get_opfs_root().await {
+            Ok(_) => report.opfs_available = true,
+            Err(e) => report.fail(e.to_string()),
+        }
+        report.workers_available = workers_available();
+        if !report.workers_available {
+            report.fail("Web Workers are not available");
+            return Ok(report.finish());
+        }
+
+        let options = ConnectionOptions {
+            isolated: true,
+            in_memory: true,
+            ..Default::default()
+        };
+        let probe = match Self::construct(SELF_TEST_DB_NAME, options) {
+            Ok(db) => db.wait_until_ready().await.map(|_| db),
+            Err(e) => Err(e),
+        };
+        match probe {
+            Ok(db) => {
+                db.run_self_test(&mut report).await;
+                db.worker.borrow().terminate();
+            }
+            Err(e) => report.fail(format!("Opening a connection failed: {e}")),
+        }
+        Ok(report.finish())
+    }
+
+    async fn run_self_test(&self, report: &mut SelfTestReport) {
+        let round_trip = async {
+            self.query(
+                "CREATE TEMP TABLE IF NOT EXISTS self_test (id INTEGER PRIMARY KEY, label TEXT)",
+                JsValue::UNDEFINED,
+                None,
+            )
+            .await?;
+            self.query("DELETE FROM temp.self_test", JsValue::UNDEFINED, None)
+                .await?;
+            let params = Array::of1(&JsValue::from_str("ok"));
+            self.query(
+                "INSERT INTO temp.self_test (label) VALUES (?)",
+                params.into(),
+                None,
+            )
+            .await?;
+            self.query_rows("SELECT label FROM temp.self_test", Array::new())
+                .await
+        };
+        match round_trip.await {
+            Ok(rows) if rows == [serde_json::json!({ "label": "ok" })] => report.query_ok = true,
+            Ok(rows) => report.fail(format!(
+                "Query round trip returned {}",
+                serde_json::Value::from(rows)
+            )),
+            Err(e) => report.fail(format!("Query round trip failed: {e}")),
+        }
+
+        match self
+            .query_rows(
+                "SELECT FLOAT_IS_ZERO(FLOAT_ZERO_HEX()) AS zero, sqlite_version() AS version",
+                Array::new(),
+            )
+            .await
+        {
+            Ok(rows) => {
+                let row = rows.first();
+                report.sqlite_version = row
+                    .and_then(|row| row.get("version"))
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string);
+                if row.and_then(|row| row.get("zero")) == Some(&serde_json::json!(1)) {
+                    report.custom_functions_ok = true;
+                } else {
+                    report.fail("FLOAT_IS_ZERO(FLOAT_ZERO_HEX()) did not return 1");
+                }
+            }
+            Err(e) => report.fail(format!("Custom functions failed: {e}")),
+        }
+    }
+
+    fn construct(
+        db_name: &str,
+        options: ConnectionOptions,
+    ) -> Result<SQLiteWasmDatabase, SQLiteWasmDatabaseError> {
+        let worker_code = generate_self_contained_worker(db_name, &options);
         let worker = create_worker_from_code(&worker_code)?;
 
-        let pending_queries: Rc<RefCell<HashMap<u32, (js_sys::Function, js_sys::Function)>>> =
-            Rc::new(RefCell::new(HashMap::new()));
+        let pending_queries: PendingQueries = Rc::new(RefCell::new(HashMap::new()));
         let ready_signal = ReadySignal::new();
-        install_onmessage_handler(&worker, Rc::clone(&pending_queries), ready_signal.clone());
+        let listeners = EventListeners::default();
+        if let Some(callback) = &options.on_progress {
+            listeners.subscribe(EVENT_INIT_PROGRESS, callback.clone());
+        }
+        let cancel_signal = CancelSignal::default();
+        install_onmessage_handler(
+            &worker,
+            Rc::clone(&pending_queries),
+            ready_signal.clone(),
+            listeners.clone(),
+            cancel_signal.clone(),
+        );
         let next_request_id = Rc::new(RefCell::new(1u32));
+        let worker = Rc::new(RefCell::new(worker));
+        let pagehide = PagehideListener::install(Rc::clone(&worker));
 
         Ok(SQLiteWasmDatabase {
-            worker: Rc::new(RefCell::new(worker)),
+            worker,
             db_name: db_name.to_string(),
+            options,
             pending_queries,
             next_request_id,
             ready_signal,
+            listeners,
+            cancel_signal,
+            pagehide: Rc::new(RefCell::new(pagehide)),
+            closed: Rc::new(Cell::new(false)),
+            pool_lock: Rc::new(RefCell::new(None)),
         })
     }
 
-    fn normalize_params(params: Option<Array>) -> Result<Array, SQLiteWasmDatabaseError> {
-        let params_js = params.map(JsValue::from).unwrap_or(JsValue::UNDEFINED);
-        normalize_params_js(&params_js)
-    }
-
     async fn wait_until_ready(&self) -> Result<(), SQLiteWasmDatabaseError> {
         match self.ready_signal.current_state() {
             InitializationState::Ready => return Ok(()),
             InitializationState::Failed(reason) => {
-                return Err(SQLiteWasmDatabaseError::InitializationFailed(reason));
+                return Err(initialization_error(reason));
             }
             InitializationState::Pending => {}
         }
@@ -92,37 +349,197 @@ impl SQLiteWasmDatabase {
         match JsFuture::from(promise).await {
             Ok(_) => match self.ready_signal.current_state() {
                 InitializationState::Ready => Ok(()),
-                InitializationState::Failed(reason) => {
-                    Err(SQLiteWasmDatabaseError::InitializationFailed(reason))
-                }
+                InitializationState::Failed(reason) => Err(initialization_error(reason)),
                 InitializationState::Pending => Err(SQLiteWasmDatabaseError::InitializationFailed(
                     "Worker failed to signal readiness".to_string(),
                 )),
             },
-            Err(err) => {
-                let reason = describe_js_value(&err);
-                Err(SQLiteWasmDatabaseError::InitializationFailed(reason))
+            Err(err) => Err(initialization_error(describe_js_value(&err))),
+        }
+    }
+
+    fn allocate_request_id(&self) -> u32 {
+        let mut n = self.next_request_id.borrow_mut();
+        let id = *n;
+        *n = n.wrapping_add(1).max(1);
+        id
+    }
+
+    /// Post a request to the worker and wait for its `query-result` reply.
+    ///
+    /// The `requestId` is assigned here. When a signal is supplied, aborting it
+    /// rejects the request immediately and asks the worker to cancel it; a
+    /// timeout does the same once it elapses.
+    async fn dispatch(
+        &self,
+        message: &js_sys::Object,
+        options: &QueryOptions,
+    ) -> Result<JsValue, SQLiteWasmDatabaseError> {
+        self.dispatch_transferring(message, options, &Array::new())
+            .await
+    }
+
+    /// `dispatch`, moving the buffers in `transfer` to the worker instead of
+    /// copying them.
+    async fn dispatch_transferring(
+        &self,
+        message: &js_sys::Object,
+        options: &QueryOptions,
+        transfer: &Array,
+    ) -> Result<JsValue, SQLiteWasmDatabaseError> {
+        if self.closed.get() {
+            return Err(SQLiteWasmDatabaseError::Closed);
+        }
+        if let InitializationState::Failed(reason) = self.ready_signal.current_state() {
+            return Err(initialization_error(reason));
+        }
+        let signal = options.signal.as_ref();
+        if signal.is_some_and(|signal| signal.aborted()) {
+            return Err(SQLiteWasmDatabaseError::QueryAborted);
+        }
+        if let Some(max) = self.options.max_pending_queries {
+            if self.pending_queries.borrow().len() >= max {
+                return Err(SQLiteWasmDatabaseError::TooManyPendingQueries(max));
             }
         }
+
+        let request_id = self.allocate_request_id();
+        js_sys::Reflect::set(
+            message,
+            &JsValue::from_str("requestId"),
+            &JsValue::from_f64(request_id as f64),
+        )
+        .map_err(SQLiteWasmDatabaseError::JsError)?;
+
+        let worker = Rc::clone(&self.worker);
+        let pending_queries = Rc::clone(&self.pending_queries);
+        let promise = js_sys::Promise::new(&mut |resolve, reject| match worker
+            .borrow()
+            .post_message_with_transfer(message, transfer)
+        {
+            Ok(()) => {
+                pending_queries
+                    .borrow_mut()
+                    .insert(request_id, (resolve, reject));
+            }
+            Err(err) => {
+                let _ = reject.call1(&JsValue::NULL, &err);
+            }
+        });
+
+        let _abort_listener = signal.map(|signal| {
+            AbortListener::attach(
+                signal,
+                request_id,
+                Rc::clone(&self.worker),
+                Rc::clone(&self.pending_queries),
+                self.cancel_signal.clone(),
+            )
+        });
+        let _timeout_guard = options.timeout_ms.and_then(|timeout_ms| {
+            TimeoutGuard::start(
+                timeout_ms,
+                request_id,
+                Rc::clone(&self.worker),
+                Rc::clone(&self.pending_queries),
+                self.cancel_signal.clone(),
+            )
+        });
+
+        JsFuture::from(promise).await.map_err(map_worker_error)
     }
 
     /// Execute a SQL query (optionally parameterized via JS Array)
     ///
-    /// Passing `undefined`/`null` from JS maps to `None`.
+    /// Passing `undefined`/`null` from JS maps to `None`. A parameter of the
+    /// form `{ __type: "text_blob", value }` stores the UTF-8 bytes of `value`
+    /// as a BLOB instead of TEXT. Numbers bind as INTEGER when they are whole
+    /// and REAL otherwise; `{ __type: "int", value: "123" }` and
+    /// `{ __type: "double", value: 3 }` force one or the other, and an
+    /// `Int32Array` or `Float64Array` (or another integer or float typed
+    /// array) passed as the parameter list does the same for every element.
+    /// `options.signal` accepts an `AbortSignal` that cancels the query when
+    /// aborted, and `options.timeoutMs` interrupts the query once it runs for
+    /// that long; in a follower tab it also replaces the default 30s wait
+    /// for the leader's answer, so long queries can be given more time.
+    /// Without parameters, SQL ending in a semicolon runs every statement
+    /// and anything else runs only the first. `options.allowMultiple: false`
+    /// rejects SQL holding more than one statement instead, and `true` runs
+    /// them all. Parameterized queries are a single statement unless
+    /// `allowMultiple` is `true`; then the statements run in one
+    /// transaction and each binds from the start of the same params, taking
+    /// one value per `?` or values up to its highest `?N`, so `?1` is the
+    /// same value in every statement. Every value must be used somewhere.
+    /// SQL with its own `BEGIN`/`COMMIT` (or `SAVEPOINT`) manages its own
+    /// transaction instead, and a failure rolls back whatever is open.
+    /// A statement that returns columns resolves to a JSON array of rows,
+    /// even when empty, and any other to an affected-row summary. This
+    /// follows the prepared statement, not the leading keyword, so `WITH`
+    /// queries and row-returning pragmas such as `PRAGMA table_info(t)`
+    /// give rows.
+    /// Columns declared `BOOLEAN` return their 0/1 values as `false`/`true`;
+    /// `INTEGER` columns and expressions stay numeric.
+    /// Result keys are SQLite's column names (`count(*)` for an unaliased
+    /// aggregate); `options.dealiasColumns` renames non-identifier keys to
+    /// `col_<index>`. With `options.returnRowids` the result is a JSON object
+    /// `{ result, rowids }` listing the rowids written by the statement.
+    /// Rows its triggers and foreign key actions write are listed too, in
+    /// whatever table they are in, since SQLite reports them the same way.
+    /// `options.returnResultCode` adds `resultCode` to that object, the raw
+    /// SQLite code of the last step (`101`, `SQLITE_DONE`, on success), and
+    /// `options.returnNotices` adds `notices`, planner and trigger notices
+    /// such as `"automatic index: ..."` and `"TRIGGER name"`. For debugging,
+    /// `options.returnExpandedSql` adds `expandedSql`, each statement run
+    /// with its bound parameters written in as SQL literals.
+    /// `options.returnStatementCounts` adds `statementCounts`, one
+    /// `{ statementIndex, rowsAffected, isQuery }` entry per statement run,
+    /// so a migration script can check what each step changed.
+    /// `options.maxCellBytes` replaces longer text and blob values with
+    /// `{ __truncated: true, preview, totalBytes }`, where `preview` holds
+    /// the first `maxCellBytes` bytes of text. `options.serializeBudgetMs`
+    /// fails the query with "Result too large to serialize" rather than let
+    /// rendering a huge result block the worker for longer than that.
+    /// `options.maxResultBytes` fails a query whose serialized result is
+    /// longer than that with "Result too large", pointing at `queryStream`.
+    /// `options.profile` returns `{ result, timing: { queuedMs, execMs } }`,
+    /// where `result` is what the query would otherwise return and `timing`
+    /// the time it waited behind others on the leader and the time it ran.
+    /// `options.typedCells` returns every cell as `{ type, value }`,
+    /// with `type` one of `"integer"`, `"float"`, `"text"`, `"blob"` or
+    /// `"null"` as SQLite stored it, so `1` and `"1"` stay distinguishable.
+    /// `options.coerceAffinity` returns numbers as their declared column's
+    /// type instead, so a `REAL` column always yields a float such as `1.0`.
+    /// A single parameter may be passed without wrapping it in an array, as
+    /// in `query("SELECT * FROM t WHERE id = ?", 7)`; `options.strictParams`
+    /// rejects that with "params must be an array" instead.
+    /// SQLite's built-in JSON functions are available, so a query can return
+    /// a single JSON text column, e.g. `SELECT json_group_array(json_object(
+    /// 'id', id, 'name', name)) AS rows FROM users`.
+    /// `NOW_MS()` returns the Unix epoch in milliseconds as an INTEGER. It
+    /// reads `Date.now()` in the worker that leads the database, so values
+    /// written from different tabs all come from that one clock.
+    /// When SQLite itself rejects the query, the error message ends with
+    /// its result code and, for syntax errors and unknown names, the byte
+    /// offset of the offending token, e.g. `(SQLite error 1 at offset 15)`.
+    /// Unlike `lastError()`, this cannot be overwritten by another tab's
+    /// query in between.
+    /// Temporary tables, views, indexes and triggers can only be created
+    /// from the leader tab: a follower's queries run on the leader's one
+    /// connection, so its `CREATE TEMP ...` fails instead of leaking a
+    /// temp object that every tab would see.
     #[wasm_export(js_name = "query", unchecked_return_type = "string")]
     pub async fn query(
         &self,
         sql: &str,
-        params: Option<Array>,
+        #[wasm_export(unchecked_param_type = "unknown[] | unknown | void")] params: JsValue,
+        options: Option<js_sys::Object>,
     ) -> Result<String, SQLiteWasmDatabaseError> {
-        let worker = Rc::clone(&self.worker);
-        let pending_queries = Rc::clone(&self.pending_queries);
-        let sql = sql.to_string();
-        let params_array = Self::normalize_params(params)?;
-
-        if let InitializationState::Failed(reason) = self.ready_signal.current_state() {
-            return Err(SQLiteWasmDatabaseError::InitializationFailed(reason));
-        }
+        let options = QueryOptions::from_js(options.as_ref())?;
+        let params_array = if options.strict_params {
+            normalize_params_js(&params)?
+        } else {
+            normalize_scalar_or_params_js(&params)?
+        };
 
         let message = js_sys::Object::new();
         js_sys::Reflect::set(
@@ -131,116 +548,1237 @@ impl SQLiteWasmDatabase {
             &JsValue::from_str("execute-query"),
         )
         .map_err(SQLiteWasmDatabaseError::JsError)?;
+        js_sys::Reflect::set(&message, &JsValue::from_str("sql"), &JsValue::from_str(sql))
+            .map_err(SQLiteWasmDatabaseError::JsError)?;
+        if params_array.length() > 0 {
+            let params_js = JsValue::from(params_array.clone());
+            js_sys::Reflect::set(&message, &JsValue::from_str("params"), &params_js)
+                .map_err(SQLiteWasmDatabaseError::JsError)?;
+        }
+        if let Some(worker_options) = options.worker_options()? {
+            js_sys::Reflect::set(&message, &JsValue::from_str("options"), &worker_options)
+                .map_err(SQLiteWasmDatabaseError::JsError)?;
+        }
+
+        let result = self.dispatch(&message, &options).await?;
+        Ok(result.as_string().unwrap_or_else(|| format!("{result:?}")))
+    }
+
+    /// Run a read query through `query` and parse the rows it returns.
+    async fn query_rows(
+        &self,
+        sql: &str,
+        params: Array,
+    ) -> Result<Vec<serde_json::Value>, SQLiteWasmDatabaseError> {
+        let result = self.query(sql, params.into(), None).await?;
+        serde_json::from_str(&result).map_err(|e| {
+            SQLiteWasmDatabaseError::JsError(JsValue::from_str(&format!(
+                "Invalid query response from worker: {e}"
+            )))
+        })
+    }
+
+    /// Run `sql` like `query` and resolve to its first row, or `null` when
+    /// it returns none. Rejects when the statement returns no columns, such
+    /// as a plain `UPDATE`; the statement has still run by then.
+    #[wasm_export(
+        js_name = "queryOne",
+        unchecked_return_type = "Record<string, unknown> | null"
+    )]
+    pub async fn query_one(
+        &self,
+        sql: &str,
+        #[wasm_export(unchecked_param_type = "unknown[] | unknown | void")] params: JsValue,
+    ) -> Result<serde_json::Value, SQLiteWasmDatabaseError> {
+        let rows = self.query_result_rows("queryOne", sql, params).await?;
+        Ok(rows.into_iter().next().unwrap_or(serde_json::Value::Null))
+    }
 
-        let request_id = {
-            let mut n = self.next_request_id.borrow_mut();
-            let id = *n;
-            *n = n.wrapping_add(1).max(1);
-            id
+    /// Run `sql` like `query` and resolve to the single column of its first
+    /// row, e.g. the count from `SELECT COUNT(*) FROM t`, or `null` when it
+    /// returns no rows. Rejects when the query returns more than one column,
+    /// since row objects do not keep column order, or no columns at all.
+    #[wasm_export(js_name = "queryValue", unchecked_return_type = "unknown")]
+    pub async fn query_value(
+        &self,
+        sql: &str,
+        #[wasm_export(unchecked_param_type = "unknown[] | unknown | void")] params: JsValue,
+    ) -> Result<serde_json::Value, SQLiteWasmDatabaseError> {
+        let rows = self.query_result_rows("queryValue", sql, params).await?;
+        let Some(serde_json::Value::Object(row)) = rows.into_iter().next() else {
+            return Ok(serde_json::Value::Null);
         };
+        if row.len() != 1 {
+            return Err(SQLiteWasmDatabaseError::JsError(JsValue::from_str(
+                &format!(
+                    "queryValue expects one column but the query returned {}",
+                    row.len()
+                ),
+            )));
+        }
+        Ok(row
+            .into_iter()
+            .next()
+            .map(|(_, value)| value)
+            .unwrap_or_default())
+    }
+
+    /// The rows `query` returns for `sql`, rejecting with a message naming
+    /// `method` when the statement produced a summary instead of rows.
+    async fn query_result_rows(
+        &self,
+        method: &str,
+        sql: &str,
+        params: JsValue,
+    ) -> Result<Vec<serde_json::Value>, SQLiteWasmDatabaseError> {
+        let result = self.query(sql, params, None).await?;
+        serde_json::from_str(&result).map_err(|_| {
+            SQLiteWasmDatabaseError::JsError(JsValue::from_str(&format!(
+                "{method} requires a statement that returns rows"
+            )))
+        })
+    }
+
+    /// Run `sql` with `:name` placeholders bound from the properties of
+    /// `params`, expanding array values into lists so that
+    /// `queryIn("SELECT * FROM t WHERE id IN (:ids)", { ids: [1, 2, 3] })`
+    /// binds each id. An empty array matches nothing. Other values bind
+    /// like `query` parameters, and `options` are the same as for `query`.
+    #[wasm_export(js_name = "queryIn", unchecked_return_type = "string")]
+    pub async fn query_in(
+        &self,
+        sql: &str,
+        params: js_sys::Object,
+        options: Option<js_sys::Object>,
+    ) -> Result<String, SQLiteWasmDatabaseError> {
+        let (sql, params) = expand_named_params(sql, &params)?;
+        self.query(&sql, params.into(), options).await
+    }
+
+    /// Insert one row into `table`, taking column names from the keys of
+    /// `values` and binding the values like `query` parameters. Resolves to
+    /// what `query` would return for the statement.
+    ///
+    /// `options.returnGenerated` appends `RETURNING` for the table's
+    /// `GENERATED ALWAYS AS` columns, found with `PRAGMA table_xinfo`, so
+    /// the result holds their computed values without a follow-up select.
+    #[wasm_export(js_name = "insert", unchecked_return_type = "string")]
+    pub async fn insert(
+        &self,
+        table: &str,
+        values: js_sys::Object,
+        options: Option<js_sys::Object>,
+    ) -> Result<String, SQLiteWasmDatabaseError> {
+        let options = InsertOptions::from_js(options.as_ref())?;
+        let mut columns = Vec::new();
+        let params = Array::new();
+        for entry in js_sys::Object::entries(&values).iter() {
+            let entry = Array::from(&entry);
+            let column = entry.get(0).as_string().unwrap_or_default();
+            columns.push(quote_identifier(&column));
+            params.push(&entry.get(1));
+        }
+        let mut sql = if columns.is_empty() {
+            format!("INSERT INTO {} DEFAULT VALUES", quote_identifier(table))
+        } else {
+            format!(
+                "INSERT INTO {} ({}) VALUES ({})",
+                quote_identifier(table),
+                columns.join(", "),
+                vec!["?"; columns.len()].join(", ")
+            )
+        };
+        if options.return_generated {
+            let generated = self.generated_columns(table).await?;
+            if !generated.is_empty() {
+                sql.push_str(" RETURNING ");
+                sql.push_str(&generated.join(", "));
+            }
+        }
+        self.query(&sql, params.into(), None).await
+    }
+
+    /// Insert `rows` into `table` with one multi-row `INSERT ... VALUES
+    /// (...), (...)` per chunk, all inside a single transaction and one
+    /// round trip to the worker. Each row is an array of values in
+    /// `columns` order, bound like `query` parameters. Chunks stay under
+    /// SQLite's 999 bound variable limit. Resolves to the number of rows
+    /// inserted; on failure nothing is inserted.
+    #[wasm_export(js_name = "insertMany", unchecked_return_type = "number")]
+    pub async fn insert_many(
+        &self,
+        table: &str,
+        columns: Vec<String>,
+        rows: Array,
+    ) -> Result<f64, SQLiteWasmDatabaseError> {
+        let statements = insert_many_statements(table, &columns, &rows)?;
+        if statements.length() == 0 {
+            return Ok(0.0);
+        }
+        let result = self.transaction(statements, None).await?;
+        let summaries: Vec<String> = serde_json::from_str(&result).map_err(|e| {
+            SQLiteWasmDatabaseError::JsError(JsValue::from_str(&format!(
+                "Invalid transaction response from worker: {e}"
+            )))
+        })?;
+        Ok(summaries
+            .iter()
+            .filter_map(|summary| rows_affected(summary))
+            .sum::<u64>() as f64)
+    }
+
+    /// Quoted names of `table`'s generated columns, virtual or stored.
+    async fn generated_columns(&self, table: &str) -> Result<Vec<String>, SQLiteWasmDatabaseError> {
+        let rows = self
+            .query_rows(
+                "SELECT name FROM pragma_table_xinfo(?) WHERE hidden IN (2, 3) ORDER BY cid",
+                Array::of1(&JsValue::from_str(table)),
+            )
+            .await?;
+        Ok(rows
+            .iter()
+            .filter_map(|row| row.get("name")?.as_str().map(quote_identifier))
+            .collect())
+    }
+
+    /// Whether a table called `name` exists, ignoring case like SQLite's own
+    /// name lookup. The name is bound as a parameter, never spliced into
+    /// SQL. `options.types` widens the match to other schema objects, such
+    /// as `["table", "view"]`.
+    #[wasm_export(js_name = "tableExists", unchecked_return_type = "boolean")]
+    pub async fn table_exists(
+        &self,
+        name: &str,
+        options: Option<js_sys::Object>,
+    ) -> Result<bool, SQLiteWasmDatabaseError> {
+        let options = TableExistsOptions::from_js(options.as_ref())?;
+        let params = Array::of1(&JsValue::from_str(name));
+        for kind in &options.types {
+            params.push(&JsValue::from_str(kind));
+        }
+        let placeholders = vec!["?"; options.types.len()].join(", ");
+        let sql = format!(
+            "SELECT 1 AS found FROM sqlite_master \
+             WHERE name = ? COLLATE NOCASE AND type IN ({placeholders}) LIMIT 1"
+        );
+        Ok(!self.query_rows(&sql, params).await?.is_empty())
+    }
+
+    /// Whether `table` has a column called `column`, ignoring case like
+    /// SQLite does. Reads `PRAGMA table_info` through its table-valued form
+    /// so both names are bound rather than spliced into SQL. A missing
+    /// table has no columns.
+    #[wasm_export(js_name = "columnExists", unchecked_return_type = "boolean")]
+    pub async fn column_exists(
+        &self,
+        table: &str,
+        column: &str,
+    ) -> Result<bool, SQLiteWasmDatabaseError> {
+        let params = Array::of2(&JsValue::from_str(table), &JsValue::from_str(column));
+        let rows = self
+            .query_rows(
+                "SELECT 1 AS found FROM pragma_table_info(?) WHERE name = ? COLLATE NOCASE",
+                params,
+            )
+            .await?;
+        Ok(!rows.is_empty())
+    }
+
+    /// Add a column with `ALTER TABLE ... ADD COLUMN column_def` unless the
+    /// table already has one by that name, standing in for the missing
+    /// `ADD COLUMN IF NOT EXISTS`. Resolves to whether the column was added.
+    /// The statement runs with `allowMultiple: false`, so a `column_def`
+    /// holding a second statement is rejected rather than run, and a column
+    /// another tab added in the meantime also resolves to `false`.
+    #[wasm_export(js_name = "addColumnIfMissing", unchecked_return_type = "boolean")]
+    pub async fn add_column_if_missing(
+        &self,
+        table: &str,
+        column_def: &str,
+    ) -> Result<bool, SQLiteWasmDatabaseError> {
+        let column = column_def_name(column_def).ok_or_else(|| {
+            SQLiteWasmDatabaseError::JsError(JsValue::from_str(
+                "Column definition must start with a column name",
+            ))
+        })?;
+        if self.column_exists(table, &column).await? {
+            return Ok(false);
+        }
+        let sql = format!(
+            "ALTER TABLE {} ADD COLUMN {}",
+            quote_identifier(table),
+            column_def.trim()
+        );
+        let options = js_sys::Object::new();
         js_sys::Reflect::set(
-            &message,
-            &JsValue::from_str("requestId"),
-            &JsValue::from_f64(request_id as f64),
+            &options,
+            &JsValue::from_str("allowMultiple"),
+            &JsValue::FALSE,
         )
         .map_err(SQLiteWasmDatabaseError::JsError)?;
+        match self.query(&sql, JsValue::UNDEFINED, Some(options)).await {
+            Ok(_) => Ok(true),
+            Err(SQLiteWasmDatabaseError::Sqlite { sqlite, .. })
+                if sqlite.message.starts_with("duplicate column name") =>
+            {
+                Ok(false)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Run `statements`, each `{ sql, params? }`, in order inside a single
+    /// transaction with one round trip to the worker. The first failure
+    /// rolls back every statement and rejects with "Statement N failed",
+    /// counting from 1 like every other such error; otherwise the result
+    /// is a JSON array with each statement's rows or summary message, as
+    /// `query` would return them.
+    ///
+    /// `options.chunkSize` commits after every that many statements, so a
+    /// large import is durable as it goes and never builds one huge
+    /// rollback journal. A failure then rolls back only the current chunk
+    /// and the error reports how many statements were committed.
+    /// As with `query`, a follower tab cannot create temporary objects here.
+    #[wasm_export(js_name = "transaction", unchecked_return_type = "string")]
+    pub async fn transaction(
+        &self,
+        statements: Array,
+        options: Option<js_sys::Object>,
+    ) -> Result<String, SQLiteWasmDatabaseError> {
+        let options = TransactionOptions::from_js(options.as_ref())?;
+        let operation = Self::operation("transaction")?;
+        js_sys::Reflect::set(
+            &operation,
+            &JsValue::from_str("statements"),
+            &batch_statements(&statements)?,
+        )
+        .map_err(SQLiteWasmDatabaseError::JsError)?;
+        if let Some(chunk_size) = options.chunk_size {
+            js_sys::Reflect::set(
+                &operation,
+                &JsValue::from_str("chunkSize"),
+                &JsValue::from_f64(chunk_size as f64),
+            )
+            .map_err(SQLiteWasmDatabaseError::JsError)?;
+        }
+        let result = self.run_operation(operation).await?;
+        Ok(result.as_string().unwrap_or_else(|| format!("{result:?}")))
+    }
+
+    /// Run a database operation on the worker queue and return its raw result.
+    pub(crate) async fn run_operation(
+        &self,
+        operation: js_sys::Object,
+    ) -> Result<JsValue, SQLiteWasmDatabaseError> {
+        self.run_operation_transferring(operation, &Array::new())
+            .await
+    }
+
+    /// `run_operation`, moving the buffers in `transfer` to the worker.
+    pub(crate) async fn run_operation_transferring(
+        &self,
+        operation: js_sys::Object,
+        transfer: &Array,
+    ) -> Result<JsValue, SQLiteWasmDatabaseError> {
+        let message = js_sys::Object::new();
         js_sys::Reflect::set(
             &message,
+            &JsValue::from_str("type"),
+            &JsValue::from_str("run-operation"),
+        )
+        .map_err(SQLiteWasmDatabaseError::JsError)?;
+        js_sys::Reflect::set(&message, &JsValue::from_str("operation"), &operation)
+            .map_err(SQLiteWasmDatabaseError::JsError)?;
+        self.dispatch_transferring(&message, &QueryOptions::default(), transfer)
+            .await
+    }
+
+    pub(crate) fn operation(kind: &str) -> Result<js_sys::Object, SQLiteWasmDatabaseError> {
+        let operation = js_sys::Object::new();
+        js_sys::Reflect::set(
+            &operation,
+            &JsValue::from_str("kind"),
+            &JsValue::from_str(kind),
+        )
+        .map_err(SQLiteWasmDatabaseError::JsError)?;
+        Ok(operation)
+    }
+
+    /// Resolve once every previously issued query has executed and committed
+    /// data is durable in OPFS (checkpointing the WAL when it is enabled).
+    #[wasm_export(js_name = "flush", unchecked_return_type = "void")]
+    pub async fn flush(&self) -> Result<(), SQLiteWasmDatabaseError> {
+        self.run_operation(Self::operation("flush")?).await?;
+        Ok(())
+    }
+
+    /// Export a snapshot of the whole database as the bytes of a SQLite file,
+    /// e.g. to back it up or move it off OPFS. The image is transferred from
+    /// the worker rather than copied. It holds every write issued before the
+    /// call and none issued after, and the export fails while a transaction
+    /// is open rather than include uncommitted changes.
+    #[wasm_export(js_name = "export", unchecked_return_type = "Uint8Array")]
+    pub async fn export(&self) -> Result<DatabaseImage, SQLiteWasmDatabaseError> {
+        let result = self.run_operation(Self::operation("export")?).await?;
+        let bytes = result.dyn_into::<js_sys::Uint8Array>().map_err(|_| {
+            SQLiteWasmDatabaseError::JsError(JsValue::from_str(
+                "Worker did not return a database image",
+            ))
+        })?;
+        Ok(DatabaseImage(bytes.to_vec()))
+    }
+
+    /// Rebuild the database with `VACUUM` so space freed by deletes is
+    /// returned to OPFS. Runs on the leader and fails if a transaction is
+    /// open.
+    #[wasm_export(
+        js_name = "vacuum",
+        unchecked_return_type = "{ pageCountBefore: number; pageCountAfter: number; bytesFreed: number }"
+    )]
+    pub async fn vacuum(&self) -> Result<VacuumReport, SQLiteWasmDatabaseError> {
+        let result = self.run_operation(Self::operation("vacuum")?).await?;
+        VacuumReport::from_worker_result(&result.as_string().unwrap_or_default())
+    }
+
+    /// Size of the database file in bytes, from `page_count * page_size`
+    /// on the leader. Space freed by deletes is included until `vacuum()`.
+    #[wasm_export(js_name = "databaseSize", unchecked_return_type = "number")]
+    pub async fn database_size(&self) -> Result<f64, SQLiteWasmDatabaseError> {
+        let result = self
+            .run_operation(Self::operation("database-size")?)
+            .await?;
+        result
+            .as_string()
+            .and_then(|text| text.parse().ok())
+            .ok_or_else(|| {
+                SQLiteWasmDatabaseError::JsError(JsValue::from_str(
+                    "Invalid database size response from worker",
+                ))
+            })
+    }
+
+    /// Read `PRAGMA page_count`, the number of pages in the database file.
+    #[wasm_export(js_name = "pageCount", unchecked_return_type = "number")]
+    pub async fn page_count(&self) -> Result<f64, SQLiteWasmDatabaseError> {
+        let result = self.run_operation(Self::operation("page-count")?).await?;
+        parse_number_result(&result, "page_count")
+    }
+
+    /// Read `PRAGMA page_size`, the size of a database page in bytes.
+    #[wasm_export(js_name = "pageSize", unchecked_return_type = "number")]
+    pub async fn page_size(&self) -> Result<f64, SQLiteWasmDatabaseError> {
+        let result = self.run_operation(Self::operation("page-size")?).await?;
+        parse_number_result(&result, "page_size")
+    }
+
+    /// Set `PRAGMA page_size` and return the stored value. `size` must be a
+    /// power of two from 512 to 65536, and the call fails once the database
+    /// holds any table, since existing pages keep their size.
+    #[wasm_export(js_name = "setPageSize", unchecked_return_type = "number")]
+    pub async fn set_page_size(&self, size: u32) -> Result<f64, SQLiteWasmDatabaseError> {
+        let operation = Self::operation("page-size")?;
+        js_sys::Reflect::set(
+            &operation,
+            &JsValue::from_str("value"),
+            &JsValue::from_f64(size as f64),
+        )
+        .map_err(SQLiteWasmDatabaseError::JsError)?;
+        let result = self.run_operation(operation).await?;
+        parse_number_result(&result, "page_size")
+    }
+
+    /// Send a message through this tab's worker to the leader's DB worker
+    /// and back, and resolve to the round trip in milliseconds. It is
+    /// answered on arrival without touching SQLite, so a slow or failing
+    /// ping points at the messaging between workers rather than the
+    /// database, which `query("SELECT 1")` cannot tell apart.
+    #[wasm_export(js_name = "ping", unchecked_return_type = "number")]
+    pub async fn ping(&self) -> Result<f64, SQLiteWasmDatabaseError> {
+        let started_ms = js_sys::Date::now();
+        self.run_operation(Self::operation("ping")?).await?;
+        Ok((js_sys::Date::now() - started_ms).max(0.0))
+    }
+
+    /// Whether the leader opened the database in memory because OPFS was
+    /// unavailable, which only happens with `allowMemoryFallback`. Data
+    /// written then is lost once the leading worker stops.
+    #[wasm_export(js_name = "isMemoryOnly", unchecked_return_type = "boolean")]
+    pub async fn is_memory_only(&self) -> Result<bool, SQLiteWasmDatabaseError> {
+        let result = self.run_operation(Self::operation("memory-only")?).await?;
+        match result.as_string().as_deref() {
+            Some("true") => Ok(true),
+            Some("false") => Ok(false),
+            _ => Err(SQLiteWasmDatabaseError::JsError(JsValue::from_str(
+                "Invalid memory-only response from worker",
+            ))),
+        }
+    }
+
+    /// Number of requests waiting in the leader's queue, for diagnostics.
+    /// Once it reaches the leader's limit new requests fail as busy.
+    #[wasm_export(js_name = "queueDepth", unchecked_return_type = "number")]
+    pub async fn queue_depth(&self) -> Result<u32, SQLiteWasmDatabaseError> {
+        let result = self.run_operation(Self::operation("queue-depth")?).await?;
+        result
+            .as_string()
+            .and_then(|text| text.parse().ok())
+            .ok_or_else(|| {
+                SQLiteWasmDatabaseError::JsError(JsValue::from_str(
+                    "Invalid queue depth response from worker",
+                ))
+            })
+    }
+
+    async fn header_value(
+        &self,
+        pragma: &str,
+        value: Option<i32>,
+    ) -> Result<i32, SQLiteWasmDatabaseError> {
+        let operation = Self::operation("header-value")?;
+        js_sys::Reflect::set(
+            &operation,
+            &JsValue::from_str("pragma"),
+            &JsValue::from_str(pragma),
+        )
+        .map_err(SQLiteWasmDatabaseError::JsError)?;
+        if let Some(value) = value {
+            js_sys::Reflect::set(
+                &operation,
+                &JsValue::from_str("value"),
+                &JsValue::from_f64(value as f64),
+            )
+            .map_err(SQLiteWasmDatabaseError::JsError)?;
+        }
+        let result = self.run_operation(operation).await?;
+        result
+            .as_string()
+            .and_then(|text| text.parse().ok())
+            .ok_or_else(|| {
+                SQLiteWasmDatabaseError::JsError(JsValue::from_str(&format!(
+                    "Invalid {pragma} response from worker"
+                )))
+            })
+    }
+
+    /// Read `PRAGMA user_version`, the schema version migration tools use.
+    #[wasm_export(js_name = "userVersion", unchecked_return_type = "number")]
+    pub async fn user_version(&self) -> Result<i32, SQLiteWasmDatabaseError> {
+        self.header_value("user_version", None).await
+    }
+
+    /// Set `PRAGMA user_version` on the leader and return the stored value.
+    /// Inside an open transaction the change commits or rolls back with it.
+    #[wasm_export(js_name = "setUserVersion", unchecked_return_type = "number")]
+    pub async fn set_user_version(&self, version: i32) -> Result<i32, SQLiteWasmDatabaseError> {
+        self.header_value("user_version", Some(version)).await
+    }
+
+    /// Read `PRAGMA application_id`, which identifies the file's format.
+    #[wasm_export(js_name = "applicationId", unchecked_return_type = "number")]
+    pub async fn application_id(&self) -> Result<i32, SQLiteWasmDatabaseError> {
+        self.header_value("application_id", None).await
+    }
+
+    /// Set `PRAGMA application_id` on the leader and return the stored value.
+    /// Inside an open transaction the change commits or rolls back with it.
+    #[wasm_export(js_name = "setApplicationId", unchecked_return_type = "number")]
+    pub async fn set_application_id(&self, id: i32) -> Result<i32, SQLiteWasmDatabaseError> {
+        self.header_value("application_id", Some(id)).await
+    }
+
+    /// Report how SQLite would run `sql` using `EXPLAIN QUERY PLAN`, one
+    /// entry per plan row. The statement is only planned, never run, so
+    /// explaining a write changes nothing. Parameters bind like `query()`.
+    #[wasm_export(
+        js_name = "explain",
+        unchecked_return_type = "{ id: number; parent: number; detail: string }[]"
+    )]
+    pub async fn explain(
+        &self,
+        sql: &str,
+        #[wasm_export(
+            unchecked_param_type = "unknown[] | Int8Array | Int16Array | Int32Array | Uint16Array | Uint32Array | Float32Array | Float64Array | null | void"
+        )]
+        params: JsValue,
+    ) -> Result<Vec<QueryPlanStep>, SQLiteWasmDatabaseError> {
+        let params = normalize_params_js(&params)?;
+        let operation = Self::operation("explain")?;
+        js_sys::Reflect::set(
+            &operation,
             &JsValue::from_str("sql"),
-            &JsValue::from_str(&sql),
+            &JsValue::from_str(sql),
         )
         .map_err(SQLiteWasmDatabaseError::JsError)?;
-        if params_array.length() > 0 {
-            let params_js = JsValue::from(params_array.clone());
-            js_sys::Reflect::set(&message, &JsValue::from_str("params"), &params_js)
+        if params.length() > 0 {
+            js_sys::Reflect::set(&operation, &JsValue::from_str("params"), &params)
                 .map_err(SQLiteWasmDatabaseError::JsError)?;
         }
+        let result = self.run_operation(operation).await?;
+        QueryPlanStep::from_worker_result(&result.as_string().unwrap_or_default())
+    }
 
-        let rid_for_insert = request_id;
-        let promise = js_sys::Promise::new(&mut |resolve, reject| match worker
-            .borrow()
-            .post_message(&message)
-        {
-            Ok(()) => {
-                pending_queries
-                    .borrow_mut()
-                    .insert(rid_for_insert, (resolve, reject));
-            }
-            Err(err) => {
-                let _ = reject.call1(&JsValue::NULL, &err);
-            }
-        });
+    /// Check that every statement in `sql` compiles without running any of
+    /// them, e.g. to lint SQL in an editor. Reports the zero-based
+    /// `statementIndex` and SQLite's message for the first one that fails.
+    /// Statements are checked against the current schema, so one using a
+    /// table an earlier statement would create is reported as invalid.
+    #[wasm_export(
+        js_name = "validate",
+        unchecked_return_type = "{ valid: boolean; error?: string; statementIndex?: number }"
+    )]
+    pub async fn validate(&self, sql: &str) -> Result<SqlValidation, SQLiteWasmDatabaseError> {
+        let operation = Self::operation("validate")?;
+        js_sys::Reflect::set(
+            &operation,
+            &JsValue::from_str("sql"),
+            &JsValue::from_str(sql),
+        )
+        .map_err(SQLiteWasmDatabaseError::JsError)?;
+        let result = self.run_operation(operation).await?;
+        SqlValidation::from_worker_result(&result.as_string().unwrap_or_default())
+    }
 
-        let result = match JsFuture::from(promise).await {
-            Ok(value) => value,
-            Err(err) if is_initialization_pending_error(&err) => {
-                return Err(SQLiteWasmDatabaseError::InitializationPending);
-            }
-            Err(err) => {
-                return Err(SQLiteWasmDatabaseError::JsError(err));
+    /// What SQLite reported for the last query that failed: its message,
+    /// result code and, for syntax errors and unknown names, the byte
+    /// `offset` of the offending token in that query's SQL, e.g. to
+    /// underline it in an editor. `null` when the most recent query
+    /// succeeded. The leader's connection runs every tab's queries, so
+    /// another tab's query can replace it; a failed query's own error
+    /// already names the same code and offset, and this is only a
+    /// convenience for reading them as fields.
+    #[wasm_export(
+        js_name = "lastError",
+        unchecked_return_type = "{ message: string; code: number; offset?: number } | null"
+    )]
+    pub async fn last_error(&self) -> Result<Option<LastError>, SQLiteWasmDatabaseError> {
+        let result = self.run_operation(Self::operation("last-error")?).await?;
+        LastError::from_worker_result(&result.as_string().unwrap_or_default())
+    }
+
+    /// Run `PRAGMA integrity_check` on the leader, e.g. after recovering from
+    /// a crash. Queued queries run before and after it as usual.
+    #[wasm_export(
+        js_name = "integrityCheck",
+        unchecked_return_type = "{ ok: boolean; problems: string[] }"
+    )]
+    pub async fn integrity_check(&self) -> Result<IntegrityReport, SQLiteWasmDatabaseError> {
+        let result = self
+            .run_operation(Self::operation("integrity-check")?)
+            .await?;
+        IntegrityReport::from_worker_result(&result.as_string().unwrap_or_default())
+    }
+
+    /// Write a compacted copy of the database to OPFS under `name` using
+    /// `VACUUM INTO`, without holding its bytes in memory. The copy can be
+    /// opened with `SQLiteWasmDatabase.new(name)`. Fails if `name` already
+    /// holds data or a transaction is open.
+    #[wasm_export(js_name = "backupInto", unchecked_return_type = "void")]
+    pub async fn backup_into(&self, name: &str) -> Result<(), SQLiteWasmDatabaseError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(SQLiteWasmDatabaseError::JsError(JsValue::from_str(
+                "Backup name is required",
+            )));
+        }
+        let operation = Self::operation("backup-into")?;
+        js_sys::Reflect::set(
+            &operation,
+            &JsValue::from_str("name"),
+            &JsValue::from_str(name),
+        )
+        .map_err(SQLiteWasmDatabaseError::JsError)?;
+        self.run_operation(operation).await?;
+        Ok(())
+    }
+
+    /// Report whether this connection's worker currently leads the database,
+    /// along with the id of the known leader. Does not affect election.
+    #[wasm_export(
+        js_name = "role",
+        unchecked_return_type = "{ role: \"leader\" | \"follower\"; leaderId?: string }"
+    )]
+    pub async fn role(&self) -> Result<LeadershipInfo, SQLiteWasmDatabaseError> {
+        if self.options.isolated {
+            return Ok(LeadershipInfo {
+                role: "leader".to_string(),
+                leader_id: None,
+            });
+        }
+        let message = js_sys::Object::new();
+        js_sys::Reflect::set(
+            &message,
+            &JsValue::from_str("type"),
+            &JsValue::from_str("get-role"),
+        )
+        .map_err(SQLiteWasmDatabaseError::JsError)?;
+        let result = self.dispatch(&message, &QueryOptions::default()).await?;
+        LeadershipInfo::from_worker_result(&result.as_string().unwrap_or_default())
+    }
+
+    /// Call `callback` whenever this connection observes a new leader, e.g.
+    /// after the leading tab closes and another is elected. It receives
+    /// `{ leaderId, isLeader }`. Returns an id to pass to `removeListener`.
+    #[wasm_export(js_name = "onLeadershipChange", unchecked_return_type = "number")]
+    pub fn on_leadership_change(
+        &self,
+        callback: js_sys::Function,
+    ) -> Result<u32, SQLiteWasmDatabaseError> {
+        Ok(self.listeners.subscribe(EVENT_LEADERSHIP_CHANGE, callback))
+    }
+
+    /// Call `callback` after a query writes rows, in this tab or any other
+    /// connected to the same database. It receives `{ changes }`, one
+    /// `{ op, table, rowid }` entry per row in the order SQLite wrote them.
+    /// Returns an id to pass to `removeListener`.
+    #[wasm_export(js_name = "onChange", unchecked_return_type = "number")]
+    pub fn on_change(&self, callback: js_sys::Function) -> Result<u32, SQLiteWasmDatabaseError> {
+        Ok(self.listeners.subscribe(EVENT_DB_CHANGE, callback))
+    }
+
+    /// Call `callback` whenever a write transaction commits, in this tab or
+    /// any other connected to the same database. Unlike `onChange`, this only
+    /// fires once the writes are durable. Returns an id to pass to
+    /// `removeListener`.
+    #[wasm_export(js_name = "onCommit", unchecked_return_type = "number")]
+    pub fn on_commit(&self, callback: js_sys::Function) -> Result<u32, SQLiteWasmDatabaseError> {
+        Ok(self
+            .listeners
+            .subscribe(EVENT_TRANSACTION_COMMITTED, callback))
+    }
+
+    /// Call `callback` whenever a transaction rolls back, explicitly or after
+    /// a failing statement, in this tab or any other connected to the same
+    /// database. Returns an id to pass to `removeListener`.
+    #[wasm_export(js_name = "onRollback", unchecked_return_type = "number")]
+    pub fn on_rollback(&self, callback: js_sys::Function) -> Result<u32, SQLiteWasmDatabaseError> {
+        Ok(self
+            .listeners
+            .subscribe(EVENT_TRANSACTION_ROLLED_BACK, callback))
+    }
+
+    /// Call `callback` with each line the workers log, as
+    /// `{ level, message, timestamp }` where `level` is `"debug"`, `"info"`,
+    /// `"warn"` or `"error"` and `timestamp` is milliseconds since the
+    /// epoch. Lines are only sent when the connection was opened with
+    /// `debug: true`; the first callback registered also receives those
+    /// logged while `new()` was opening the database. Returns an id to pass
+    /// to `removeListener`.
+    #[wasm_export(js_name = "onLog", unchecked_return_type = "number")]
+    pub fn on_log(&self, callback: js_sys::Function) -> Result<u32, SQLiteWasmDatabaseError> {
+        Ok(self.listeners.subscribe(EVENT_WORKER_LOG, callback))
+    }
+
+    /// Remove a callback registered by one of the `on*` methods. Returns
+    /// `false` when the id is unknown.
+    #[wasm_export(js_name = "removeListener", unchecked_return_type = "boolean")]
+    pub fn remove_listener(&self, id: u32) -> Result<bool, SQLiteWasmDatabaseError> {
+        Ok(self.listeners.unsubscribe(id))
+    }
+
+    #[wasm_export(js_name = "wipeAndRecreate", unchecked_return_type = "void")]
+    pub async fn wipe_and_recreate(&self) -> Result<(), SQLiteWasmDatabaseError> {
+        self.worker.borrow().terminate();
+        self.closed.set(false);
+
+        for (_, (_, reject)) in self.pending_queries.borrow_mut().drain() {
+            let err = JsValue::from_str("Database wipe in progress");
+            let _ = reject.call1(&JsValue::NULL, &err);
+        }
+
+        self.ready_signal.reset();
+
+        let deletion_result =
+            delete_opfs_sahpool_directory(self.options.namespace.as_deref()).await;
+
+        self.replace_worker()?;
+
+        self.wait_until_ready().await?;
+
+        deletion_result
+    }
+
+    /// Close the connection and stop its worker. If this tab leads the
+    /// database it hands it off first, so other tabs elect a new leader
+    /// straight away instead of waiting for it to time out. Requests still
+    /// pending reject with "Query aborted"; later ones fail until
+    /// `reconnect()` is called. Closing twice does nothing. A connection
+    /// opened with `shared: false` also deletes its database.
+    #[wasm_export(js_name = "close", unchecked_return_type = "void")]
+    pub async fn close(&self) -> Result<(), SQLiteWasmDatabaseError> {
+        if self.closed.get() {
+            return Ok(());
+        }
+        // An unshared connection has no other tabs to hand the database to.
+        if !self.options.isolated {
+            self.hand_off_leadership().await?;
+        }
+
+        self.closed.set(true);
+        self.pagehide.replace(None);
+        self.worker.borrow().terminate();
+        for (_, (_, reject)) in self.pending_queries.borrow_mut().drain() {
+            let _ = reject.call1(&JsValue::NULL, &make_aborted_error());
+        }
+        if self.options.isolated {
+            // Nothing can open this connection's private pool again.
+            delete_opfs_sahpool_directory(self.options.namespace.as_deref()).await?;
+            if let Some(pool_lock) = self.pool_lock.take() {
+                pool_lock.release();
             }
+        }
+        Ok(())
+    }
+
+    /// Ask the worker to step down and wait until it has, or until
+    /// `CLOSE_HANDOFF_TIMEOUT_MS` passes.
+    async fn hand_off_leadership(&self) -> Result<(), SQLiteWasmDatabaseError> {
+        let message = js_sys::Object::new();
+        js_sys::Reflect::set(
+            &message,
+            &JsValue::from_str("type"),
+            &JsValue::from_str("step-down"),
+        )
+        .map_err(SQLiteWasmDatabaseError::JsError)?;
+        let _ = self.worker.borrow().post_message(&message);
+        // The worker handles messages in order, so once it answers this the
+        // stepdown has been broadcast and the worker can be stopped.
+        let role = js_sys::Object::new();
+        js_sys::Reflect::set(
+            &role,
+            &JsValue::from_str("type"),
+            &JsValue::from_str("get-role"),
+        )
+        .map_err(SQLiteWasmDatabaseError::JsError)?;
+        let options = QueryOptions {
+            timeout_ms: Some(CLOSE_HANDOFF_TIMEOUT_MS),
+            ..Default::default()
         };
-        Ok(result.as_string().unwrap_or_else(|| format!("{result:?}")))
+        let _ = self.dispatch(&role, &options).await;
+        Ok(())
     }
 
-    #[wasm_export(js_name = "wipeAndRecreate", unchecked_return_type = "void")]
-    pub async fn wipe_and_recreate(&self) -> Result<(), SQLiteWasmDatabaseError> {
+    /// Replace the worker with a fresh one, keeping this handle and its
+    /// listeners. Use it when the worker has died and queries keep failing,
+    /// or to reopen a closed connection.
+    /// Requests still awaiting the old worker reject with a "worker
+    /// restarted" error and can be sent again once this resolves.
+    #[wasm_export(js_name = "reconnect", unchecked_return_type = "void")]
+    pub async fn reconnect(&self) -> Result<(), SQLiteWasmDatabaseError> {
         self.worker.borrow().terminate();
+        self.closed.set(false);
+        if self.pagehide.borrow().is_none() {
+            self.pagehide
+                .replace(PagehideListener::install(Rc::clone(&self.worker)));
+        }
 
         for (_, (_, reject)) in self.pending_queries.borrow_mut().drain() {
-            let err = JsValue::from_str("Database wipe in progress");
-            let _ = reject.call1(&JsValue::NULL, &err);
+            let _ = reject.call1(&JsValue::NULL, &make_worker_restarted_error());
         }
 
         self.ready_signal.reset();
+        if self.pool_lock.borrow().is_none() {
+            let pool_lock = Self::lock_private_pool(&self.options).await;
+            self.pool_lock.replace(pool_lock);
+        }
+        self.replace_worker()?;
+        self.wait_until_ready().await
+    }
+
+    /// Reject every request still awaiting a reply with a "Query aborted"
+    /// error and ask the worker to stop them: queued ones are dropped, and on
+    /// cross-origin isolated pages, where `SharedArrayBuffer` is available,
+    /// the running statement is interrupted. Elsewhere the running statement
+    /// finishes first. Unlike `reconnect()` the worker keeps running, so the
+    /// connection is usable as soon as that statement stops.
+    /// Returns the number of requests cancelled.
+    #[wasm_export(js_name = "cancelAll", unchecked_return_type = "number")]
+    pub fn cancel_all(&self) -> Result<u32, SQLiteWasmDatabaseError> {
+        let pending = self
+            .pending_queries
+            .borrow_mut()
+            .drain()
+            .collect::<Vec<_>>();
+        for (_, (_, reject)) in &pending {
+            let _ = reject.call1(&JsValue::NULL, &make_aborted_error());
+        }
+        // An unshared connection's worker runs the database itself, and it
+        // only cancels requests one at a time.
+        if self.options.isolated {
+            for (request_id, _) in &pending {
+                self.cancel_signal.cancel(*request_id);
+                post_cancel_message(&self.worker.borrow(), *request_id)
+                    .map_err(SQLiteWasmDatabaseError::JsError)?;
+            }
+            return Ok(pending.len() as u32);
+        }
 
-        let deletion_result = delete_opfs_sahpool_directory().await;
+        let message = js_sys::Object::new();
+        js_sys::Reflect::set(
+            &message,
+            &JsValue::from_str("type"),
+            &JsValue::from_str("cancel-all"),
+        )
+        .map_err(SQLiteWasmDatabaseError::JsError)?;
+        self.worker
+            .borrow()
+            .post_message(&message)
+            .map_err(SQLiteWasmDatabaseError::JsError)?;
+        Ok(pending.len() as u32)
+    }
 
-        let worker_code = generate_self_contained_worker(&self.db_name);
+    /// Start a new worker for this connection in place of the current one,
+    /// which the caller has already terminated.
+    fn replace_worker(&self) -> Result<(), SQLiteWasmDatabaseError> {
+        let worker_code = generate_self_contained_worker(&self.db_name, &self.options);
         let new_worker =
             create_worker_from_code(&worker_code).map_err(SQLiteWasmDatabaseError::JsError)?;
 
+        self.cancel_signal.clear();
         install_onmessage_handler(
             &new_worker,
             Rc::clone(&self.pending_queries),
             self.ready_signal.clone(),
+            self.listeners.clone(),
+            self.cancel_signal.clone(),
         );
 
         *self.worker.borrow_mut() = new_worker;
+        Ok(())
+    }
+}
 
-        self.wait_until_ready().await?;
+/// Parse a worker result holding a single number, such as a pragma value.
+fn parse_number_result(result: &JsValue, what: &str) -> Result<f64, SQLiteWasmDatabaseError> {
+    result
+        .as_string()
+        .and_then(|text| text.parse().ok())
+        .ok_or_else(|| {
+            SQLiteWasmDatabaseError::JsError(JsValue::from_str(&format!(
+                "Invalid {what} response from worker"
+            )))
+        })
+}
 
-        deletion_result
+/// The column name a `column_def` starts with, unquoting `"name"`,
+/// `` `name` `` and `[name]`.
+fn column_def_name(column_def: &str) -> Option<String> {
+    let def = column_def.trim_start();
+    let mut chars = def.chars();
+    let close = match chars.next()? {
+        '"' => '"',
+        '`' => '`',
+        '[' => ']',
+        _ => {
+            let name: String = def
+                .chars()
+                .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '$')
+                .collect();
+            return (!name.is_empty()).then_some(name);
+        }
+    };
+    // A doubled closing quote stands for one inside the name.
+    let mut name = String::new();
+    let mut rest = chars.peekable();
+    while let Some(c) = rest.next() {
+        if c == close {
+            if close != ']' && rest.peek() == Some(&close) {
+                rest.next();
+            } else {
+                return (!name.is_empty()).then_some(name);
+            }
+        }
+        name.push(c);
     }
+    None
 }
 
-fn is_initialization_pending_error(err: &JsValue) -> bool {
-    let error_type = Reflect::get(err, &JsValue::from_str("type"))
+/// Quote `name` as a SQL identifier.
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Most `?` variables one `insertMany` statement binds. SQLite builds
+/// before 3.32 cap statements at 999, so this holds whatever the build.
+const INSERT_MANY_MAX_VARIABLES: usize = 999;
+
+/// The `{ sql, params }` statements `insertMany` runs: one multi-row
+/// `INSERT` per chunk of rows, each binding at most
+/// `INSERT_MANY_MAX_VARIABLES` values.
+fn insert_many_statements(
+    table: &str,
+    columns: &[String],
+    rows: &Array,
+) -> Result<Array, SQLiteWasmDatabaseError> {
+    if columns.is_empty() {
+        return Err(SQLiteWasmDatabaseError::JsError(JsValue::from_str(
+            "insertMany requires at least one column",
+        )));
+    }
+    let quoted: Vec<String> = columns.iter().map(|c| quote_identifier(c)).collect();
+    let row_placeholders = format!("({})", vec!["?"; columns.len()].join(", "));
+    let rows_per_chunk = (INSERT_MANY_MAX_VARIABLES / columns.len()).max(1);
+
+    let statements = Array::new();
+    let mut params = Array::new();
+    let mut chunk_rows = 0;
+    let push_chunk = |params: &Array, chunk_rows: usize| -> Result<(), SQLiteWasmDatabaseError> {
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES {}",
+            quote_identifier(table),
+            quoted.join(", "),
+            vec![row_placeholders.as_str(); chunk_rows].join(", ")
+        );
+        let statement = js_sys::Object::new();
+        Reflect::set(
+            &statement,
+            &JsValue::from_str("sql"),
+            &JsValue::from_str(&sql),
+        )
+        .map_err(SQLiteWasmDatabaseError::JsError)?;
+        Reflect::set(&statement, &JsValue::from_str("params"), params)
+            .map_err(SQLiteWasmDatabaseError::JsError)?;
+        statements.push(&statement);
+        Ok(())
+    };
+    for (index, row) in rows.iter().enumerate() {
+        let values = Array::is_array(&row)
+            .then(|| Array::from(&row))
+            .filter(|values| values.length() as usize == columns.len())
+            .ok_or_else(|| {
+                SQLiteWasmDatabaseError::JsError(JsValue::from_str(&format!(
+                    "Row {index} must be an array of {} values",
+                    columns.len()
+                )))
+            })?;
+        for value in values.iter() {
+            params.push(&value);
+        }
+        chunk_rows += 1;
+        if chunk_rows == rows_per_chunk {
+            push_chunk(&params, chunk_rows)?;
+            params = Array::new();
+            chunk_rows = 0;
+        }
+    }
+    if chunk_rows > 0 {
+        push_chunk(&params, chunk_rows)?;
+    }
+    Ok(statements)
+}
+
+/// The count in a `"... Rows affected: N"` statement summary.
+fn rows_affected(summary: &str) -> Option<u64> {
+    summary
+        .rsplit_once("Rows affected: ")
+        .and_then(|(_, count)| count.trim().parse().ok())
+}
+
+/// Validate `{ sql, params? }` batch entries and normalize their params
+/// the way `query` does.
+fn batch_statements(statements: &Array) -> Result<Array, SQLiteWasmDatabaseError> {
+    let normalized = Array::new();
+    for (index, entry) in statements.iter().enumerate() {
+        let sql = Reflect::get(&entry, &JsValue::from_str("sql"))
+            .ok()
+            .and_then(|sql| sql.as_string())
+            .ok_or_else(|| {
+                SQLiteWasmDatabaseError::JsError(JsValue::from_str(&format!(
+                    "Statement {index} must be an object with a string sql"
+                )))
+            })?;
+        let statement = js_sys::Object::new();
+        Reflect::set(
+            &statement,
+            &JsValue::from_str("sql"),
+            &JsValue::from_str(&sql),
+        )
+        .map_err(SQLiteWasmDatabaseError::JsError)?;
+        let params = Reflect::get(&entry, &JsValue::from_str("params"))
+            .map_err(SQLiteWasmDatabaseError::JsError)?;
+        let params = normalize_params_js(&params)?;
+        if params.length() > 0 {
+            Reflect::set(&statement, &JsValue::from_str("params"), &params)
+                .map_err(SQLiteWasmDatabaseError::JsError)?;
+        }
+        normalized.push(&statement);
+    }
+    Ok(normalized)
+}
+
+fn worker_error_type(err: &JsValue) -> Option<String> {
+    Reflect::get(err, &JsValue::from_str("type"))
         .ok()
-        .and_then(|value| value.as_string());
-    if error_type.as_deref() == Some(WORKER_ERROR_TYPE_INITIALIZATION_PENDING) {
+        .and_then(|value| value.as_string())
+}
+
+fn is_initialization_pending_error(err: &JsValue) -> bool {
+    if worker_error_type(err).as_deref() == Some(WORKER_ERROR_TYPE_INITIALIZATION_PENDING) {
         return true;
     }
     err.as_string().as_deref() == Some(WORKER_ERROR_TYPE_INITIALIZATION_PENDING)
 }
 
+/// The error for a worker that failed to start. An election timeout is kept
+/// distinct because opening the database again can succeed.
+fn initialization_error(reason: String) -> SQLiteWasmDatabaseError {
+    if reason == WORKER_ERROR_TYPE_LEADER_ELECTION_TIMEOUT {
+        return SQLiteWasmDatabaseError::LeaderElectionTimeout;
+    }
+    SQLiteWasmDatabaseError::InitializationFailed(reason)
+}
+
+fn map_worker_error(err: JsValue) -> SQLiteWasmDatabaseError {
+    if is_initialization_pending_error(&err) {
+        return SQLiteWasmDatabaseError::InitializationPending;
+    }
+    match worker_error_type(&err).as_deref() {
+        Some(WORKER_ERROR_TYPE_QUERY_ABORTED) => SQLiteWasmDatabaseError::QueryAborted,
+        Some(WORKER_ERROR_TYPE_QUERY_TIMEOUT) => SQLiteWasmDatabaseError::QueryTimeout,
+        Some(WORKER_ERROR_TYPE_LEADER_STEPDOWN) => SQLiteWasmDatabaseError::LeaderStepdown,
+        Some(WORKER_ERROR_TYPE_WORKER_RESTARTED) => SQLiteWasmDatabaseError::WorkerRestarted,
+        Some(WORKER_ERROR_TYPE_SERVER_BUSY) => SQLiteWasmDatabaseError::ServerBusy,
+        _ => sqlite_error(&err).unwrap_or(SQLiteWasmDatabaseError::JsError(err)),
+    }
+}
+
+/// The error for a worker payload carrying SQLite's own report in
+/// `sqlite`, or `None` when the failure did not come from SQLite.
+fn sqlite_error(err: &JsValue) -> Option<SQLiteWasmDatabaseError> {
+    let sqlite = Reflect::get(err, &JsValue::from_str("sqlite")).ok()?;
+    if sqlite.is_undefined() || sqlite.is_null() {
+        return None;
+    }
+    let sqlite: LastError = serde_wasm_bindgen::from_value(sqlite).ok()?;
+    let message = Reflect::get(err, &JsValue::from_str("message"))
+        .ok()
+        .and_then(|message| message.as_string())
+        .unwrap_or_else(|| sqlite.message.clone());
+    Some(SQLiteWasmDatabaseError::Sqlite { message, sqlite })
+}
+
 #[cfg(all(test, target_family = "wasm"))]
 mod tests {
     use super::*;
+    use crate::worker::handle_worker_control_message;
     use base64::Engine;
     use js_sys::{Array, ArrayBuffer, BigInt, Object, Uint8Array};
     use wasm_bindgen_test::*;
 
     wasm_bindgen_test_configure!(run_in_browser);
 
+    #[wasm_bindgen_test]
+    fn column_def_name_reads_plain_and_quoted_names() {
+        assert_eq!(
+            column_def_name("  email TEXT DEFAULT ''").as_deref(),
+            Some("email")
+        );
+        assert_eq!(
+            column_def_name("\"full name\" TEXT").as_deref(),
+            Some("full name")
+        );
+        assert_eq!(
+            column_def_name("\"say \"\"hi\"\"\" TEXT").as_deref(),
+            Some("say \"hi\"")
+        );
+        assert_eq!(column_def_name("`ticks` INTEGER").as_deref(), Some("ticks"));
+        assert_eq!(
+            column_def_name("[weird col] BLOB").as_deref(),
+            Some("weird col")
+        );
+        assert_eq!(column_def_name(""), None);
+        assert_eq!(column_def_name("\"unterminated TEXT"), None);
+        assert_eq!(quote_identifier("a\"b"), "\"a\"\"b\"");
+    }
+
+    #[wasm_bindgen_test]
+    fn insert_many_statements_chunk_under_the_variable_limit() {
+        let columns = vec!["id".to_string(), "name".to_string(), "score".to_string()];
+        let rows = Array::new();
+        for i in 0..5000 {
+            rows.push(&Array::of3(
+                &JsValue::from_f64(i as f64),
+                &JsValue::from_str(&format!("row {i}")),
+                &JsValue::from_f64(i as f64 / 2.0),
+            ));
+        }
+
+        let statements = insert_many_statements("my table", &columns, &rows).expect("valid rows");
+        // 999 variables / 3 columns = 333 rows per chunk: 15 full chunks and 5 rows left over.
+        assert_eq!(statements.length(), 16);
+        let params_len = |i: u32| {
+            Array::from(&Reflect::get(&statements.get(i), &"params".into()).unwrap()).length()
+        };
+        for i in 0..15 {
+            assert_eq!(params_len(i), 999);
+        }
+        assert_eq!(params_len(15), 15);
+
+        let first = Array::from(&Reflect::get(&statements.get(0), &"params".into()).unwrap());
+        assert_eq!(first.get(0).as_f64(), Some(0.0));
+        let second = Array::from(&Reflect::get(&statements.get(1), &"params".into()).unwrap());
+        assert_eq!(second.get(0).as_f64(), Some(333.0));
+
+        let last_sql = Reflect::get(&statements.get(15), &"sql".into())
+            .unwrap()
+            .as_string()
+            .unwrap();
+        assert_eq!(
+            last_sql,
+            format!(
+                "INSERT INTO \"my table\" (\"id\", \"name\", \"score\") VALUES {}",
+                vec!["(?, ?, ?)"; 5].join(", ")
+            )
+        );
+        let total: u32 = (0..16).map(params_len).sum();
+        assert_eq!(total, 15000);
+    }
+
+    #[wasm_bindgen_test]
+    fn insert_many_statements_reject_bad_shapes() {
+        let rows = Array::of1(&Array::of1(&JsValue::from_f64(1.0)));
+        assert!(insert_many_statements("t", &[], &rows).is_err());
+        let two = vec!["a".to_string(), "b".to_string()];
+        assert!(insert_many_statements("t", &two, &rows).is_err());
+        assert!(insert_many_statements("t", &two, &Array::of1(&"ab".into())).is_err());
+        let none = insert_many_statements("t", &two, &Array::new()).expect("no rows");
+        assert_eq!(none.length(), 0);
+
+        assert_eq!(
+            rows_affected("Query executed successfully. Rows affected: 333"),
+            Some(333)
+        );
+        assert_eq!(rows_affected("[]"), None);
+    }
+
+    #[wasm_bindgen_test]
+    fn batch_statements_normalize_params_and_require_sql() {
+        let entry = Object::new();
+        Reflect::set(&entry, &"sql".into(), &"INSERT INTO t VALUES (?)".into()).unwrap();
+        let params = Array::new();
+        params.push(&BigInt::from(5));
+        Reflect::set(&entry, &"params".into(), &params).unwrap();
+        let bare = Object::new();
+        Reflect::set(&bare, &"sql".into(), &"DELETE FROM t".into()).unwrap();
+
+        let normalized = batch_statements(&Array::of2(&entry, &bare)).expect("valid batch");
+        let first = normalized.get(0);
+        let first_params = Array::from(&Reflect::get(&first, &"params".into()).unwrap());
+        assert_eq!(first_params.length(), 1);
+        assert!(Reflect::get(&normalized.get(1), &"params".into())
+            .unwrap()
+            .is_undefined());
+
+        let missing = batch_statements(&Array::of2(&entry, &Object::new()));
+        assert!(missing.is_err());
+    }
+
     #[wasm_bindgen_test]
     fn normalize_params_handles_none_and_empty_arrays() {
-        let empty = SQLiteWasmDatabase::normalize_params(None).expect("None => empty array");
+        let empty = normalize_params_js(&JsValue::UNDEFINED).expect("undefined => empty array");
         assert_eq!(empty.length(), 0);
 
         let arr = Array::new();
-        let normalized =
-            SQLiteWasmDatabase::normalize_params(Some(arr)).expect("empty array stays empty");
+        let normalized = normalize_params_js(&arr.into()).expect("empty array stays empty");
         assert_eq!(normalized.length(), 0);
     }
 
@@ -258,8 +1796,7 @@ mod tests {
         let buf_js: JsValue = buf.into();
         params.push(&buf_js);
 
-        let normalized =
-            SQLiteWasmDatabase::normalize_params(Some(params)).expect("normalization works");
+        let normalized = normalize_params_js(&params.into()).expect("normalization works");
         assert_eq!(normalized.length(), 6);
         assert_eq!(normalized.get(0).as_f64(), Some(123.0));
         assert_eq!(normalized.get(1).as_string().as_deref(), Some("hey"));
@@ -300,7 +1837,7 @@ mod tests {
 
     #[wasm_bindgen_test(async)]
     async fn new_rejects_blank_database_name() {
-        let err = match SQLiteWasmDatabase::new("   ").await {
+        let err = match SQLiteWasmDatabase::new("   ", None).await {
             Ok(_) => panic!("blank names should be rejected before constructing worker"),
             Err(err) => err,
         };
@@ -312,6 +1849,51 @@ mod tests {
         }
     }
 
+    /// A connection whose worker swallows every request, so replies never
+    /// arrive and requests stay pending.
+    fn swallowing_db(options: ConnectionOptions) -> SQLiteWasmDatabase {
+        let worker = create_worker_from_code("self.onmessage = () => {};").expect("worker");
+        let ready_signal = ReadySignal::new();
+        ready_signal.mark_ready();
+        SQLiteWasmDatabase {
+            worker: Rc::new(RefCell::new(worker)),
+            db_name: "swallowing".to_string(),
+            options,
+            pending_queries: Rc::new(RefCell::new(HashMap::new())),
+            next_request_id: Rc::new(RefCell::new(1)),
+            ready_signal,
+            listeners: EventListeners::default(),
+            cancel_signal: CancelSignal::default(),
+            pagehide: Rc::new(RefCell::new(None)),
+            closed: Rc::new(Cell::new(false)),
+            pool_lock: Rc::new(RefCell::new(None)),
+        }
+    }
+
+    #[wasm_bindgen_test(async)]
+    async fn rejects_queries_beyond_the_pending_cap() {
+        let db = swallowing_db(ConnectionOptions {
+            max_pending_queries: Some(2),
+            ..Default::default()
+        });
+        for _ in 0..2 {
+            let db = db.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let _ = db.query("SELECT 1", JsValue::UNDEFINED, None).await;
+            });
+        }
+        JsFuture::from(js_sys::Promise::resolve(&JsValue::NULL))
+            .await
+            .unwrap();
+        assert_eq!(db.pending_queries.borrow().len(), 2);
+
+        match db.query("SELECT 1", JsValue::UNDEFINED, None).await {
+            Err(SQLiteWasmDatabaseError::TooManyPendingQueries(2)) => {}
+            other => panic!("expected TooManyPendingQueries, got {other:?}"),
+        }
+        assert_eq!(db.pending_queries.borrow().len(), 2);
+    }
+
     #[wasm_bindgen_test]
     fn detects_structured_initialization_pending_errors() {
         let err = Object::new();
@@ -324,63 +1906,338 @@ mod tests {
         assert!(is_initialization_pending_error(&js_val));
     }
 
+    #[wasm_bindgen_test]
+    fn maps_aborted_worker_errors() {
+        let err = Object::new();
+        let _ = js_sys::Reflect::set(
+            &err,
+            &JsValue::from_str("type"),
+            &JsValue::from_str(WORKER_ERROR_TYPE_QUERY_ABORTED),
+        );
+        assert!(matches!(
+            map_worker_error(err.into()),
+            SQLiteWasmDatabaseError::QueryAborted
+        ));
+        assert!(matches!(
+            map_worker_error(JsValue::from_str("boom")),
+            SQLiteWasmDatabaseError::JsError(_)
+        ));
+    }
+
+    #[wasm_bindgen_test]
+    fn maps_timeout_worker_errors() {
+        assert!(matches!(
+            map_worker_error(crate::abort::make_timeout_error()),
+            SQLiteWasmDatabaseError::QueryTimeout
+        ));
+    }
+
+    #[wasm_bindgen_test]
+    fn maps_worker_errors_carrying_sqlite_detail() {
+        let err = Object::new();
+        let _ = js_sys::Reflect::set(
+            &err,
+            &JsValue::from_str("type"),
+            &JsValue::from_str("WorkerError"),
+        );
+        let _ = js_sys::Reflect::set(
+            &err,
+            &JsValue::from_str("message"),
+            &JsValue::from_str("Failed to prepare statement: near \"SELEC\": syntax error"),
+        );
+        let sqlite = Object::new();
+        let _ = js_sys::Reflect::set(
+            &sqlite,
+            &JsValue::from_str("message"),
+            &JsValue::from_str("near \"SELEC\": syntax error"),
+        );
+        let _ = js_sys::Reflect::set(&sqlite, &JsValue::from_str("code"), &JsValue::from(1));
+        let _ = js_sys::Reflect::set(&sqlite, &JsValue::from_str("offset"), &JsValue::from(0));
+        let _ = js_sys::Reflect::set(&err, &JsValue::from_str("sqlite"), &sqlite);
+
+        match map_worker_error(err.into()) {
+            SQLiteWasmDatabaseError::Sqlite { message, sqlite } => {
+                assert!(message.starts_with("Failed to prepare statement"));
+                assert_eq!(sqlite.code, 1);
+                assert_eq!(sqlite.offset, Some(0));
+            }
+            other => panic!("expected a SQLite error, got {other:?}"),
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn maps_stepdown_worker_errors() {
+        let err = Object::new();
+        let _ = js_sys::Reflect::set(
+            &err,
+            &JsValue::from_str("type"),
+            &JsValue::from_str(WORKER_ERROR_TYPE_LEADER_STEPDOWN),
+        );
+        assert!(matches!(
+            map_worker_error(err.into()),
+            SQLiteWasmDatabaseError::LeaderStepdown
+        ));
+    }
+
+    #[wasm_bindgen_test(async)]
+    async fn election_timeout_surfaces_as_a_typed_error() {
+        let db = swallowing_db(ConnectionOptions::default());
+        db.ready_signal.reset();
+        let message = Object::new();
+        let _ = js_sys::Reflect::set(
+            &message,
+            &JsValue::from_str("type"),
+            &JsValue::from_str("worker-error"),
+        );
+        let _ = js_sys::Reflect::set(
+            &message,
+            &JsValue::from_str("error"),
+            &JsValue::from_str("Leader election timed out after 5000ms"),
+        );
+        let _ = js_sys::Reflect::set(
+            &message,
+            &JsValue::from_str("errorType"),
+            &JsValue::from_str(WORKER_ERROR_TYPE_LEADER_ELECTION_TIMEOUT),
+        );
+        assert!(handle_worker_control_message(
+            &message.into(),
+            &db.ready_signal
+        ));
+
+        let err = db
+            .query("SELECT 1", JsValue::UNDEFINED, None)
+            .await
+            .expect_err("query should fail");
+        assert!(matches!(
+            err,
+            SQLiteWasmDatabaseError::LeaderElectionTimeout
+        ));
+    }
+
+    #[wasm_bindgen_test]
+    fn maps_server_busy_worker_errors() {
+        let err = Object::new();
+        let _ = js_sys::Reflect::set(
+            &err,
+            &JsValue::from_str("type"),
+            &JsValue::from_str(WORKER_ERROR_TYPE_SERVER_BUSY),
+        );
+        assert!(matches!(
+            map_worker_error(err.into()),
+            SQLiteWasmDatabaseError::ServerBusy
+        ));
+    }
+
     #[wasm_bindgen_test]
     fn detects_string_initialization_pending_errors() {
         let js_val = JsValue::from_str(WORKER_ERROR_TYPE_INITIALIZATION_PENDING);
         assert!(is_initialization_pending_error(&js_val));
     }
 
+    #[wasm_bindgen_test(async)]
+    async fn cancel_all_rejects_pending_queries_and_keeps_the_connection() {
+        let db = SQLiteWasmDatabase::new("test_cancel_all", None)
+            .await
+            .unwrap();
+
+        let results = Rc::new(RefCell::new(Vec::new()));
+        for _ in 0..3 {
+            let db = db.clone();
+            let results = Rc::clone(&results);
+            wasm_bindgen_futures::spawn_local(async move {
+                let result = db
+                    .query(
+                        "WITH RECURSIVE n(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM n \
+                         WHERE x < 50000) SELECT COUNT(*) AS count FROM n",
+                        JsValue::UNDEFINED,
+                        None,
+                    )
+                    .await;
+                results.borrow_mut().push(result);
+            });
+        }
+        JsFuture::from(js_sys::Promise::resolve(&JsValue::NULL))
+            .await
+            .unwrap();
+        assert_eq!(db.pending_queries.borrow().len(), 3);
+
+        assert_eq!(db.cancel_all().expect("cancel all"), 3);
+        // Test pages are not cross-origin isolated, so the running CTE is
+        // not interrupted here; the integration tests cover that.
+        let result = db
+            .query("SELECT 1 AS one", JsValue::UNDEFINED, None)
+            .await
+            .expect("queries work after cancelAll");
+        assert!(result.contains("\"one\": 1"));
+
+        let results = results.borrow();
+        assert_eq!(results.len(), 3);
+        assert!(results
+            .iter()
+            .all(|result| matches!(result, Err(SQLiteWasmDatabaseError::QueryAborted))));
+    }
+
+    #[wasm_bindgen_test(async)]
+    async fn close_rejects_later_requests_until_reconnect() {
+        let db = SQLiteWasmDatabase::new("test_close", None).await.unwrap();
+        db.close().await.expect("close");
+        db.close().await.expect("closing twice is fine");
+        assert!(db.pagehide.borrow().is_none(), "close removes the listener");
+
+        let result = db.query("SELECT 1 AS one", JsValue::UNDEFINED, None).await;
+        assert!(matches!(result, Err(SQLiteWasmDatabaseError::Closed)));
+
+        db.reconnect().await.expect("reconnect");
+        assert!(db.pagehide.borrow().is_some());
+        let result = db
+            .query("SELECT 1 AS one", JsValue::UNDEFINED, None)
+            .await
+            .expect("queries work after reconnect");
+        assert!(result.contains("\"one\": 1"));
+    }
+
+    #[wasm_bindgen_test(async)]
+    async fn reconnect_restores_a_dead_worker() {
+        let db = SQLiteWasmDatabase::new("test_reconnect", None)
+            .await
+            .unwrap();
+        db.query(
+            "CREATE TABLE IF NOT EXISTS kept (id INTEGER)",
+            JsValue::UNDEFINED,
+            None,
+        )
+        .await
+        .unwrap();
+        db.worker.borrow().terminate();
+
+        // Nothing answers the dead worker, so this stays pending until
+        // reconnect() rejects it.
+        let stranded = Rc::new(RefCell::new(None));
+        {
+            let db = db.clone();
+            let stranded = Rc::clone(&stranded);
+            wasm_bindgen_futures::spawn_local(async move {
+                let result = db.query("SELECT 1", JsValue::UNDEFINED, None).await;
+                *stranded.borrow_mut() = Some(result);
+            });
+        }
+        JsFuture::from(js_sys::Promise::resolve(&JsValue::NULL))
+            .await
+            .unwrap();
+        assert_eq!(db.pending_queries.borrow().len(), 1);
+
+        db.reconnect().await.expect("reconnect");
+        assert!(matches!(
+            stranded.borrow_mut().take(),
+            Some(Err(SQLiteWasmDatabaseError::WorkerRestarted))
+        ));
+
+        let result = db
+            .query(
+                "SELECT COUNT(*) AS count FROM kept",
+                JsValue::UNDEFINED,
+                None,
+            )
+            .await
+            .expect("queries work after reconnect");
+        assert!(result.contains("\"count\": 0"));
+    }
+
+    #[wasm_bindgen_test(async)]
+    async fn self_test_passes_in_the_test_browser() {
+        let report = SQLiteWasmDatabase::self_test().await.expect("self test");
+        assert!(
+            report.problems.is_empty(),
+            "problems: {:?}",
+            report.problems
+        );
+        assert!(report.opfs_available);
+        assert!(report.workers_available);
+        assert!(report.query_ok);
+        assert!(report.custom_functions_ok);
+        assert_eq!(report.version, env!("CARGO_PKG_VERSION"));
+        assert!(report.sqlite_version.is_some());
+        assert!(report.ok);
+    }
+
     #[wasm_bindgen_test(async)]
     async fn wipe_and_recreate_tests() {
-        let db = SQLiteWasmDatabase::new("test_wipe").await.unwrap();
+        let db = SQLiteWasmDatabase::new("test_wipe", None).await.unwrap();
         db.wipe_and_recreate().await.unwrap();
 
         db.query(
             "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)",
+            JsValue::UNDEFINED,
+            None,
+        )
+        .await
+        .unwrap();
+        db.query(
+            "INSERT INTO users (name) VALUES ('Alice')",
+            JsValue::UNDEFINED,
             None,
         )
         .await
         .unwrap();
-        db.query("INSERT INTO users (name) VALUES ('Alice')", None)
-            .await
-            .unwrap();
 
         let result = db
-            .query("SELECT COUNT(*) as count FROM users", None)
+            .query(
+                "SELECT COUNT(*) as count FROM users",
+                JsValue::UNDEFINED,
+                None,
+            )
             .await
             .unwrap();
         assert!(result.contains("\"count\": 1"));
 
         db.wipe_and_recreate().await.unwrap();
 
-        let result = db.query("SELECT * FROM users", None).await;
+        let result = db
+            .query("SELECT * FROM users", JsValue::UNDEFINED, None)
+            .await;
         assert!(result.is_err() || result.unwrap().contains("no such table"));
 
         let create_result = db
             .query(
                 "CREATE TABLE new_table (id INTEGER PRIMARY KEY, value TEXT)",
+                JsValue::UNDEFINED,
                 None,
             )
             .await;
         assert!(create_result.is_ok());
 
         let insert_result = db
-            .query("INSERT INTO new_table (value) VALUES ('test')", None)
+            .query(
+                "INSERT INTO new_table (value) VALUES ('test')",
+                JsValue::UNDEFINED,
+                None,
+            )
             .await;
         assert!(insert_result.is_ok());
 
-        let select_result = db.query("SELECT * FROM new_table", None).await.unwrap();
+        let select_result = db
+            .query("SELECT * FROM new_table", JsValue::UNDEFINED, None)
+            .await
+            .unwrap();
         assert!(select_result.contains("test"));
 
         for i in 0..3 {
-            db.query(&format!("CREATE TABLE t{} (id INTEGER)", i), None)
-                .await
-                .unwrap();
+            db.query(
+                &format!("CREATE TABLE t{} (id INTEGER)", i),
+                JsValue::UNDEFINED,
+                None,
+            )
+            .await
+            .unwrap();
             db.wipe_and_recreate().await.unwrap();
         }
 
         let result = db
-            .query("SELECT name FROM sqlite_master WHERE type='table'", None)
+            .query(
+                "SELECT name FROM sqlite_master WHERE type='table'",
+                JsValue::UNDEFINED,
+                None,
+            )
             .await
             .unwrap();
         assert!(!result.contains("t0"));
@@ -389,17 +2246,17 @@ mod tests {
 
         let arr = Array::new();
         arr.push(&JsValue::from_f64(f64::NAN));
-        let res = db.query("SELECT ?", Some(arr)).await;
+        let res = db.query("SELECT ?", arr.into(), None).await;
         assert!(res.is_err(), "NaN should be rejected");
 
         let arr = Array::new();
         arr.push(&JsValue::from_f64(f64::INFINITY));
-        let res = db.query("SELECT ?", Some(arr)).await;
+        let res = db.query("SELECT ?", arr.into(), None).await;
         assert!(res.is_err(), "+Infinity should be rejected");
 
         let arr = Array::new();
         arr.push(&JsValue::from_f64(f64::NEG_INFINITY));
-        let res = db.query("SELECT ?", Some(arr)).await;
+        let res = db.query("SELECT ?", arr.into(), None).await;
         assert!(res.is_err(), "-Infinity should be rejected");
     }
 }
@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// Name of the private in-memory database `selfTest()` opens, so the probe
+/// neither writes to OPFS nor joins another tab's leader election.
+pub(crate) const SELF_TEST_DB_NAME: &str = "__sqlite_web_self_test";
+
+/// Outcome of `selfTest()`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfTestReport {
+    /// `true` when every check below passed.
+    pub ok: bool,
+    /// The origin private file system could be opened.
+    pub opfs_available: bool,
+    /// `Worker` exists in this context.
+    pub workers_available: bool,
+    /// A create/insert/select round trip returned the inserted row.
+    pub query_ok: bool,
+    /// The FLOAT_* custom functions are registered and callable.
+    pub custom_functions_ok: bool,
+    /// Version of this package.
+    pub version: String,
+    /// SQLite library version reported by the worker, once it answered.
+    pub sqlite_version: Option<String>,
+    /// One entry per failed check, empty when `ok`.
+    pub problems: Vec<String>,
+}
+
+impl SelfTestReport {
+    pub(crate) fn new() -> Self {
+        Self {
+            ok: false,
+            opfs_available: false,
+            workers_available: false,
+            query_ok: false,
+            custom_functions_ok: false,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            sqlite_version: None,
+            problems: Vec::new(),
+        }
+    }
+
+    /// Record a failed check.
+    pub(crate) fn fail(&mut self, problem: impl Into<String>) {
+        self.problems.push(problem.into());
+    }
+
+    pub(crate) fn finish(mut self) -> Self {
+        self.ok = self.problems.is_empty()
+            && self.opfs_available
+            && self.workers_available
+            && self.query_ok
+            && self.custom_functions_ok;
+        self
+    }
+}
+
+/// Whether this context can construct a `Worker`.
+pub(crate) fn workers_available() -> bool {
+    js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("Worker"))
+        .map(|ctor| ctor.is_function())
+        .unwrap_or(false)
+}
+
+#[cfg(all(test, target_family = "wasm"))]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn report_is_ok_only_when_every_check_passed() {
+        let mut report = SelfTestReport::new();
+        report.opfs_available = true;
+        report.workers_available = true;
+        report.query_ok = true;
+        report.custom_functions_ok = true;
+        assert!(report.clone().finish().ok);
+
+        report.fail("Query round trip failed");
+        assert!(!report.clone().finish().ok);
+
+        let mut missing = SelfTestReport::new();
+        missing.workers_available = true;
+        assert!(!missing.finish().ok);
+    }
+
+    #[wasm_bindgen_test]
+    fn workers_are_available_in_the_test_browser() {
+        assert!(workers_available());
+    }
+}
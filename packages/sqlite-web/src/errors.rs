@@ -2,6 +2,8 @@ use thiserror::Error;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_utils::prelude::{serde_wasm_bindgen, WasmEncodedError};
 
+use crate::validation::LastError;
+
 #[derive(Debug, Error)]
 pub enum SQLiteWasmDatabaseError {
     #[error(transparent)]
@@ -14,6 +16,35 @@ pub enum SQLiteWasmDatabaseError {
     InitializationFailed(String),
     #[error("OPFS deletion failed: {0}")]
     OpfsDeletionFailed(String),
+    #[error("Query aborted")]
+    QueryAborted,
+    #[error("Query timed out")]
+    QueryTimeout,
+    /// The leading tab closed before answering; the request did not finish
+    /// and can be sent again once another tab takes over.
+    #[error("Leader stepped down before answering; retry the request")]
+    LeaderStepdown,
+    /// The leader's queue is full; the request was not run and can be sent
+    /// again once earlier ones finish.
+    #[error("Database is busy; retry the request later")]
+    ServerBusy,
+    /// `reconnect()` replaced the worker while the request was pending; it
+    /// can be sent again once `reconnect()` resolves.
+    #[error("Worker restarted before answering; retry the request")]
+    WorkerRestarted,
+    /// No tab answered as leader in time, e.g. while another tab was still
+    /// starting up; opening the database again usually succeeds.
+    #[error("Leader election timed out; retry opening the database")]
+    LeaderElectionTimeout,
+    #[error("Too many pending queries (limit {0})")]
+    TooManyPendingQueries(usize),
+    /// `close()` was called; `reconnect()` reopens the connection.
+    #[error("Database connection is closed")]
+    Closed,
+    /// SQLite rejected the query. `message` is the worker's error and
+    /// `sqlite` what SQLite itself reported, as `lastError()` would.
+    #[error("{message} ({})", .sqlite.describe())]
+    Sqlite { message: String, sqlite: LastError },
 }
 
 impl From<JsValue> for SQLiteWasmDatabaseError {
@@ -77,6 +108,35 @@ mod tests {
         assert!(wasm_err.readable_msg.contains("Initialization pending"));
     }
 
+    #[wasm_bindgen_test]
+    fn sqlite_errors_name_their_code_and_offset() {
+        let err = SQLiteWasmDatabaseError::Sqlite {
+            message: "Failed to prepare statement: near \"SELEC\": syntax error".to_string(),
+            sqlite: LastError {
+                message: "near \"SELEC\": syntax error".to_string(),
+                code: 1,
+                offset: Some(9),
+            },
+        };
+        assert_eq!(
+            WasmEncodedError::from(err).msg,
+            "Failed to prepare statement: near \"SELEC\": syntax error (SQLite error 1 at offset 9)"
+        );
+
+        let err = SQLiteWasmDatabaseError::Sqlite {
+            message: "UNIQUE constraint failed: t.id".to_string(),
+            sqlite: LastError {
+                message: "UNIQUE constraint failed: t.id".to_string(),
+                code: 19,
+                offset: None,
+            },
+        };
+        assert_eq!(
+            err.to_string(),
+            "UNIQUE constraint failed: t.id (SQLite error 19)"
+        );
+    }
+
     #[wasm_bindgen_test]
     fn serde_error_variant_is_detectable() {
         let serde_err = serde_wasm_bindgen::Error::new("bad serde");
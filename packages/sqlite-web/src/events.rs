@@ -0,0 +1,279 @@
+use js_sys::{Function, Object, Reflect};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+
+use crate::messages::EVENT_WORKER_LOG;
+
+/// Worker log lines kept until the first `onLog` callback is registered.
+/// The workers log most while `new()` is still opening the database, before
+/// the caller has a connection to subscribe on.
+const LOG_BACKLOG_LIMIT: usize = 200;
+
+/// Callbacks registered through the `on*` subscription methods, keyed by the
+/// `kind` of worker event they listen for. Cloning shares the registry so it
+/// survives the worker being replaced.
+#[derive(Clone, Default)]
+pub(crate) struct EventListeners {
+    inner: Rc<RefCell<ListenerRegistry>>,
+}
+
+#[derive(Default)]
+struct ListenerRegistry {
+    next_id: u32,
+    entries: Vec<(u32, &'static str, Function)>,
+    /// Log lines that arrived while nobody listened for them.
+    log_backlog: VecDeque<JsValue>,
+}
+
+impl EventListeners {
+    /// Register `callback` for `kind`. The first log listener is handed the
+    /// lines logged before it was registered straight away.
+    pub(crate) fn subscribe(&self, kind: &'static str, callback: Function) -> u32 {
+        let (id, backlog) = {
+            let mut registry = self.inner.borrow_mut();
+            registry.next_id = registry.next_id.wrapping_add(1).max(1);
+            let id = registry.next_id;
+            registry.entries.push((id, kind, callback.clone()));
+            let backlog = if kind == EVENT_WORKER_LOG {
+                std::mem::take(&mut registry.log_backlog)
+            } else {
+                VecDeque::new()
+            };
+            (id, backlog)
+        };
+        for line in backlog {
+            let _ = callback.call1(&JsValue::NULL, &line);
+        }
+        id
+    }
+
+    pub(crate) fn unsubscribe(&self, id: u32) -> bool {
+        let mut registry = self.inner.borrow_mut();
+        let before = registry.entries.len();
+        registry.entries.retain(|(entry_id, _, _)| *entry_id != id);
+        registry.entries.len() != before
+    }
+
+    pub(crate) fn emit(&self, kind: &str, detail: &JsValue) {
+        // Collect first so callbacks may subscribe or unsubscribe re-entrantly.
+        let callbacks: Vec<Function> = self
+            .inner
+            .borrow()
+            .entries
+            .iter()
+            .filter(|(_, entry_kind, _)| *entry_kind == kind)
+            .map(|(_, _, callback)| callback.clone())
+            .collect();
+        if callbacks.is_empty() && kind == EVENT_WORKER_LOG {
+            let mut registry = self.inner.borrow_mut();
+            if registry.log_backlog.len() == LOG_BACKLOG_LIMIT {
+                registry.log_backlog.pop_front();
+            }
+            registry.log_backlog.push_back(detail.clone());
+            return;
+        }
+        for callback in callbacks {
+            let _ = callback.call1(&JsValue::NULL, detail);
+        }
+    }
+}
+
+/// Dispatch a `worker-event` message to its listeners. Returns `false` for
+/// any other message type.
+pub(crate) fn handle_worker_event_message(data: &JsValue, listeners: &EventListeners) -> bool {
+    let msg_type = Reflect::get(data, &JsValue::from_str("type"))
+        .ok()
+        .and_then(|value| value.as_string());
+    if msg_type.as_deref() != Some("worker-event") {
+        return false;
+    }
+    let Ok(event) = Reflect::get(data, &JsValue::from_str("event")) else {
+        return true;
+    };
+    if let Some(kind) = Reflect::get(&event, &JsValue::from_str("kind"))
+        .ok()
+        .and_then(|value| value.as_string())
+    {
+        listeners.emit(&kind, &event);
+    }
+    true
+}
+
+/// Pass a `worker-log` message on to `onLog` callbacks as
+/// `{ level, message, timestamp }`. Returns `false` for any other message
+/// type.
+pub(crate) fn handle_worker_log_message(data: &JsValue, listeners: &EventListeners) -> bool {
+    let msg_type = Reflect::get(data, &JsValue::from_str("type"))
+        .ok()
+        .and_then(|value| value.as_string());
+    if msg_type.as_deref() != Some(EVENT_WORKER_LOG) {
+        return false;
+    }
+    let line = Object::new();
+    for key in ["level", "message", "timestamp"] {
+        let key = JsValue::from_str(key);
+        if let Ok(value) = Reflect::get(data, &key) {
+            let _ = Reflect::set(&line, &key, &value);
+        }
+    }
+    listeners.emit(EVENT_WORKER_LOG, &line);
+    true
+}
+
+#[cfg(all(test, target_family = "wasm"))]
+mod tests {
+    use super::*;
+    use crate::messages::{EVENT_DB_CHANGE, EVENT_LEADERSHIP_CHANGE};
+    use js_sys::Object;
+    use wasm_bindgen::closure::Closure;
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn recorder_function() -> (Function, Rc<RefCell<Vec<JsValue>>>) {
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let calls_clone = Rc::clone(&calls);
+        let closure = Closure::wrap(Box::new(move |value: JsValue| {
+            calls_clone.borrow_mut().push(value);
+        }) as Box<dyn FnMut(JsValue)>);
+        let func: Function = closure.as_ref().unchecked_ref::<Function>().clone();
+        closure.forget();
+        (func, calls)
+    }
+
+    fn leadership_message(leader_id: &str) -> JsValue {
+        let event = Object::new();
+        let _ = Reflect::set(
+            &event,
+            &JsValue::from_str("kind"),
+            &JsValue::from_str(EVENT_LEADERSHIP_CHANGE),
+        );
+        let _ = Reflect::set(
+            &event,
+            &JsValue::from_str("leaderId"),
+            &JsValue::from_str(leader_id),
+        );
+        let msg = Object::new();
+        let _ = Reflect::set(
+            &msg,
+            &JsValue::from_str("type"),
+            &JsValue::from_str("worker-event"),
+        );
+        let _ = Reflect::set(&msg, &JsValue::from_str("event"), &event);
+        msg.into()
+    }
+
+    #[wasm_bindgen_test]
+    fn worker_event_reaches_matching_listeners_until_unsubscribed() {
+        let listeners = EventListeners::default();
+        let (callback, calls) = recorder_function();
+        let id = listeners.subscribe(EVENT_LEADERSHIP_CHANGE, callback);
+        let (other, other_calls) = recorder_function();
+        listeners.subscribe(EVENT_DB_CHANGE, other);
+
+        assert!(handle_worker_event_message(
+            &leadership_message("leader-1"),
+            &listeners
+        ));
+        assert_eq!(calls.borrow().len(), 1);
+        assert!(other_calls.borrow().is_empty());
+        let leader_id = Reflect::get(&calls.borrow()[0], &JsValue::from_str("leaderId"))
+            .unwrap()
+            .as_string();
+        assert_eq!(leader_id.as_deref(), Some("leader-1"));
+
+        assert!(listeners.unsubscribe(id));
+        assert!(!listeners.unsubscribe(id));
+        handle_worker_event_message(&leadership_message("leader-2"), &listeners);
+        assert_eq!(calls.borrow().len(), 1);
+    }
+
+    #[wasm_bindgen_test]
+    fn ignores_other_message_types() {
+        let listeners = EventListeners::default();
+        let msg = Object::new();
+        let _ = Reflect::set(
+            &msg,
+            &JsValue::from_str("type"),
+            &JsValue::from_str("query-result"),
+        );
+        assert!(!handle_worker_event_message(&msg.into(), &listeners));
+    }
+
+    fn log_message(message: &str) -> JsValue {
+        let msg = Object::new();
+        let _ = Reflect::set(
+            &msg,
+            &JsValue::from_str("type"),
+            &JsValue::from_str(EVENT_WORKER_LOG),
+        );
+        let _ = Reflect::set(
+            &msg,
+            &JsValue::from_str("level"),
+            &JsValue::from_str("info"),
+        );
+        let _ = Reflect::set(
+            &msg,
+            &JsValue::from_str("message"),
+            &JsValue::from_str(message),
+        );
+        let _ = Reflect::set(
+            &msg,
+            &JsValue::from_str("timestamp"),
+            &JsValue::from_f64(1.0),
+        );
+        msg.into()
+    }
+
+    fn logged_message(line: &JsValue) -> Option<String> {
+        Reflect::get(line, &JsValue::from_str("message"))
+            .ok()
+            .and_then(|value| value.as_string())
+    }
+
+    #[wasm_bindgen_test]
+    fn log_lines_before_the_first_listener_are_replayed_to_it() {
+        let listeners = EventListeners::default();
+        assert!(handle_worker_log_message(
+            &log_message("opened"),
+            &listeners
+        ));
+        assert!(!handle_worker_event_message(
+            &log_message("opened"),
+            &listeners
+        ));
+
+        let (callback, calls) = recorder_function();
+        listeners.subscribe(EVENT_WORKER_LOG, callback);
+        assert_eq!(calls.borrow().len(), 1);
+        assert_eq!(
+            logged_message(&calls.borrow()[0]).as_deref(),
+            Some("opened")
+        );
+        let has_type = Reflect::has(&calls.borrow()[0], &JsValue::from_str("type")).unwrap();
+        assert!(!has_type, "callbacks get only level, message and timestamp");
+
+        handle_worker_log_message(&log_message("leader"), &listeners);
+        assert_eq!(calls.borrow().len(), 2);
+
+        // Only the first listener gets the backlog.
+        let (late, late_calls) = recorder_function();
+        listeners.subscribe(EVENT_WORKER_LOG, late);
+        assert!(late_calls.borrow().is_empty());
+    }
+
+    #[wasm_bindgen_test]
+    fn log_backlog_keeps_the_latest_lines() {
+        let listeners = EventListeners::default();
+        for i in 0..LOG_BACKLOG_LIMIT + 5 {
+            handle_worker_log_message(&log_message(&i.to_string()), &listeners);
+        }
+        let (callback, calls) = recorder_function();
+        listeners.subscribe(EVENT_WORKER_LOG, callback);
+        assert_eq!(calls.borrow().len(), LOG_BACKLOG_LIMIT);
+        assert_eq!(logged_message(&calls.borrow()[0]).as_deref(), Some("5"));
+    }
+}
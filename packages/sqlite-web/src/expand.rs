@@ -0,0 +1,156 @@
+use js_sys::{Array, Object, Reflect};
+use wasm_bindgen::prelude::*;
+
+use crate::errors::SQLiteWasmDatabaseError;
+
+/// Rewrite the `:name` placeholders in `sql` into positional `?`s for
+/// `queryIn`, taking each value from `values[name]`. An array expands into
+/// one `?` per element, so `IN (:ids)` with `{ ids: [1, 2, 3] }` becomes
+/// `IN (?, ?, ?)`, and an empty array leaves the list empty, which SQLite
+/// accepts and which matches nothing. Any other value binds as one `?`.
+pub(crate) fn expand_named_params(
+    sql: &str,
+    values: &Object,
+) -> Result<(String, Array), SQLiteWasmDatabaseError> {
+    let params = Array::new();
+    let sql = rewrite_placeholders(sql, |name| {
+        let value = Reflect::get(values, &JsValue::from_str(name))?;
+        if value.is_undefined() {
+            return Err(SQLiteWasmDatabaseError::JsError(JsValue::from_str(
+                &format!("No value given for :{name}"),
+            )));
+        }
+        if !Array::is_array(&value) {
+            params.push(&value);
+            return Ok("?".to_string());
+        }
+        let list = Array::from(&value);
+        for item in list.iter() {
+            params.push(&item);
+        }
+        Ok(vec!["?"; list.length() as usize].join(", "))
+    })?;
+    Ok((sql, params))
+}
+
+/// Copy `sql`, replacing each `:name` outside string literals, quoted
+/// identifiers and comments with `replace(name)`. Positional `?`
+/// placeholders are rejected since their values would have no name.
+fn rewrite_placeholders(
+    sql: &str,
+    mut replace: impl FnMut(&str) -> Result<String, SQLiteWasmDatabaseError>,
+) -> Result<String, SQLiteWasmDatabaseError> {
+    let mut out = String::with_capacity(sql.len());
+    let mut rest = sql;
+    while let Some(c) = rest.chars().next() {
+        let skip = match c {
+            '\'' | '"' | '`' => quoted_len(rest, c),
+            '[' => rest.find(']').map_or(rest.len(), |end| end + 1),
+            '-' if rest.starts_with("--") => rest.find('\n').unwrap_or(rest.len()),
+            '/' if rest.starts_with("/*") => rest[2..].find("*/").map_or(rest.len(), |end| end + 4),
+            '?' => {
+                return Err(SQLiteWasmDatabaseError::JsError(JsValue::from_str(
+                    "queryIn takes :name placeholders, not ?",
+                )))
+            }
+            ':' => {
+                let name_len = rest[1..]
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .unwrap_or(rest.len() - 1);
+                if name_len > 0 {
+                    out.push_str(&replace(&rest[1..=name_len])?);
+                    rest = &rest[1 + name_len..];
+                    continue;
+                }
+                1
+            }
+            _ => c.len_utf8(),
+        };
+        out.push_str(&rest[..skip]);
+        rest = &rest[skip..];
+    }
+    Ok(out)
+}
+
+/// Length of the literal opening `sql` and closed by `quote`, where a
+/// doubled quote stands for one inside it. Unterminated runs to the end.
+fn quoted_len(sql: &str, quote: char) -> usize {
+    let mut chars = sql.char_indices().skip(1).peekable();
+    while let Some((i, c)) = chars.next() {
+        if c == quote {
+            if chars.peek().map(|(_, next)| *next) == Some(quote) {
+                chars.next();
+            } else {
+                return i + 1;
+            }
+        }
+    }
+    sql.len()
+}
+
+#[cfg(all(test, target_family = "wasm"))]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn values(entries: &[(&str, JsValue)]) -> Object {
+        let obj = Object::new();
+        for (key, value) in entries {
+            Reflect::set(&obj, &JsValue::from_str(key), value).unwrap();
+        }
+        obj
+    }
+
+    #[wasm_bindgen_test]
+    fn arrays_expand_into_one_placeholder_per_element() {
+        let ids = Array::of3(&1.into(), &2.into(), &3.into());
+        let (sql, params) = expand_named_params(
+            "SELECT * FROM t WHERE id IN (:ids) AND kind = :kind",
+            &values(&[("ids", ids.into()), ("kind", "a".into())]),
+        )
+        .unwrap();
+        assert_eq!(sql, "SELECT * FROM t WHERE id IN (?, ?, ?) AND kind = ?");
+        assert_eq!(params.length(), 4);
+        assert_eq!(params.get(2).as_f64(), Some(3.0));
+        assert_eq!(params.get(3).as_string().as_deref(), Some("a"));
+    }
+
+    #[wasm_bindgen_test]
+    fn empty_arrays_leave_an_empty_list() {
+        let (sql, params) = expand_named_params(
+            "SELECT * FROM t WHERE id IN (:ids)",
+            &values(&[("ids", Array::new().into())]),
+        )
+        .unwrap();
+        assert_eq!(sql, "SELECT * FROM t WHERE id IN ()");
+        assert_eq!(params.length(), 0);
+    }
+
+    #[wasm_bindgen_test]
+    fn placeholders_inside_literals_and_comments_are_left_alone() {
+        let sql =
+            "SELECT ':ids', \":ids\", [:ids], `:ids` -- :ids\n /* :ids */ FROM t WHERE a = :ids";
+        let (out, params) =
+            expand_named_params(sql, &values(&[("ids", JsValue::from_f64(1.0))])).unwrap();
+        assert_eq!(
+            out,
+            "SELECT ':ids', \":ids\", [:ids], `:ids` -- :ids\n /* :ids */ FROM t WHERE a = ?"
+        );
+        assert_eq!(params.length(), 1);
+
+        let (out, _) = expand_named_params("SELECT 'it''s :x' AS s", &Object::new()).unwrap();
+        assert_eq!(out, "SELECT 'it''s :x' AS s");
+    }
+
+    #[wasm_bindgen_test]
+    fn missing_values_and_positional_placeholders_are_rejected() {
+        assert!(expand_named_params("SELECT :missing", &Object::new()).is_err());
+        assert!(expand_named_params("SELECT ?", &Object::new()).is_err());
+        let (out, params) = expand_named_params("SELECT :v", &values(&[("v", JsValue::NULL)]))
+            .expect("null is a value");
+        assert_eq!(out, "SELECT ?");
+        assert!(params.get(0).is_null());
+    }
+}
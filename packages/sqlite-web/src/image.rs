@@ -0,0 +1,65 @@
+use serde::{Serialize, Serializer};
+
+/// Raw bytes of a SQLite database file, as produced by `export()`. Serialized
+/// as bytes so it reaches JavaScript as a `Uint8Array`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatabaseImage(pub Vec<u8>);
+
+/// Magic string at the start of every SQLite database file.
+const SQLITE_HEADER_MAGIC: &[u8] = b"SQLite format 3\0";
+
+/// Whether `bytes` starts with the SQLite file header.
+pub(crate) fn has_sqlite_header(bytes: &[u8]) -> bool {
+    bytes.starts_with(SQLITE_HEADER_MAGIC)
+}
+
+/// `has_sqlite_header` for an image still in JavaScript, copying only the
+/// header out of it.
+pub(crate) fn image_has_sqlite_header(image: &js_sys::Uint8Array) -> bool {
+    has_sqlite_header(&image.subarray(0, SQLITE_HEADER_MAGIC.len() as u32).to_vec())
+}
+
+impl Serialize for DatabaseImage {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.0)
+    }
+}
+
+#[cfg(all(test, target_family = "wasm"))]
+mod tests {
+    use super::*;
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_test::*;
+    use wasm_bindgen_utils::prelude::serde_wasm_bindgen;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn serializes_to_uint8_array() {
+        let image = DatabaseImage(vec![1, 2, 3]);
+        let value = serde_wasm_bindgen::to_value(&image).expect("serializes");
+        let bytes = value
+            .dyn_into::<js_sys::Uint8Array>()
+            .expect("should be a Uint8Array");
+        assert_eq!(bytes.to_vec(), vec![1, 2, 3]);
+    }
+
+    #[wasm_bindgen_test]
+    fn detects_sqlite_header() {
+        let mut image = b"SQLite format 3\0".to_vec();
+        image.extend_from_slice(&[0; 84]);
+        assert!(has_sqlite_header(&image));
+        assert!(!has_sqlite_header(b"SQLite format 3"));
+        assert!(!has_sqlite_header(b"not a database file at all"));
+
+        assert!(image_has_sqlite_header(&js_sys::Uint8Array::from(
+            &image[..]
+        )));
+        assert!(!image_has_sqlite_header(&js_sys::Uint8Array::from(
+            &b"SQLite"[..]
+        )));
+    }
+}
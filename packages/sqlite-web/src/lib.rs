@@ -1,15 +1,33 @@
+mod abort;
 mod db;
+mod diagnostics;
 mod errors;
+mod events;
+mod expand;
+mod image;
+mod maintenance;
 mod messages;
 mod opfs;
+mod options;
 mod params;
+mod plan;
 mod ready;
+mod role;
+mod stream;
 mod utils;
+mod validation;
 mod worker;
 mod worker_template;
 
 pub use db::SQLiteWasmDatabase;
+pub use diagnostics::SelfTestReport;
 pub use errors::SQLiteWasmDatabaseError;
+pub use image::DatabaseImage;
+pub use maintenance::{IntegrityReport, VacuumReport};
+pub use plan::QueryPlanStep;
+pub use role::LeadershipInfo;
+pub use stream::QueryStream;
+pub use validation::{LastError, SqlValidation};
 
 #[cfg(all(test, target_family = "wasm"))]
 mod tests;
@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+
+use crate::errors::SQLiteWasmDatabaseError;
+
+/// Outcome of `vacuum()`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct VacuumReport {
+    /// Pages in the database file before `VACUUM` ran.
+    pub page_count_before: i64,
+    /// Pages in the database file afterwards.
+    pub page_count_after: i64,
+    /// Estimated bytes returned to OPFS.
+    pub bytes_freed: i64,
+}
+
+impl VacuumReport {
+    pub(crate) fn from_worker_result(result: &str) -> Result<Self, SQLiteWasmDatabaseError> {
+        serde_json::from_str(result).map_err(|e| {
+            SQLiteWasmDatabaseError::JsError(wasm_bindgen::JsValue::from_str(&format!(
+                "Invalid vacuum response from worker: {e}"
+            )))
+        })
+    }
+}
+
+/// Outcome of `integrityCheck()`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct IntegrityReport {
+    /// `true` when SQLite found no problems.
+    pub ok: bool,
+    /// Problems reported by `PRAGMA integrity_check`, empty when `ok`.
+    pub problems: Vec<String>,
+}
+
+impl IntegrityReport {
+    pub(crate) fn from_worker_result(result: &str) -> Result<Self, SQLiteWasmDatabaseError> {
+        serde_json::from_str(result).map_err(|e| {
+            SQLiteWasmDatabaseError::JsError(wasm_bindgen::JsValue::from_str(&format!(
+                "Invalid integrity check response from worker: {e}"
+            )))
+        })
+    }
+}
+
+#[cfg(all(test, target_family = "wasm"))]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn parses_worker_vacuum_result() {
+        let report = VacuumReport::from_worker_result(
+            r#"{"pageCountBefore":120,"pageCountAfter":4,"bytesFreed":475136}"#,
+        )
+        .expect("valid report");
+        assert_eq!(report.page_count_before, 120);
+        assert_eq!(report.page_count_after, 4);
+        assert_eq!(report.bytes_freed, 475136);
+
+        assert!(VacuumReport::from_worker_result("not json").is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn parses_worker_integrity_result() {
+        let healthy = IntegrityReport::from_worker_result(r#"{"ok":true,"problems":[]}"#)
+            .expect("valid report");
+        assert!(healthy.ok);
+        assert!(healthy.problems.is_empty());
+
+        let broken = IntegrityReport::from_worker_result(
+            r#"{"ok":false,"problems":["row 3 missing from index idx_a"]}"#,
+        )
+        .expect("valid report");
+        assert!(!broken.ok);
+        assert_eq!(broken.problems, vec!["row 3 missing from index idx_a"]);
+    }
+}
@@ -1,3 +1,18 @@
 #[cfg(all(test, target_family = "wasm"))]
 pub const WORKER_ERROR_TYPE_GENERIC: &str = "WorkerError";
 pub const WORKER_ERROR_TYPE_INITIALIZATION_PENDING: &str = "InitializationPending";
+pub const WORKER_ERROR_TYPE_QUERY_ABORTED: &str = "QueryAborted";
+pub const WORKER_ERROR_TYPE_QUERY_TIMEOUT: &str = "QueryTimeout";
+pub const WORKER_ERROR_TYPE_LEADER_STEPDOWN: &str = "LeaderStepdown";
+pub const WORKER_ERROR_TYPE_LEADER_ELECTION_TIMEOUT: &str = "LeaderElectionTimeout";
+pub const WORKER_ERROR_TYPE_SERVER_BUSY: &str = "ServerBusy";
+/// Main-thread only: a request was pending when `reconnect()` replaced the worker.
+pub const WORKER_ERROR_TYPE_WORKER_RESTARTED: &str = "WorkerRestarted";
+pub const EVENT_LEADERSHIP_CHANGE: &str = "leadership-change";
+pub const EVENT_DB_CHANGE: &str = "db-change";
+pub const EVENT_TRANSACTION_COMMITTED: &str = "transaction-committed";
+pub const EVENT_TRANSACTION_ROLLED_BACK: &str = "transaction-rolled-back";
+pub const EVENT_INIT_PROGRESS: &str = "init-progress";
+/// Kind under which `onLog` callbacks are registered; also the `type` of
+/// the control message carrying a worker log line.
+pub const EVENT_WORKER_LOG: &str = "worker-log";
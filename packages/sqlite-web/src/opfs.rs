@@ -1,4 +1,4 @@
-use js_sys::Reflect;
+use js_sys::{Array, Function, Object, Promise, Reflect};
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::JsFuture;
 use web_sys::{FileSystemDirectoryHandle, FileSystemGetDirectoryOptions, FileSystemRemoveOptions};
@@ -8,10 +8,172 @@ use crate::utils::describe_js_value;
 
 const SAHPOOL_DIR_NAME: &str = ".opfs-sahpool";
 
-pub async fn delete_opfs_sahpool_directory() -> Result<(), SQLiteWasmDatabaseError> {
+/// Prefix of the Web Lock a `shared: false` connection holds on its pool
+/// for as long as it is open.
+const POOL_LOCK_PREFIX: &str = "sqlite-web-pool:";
+
+/// The OPFS directory holding the pool for `namespace`. Mirrors the VFS
+/// naming in the core crate: a namespace gets its own `.opfs-sahpool-<ns>`.
+fn sahpool_dir_name(namespace: Option<&str>) -> String {
+    match namespace {
+        None => SAHPOOL_DIR_NAME.to_string(),
+        Some(namespace) => format!("{SAHPOOL_DIR_NAME}-{}", encode_namespace(namespace)),
+    }
+}
+
+/// Same rules as the core crate's `encode_namespace`; the tests of both
+/// copies check the same vectors, so the pool directory deleted here is
+/// always the one the core created.
+fn encode_namespace(namespace: &str) -> String {
+    let mut encoded = String::with_capacity(namespace.len());
+    for byte in namespace.bytes() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'.' | b'-' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("_{byte:02x}")),
+        }
+    }
+    encoded
+}
+
+/// Whether `dir_name` is the pool of a `shared: false` connection, whose
+/// namespace ends in the `private-<time>-<random>` id `options.rs` gives it.
+fn is_private_pool_dir(dir_name: &str) -> bool {
+    let Some(namespace) = dir_name
+        .strip_prefix(SAHPOOL_DIR_NAME)
+        .and_then(|rest| rest.strip_prefix('-'))
+    else {
+        return false;
+    };
+    let mut parts = namespace.rsplitn(3, '-');
+    let (Some(random), Some(time), Some(head)) = (parts.next(), parts.next(), parts.next()) else {
+        return false;
+    };
+    let is_hex = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_hexdigit());
+    random.len() == 8
+        && is_hex(random)
+        && is_hex(time)
+        && (head == "private" || head.ends_with("-private"))
+}
+
+/// Delete the OPFS pool used by connections in `namespace`, leaving other
+/// namespaces' pools alone.
+pub async fn delete_opfs_sahpool_directory(
+    namespace: Option<&str>,
+) -> Result<(), SQLiteWasmDatabaseError> {
+    let root = get_opfs_root().await?;
+    delete_sahpool_dir(&root, &sahpool_dir_name(namespace)).await
+}
+
+/// Delete the pools that `shared: false` connections left behind when their
+/// tab closed without `close()`. A pool still in use is kept: its
+/// connection holds the pool's lock until it closes, and the browser
+/// releases it when the tab goes away. Returns how many pools were deleted.
+pub(crate) async fn sweep_stale_private_pools() -> Result<u32, SQLiteWasmDatabaseError> {
     let root = get_opfs_root().await?;
+    // Listed before the locks are read: a pool created in between already
+    // has its lock held, as connections take it before creating the pool.
+    let private_pools: Vec<String> = collect_entry_names(&root)
+        .await?
+        .into_iter()
+        .filter(|name| is_private_pool_dir(name))
+        .collect();
+    if private_pools.is_empty() {
+        return Ok(0);
+    }
+    let held = held_lock_names().await?;
+    let mut deleted = 0;
+    for dir_name in private_pools {
+        if held.contains(&format!("{POOL_LOCK_PREFIX}{dir_name}")) {
+            continue;
+        }
+        delete_sahpool_dir(&root, &dir_name).await?;
+        deleted += 1;
+    }
+    Ok(deleted)
+}
+
+/// The Web Lock a `shared: false` connection holds on its pool, released
+/// by `close()` or by the browser when the tab goes away.
+pub(crate) struct PoolLock {
+    release: Function,
+}
+
+impl PoolLock {
+    pub(crate) fn release(self) {
+        let _ = self.release.call0(&JsValue::NULL);
+    }
+}
+
+/// Take the lock on `namespace`'s pool, waiting until it is granted.
+pub(crate) async fn hold_pool_lock(namespace: &str) -> Result<PoolLock, SQLiteWasmDatabaseError> {
+    let locks = lock_manager()?;
+    let request = Reflect::get(&locks, &JsValue::from_str("request"))?
+        .dyn_into::<Function>()
+        .map_err(|_| lock_error("navigator.locks.request unavailable"))?;
+
+    let mut release = None;
+    let held = Promise::new(&mut |resolve, _| release = Some(resolve));
+    let mut grant = None;
+    let granted = Promise::new(&mut |resolve, _| grant = Some(resolve));
+    let (Some(release), Some(grant)) = (release, grant) else {
+        return Err(lock_error("failed to create lock promises"));
+    };
+    // The lock is held until `held` resolves, which `PoolLock::release` does.
+    let handler = Closure::once(move |_lock: JsValue| -> Promise {
+        let _ = grant.call0(&JsValue::NULL);
+        held
+    });
+    let options = Object::new();
+    Reflect::set(
+        &options,
+        &JsValue::from_str("mode"),
+        &JsValue::from_str("exclusive"),
+    )?;
+    let lock_name = format!("{POOL_LOCK_PREFIX}{}", sahpool_dir_name(Some(namespace)));
+    request.call3(
+        &locks,
+        &JsValue::from_str(&lock_name),
+        &options,
+        handler.as_ref().unchecked_ref(),
+    )?;
+    handler.forget();
+    JsFuture::from(granted).await?;
+    Ok(PoolLock { release })
+}
 
-    let sahpool_dir = match get_directory_if_exists(&root, SAHPOOL_DIR_NAME).await? {
+fn lock_manager() -> Result<JsValue, SQLiteWasmDatabaseError> {
+    let navigator = Reflect::get(&js_sys::global(), &JsValue::from_str("navigator"))?;
+    let locks = Reflect::get(&navigator, &JsValue::from_str("locks"))?;
+    if locks.is_undefined() || locks.is_null() {
+        return Err(lock_error("navigator.locks unavailable"));
+    }
+    Ok(locks)
+}
+
+/// Names of the Web Locks currently held anywhere in this origin.
+async fn held_lock_names() -> Result<Vec<String>, SQLiteWasmDatabaseError> {
+    let locks = lock_manager()?;
+    let query = Reflect::get(&locks, &JsValue::from_str("query"))?
+        .dyn_into::<Function>()
+        .map_err(|_| lock_error("navigator.locks.query unavailable"))?;
+    let snapshot = JsFuture::from(Promise::from(query.call0(&locks)?)).await?;
+    let held = Reflect::get(&snapshot, &JsValue::from_str("held"))?;
+    Ok(Array::from(&held)
+        .iter()
+        .filter_map(|lock| Reflect::get(&lock, &JsValue::from_str("name")).ok())
+        .filter_map(|name| name.as_string())
+        .collect())
+}
+
+fn lock_error(message: &str) -> SQLiteWasmDatabaseError {
+    SQLiteWasmDatabaseError::JsError(JsValue::from_str(message))
+}
+
+async fn delete_sahpool_dir(
+    root: &FileSystemDirectoryHandle,
+    dir_name: &str,
+) -> Result<(), SQLiteWasmDatabaseError> {
+    let sahpool_dir = match get_directory_if_exists(root, dir_name).await? {
         Some(dir) => dir,
         None => return Ok(()),
     };
@@ -20,7 +182,7 @@ pub async fn delete_opfs_sahpool_directory() -> Result<(), SQLiteWasmDatabaseErr
 
     let remove_options = FileSystemRemoveOptions::new();
     remove_options.set_recursive(true);
-    JsFuture::from(root.remove_entry_with_options(SAHPOOL_DIR_NAME, &remove_options))
+    JsFuture::from(root.remove_entry_with_options(dir_name, &remove_options))
         .await
         .map_err(|e| {
             SQLiteWasmDatabaseError::OpfsDeletionFailed(format!(
@@ -32,7 +194,7 @@ pub async fn delete_opfs_sahpool_directory() -> Result<(), SQLiteWasmDatabaseErr
     Ok(())
 }
 
-async fn get_opfs_root() -> Result<FileSystemDirectoryHandle, SQLiteWasmDatabaseError> {
+pub(crate) async fn get_opfs_root() -> Result<FileSystemDirectoryHandle, SQLiteWasmDatabaseError> {
     let navigator = web_sys::window()
         .map(|w| w.navigator())
         .or_else(|| {
@@ -152,3 +314,44 @@ async fn collect_entry_names(
 
     Ok(names)
 }
+
+#[cfg(all(test, target_family = "wasm"))]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn namespaces_get_their_own_pool_directory() {
+        assert_eq!(sahpool_dir_name(None), ".opfs-sahpool");
+        assert_eq!(sahpool_dir_name(Some("app-one")), ".opfs-sahpool-app-one");
+        assert_eq!(sahpool_dir_name(Some("my app")), ".opfs-sahpool-my_20app");
+        assert_eq!(sahpool_dir_name(Some("my_app")), ".opfs-sahpool-my_5fapp");
+    }
+
+    #[wasm_bindgen_test]
+    fn namespace_encoding_matches_the_core_crate() {
+        // Keep in sync with `test_encode_namespace` in the core crate's util.rs.
+        assert_eq!(encode_namespace("app-one.v2"), "app-one.v2");
+        assert_eq!(encode_namespace("app one"), "app_20one");
+        assert_eq!(encode_namespace("app_one"), "app_5fone");
+        assert_eq!(encode_namespace("é"), "_c3_a9");
+    }
+
+    #[wasm_bindgen_test]
+    fn only_unshared_connection_pools_are_private() {
+        assert!(is_private_pool_dir(".opfs-sahpool-private-18f2a-0badf00d"));
+        assert!(is_private_pool_dir(
+            ".opfs-sahpool-app-one-private-18f2a-0badf00d"
+        ));
+        assert!(!is_private_pool_dir(".opfs-sahpool"));
+        assert!(!is_private_pool_dir(".opfs-sahpool-app-one"));
+        assert!(!is_private_pool_dir(".opfs-sahpool-private"));
+        assert!(!is_private_pool_dir(".opfs-sahpool-private-18f2a-0bad"));
+        assert!(!is_private_pool_dir(
+            ".opfs-sahpool-myprivate-18f2a-0badf00d"
+        ));
+        assert!(!is_private_pool_dir("private-18f2a-0badf00d"));
+    }
+}
@@ -0,0 +1,1106 @@
+use js_sys::{Array, Function, Object, Reflect};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::AbortSignal;
+
+use crate::errors::SQLiteWasmDatabaseError;
+
+/// Per-call options accepted by `query()`.
+#[derive(Default)]
+pub(crate) struct QueryOptions {
+    pub signal: Option<AbortSignal>,
+    pub timeout_ms: Option<f64>,
+    pub dealias_columns: Option<bool>,
+    pub return_rowids: Option<bool>,
+    pub return_result_code: Option<bool>,
+    pub return_notices: Option<bool>,
+    pub return_expanded_sql: Option<bool>,
+    pub return_statement_counts: Option<bool>,
+    pub allow_multiple: Option<bool>,
+    pub max_cell_bytes: Option<u32>,
+    pub serialize_budget_ms: Option<f64>,
+    pub max_result_bytes: Option<u32>,
+    pub profile: Option<bool>,
+    pub typed_cells: Option<bool>,
+    pub coerce_affinity: Option<bool>,
+    /// Reject a bare parameter value instead of binding it as `[value]`.
+    pub strict_params: bool,
+}
+
+/// Options accepted by `queryStream()`.
+pub(crate) struct StreamOptions {
+    pub chunk_size: u32,
+    pub max_cell_bytes: Option<u32>,
+}
+
+/// Rows per chunk when `queryStream()` is not given a `chunkSize`.
+const DEFAULT_STREAM_CHUNK_SIZE: u32 = 256;
+
+impl StreamOptions {
+    pub(crate) fn from_js(options: Option<&Object>) -> Result<Self, SQLiteWasmDatabaseError> {
+        Ok(Self {
+            chunk_size: chunk_size_option(options)?.unwrap_or(DEFAULT_STREAM_CHUNK_SIZE),
+            max_cell_bytes: match options {
+                Some(options) => max_cell_bytes_option(options)?,
+                None => None,
+            },
+        })
+    }
+}
+
+/// Options accepted by `transaction()`.
+#[derive(Default)]
+pub(crate) struct TransactionOptions {
+    /// Commit after every `chunk_size` statements instead of once at the end.
+    pub chunk_size: Option<u32>,
+}
+
+impl TransactionOptions {
+    pub(crate) fn from_js(options: Option<&Object>) -> Result<Self, SQLiteWasmDatabaseError> {
+        Ok(Self {
+            chunk_size: chunk_size_option(options)?,
+        })
+    }
+}
+
+fn chunk_size_option(options: Option<&Object>) -> Result<Option<u32>, SQLiteWasmDatabaseError> {
+    let chunk_size = match options {
+        Some(options) => Reflect::get(options, &JsValue::from_str("chunkSize"))?,
+        None => JsValue::UNDEFINED,
+    };
+    if chunk_size.is_undefined() || chunk_size.is_null() {
+        return Ok(None);
+    }
+    match chunk_size.as_f64() {
+        Some(size) if size.fract() == 0.0 && (1.0..=u32::MAX as f64).contains(&size) => {
+            Ok(Some(size as u32))
+        }
+        _ => Err(SQLiteWasmDatabaseError::JsError(JsValue::from_str(
+            "options.chunkSize must be a positive integer",
+        ))),
+    }
+}
+
+/// `maxCellBytes`, shared by `query()` and `queryStream()`.
+fn max_cell_bytes_option(options: &Object) -> Result<Option<u32>, SQLiteWasmDatabaseError> {
+    let max_cell_bytes = Reflect::get(options, &JsValue::from_str("maxCellBytes"))?;
+    if max_cell_bytes.is_undefined() || max_cell_bytes.is_null() {
+        return Ok(None);
+    }
+    match max_cell_bytes.as_f64() {
+        Some(max) if max.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(&max) => {
+            Ok(Some(max as u32))
+        }
+        _ => Err(SQLiteWasmDatabaseError::JsError(JsValue::from_str(
+            "options.maxCellBytes must be a non-negative integer",
+        ))),
+    }
+}
+
+/// Options accepted by `insert()`.
+#[derive(Default)]
+pub(crate) struct InsertOptions {
+    /// Append a `RETURNING` clause listing the table's generated columns.
+    pub return_generated: bool,
+}
+
+impl InsertOptions {
+    pub(crate) fn from_js(options: Option<&Object>) -> Result<Self, SQLiteWasmDatabaseError> {
+        let Some(options) = options else {
+            return Ok(Self::default());
+        };
+        Ok(Self {
+            return_generated: bool_option(options, "returnGenerated")?.unwrap_or(false),
+        })
+    }
+}
+
+/// Options accepted by `tableExists()`.
+pub(crate) struct TableExistsOptions {
+    /// `sqlite_master` types to match, `["table"]` unless given.
+    pub types: Vec<String>,
+}
+
+const SCHEMA_OBJECT_TYPES: &[&str] = &["table", "view", "index", "trigger"];
+
+impl TableExistsOptions {
+    pub(crate) fn from_js(options: Option<&Object>) -> Result<Self, SQLiteWasmDatabaseError> {
+        let value = match options {
+            Some(options) => Reflect::get(options, &JsValue::from_str("types"))?,
+            None => JsValue::UNDEFINED,
+        };
+        if value.is_undefined() || value.is_null() {
+            return Ok(Self {
+                types: vec!["table".to_string()],
+            });
+        }
+        let invalid = || {
+            SQLiteWasmDatabaseError::JsError(JsValue::from_str(
+                "options.types must be a non-empty array of \"table\", \"view\", \"index\" or \"trigger\"",
+            ))
+        };
+        if !Array::is_array(&value) {
+            return Err(invalid());
+        }
+        let types = Array::from(&value)
+            .iter()
+            .map(|kind| {
+                kind.as_string()
+                    .filter(|kind| SCHEMA_OBJECT_TYPES.contains(&kind.as_str()))
+                    .ok_or_else(invalid)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if types.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self { types })
+    }
+}
+
+/// Options accepted by `SQLiteWasmDatabase.new()`, fixed for the lifetime of
+/// the connection.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct ConnectionOptions {
+    /// Custom SQL functions to register, or `None` for all of them.
+    pub custom_functions: Option<Vec<String>>,
+    /// Requests allowed to await a reply at once, or `None` for no limit.
+    pub max_pending_queries: Option<usize>,
+    /// Minimum gap between this tab's answers to leader pings, or `None` to
+    /// answer every ping.
+    pub announce_interval_ms: Option<f64>,
+    /// Times a forwarded request is resent after its leader goes away, or
+    /// `None` for the default.
+    pub follower_retries: Option<u32>,
+    /// Other tabs' requests this tab runs at once while it leads, or `None`
+    /// for the default.
+    pub max_follower_queries: Option<u32>,
+    /// Requests the leader's database worker holds queued before rejecting
+    /// new ones as busy, or `None` for the default.
+    pub max_queue_depth: Option<u32>,
+    /// Pragmas run, in order, when the leader opens the database.
+    pub pragmas: Vec<String>,
+    /// `PRAGMA synchronous` level applied before `pragmas`, or `None` to
+    /// keep SQLite's default (`FULL`).
+    pub synchronous: Option<String>,
+    /// Keeps the channel, leader lock and OPFS pool apart from connections
+    /// in other namespaces, or `None` for the shared default.
+    pub namespace: Option<String>,
+    /// Forward the workers' log lines to `onLog` callbacks.
+    pub debug: bool,
+    /// Called with each initialization milestone the worker reports.
+    pub on_progress: Option<Function>,
+    /// Open the database in memory when OPFS cannot be installed.
+    pub allow_memory_fallback: bool,
+    /// Set by `shared: false`: the worker opens the database itself in a
+    /// namespace of its own instead of joining the election.
+    pub isolated: bool,
+    /// Set by `selfTest()` along with `isolated`: the worker opens a private
+    /// in-memory database and never touches OPFS.
+    pub in_memory: bool,
+}
+
+impl ConnectionOptions {
+    pub(crate) fn from_js(options: Option<&Object>) -> Result<Self, SQLiteWasmDatabaseError> {
+        let Some(options) = options else {
+            return Ok(Self::default());
+        };
+        let isolated = bool_option(options, "shared")? == Some(false);
+        let namespace = namespace_option(options)?;
+        let namespace = if isolated {
+            Some(private_namespace(namespace.as_deref()))
+        } else {
+            namespace
+        };
+        Ok(Self {
+            custom_functions: custom_functions_option(options)?,
+            max_pending_queries: max_pending_queries_option(options)?,
+            announce_interval_ms: announce_interval_option(options)?,
+            follower_retries: integer_option(options, "followerRetries", 0)?,
+            max_follower_queries: integer_option(options, "maxFollowerQueries", 1)?,
+            max_queue_depth: integer_option(options, "maxQueueDepth", 1)?,
+            pragmas: pragmas_option(options)?,
+            synchronous: synchronous_option(options)?,
+            namespace,
+            debug: bool_option(options, "debug")?.unwrap_or(false),
+            on_progress: on_progress_option(options)?,
+            allow_memory_fallback: bool_option(options, "allowMemoryFallback")?.unwrap_or(false),
+            isolated,
+            in_memory: false,
+        })
+    }
+
+    /// Every pragma the leader runs on open: the `synchronous` level first,
+    /// then the user's `pragmas` so they can still override it.
+    pub(crate) fn open_pragmas(&self) -> Vec<String> {
+        self.synchronous
+            .iter()
+            .map(|level| format!("synchronous = {level}"))
+            .chain(self.pragmas.iter().cloned())
+            .collect()
+    }
+}
+
+/// A namespace no other connection uses, inside `namespace` if one was
+/// given, so an unshared connection gets an OPFS pool of its own. Keep the
+/// id's shape in sync with `is_private_pool_dir` in `opfs.rs`, which finds
+/// the pools of closed connections by it.
+fn private_namespace(namespace: Option<&str>) -> String {
+    let id = format!(
+        "private-{:x}-{:08x}",
+        js_sys::Date::now() as u64,
+        (js_sys::Math::random() * u32::MAX as f64) as u32
+    );
+    match namespace {
+        Some(namespace) => format!("{namespace}-{id}"),
+        None => id,
+    }
+}
+
+fn on_progress_option(options: &Object) -> Result<Option<Function>, SQLiteWasmDatabaseError> {
+    let value = Reflect::get(options, &JsValue::from_str("onProgress"))?;
+    if value.is_undefined() || value.is_null() {
+        return Ok(None);
+    }
+    value.dyn_into::<Function>().map(Some).map_err(|_| {
+        SQLiteWasmDatabaseError::JsError(JsValue::from_str("options.onProgress must be a function"))
+    })
+}
+
+fn namespace_option(options: &Object) -> Result<Option<String>, SQLiteWasmDatabaseError> {
+    let value = Reflect::get(options, &JsValue::from_str("namespace"))?;
+    if value.is_undefined() || value.is_null() {
+        return Ok(None);
+    }
+    value
+        .as_string()
+        .map(|namespace| namespace.trim().to_string())
+        .filter(|namespace| {
+            !namespace.is_empty()
+                && namespace
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+        })
+        .map(Some)
+        .ok_or_else(|| {
+            SQLiteWasmDatabaseError::JsError(JsValue::from_str(
+                "options.namespace must be a non-empty string of letters, digits, '.', '_' or '-'",
+            ))
+        })
+}
+
+/// `synchronous` is one of `OFF`, `NORMAL`, `FULL` or `EXTRA`, in any case.
+fn synchronous_option(options: &Object) -> Result<Option<String>, SQLiteWasmDatabaseError> {
+    const LEVELS: [&str; 4] = ["OFF", "NORMAL", "FULL", "EXTRA"];
+    let value = Reflect::get(options, &JsValue::from_str("synchronous"))?;
+    if value.is_undefined() || value.is_null() {
+        return Ok(None);
+    }
+    value
+        .as_string()
+        .map(|level| level.to_ascii_uppercase())
+        .filter(|level| LEVELS.contains(&level.as_str()))
+        .map(Some)
+        .ok_or_else(|| {
+            SQLiteWasmDatabaseError::JsError(JsValue::from_str(
+                "options.synchronous must be one of OFF, NORMAL, FULL or EXTRA",
+            ))
+        })
+}
+
+/// `pragmas` is an array of strings such as `"foreign_keys = ON"`.
+fn pragmas_option(options: &Object) -> Result<Vec<String>, SQLiteWasmDatabaseError> {
+    let value = Reflect::get(options, &JsValue::from_str("pragmas"))?;
+    if value.is_undefined() || value.is_null() {
+        return Ok(Vec::new());
+    }
+    let invalid = || {
+        SQLiteWasmDatabaseError::JsError(JsValue::from_str(
+            "options.pragmas must be an array of strings",
+        ))
+    };
+    if !Array::is_array(&value) {
+        return Err(invalid());
+    }
+    Array::from(&value)
+        .iter()
+        .map(|pragma| pragma.as_string().ok_or_else(invalid))
+        .collect()
+}
+
+fn announce_interval_option(options: &Object) -> Result<Option<f64>, SQLiteWasmDatabaseError> {
+    let value = Reflect::get(options, &JsValue::from_str("announceIntervalMs"))?;
+    if value.is_undefined() || value.is_null() {
+        return Ok(None);
+    }
+    match value.as_f64() {
+        Some(ms) if ms.is_finite() && ms >= 0.0 => Ok(Some(ms)),
+        _ => Err(SQLiteWasmDatabaseError::JsError(JsValue::from_str(
+            "options.announceIntervalMs must be a non-negative number",
+        ))),
+    }
+}
+
+/// A whole number of at least `min`, such as a retry count or a cap.
+fn integer_option(
+    options: &Object,
+    name: &str,
+    min: u32,
+) -> Result<Option<u32>, SQLiteWasmDatabaseError> {
+    let value = Reflect::get(options, &JsValue::from_str(name))?;
+    if value.is_undefined() || value.is_null() {
+        return Ok(None);
+    }
+    match value.as_f64() {
+        Some(n) if n.fract() == 0.0 && (min as f64..=u32::MAX as f64).contains(&n) => {
+            Ok(Some(n as u32))
+        }
+        _ => Err(SQLiteWasmDatabaseError::JsError(JsValue::from_str(
+            &format!(
+                "options.{name} must be a {} integer",
+                if min == 0 { "non-negative" } else { "positive" }
+            ),
+        ))),
+    }
+}
+
+fn max_pending_queries_option(options: &Object) -> Result<Option<usize>, SQLiteWasmDatabaseError> {
+    let value = Reflect::get(options, &JsValue::from_str("maxPendingQueries"))?;
+    if value.is_undefined() || value.is_null() {
+        return Ok(None);
+    }
+    match value.as_f64() {
+        Some(max) if max.fract() == 0.0 && (1.0..=u32::MAX as f64).contains(&max) => {
+            Ok(Some(max as usize))
+        }
+        _ => Err(SQLiteWasmDatabaseError::JsError(JsValue::from_str(
+            "options.maxPendingQueries must be a positive integer",
+        ))),
+    }
+}
+
+/// Functions and collations the core crate registers. Keep in sync with
+/// `CUSTOM_FUNCTIONS` and `CUSTOM_COLLATIONS` in its `database_functions`,
+/// which reject any other name only once the DB worker opens.
+const CUSTOM_FUNCTION_NAMES: &[&str] = &[
+    "BIGINT_SUM",
+    "FLOAT_SUM",
+    "FLOAT_SUM_SATURATING",
+    "FLOAT_SUM_DISTINCT",
+    "FLOAT_ZERO_HEX",
+    "FLOAT_NEGATE",
+    "FLOAT_IS_ZERO",
+    "FLOAT_SIGN",
+    "FLOAT_MOD",
+    "FLOAT_CLAMP",
+    "FLOAT_VALIDATE",
+    "BIGINT_VALIDATE",
+    "FLOAT_COUNT_NONZERO",
+    "REGEXP",
+    "UNICODE_UPPER",
+    "UNICODE_LOWER",
+    "UUID_V4",
+    "NOW_MS",
+    "FLOAT_COLL",
+    "BIGINT_COLL",
+];
+
+/// `customFunctions` is `true`/`false` for all or none, or an array naming
+/// the functions to keep.
+fn custom_functions_option(
+    options: &Object,
+) -> Result<Option<Vec<String>>, SQLiteWasmDatabaseError> {
+    let value = Reflect::get(options, &JsValue::from_str("customFunctions"))?;
+    if value.is_undefined() || value.is_null() {
+        return Ok(None);
+    }
+    if let Some(enabled) = value.as_bool() {
+        return Ok((!enabled).then(Vec::new));
+    }
+    let invalid = || {
+        SQLiteWasmDatabaseError::JsError(JsValue::from_str(
+            "options.customFunctions must be a boolean or an array of function names",
+        ))
+    };
+    if !Array::is_array(&value) {
+        return Err(invalid());
+    }
+    let names = Array::from(&value)
+        .iter()
+        .map(|name| name.as_string().ok_or_else(invalid))
+        .collect::<Result<Vec<_>, _>>()?;
+    if let Some(unknown) = names.iter().find(|name| {
+        !CUSTOM_FUNCTION_NAMES
+            .iter()
+            .any(|known| known.eq_ignore_ascii_case(name))
+    }) {
+        return Err(SQLiteWasmDatabaseError::JsError(JsValue::from_str(
+            &format!("Unknown custom function: {unknown}"),
+        )));
+    }
+    Ok(Some(names))
+}
+
+fn bool_option(options: &Object, key: &str) -> Result<Option<bool>, SQLiteWasmDatabaseError> {
+    let value = Reflect::get(options, &JsValue::from_str(key))?;
+    if value.is_undefined() || value.is_null() {
+        return Ok(None);
+    }
+    value.as_bool().map(Some).ok_or_else(|| {
+        SQLiteWasmDatabaseError::JsError(JsValue::from_str(&format!(
+            "options.{key} must be a boolean"
+        )))
+    })
+}
+
+fn set_bool_option(options: &Object, key: &str, value: Option<bool>) -> Result<bool, JsValue> {
+    let Some(value) = value else {
+        return Ok(false);
+    };
+    Reflect::set(options, &JsValue::from_str(key), &JsValue::from_bool(value))?;
+    Ok(true)
+}
+
+impl QueryOptions {
+    pub(crate) fn from_js(options: Option<&Object>) -> Result<Self, SQLiteWasmDatabaseError> {
+        let Some(options) = options else {
+            return Ok(Self::default());
+        };
+
+        let signal = Reflect::get(options, &JsValue::from_str("signal"))?;
+        let signal = if signal.is_undefined() || signal.is_null() {
+            None
+        } else {
+            let signal = signal.dyn_into::<AbortSignal>().map_err(|_| {
+                SQLiteWasmDatabaseError::JsError(JsValue::from_str(
+                    "options.signal must be an AbortSignal",
+                ))
+            })?;
+            Some(signal)
+        };
+
+        let timeout_ms = Reflect::get(options, &JsValue::from_str("timeoutMs"))?;
+        let timeout_ms = if timeout_ms.is_undefined() || timeout_ms.is_null() {
+            None
+        } else {
+            match timeout_ms.as_f64() {
+                Some(ms) if ms.is_finite() && ms > 0.0 => Some(ms),
+                _ => {
+                    return Err(SQLiteWasmDatabaseError::JsError(JsValue::from_str(
+                        "options.timeoutMs must be a positive number",
+                    )))
+                }
+            }
+        };
+
+        let serialize_budget_ms = Reflect::get(options, &JsValue::from_str("serializeBudgetMs"))?;
+        let serialize_budget_ms =
+            if serialize_budget_ms.is_undefined() || serialize_budget_ms.is_null() {
+                None
+            } else {
+                match serialize_budget_ms.as_f64() {
+                    Some(ms) if ms.is_finite() && ms >= 0.0 => Some(ms),
+                    _ => {
+                        return Err(SQLiteWasmDatabaseError::JsError(JsValue::from_str(
+                            "options.serializeBudgetMs must be a non-negative number",
+                        )))
+                    }
+                }
+            };
+
+        let max_result_bytes = Reflect::get(options, &JsValue::from_str("maxResultBytes"))?;
+        let max_result_bytes = if max_result_bytes.is_undefined() || max_result_bytes.is_null() {
+            None
+        } else {
+            match max_result_bytes.as_f64() {
+                Some(max) if max.fract() == 0.0 && (1.0..=u32::MAX as f64).contains(&max) => {
+                    Some(max as u32)
+                }
+                _ => {
+                    return Err(SQLiteWasmDatabaseError::JsError(JsValue::from_str(
+                        "options.maxResultBytes must be a positive integer",
+                    )))
+                }
+            }
+        };
+
+        Ok(Self {
+            signal,
+            timeout_ms,
+            dealias_columns: bool_option(options, "dealiasColumns")?,
+            return_rowids: bool_option(options, "returnRowids")?,
+            return_result_code: bool_option(options, "returnResultCode")?,
+            return_notices: bool_option(options, "returnNotices")?,
+            return_expanded_sql: bool_option(options, "returnExpandedSql")?,
+            return_statement_counts: bool_option(options, "returnStatementCounts")?,
+            allow_multiple: bool_option(options, "allowMultiple")?,
+            max_cell_bytes: max_cell_bytes_option(options)?,
+            serialize_budget_ms,
+            max_result_bytes,
+            profile: bool_option(options, "profile")?,
+            typed_cells: bool_option(options, "typedCells")?,
+            coerce_affinity: bool_option(options, "coerceAffinity")?,
+            strict_params: bool_option(options, "strictParams")?.unwrap_or(false),
+        })
+    }
+
+    /// The subset of options the worker needs, or `None` when all are unset.
+    pub(crate) fn worker_options(&self) -> Result<Option<Object>, SQLiteWasmDatabaseError> {
+        let options = Object::new();
+        let mut any = false;
+        if let Some(timeout_ms) = self.timeout_ms {
+            Reflect::set(
+                &options,
+                &JsValue::from_str("timeoutMs"),
+                &JsValue::from_f64(timeout_ms),
+            )?;
+            any = true;
+        }
+        any |= set_bool_option(&options, "dealiasColumns", self.dealias_columns)?;
+        any |= set_bool_option(&options, "returnRowids", self.return_rowids)?;
+        any |= set_bool_option(&options, "returnResultCode", self.return_result_code)?;
+        any |= set_bool_option(&options, "returnNotices", self.return_notices)?;
+        any |= set_bool_option(&options, "returnExpandedSql", self.return_expanded_sql)?;
+        any |= set_bool_option(
+            &options,
+            "returnStatementCounts",
+            self.return_statement_counts,
+        )?;
+        any |= set_bool_option(&options, "allowMultiple", self.allow_multiple)?;
+        any |= set_bool_option(&options, "profile", self.profile)?;
+        any |= set_bool_option(&options, "typedCells", self.typed_cells)?;
+        any |= set_bool_option(&options, "coerceAffinity", self.coerce_affinity)?;
+        if let Some(max_cell_bytes) = self.max_cell_bytes {
+            Reflect::set(
+                &options,
+                &JsValue::from_str("maxCellBytes"),
+                &JsValue::from_f64(max_cell_bytes as f64),
+            )?;
+            any = true;
+        }
+        if let Some(budget_ms) = self.serialize_budget_ms {
+            Reflect::set(
+                &options,
+                &JsValue::from_str("serializeBudgetMs"),
+                &JsValue::from_f64(budget_ms),
+            )?;
+            any = true;
+        }
+        if let Some(max_result_bytes) = self.max_result_bytes {
+            Reflect::set(
+                &options,
+                &JsValue::from_str("maxResultBytes"),
+                &JsValue::from_f64(max_result_bytes as f64),
+            )?;
+            any = true;
+        }
+        Ok(any.then_some(options))
+    }
+}
+
+#[cfg(all(test, target_family = "wasm"))]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn missing_options_use_defaults() {
+        let options = QueryOptions::from_js(None).expect("defaults");
+        assert!(options.signal.is_none());
+
+        let empty = Object::new();
+        let options = QueryOptions::from_js(Some(&empty)).expect("empty object");
+        assert!(options.signal.is_none());
+        assert!(options.timeout_ms.is_none());
+        assert!(options.worker_options().expect("worker options").is_none());
+    }
+
+    #[wasm_bindgen_test]
+    fn rejects_non_signal_values() {
+        let options = Object::new();
+        let _ = Reflect::set(
+            &options,
+            &JsValue::from_str("signal"),
+            &JsValue::from_str("nope"),
+        );
+        assert!(QueryOptions::from_js(Some(&options)).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn parses_timeout_and_forwards_it_to_the_worker() {
+        let options = Object::new();
+        let _ = Reflect::set(
+            &options,
+            &JsValue::from_str("timeoutMs"),
+            &JsValue::from_f64(500.0),
+        );
+        let parsed = QueryOptions::from_js(Some(&options)).expect("valid timeout");
+        assert_eq!(parsed.timeout_ms, Some(500.0));
+
+        let worker = parsed
+            .worker_options()
+            .expect("worker options")
+            .expect("timeout is forwarded");
+        let forwarded = Reflect::get(&worker, &JsValue::from_str("timeoutMs")).unwrap();
+        assert_eq!(forwarded.as_f64(), Some(500.0));
+    }
+
+    #[wasm_bindgen_test]
+    fn forwards_dealias_columns_flag() {
+        let options = Object::new();
+        let _ = Reflect::set(
+            &options,
+            &JsValue::from_str("dealiasColumns"),
+            &JsValue::TRUE,
+        );
+        let parsed = QueryOptions::from_js(Some(&options)).expect("valid flag");
+        let worker = parsed
+            .worker_options()
+            .expect("worker options")
+            .expect("flag is forwarded");
+        let forwarded = Reflect::get(&worker, &JsValue::from_str("dealiasColumns")).unwrap();
+        assert_eq!(forwarded.as_bool(), Some(true));
+
+        let _ = Reflect::set(
+            &options,
+            &JsValue::from_str("dealiasColumns"),
+            &JsValue::from_str("yes"),
+        );
+        assert!(QueryOptions::from_js(Some(&options)).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn forwards_return_rowids_flag() {
+        let options = Object::new();
+        let _ = Reflect::set(&options, &JsValue::from_str("returnRowids"), &JsValue::TRUE);
+        let parsed = QueryOptions::from_js(Some(&options)).expect("valid flag");
+        assert_eq!(parsed.return_rowids, Some(true));
+        let worker = parsed
+            .worker_options()
+            .expect("worker options")
+            .expect("flag is forwarded");
+        let forwarded = Reflect::get(&worker, &JsValue::from_str("returnRowids")).unwrap();
+        assert_eq!(forwarded.as_bool(), Some(true));
+
+        let _ = Reflect::set(
+            &options,
+            &JsValue::from_str("returnRowids"),
+            &JsValue::from_f64(1.0),
+        );
+        assert!(QueryOptions::from_js(Some(&options)).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn parses_connection_options() {
+        assert_eq!(
+            ConnectionOptions::from_js(None).expect("defaults"),
+            ConnectionOptions::default()
+        );
+
+        let options = Object::new();
+        let _ = Reflect::set(
+            &options,
+            &JsValue::from_str("customFunctions"),
+            &JsValue::FALSE,
+        );
+        let parsed = ConnectionOptions::from_js(Some(&options)).expect("valid flag");
+        assert_eq!(parsed.custom_functions, Some(vec![]));
+
+        let _ = Reflect::set(
+            &options,
+            &JsValue::from_str("customFunctions"),
+            &JsValue::TRUE,
+        );
+        let parsed = ConnectionOptions::from_js(Some(&options)).expect("valid flag");
+        assert_eq!(parsed.custom_functions, None);
+
+        let names = Array::of1(&JsValue::from_str("BIGINT_SUM"));
+        let _ = Reflect::set(&options, &JsValue::from_str("customFunctions"), &names);
+        let parsed = ConnectionOptions::from_js(Some(&options)).expect("valid list");
+        assert_eq!(
+            parsed.custom_functions,
+            Some(vec!["BIGINT_SUM".to_string()])
+        );
+
+        let names = Array::of2(&"bigint_sum".into(), &"NOT_A_FUNCTION".into());
+        let _ = Reflect::set(&options, &JsValue::from_str("customFunctions"), &names);
+        assert!(ConnectionOptions::from_js(Some(&options)).is_err());
+
+        names.set(1, JsValue::from_f64(1.0));
+        let _ = Reflect::set(&options, &JsValue::from_str("customFunctions"), &names);
+        assert!(ConnectionOptions::from_js(Some(&options)).is_err());
+
+        let options = Object::new();
+        let _ = Reflect::set(
+            &options,
+            &JsValue::from_str("maxPendingQueries"),
+            &JsValue::from_f64(8.0),
+        );
+        let parsed = ConnectionOptions::from_js(Some(&options)).expect("valid cap");
+        assert_eq!(parsed.max_pending_queries, Some(8));
+        for invalid in [JsValue::from_f64(0.0), JsValue::from_f64(1.5), "8".into()] {
+            let _ = Reflect::set(&options, &JsValue::from_str("maxPendingQueries"), &invalid);
+            assert!(ConnectionOptions::from_js(Some(&options)).is_err());
+        }
+
+        let options = Object::new();
+        let pragmas = Array::of1(&JsValue::from_str("foreign_keys = ON"));
+        let _ = Reflect::set(&options, &JsValue::from_str("pragmas"), &pragmas);
+        let parsed = ConnectionOptions::from_js(Some(&options)).expect("valid pragmas");
+        assert_eq!(parsed.pragmas, vec!["foreign_keys = ON".to_string()]);
+        pragmas.push(&JsValue::from_f64(1.0));
+        assert!(ConnectionOptions::from_js(Some(&options)).is_err());
+
+        let options = Object::new();
+        let _ = Reflect::set(&options, &JsValue::from_str("pragmas"), &Array::new());
+        let _ = Reflect::set(
+            &options,
+            &JsValue::from_str("synchronous"),
+            &JsValue::from_str("normal"),
+        );
+        let parsed = ConnectionOptions::from_js(Some(&options)).expect("valid level");
+        assert_eq!(parsed.synchronous.as_deref(), Some("NORMAL"));
+        assert_eq!(
+            parsed.open_pragmas(),
+            vec!["synchronous = NORMAL".to_string()]
+        );
+        for invalid in ["sometimes".into(), JsValue::from_f64(1.0)] {
+            let _ = Reflect::set(&options, &JsValue::from_str("synchronous"), &invalid);
+            assert!(ConnectionOptions::from_js(Some(&options)).is_err());
+        }
+
+        let options = Object::new();
+        let _ = Reflect::set(
+            &options,
+            &JsValue::from_str("namespace"),
+            &JsValue::from_str(" app-one "),
+        );
+        let parsed = ConnectionOptions::from_js(Some(&options)).expect("valid namespace");
+        assert_eq!(parsed.namespace.as_deref(), Some("app-one"));
+        for invalid in [
+            " ".into(),
+            "app one".into(),
+            "app:one".into(),
+            "app/one".into(),
+            JsValue::from_f64(1.0),
+        ] {
+            let _ = Reflect::set(&options, &JsValue::from_str("namespace"), &invalid);
+            assert!(ConnectionOptions::from_js(Some(&options)).is_err());
+        }
+
+        let options = Object::new();
+        let _ = Reflect::set(
+            &options,
+            &JsValue::from_str("announceIntervalMs"),
+            &JsValue::from_f64(250.0),
+        );
+        let parsed = ConnectionOptions::from_js(Some(&options)).expect("valid interval");
+        assert_eq!(parsed.announce_interval_ms, Some(250.0));
+        for invalid in [
+            JsValue::from_f64(-1.0),
+            JsValue::from_f64(f64::NAN),
+            "5".into(),
+        ] {
+            let _ = Reflect::set(&options, &JsValue::from_str("announceIntervalMs"), &invalid);
+            assert!(ConnectionOptions::from_js(Some(&options)).is_err());
+        }
+
+        let options = Object::new();
+        let _ = Reflect::set(
+            &options,
+            &JsValue::from_str("followerRetries"),
+            &JsValue::from_f64(0.0),
+        );
+        let parsed = ConnectionOptions::from_js(Some(&options)).expect("valid retries");
+        assert_eq!(parsed.follower_retries, Some(0));
+        for invalid in [JsValue::from_f64(-1.0), JsValue::from_f64(1.5), "2".into()] {
+            let _ = Reflect::set(&options, &JsValue::from_str("followerRetries"), &invalid);
+            assert!(ConnectionOptions::from_js(Some(&options)).is_err());
+        }
+
+        let options = Object::new();
+        let _ = Reflect::set(
+            &options,
+            &JsValue::from_str("maxFollowerQueries"),
+            &JsValue::from_f64(16.0),
+        );
+        let parsed = ConnectionOptions::from_js(Some(&options)).expect("valid cap");
+        assert_eq!(parsed.max_follower_queries, Some(16));
+        for invalid in [JsValue::from_f64(0.0), JsValue::from_f64(2.5), "16".into()] {
+            let _ = Reflect::set(&options, &JsValue::from_str("maxFollowerQueries"), &invalid);
+            assert!(ConnectionOptions::from_js(Some(&options)).is_err());
+        }
+
+        let options = Object::new();
+        let _ = Reflect::set(
+            &options,
+            &JsValue::from_str("maxQueueDepth"),
+            &JsValue::from_f64(4096.0),
+        );
+        let parsed = ConnectionOptions::from_js(Some(&options)).expect("valid depth");
+        assert_eq!(parsed.max_queue_depth, Some(4096));
+        for invalid in [
+            JsValue::from_f64(0.0),
+            JsValue::from_f64(-3.0),
+            "4096".into(),
+        ] {
+            let _ = Reflect::set(&options, &JsValue::from_str("maxQueueDepth"), &invalid);
+            assert!(ConnectionOptions::from_js(Some(&options)).is_err());
+        }
+
+        let options = Object::new();
+        let _ = Reflect::set(&options, &JsValue::from_str("debug"), &JsValue::TRUE);
+        let parsed = ConnectionOptions::from_js(Some(&options)).expect("valid debug flag");
+        assert!(parsed.debug);
+        let _ = Reflect::set(
+            &options,
+            &JsValue::from_str("debug"),
+            &JsValue::from_f64(1.0),
+        );
+        assert!(ConnectionOptions::from_js(Some(&options)).is_err());
+
+        let options = Object::new();
+        let _ = Reflect::set(
+            &options,
+            &JsValue::from_str("allowMemoryFallback"),
+            &JsValue::TRUE,
+        );
+        let parsed = ConnectionOptions::from_js(Some(&options)).expect("valid fallback flag");
+        assert!(parsed.allow_memory_fallback);
+        let _ = Reflect::set(
+            &options,
+            &JsValue::from_str("allowMemoryFallback"),
+            &JsValue::from_str("yes"),
+        );
+        assert!(ConnectionOptions::from_js(Some(&options)).is_err());
+
+        let options = Object::new();
+        let _ = Reflect::set(&options, &JsValue::from_str("shared"), &JsValue::FALSE);
+        let first = ConnectionOptions::from_js(Some(&options)).expect("valid shared flag");
+        let second = ConnectionOptions::from_js(Some(&options)).expect("valid shared flag");
+        assert!(first.isolated);
+        assert!(first.namespace.as_deref().unwrap().starts_with("private-"));
+        assert_ne!(first.namespace, second.namespace);
+        let _ = Reflect::set(
+            &options,
+            &JsValue::from_str("namespace"),
+            &JsValue::from_str("app-one"),
+        );
+        let parsed = ConnectionOptions::from_js(Some(&options)).expect("valid shared flag");
+        assert!(parsed
+            .namespace
+            .as_deref()
+            .unwrap()
+            .starts_with("app-one-private-"));
+        let _ = Reflect::set(&options, &JsValue::from_str("shared"), &JsValue::TRUE);
+        let parsed = ConnectionOptions::from_js(Some(&options)).expect("valid shared flag");
+        assert!(!parsed.isolated);
+        assert_eq!(parsed.namespace.as_deref(), Some("app-one"));
+
+        let options = Object::new();
+        let callback = Function::new_no_args("");
+        let _ = Reflect::set(&options, &JsValue::from_str("onProgress"), &callback);
+        let parsed = ConnectionOptions::from_js(Some(&options)).expect("valid callback");
+        assert_eq!(parsed.on_progress, Some(callback));
+        let _ = Reflect::set(&options, &JsValue::from_str("onProgress"), &JsValue::TRUE);
+        assert!(ConnectionOptions::from_js(Some(&options)).is_err());
+
+        let _ = Reflect::set(&options, &JsValue::from_str("onProgress"), &JsValue::NULL);
+        let _ = Reflect::set(
+            &options,
+            &JsValue::from_str("customFunctions"),
+            &JsValue::from_str("no"),
+        );
+        assert!(ConnectionOptions::from_js(Some(&options)).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn rejects_non_positive_timeouts() {
+        for value in [JsValue::from_f64(0.0), JsValue::from_f64(-5.0), "10".into()] {
+            let options = Object::new();
+            let _ = Reflect::set(&options, &JsValue::from_str("timeoutMs"), &value);
+            assert!(QueryOptions::from_js(Some(&options)).is_err());
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn forwards_max_cell_bytes() {
+        let options = Object::new();
+        let _ = Reflect::set(
+            &options,
+            &JsValue::from_str("maxCellBytes"),
+            &JsValue::from_f64(1024.0),
+        );
+        let parsed = QueryOptions::from_js(Some(&options)).expect("valid limit");
+        let worker = parsed
+            .worker_options()
+            .expect("worker options")
+            .expect("limit is forwarded");
+        let forwarded = Reflect::get(&worker, &JsValue::from_str("maxCellBytes")).unwrap();
+        assert_eq!(forwarded.as_f64(), Some(1024.0));
+
+        for value in [JsValue::from_f64(-1.0), JsValue::from_f64(1.5), "10".into()] {
+            let _ = Reflect::set(&options, &JsValue::from_str("maxCellBytes"), &value);
+            assert!(QueryOptions::from_js(Some(&options)).is_err());
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn forwards_max_result_bytes() {
+        let options = Object::new();
+        let _ = Reflect::set(
+            &options,
+            &JsValue::from_str("maxResultBytes"),
+            &JsValue::from_f64(4096.0),
+        );
+        let parsed = QueryOptions::from_js(Some(&options)).expect("valid limit");
+        let worker = parsed
+            .worker_options()
+            .expect("worker options")
+            .expect("limit is forwarded");
+        let forwarded = Reflect::get(&worker, &JsValue::from_str("maxResultBytes")).unwrap();
+        assert_eq!(forwarded.as_f64(), Some(4096.0));
+
+        for value in [JsValue::from_f64(0.0), JsValue::from_f64(1.5), "10".into()] {
+            let _ = Reflect::set(&options, &JsValue::from_str("maxResultBytes"), &value);
+            assert!(QueryOptions::from_js(Some(&options)).is_err());
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn forwards_serialize_budget() {
+        let options = Object::new();
+        let _ = Reflect::set(
+            &options,
+            &JsValue::from_str("serializeBudgetMs"),
+            &JsValue::from_f64(50.0),
+        );
+        let parsed = QueryOptions::from_js(Some(&options)).expect("valid budget");
+        let worker = parsed
+            .worker_options()
+            .expect("worker options")
+            .expect("budget is forwarded");
+        let forwarded = Reflect::get(&worker, &JsValue::from_str("serializeBudgetMs")).unwrap();
+        assert_eq!(forwarded.as_f64(), Some(50.0));
+
+        for value in [
+            JsValue::from_f64(-1.0),
+            JsValue::from_f64(f64::NAN),
+            "10".into(),
+        ] {
+            let _ = Reflect::set(&options, &JsValue::from_str("serializeBudgetMs"), &value);
+            assert!(QueryOptions::from_js(Some(&options)).is_err());
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn parses_stream_chunk_size() {
+        let defaults = StreamOptions::from_js(None).expect("defaults");
+        assert_eq!(defaults.chunk_size, DEFAULT_STREAM_CHUNK_SIZE);
+        assert_eq!(defaults.max_cell_bytes, None);
+
+        let options = Object::new();
+        let _ = Reflect::set(
+            &options,
+            &JsValue::from_str("chunkSize"),
+            &JsValue::from_f64(500.0),
+        );
+        let parsed = StreamOptions::from_js(Some(&options)).expect("valid chunk size");
+        assert_eq!(parsed.chunk_size, 500);
+
+        for value in [JsValue::from_f64(0.0), JsValue::from_f64(2.5), "10".into()] {
+            let _ = Reflect::set(&options, &JsValue::from_str("chunkSize"), &value);
+            assert!(StreamOptions::from_js(Some(&options)).is_err());
+        }
+
+        let options = Object::new();
+        let _ = Reflect::set(
+            &options,
+            &JsValue::from_str("maxCellBytes"),
+            &JsValue::from_f64(64.0),
+        );
+        let parsed = StreamOptions::from_js(Some(&options)).expect("valid maxCellBytes");
+        assert_eq!(parsed.max_cell_bytes, Some(64));
+        let _ = Reflect::set(
+            &options,
+            &JsValue::from_str("maxCellBytes"),
+            &JsValue::from_f64(-1.0),
+        );
+        assert!(StreamOptions::from_js(Some(&options)).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn insert_return_generated_defaults_to_false() {
+        assert!(
+            !InsertOptions::from_js(None)
+                .expect("defaults")
+                .return_generated
+        );
+
+        let options = Object::new();
+        let _ = Reflect::set(
+            &options,
+            &JsValue::from_str("returnGenerated"),
+            &JsValue::TRUE,
+        );
+        assert!(
+            InsertOptions::from_js(Some(&options))
+                .expect("valid")
+                .return_generated
+        );
+
+        let _ = Reflect::set(
+            &options,
+            &JsValue::from_str("returnGenerated"),
+            &"yes".into(),
+        );
+        assert!(InsertOptions::from_js(Some(&options)).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn table_exists_types_default_to_tables() {
+        let defaults = TableExistsOptions::from_js(None).expect("defaults");
+        assert_eq!(defaults.types, vec!["table".to_string()]);
+
+        let options = Object::new();
+        let _ = Reflect::set(
+            &options,
+            &JsValue::from_str("types"),
+            &Array::of2(&"table".into(), &"view".into()),
+        );
+        let parsed = TableExistsOptions::from_js(Some(&options)).expect("valid types");
+        assert_eq!(parsed.types, vec!["table".to_string(), "view".to_string()]);
+
+        for value in [
+            JsValue::from(Array::new()),
+            Array::of1(&"column".into()).into(),
+            "table".into(),
+        ] {
+            let _ = Reflect::set(&options, &JsValue::from_str("types"), &value);
+            assert!(TableExistsOptions::from_js(Some(&options)).is_err());
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn transaction_chunk_size_is_optional() {
+        let defaults = TransactionOptions::from_js(None).expect("defaults");
+        assert_eq!(defaults.chunk_size, None);
+
+        let options = Object::new();
+        let _ = Reflect::set(
+            &options,
+            &JsValue::from_str("chunkSize"),
+            &JsValue::from_f64(1000.0),
+        );
+        let parsed = TransactionOptions::from_js(Some(&options)).expect("valid chunk size");
+        assert_eq!(parsed.chunk_size, Some(1000));
+
+        let _ = Reflect::set(
+            &options,
+            &JsValue::from_str("chunkSize"),
+            &JsValue::from_f64(0.0),
+        );
+        assert!(TransactionOptions::from_js(Some(&options)).is_err());
+    }
+}
@@ -1,5 +1,8 @@
 use base64::Engine;
-use js_sys::{Array, ArrayBuffer, BigInt, Object, Reflect, Uint8Array};
+use js_sys::{
+    Array, ArrayBuffer, BigInt, Float32Array, Float64Array, Int16Array, Int32Array, Int8Array,
+    Object, Reflect, Uint16Array, Uint32Array, Uint8Array, JSON,
+};
 use wasm_bindgen::prelude::*;
 
 use crate::errors::SQLiteWasmDatabaseError;
@@ -13,6 +16,21 @@ pub(crate) fn normalize_params_js(params: &JsValue) -> Result<Array, SQLiteWasmD
     })
 }
 
+/// Like `normalize_params_js`, but binds a single value that is not an
+/// array as the only parameter, so `query(sql, 42)` works for one `?`.
+pub(crate) fn normalize_scalar_or_params_js(
+    params: &JsValue,
+) -> Result<Array, SQLiteWasmDatabaseError> {
+    if params.is_undefined()
+        || params.is_null()
+        || Array::is_array(params)
+        || is_typed_numeric_array(params)
+    {
+        return normalize_params_js(params);
+    }
+    normalize_params_js(&Array::of1(params).into())
+}
+
 fn ensure_array(params: &JsValue) -> Result<Array, SQLiteWasmDatabaseError> {
     if params.is_undefined() || params.is_null() {
         return Ok(Array::new());
@@ -20,6 +38,9 @@ fn ensure_array(params: &JsValue) -> Result<Array, SQLiteWasmDatabaseError> {
     if Array::is_array(params) {
         return Ok(params.clone().unchecked_into());
     }
+    if let Some(typed) = typed_numeric_params(params)? {
+        return Ok(typed);
+    }
     Err(SQLiteWasmDatabaseError::JsError(JsValue::from_str(
         "params must be an array",
     )))
@@ -39,6 +60,9 @@ fn normalize_one_param(v: &JsValue, index: u32) -> Result<JsValue, SQLiteWasmDat
         let typed = Uint8Array::new(&buf);
         return encode_binary_to_obj(typed.to_vec());
     }
+    if Array::is_array(v) {
+        return encode_json_to_obj(v, index);
+    }
     if let Some(n) = v.as_f64() {
         if !n.is_finite() {
             return Err(SQLiteWasmDatabaseError::JsError(JsValue::from_str(
@@ -56,6 +80,12 @@ fn normalize_one_param(v: &JsValue, index: u32) -> Result<JsValue, SQLiteWasmDat
     if let Some(s) = v.as_string() {
         return Ok(JsValue::from_str(&s));
     }
+    if let Some(text) = text_blob_value(v, index)? {
+        return encode_text_blob_to_obj(&text);
+    }
+    if let Some(tagged) = numeric_tag_value(v, index)? {
+        return Ok(tagged);
+    }
     Err(SQLiteWasmDatabaseError::JsError(JsValue::from_str(
         &format!("Unsupported parameter type at position {}", index + 1),
     )))
@@ -91,11 +121,162 @@ fn encode_binary_to_obj(bytes: Vec<u8>) -> Result<JsValue, SQLiteWasmDatabaseErr
     Ok(obj.into())
 }
 
+/// The string inside a `{ __type: "text_blob", value }` parameter, which is
+/// stored as the raw UTF-8 bytes of `value` rather than as TEXT.
+fn text_blob_value(v: &JsValue, index: u32) -> Result<Option<String>, SQLiteWasmDatabaseError> {
+    if !v.is_object() {
+        return Ok(None);
+    }
+    let tag = Reflect::get(v, &JsValue::from_str("__type"))?;
+    if tag.as_string().as_deref() != Some("text_blob") {
+        return Ok(None);
+    }
+    Reflect::get(v, &JsValue::from_str("value"))?
+        .as_string()
+        .map(Some)
+        .ok_or_else(|| {
+            SQLiteWasmDatabaseError::JsError(JsValue::from_str(&format!(
+                "text_blob parameter at position {} requires a string value",
+                index + 1
+            )))
+        })
+}
+
+/// Integer typed arrays bind every element as INTEGER and float typed
+/// arrays as REAL, whatever the element's value, by tagging each element
+/// like an explicit `int`/`double` parameter. `Uint8Array` is not one of
+/// them: it is binary data and binds as a single BLOB.
+fn typed_numeric_params(params: &JsValue) -> Result<Option<Array>, SQLiteWasmDatabaseError> {
+    let (values, tag): (Vec<f64>, &str) = if let Some(a) = params.dyn_ref::<Int8Array>() {
+        (a.to_vec().into_iter().map(f64::from).collect(), "int")
+    } else if let Some(a) = params.dyn_ref::<Int16Array>() {
+        (a.to_vec().into_iter().map(f64::from).collect(), "int")
+    } else if let Some(a) = params.dyn_ref::<Int32Array>() {
+        (a.to_vec().into_iter().map(f64::from).collect(), "int")
+    } else if let Some(a) = params.dyn_ref::<Uint16Array>() {
+        (a.to_vec().into_iter().map(f64::from).collect(), "int")
+    } else if let Some(a) = params.dyn_ref::<Uint32Array>() {
+        (a.to_vec().into_iter().map(f64::from).collect(), "int")
+    } else if let Some(a) = params.dyn_ref::<Float32Array>() {
+        (a.to_vec().into_iter().map(f64::from).collect(), "double")
+    } else if let Some(a) = params.dyn_ref::<Float64Array>() {
+        (a.to_vec(), "double")
+    } else {
+        return Ok(None);
+    };
+    values
+        .into_iter()
+        .try_fold(Array::new(), |out, n| {
+            out.push(&numeric_tag_object(tag, &JsValue::from_f64(n))?);
+            Ok(out)
+        })
+        .map(Some)
+}
+
+fn is_typed_numeric_array(v: &JsValue) -> bool {
+    v.is_instance_of::<Int8Array>()
+        || v.is_instance_of::<Int16Array>()
+        || v.is_instance_of::<Int32Array>()
+        || v.is_instance_of::<Uint16Array>()
+        || v.is_instance_of::<Uint32Array>()
+        || v.is_instance_of::<Float32Array>()
+        || v.is_instance_of::<Float64Array>()
+}
+
+/// A `{ __type: "int", value }` or `{ __type: "double", value }` parameter,
+/// which forces INTEGER or REAL storage instead of inferring it from the
+/// number. `int` takes a decimal string (for values past 2^53) or a safe
+/// integer; `double` takes a finite number.
+fn numeric_tag_value(v: &JsValue, index: u32) -> Result<Option<JsValue>, SQLiteWasmDatabaseError> {
+    if !v.is_object() {
+        return Ok(None);
+    }
+    let tag = Reflect::get(v, &JsValue::from_str("__type"))?.as_string();
+    let value = Reflect::get(v, &JsValue::from_str("value"))?;
+    let invalid = |kind: &str| {
+        SQLiteWasmDatabaseError::JsError(JsValue::from_str(&format!(
+            "Invalid {} parameter at position {}",
+            kind,
+            index + 1
+        )))
+    };
+    match tag.as_deref() {
+        Some("int") => {
+            let digits = if let Some(s) = value.as_string() {
+                s.trim().parse::<i64>().ok().map(|n| n.to_string())
+            } else {
+                value
+                    .as_f64()
+                    .filter(|n| n.fract() == 0.0 && n.abs() <= MAX_SAFE_INTEGER)
+                    .map(|n| (n as i64).to_string())
+            };
+            let digits = digits.ok_or_else(|| invalid("int"))?;
+            numeric_tag_object("int", &JsValue::from_str(&digits)).map(Some)
+        }
+        Some("double") => {
+            let n = value
+                .as_f64()
+                .filter(|n| n.is_finite())
+                .ok_or_else(|| invalid("double"))?;
+            numeric_tag_object("double", &JsValue::from_f64(n)).map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+fn numeric_tag_object(tag: &str, value: &JsValue) -> Result<JsValue, SQLiteWasmDatabaseError> {
+    let obj = Object::new();
+    Reflect::set(&obj, &JsValue::from_str("__type"), &JsValue::from_str(tag))
+        .map_err(SQLiteWasmDatabaseError::from)?;
+    Reflect::set(&obj, &JsValue::from_str("value"), value)
+        .map_err(SQLiteWasmDatabaseError::from)?;
+    Ok(obj.into())
+}
+
+fn encode_text_blob_to_obj(text: &str) -> Result<JsValue, SQLiteWasmDatabaseError> {
+    let obj = Object::new();
+    Reflect::set(
+        &obj,
+        &JsValue::from_str("__type"),
+        &JsValue::from_str("text_blob"),
+    )
+    .map_err(SQLiteWasmDatabaseError::from)?;
+    Reflect::set(&obj, &JsValue::from_str("value"), &JsValue::from_str(text))
+        .map_err(SQLiteWasmDatabaseError::from)?;
+    Ok(obj.into())
+}
+
+/// Arrays are bound as their JSON text so they can be stored in JSON columns
+/// and read back with SQLite's JSON functions.
+fn encode_json_to_obj(v: &JsValue, index: u32) -> Result<JsValue, SQLiteWasmDatabaseError> {
+    let text = JSON::stringify(v)
+        .ok()
+        .and_then(|s| s.as_string())
+        .ok_or_else(|| {
+            SQLiteWasmDatabaseError::JsError(JsValue::from_str(&format!(
+                "Array parameter at position {} is not JSON-serializable",
+                index + 1
+            )))
+        })?;
+    let obj = Object::new();
+    Reflect::set(
+        &obj,
+        &JsValue::from_str("__type"),
+        &JsValue::from_str("json"),
+    )
+    .map_err(SQLiteWasmDatabaseError::from)?;
+    Reflect::set(&obj, &JsValue::from_str("value"), &JsValue::from_str(&text))
+        .map_err(SQLiteWasmDatabaseError::from)?;
+    Ok(obj.into())
+}
+
 #[cfg(all(test, target_family = "wasm"))]
 mod tests {
     use super::*;
     use base64::Engine;
-    use js_sys::{Array, ArrayBuffer, BigInt, Uint8Array};
+    use js_sys::{Array, ArrayBuffer, BigInt, Float64Array, Int32Array, Uint8Array};
     use wasm_bindgen_test::*;
 
     wasm_bindgen_test_configure!(run_in_browser);
@@ -121,6 +302,20 @@ mod tests {
         }
     }
 
+    #[wasm_bindgen_test]
+    fn scalar_params_are_wrapped_into_one_element_arrays() {
+        let wrapped = normalize_scalar_or_params_js(&JsValue::from_f64(42.0)).expect("bare number");
+        assert_eq!(wrapped.length(), 1);
+        assert_eq!(wrapped.get(0).as_f64(), Some(42.0));
+
+        let arr = Array::of2(&JsValue::from_f64(1.0), &JsValue::from_str("a"));
+        let kept = normalize_scalar_or_params_js(&arr.into()).expect("array");
+        assert_eq!(kept.length(), 2);
+
+        let empty = normalize_scalar_or_params_js(&JsValue::NULL).expect("null");
+        assert_eq!(empty.length(), 0);
+    }
+
     #[wasm_bindgen_test]
     fn normalize_one_param_rejects_non_finite_numbers() {
         assert!(normalize_one_param(&JsValue::from_f64(f64::NAN), 0).is_err());
@@ -157,6 +352,135 @@ mod tests {
         assert_eq!(base64_val, expected);
     }
 
+    #[wasm_bindgen_test]
+    fn array_params_are_tagged_as_json() {
+        let inner = Array::new();
+        inner.push(&JsValue::from_f64(1.0));
+        inner.push(&JsValue::from_f64(2.0));
+        inner.push(&JsValue::from_f64(3.0));
+
+        let encoded = normalize_one_param(&inner.into(), 0).expect("arrays encode as JSON");
+        let ty = Reflect::get(&encoded, &JsValue::from_str("__type"))
+            .unwrap()
+            .as_string();
+        assert_eq!(ty.as_deref(), Some("json"));
+        let val = Reflect::get(&encoded, &JsValue::from_str("value"))
+            .unwrap()
+            .as_string();
+        assert_eq!(val.as_deref(), Some("[1,2,3]"));
+
+        let with_bigint = Array::new();
+        with_bigint.push(&BigInt::from(1u8).into());
+        assert!(normalize_one_param(&with_bigint.into(), 0).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn text_blob_params_are_forwarded() {
+        let tagged = Object::new();
+        let _ = Reflect::set(
+            &tagged,
+            &JsValue::from_str("__type"),
+            &JsValue::from_str("text_blob"),
+        );
+        let _ = Reflect::set(&tagged, &JsValue::from_str("value"), &"héllo".into());
+
+        let encoded = normalize_one_param(&tagged.clone().into(), 0).expect("text_blob encodes");
+        let ty = Reflect::get(&encoded, &JsValue::from_str("__type"))
+            .unwrap()
+            .as_string();
+        assert_eq!(ty.as_deref(), Some("text_blob"));
+        let val = Reflect::get(&encoded, &JsValue::from_str("value"))
+            .unwrap()
+            .as_string();
+        assert_eq!(val.as_deref(), Some("héllo"));
+
+        let _ = Reflect::set(
+            &tagged,
+            &JsValue::from_str("value"),
+            &JsValue::from_f64(1.0),
+        );
+        assert!(normalize_one_param(&tagged.into(), 0).is_err());
+        assert!(normalize_one_param(&Object::new().into(), 0).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn explicit_int_and_double_params_are_forwarded() {
+        let tagged = |tag: &str, value: JsValue| {
+            let obj = Object::new();
+            let _ = Reflect::set(&obj, &JsValue::from_str("__type"), &tag.into());
+            let _ = Reflect::set(&obj, &JsValue::from_str("value"), &value);
+            JsValue::from(obj)
+        };
+        let field = |v: &JsValue, key: &str| Reflect::get(v, &JsValue::from_str(key)).unwrap();
+
+        let int = normalize_one_param(&tagged("int", " 9007199254740993 ".into()), 0).unwrap();
+        assert_eq!(field(&int, "__type").as_string().as_deref(), Some("int"));
+        assert_eq!(
+            field(&int, "value").as_string().as_deref(),
+            Some("9007199254740993")
+        );
+        let int = normalize_one_param(&tagged("int", JsValue::from_f64(42.0)), 0).unwrap();
+        assert_eq!(field(&int, "value").as_string().as_deref(), Some("42"));
+
+        let double = normalize_one_param(&tagged("double", JsValue::from_f64(3.0)), 0).unwrap();
+        assert_eq!(
+            field(&double, "__type").as_string().as_deref(),
+            Some("double")
+        );
+        assert_eq!(field(&double, "value").as_f64(), Some(3.0));
+
+        for bad in [
+            tagged("int", JsValue::from_f64(1.5)),
+            tagged("int", JsValue::from_f64(2f64.powi(60))),
+            tagged("int", "12abc".into()),
+            tagged("double", JsValue::from_f64(f64::NAN)),
+            tagged("double", "3".into()),
+        ] {
+            assert!(normalize_one_param(&bad, 1).is_err());
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn typed_numeric_arrays_bind_by_element_type() {
+        let field = |v: &JsValue, key: &str| Reflect::get(v, &JsValue::from_str(key)).unwrap();
+
+        let ints = Int32Array::new_with_length(2);
+        ints.copy_from(&[-7, 12]);
+        let normalized = normalize_params_js(&ints.into()).expect("Int32Array params");
+        assert_eq!(normalized.length(), 2);
+        assert_eq!(
+            field(&normalized.get(0), "__type").as_string().as_deref(),
+            Some("int")
+        );
+        assert_eq!(
+            field(&normalized.get(1), "value").as_string().as_deref(),
+            Some("12")
+        );
+
+        let doubles = Float64Array::new_with_length(1);
+        doubles.copy_from(&[2.0]);
+        let normalized =
+            normalize_scalar_or_params_js(&doubles.into()).expect("Float64Array params");
+        assert_eq!(normalized.length(), 1);
+        assert_eq!(
+            field(&normalized.get(0), "__type").as_string().as_deref(),
+            Some("double")
+        );
+        assert_eq!(field(&normalized.get(0), "value").as_f64(), Some(2.0));
+
+        let nan = Float64Array::new_with_length(1);
+        nan.copy_from(&[f64::NAN]);
+        assert!(normalize_params_js(&nan.into()).is_err());
+
+        let bytes = Uint8Array::new_with_length(2);
+        assert!(ensure_array(&bytes.clone().into()).is_err());
+        let wrapped = normalize_scalar_or_params_js(&bytes.into()).expect("bytes bind as a blob");
+        assert_eq!(
+            field(&wrapped.get(0), "__type").as_string().as_deref(),
+            Some("blob")
+        );
+    }
+
     #[wasm_bindgen_test]
     fn normalize_params_js_handles_arrays() {
         let arr = Array::new();
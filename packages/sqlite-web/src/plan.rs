@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+
+use crate::errors::SQLiteWasmDatabaseError;
+
+/// One row of the plan reported by `explain()`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct QueryPlanStep {
+    /// Identifies the step so nested steps can name it as their `parent`.
+    pub id: i64,
+    /// `id` of the enclosing step, `0` at the top level.
+    pub parent: i64,
+    /// SQLite's description, such as `SEARCH t USING INDEX t_a (a=?)`.
+    pub detail: String,
+}
+
+impl QueryPlanStep {
+    pub(crate) fn from_worker_result(result: &str) -> Result<Vec<Self>, SQLiteWasmDatabaseError> {
+        serde_json::from_str(result).map_err(|e| {
+            SQLiteWasmDatabaseError::JsError(wasm_bindgen::JsValue::from_str(&format!(
+                "Invalid explain response from worker: {e}"
+            )))
+        })
+    }
+}
+
+#[cfg(all(test, target_family = "wasm"))]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn parses_worker_explain_result() {
+        let steps = QueryPlanStep::from_worker_result(
+            r#"[{"id":3,"parent":0,"detail":"SEARCH t USING INDEX t_a (a=?)"}]"#,
+        )
+        .expect("valid plan");
+        assert_eq!(
+            steps,
+            vec![QueryPlanStep {
+                id: 3,
+                parent: 0,
+                detail: "SEARCH t USING INDEX t_a (a=?)".to_string(),
+            }]
+        );
+
+        assert!(QueryPlanStep::from_worker_result("not json").is_err());
+    }
+}
@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+
+use crate::errors::SQLiteWasmDatabaseError;
+
+/// Leadership snapshot reported by `role()`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LeadershipInfo {
+    /// `"leader"` when this connection's worker owns the database, otherwise `"follower"`.
+    pub role: String,
+    /// Worker id of the current leader, once one is known.
+    pub leader_id: Option<String>,
+}
+
+impl LeadershipInfo {
+    pub(crate) fn from_worker_result(result: &str) -> Result<Self, SQLiteWasmDatabaseError> {
+        serde_json::from_str(result).map_err(|e| {
+            SQLiteWasmDatabaseError::JsError(wasm_bindgen::JsValue::from_str(&format!(
+                "Invalid role response from worker: {e}"
+            )))
+        })
+    }
+}
+
+#[cfg(all(test, target_family = "wasm"))]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn parses_worker_role_result() {
+        let info = LeadershipInfo::from_worker_result(r#"{"role":"follower","leaderId":"abc"}"#)
+            .expect("valid role");
+        assert_eq!(info.role, "follower");
+        assert_eq!(info.leader_id.as_deref(), Some("abc"));
+
+        let unknown = LeadershipInfo::from_worker_result(r#"{"role":"leader","leaderId":null}"#)
+            .expect("valid role");
+        assert_eq!(unknown.leader_id, None);
+
+        assert!(LeadershipInfo::from_worker_result("not json").is_err());
+    }
+}
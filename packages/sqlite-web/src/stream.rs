@@ -0,0 +1,214 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use js_sys::{Array, Function, Object, Promise, Reflect, Symbol, JSON};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::future_to_promise;
+use wasm_bindgen_utils::prelude::*;
+
+use crate::db::SQLiteWasmDatabase;
+use crate::errors::SQLiteWasmDatabaseError;
+use crate::options::StreamOptions;
+use crate::params::normalize_params_js;
+
+#[wasm_export]
+impl SQLiteWasmDatabase {
+    /// Run a row-returning query and read its rows a chunk at a time:
+    ///
+    /// `for await (const rows of db.queryStream(sql, params, { chunkSize: 500 }).value!)`
+    ///
+    /// Each chunk is an array of up to `options.chunkSize` row objects
+    /// (default 256), with cells truncated to `options.maxCellBytes` as in
+    /// `query()`. The statement stays prepared on the worker between chunks
+    /// and is finalized once the rows run out, an error is thrown, the loop
+    /// exits early, or the stream goes 30 seconds without being read; a
+    /// stream read after that fails. Until then it holds a read transaction,
+    /// so `vacuum()` and other operations that need an idle connection fail.
+    #[wasm_export(
+        js_name = "queryStream",
+        preserve_js_class,
+        unchecked_return_type = "AsyncIterableIterator<Record<string, unknown>[]>"
+    )]
+    pub fn query_stream(
+        &self,
+        sql: String,
+        #[wasm_export(
+            unchecked_param_type = "unknown[] | Int8Array | Int16Array | Int32Array | Uint16Array | Uint32Array | Float32Array | Float64Array | null | void"
+        )]
+        params: JsValue,
+        options: Option<Object>,
+    ) -> Result<JsValue, SQLiteWasmDatabaseError> {
+        let params = normalize_params_js(&params)?;
+        let options = StreamOptions::from_js(options.as_ref())?;
+        let stream = JsValue::from(QueryStream {
+            state: Rc::new(StreamState {
+                db: self.clone(),
+                sql,
+                params,
+                chunk_size: options.chunk_size,
+                max_cell_bytes: options.max_cell_bytes,
+                cursor: Cell::new(None),
+                done: Cell::new(false),
+            }),
+        });
+        Reflect::set(
+            &stream,
+            &Symbol::async_iterator(),
+            &Function::new_no_args("return this"),
+        )?;
+        Ok(stream)
+    }
+}
+
+/// Async iterator returned by `queryStream()`.
+#[wasm_bindgen]
+pub struct QueryStream {
+    state: Rc<StreamState>,
+}
+
+#[wasm_bindgen]
+impl QueryStream {
+    /// Resolve with the next chunk of rows, or `{ done: true }` once the
+    /// query has no more.
+    #[wasm_bindgen(unchecked_return_type = "Promise<IteratorResult<Record<string, unknown>[]>>")]
+    pub fn next(&self) -> Promise {
+        let state = Rc::clone(&self.state);
+        future_to_promise(async move { state.next_chunk().await.map_err(JsValue::from) })
+    }
+
+    /// Stop early and finalize the statement; `for await` calls this when
+    /// the loop body breaks or throws.
+    #[wasm_bindgen(
+        js_name = "return",
+        unchecked_return_type = "Promise<IteratorResult<Record<string, unknown>[]>>"
+    )]
+    pub fn close(&self) -> Promise {
+        let state = Rc::clone(&self.state);
+        future_to_promise(async move { state.close().await.map_err(JsValue::from) })
+    }
+}
+
+struct StreamState {
+    db: SQLiteWasmDatabase,
+    sql: String,
+    params: Array,
+    chunk_size: u32,
+    max_cell_bytes: Option<u32>,
+    /// Worker-side cursor id, set once the statement is prepared.
+    cursor: Cell<Option<u32>>,
+    done: Cell<bool>,
+}
+
+impl StreamState {
+    async fn next_chunk(&self) -> Result<JsValue, SQLiteWasmDatabaseError> {
+        if self.done.get() {
+            return iterator_result(None);
+        }
+        let cursor = match self.cursor.get() {
+            Some(cursor) => cursor,
+            None => {
+                let cursor = self.open().await.inspect_err(|_| self.done.set(true))?;
+                self.cursor.set(Some(cursor));
+                cursor
+            }
+        };
+
+        let operation = cursor_operation("query-chunk", cursor)?;
+        // The worker finalizes the cursor itself when the chunk fails or
+        // reaches the end.
+        let chunk = self
+            .db
+            .run_operation(operation)
+            .await
+            .and_then(|result| {
+                JSON::parse(&result.as_string().unwrap_or_default())
+                    .map_err(SQLiteWasmDatabaseError::JsError)
+            })
+            .inspect_err(|_| self.finish())?;
+        let rows = Array::from(&Reflect::get(&chunk, &JsValue::from_str("rows"))?);
+        if Reflect::get(&chunk, &JsValue::from_str("done"))?.is_truthy() {
+            self.finish();
+            if rows.length() == 0 {
+                return iterator_result(None);
+            }
+        }
+        iterator_result(Some(rows.into()))
+    }
+
+    async fn open(&self) -> Result<u32, SQLiteWasmDatabaseError> {
+        let operation = SQLiteWasmDatabase::operation("open-cursor")?;
+        Reflect::set(
+            &operation,
+            &JsValue::from_str("sql"),
+            &JsValue::from_str(&self.sql),
+        )?;
+        if self.params.length() > 0 {
+            Reflect::set(&operation, &JsValue::from_str("params"), &self.params)?;
+        }
+        Reflect::set(
+            &operation,
+            &JsValue::from_str("chunkSize"),
+            &JsValue::from_f64(self.chunk_size as f64),
+        )?;
+        if let Some(max_cell_bytes) = self.max_cell_bytes {
+            Reflect::set(
+                &operation,
+                &JsValue::from_str("maxCellBytes"),
+                &JsValue::from_f64(max_cell_bytes as f64),
+            )?;
+        }
+
+        let result = self.db.run_operation(operation).await?;
+        let opened = JSON::parse(&result.as_string().unwrap_or_default())?;
+        Reflect::get(&opened, &JsValue::from_str("cursor"))?
+            .as_f64()
+            .map(|cursor| cursor as u32)
+            .ok_or_else(|| {
+                SQLiteWasmDatabaseError::JsError(JsValue::from_str(
+                    "Invalid open-cursor response from worker",
+                ))
+            })
+    }
+
+    async fn close(&self) -> Result<JsValue, SQLiteWasmDatabaseError> {
+        self.done.set(true);
+        if let Some(cursor) = self.cursor.take() {
+            self.db
+                .run_operation(cursor_operation("close-cursor", cursor)?)
+                .await?;
+        }
+        iterator_result(None)
+    }
+
+    fn finish(&self) {
+        self.done.set(true);
+        self.cursor.set(None);
+    }
+}
+
+fn cursor_operation(kind: &str, cursor: u32) -> Result<Object, SQLiteWasmDatabaseError> {
+    let operation = SQLiteWasmDatabase::operation(kind)?;
+    Reflect::set(
+        &operation,
+        &JsValue::from_str("cursor"),
+        &JsValue::from_f64(cursor as f64),
+    )?;
+    Ok(operation)
+}
+
+/// `{ value, done }` as the async iterator protocol expects.
+fn iterator_result(value: Option<JsValue>) -> Result<JsValue, SQLiteWasmDatabaseError> {
+    let result = Object::new();
+    let done = value.is_none();
+    Reflect::set(
+        &result,
+        &JsValue::from_str("value"),
+        &value.unwrap_or(JsValue::UNDEFINED),
+    )?;
+    Reflect::set(
+        &result,
+        &JsValue::from_str("done"),
+        &JsValue::from_bool(done),
+    )?;
+    Ok(result.into())
+}
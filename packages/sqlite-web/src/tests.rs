@@ -1,5 +1,6 @@
 #![cfg(all(test, target_family = "wasm"))]
 
+use crate::options::ConnectionOptions;
 use crate::ready::{InitializationState, ReadySignal};
 use crate::worker::handle_worker_control_message;
 use crate::worker_template::generate_self_contained_worker;
@@ -79,7 +80,7 @@ fn test_error_propagation_chain() {
 
 #[wasm_bindgen_test]
 fn test_worker_template_generation() {
-    let worker_code = generate_self_contained_worker("testdb");
+    let worker_code = generate_self_contained_worker("testdb", &ConnectionOptions::default());
 
     assert!(!worker_code.is_empty());
     assert!(
@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+
+use crate::errors::SQLiteWasmDatabaseError;
+
+/// Outcome of `validate()`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SqlValidation {
+    /// `true` when every statement prepared.
+    pub valid: bool,
+    /// SQLite's message for the statement that failed to prepare.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub error: Option<String>,
+    /// Zero-based index of that statement within the script.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub statement_index: Option<u32>,
+}
+
+impl SqlValidation {
+    pub(crate) fn from_worker_result(result: &str) -> Result<Self, SQLiteWasmDatabaseError> {
+        serde_json::from_str(result).map_err(|e| {
+            SQLiteWasmDatabaseError::JsError(wasm_bindgen::JsValue::from_str(&format!(
+                "Invalid validate response from worker: {e}"
+            )))
+        })
+    }
+}
+
+/// Outcome of `lastError()`: what SQLite reported for the last query that
+/// failed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LastError {
+    /// `sqlite3_errmsg`, e.g. `near "SELEC": syntax error`.
+    pub message: String,
+    /// `sqlite3_errcode`, e.g. 1 for `SQLITE_ERROR`.
+    pub code: i32,
+    /// Byte offset in the query's SQL of the token SQLite blamed, when it
+    /// named one.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub offset: Option<u32>,
+}
+
+impl LastError {
+    /// `SQLite error <code>`, followed by ` at offset <offset>` when SQLite
+    /// named a token.
+    pub(crate) fn describe(&self) -> String {
+        match self.offset {
+            Some(offset) => format!("SQLite error {} at offset {offset}", self.code),
+            None => format!("SQLite error {}", self.code),
+        }
+    }
+
+    /// `None` when the worker answered `null`, i.e. the most recent query
+    /// succeeded.
+    pub(crate) fn from_worker_result(
+        result: &str,
+    ) -> Result<Option<Self>, SQLiteWasmDatabaseError> {
+        serde_json::from_str(result).map_err(|e| {
+            SQLiteWasmDatabaseError::JsError(wasm_bindgen::JsValue::from_str(&format!(
+                "Invalid lastError response from worker: {e}"
+            )))
+        })
+    }
+}
+
+#[cfg(all(test, target_family = "wasm"))]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn parses_worker_validate_result() {
+        let valid = SqlValidation::from_worker_result(r#"{"valid":true}"#).expect("valid");
+        assert_eq!(
+            valid,
+            SqlValidation {
+                valid: true,
+                error: None,
+                statement_index: None,
+            }
+        );
+
+        let invalid = SqlValidation::from_worker_result(
+            r#"{"valid":false,"error":"near \"SELEC\": syntax error","statementIndex":1}"#,
+        )
+        .expect("invalid");
+        assert!(!invalid.valid);
+        assert_eq!(invalid.statement_index, Some(1));
+        assert_eq!(
+            invalid.error.as_deref(),
+            Some("near \"SELEC\": syntax error")
+        );
+
+        assert!(SqlValidation::from_worker_result("not json").is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn parses_worker_last_error_result() {
+        let error = LastError::from_worker_result(
+            r#"{"message":"near \"SELEC\": syntax error","code":1,"offset":10}"#,
+        )
+        .expect("error")
+        .expect("some error");
+        assert_eq!(
+            error,
+            LastError {
+                message: "near \"SELEC\": syntax error".to_string(),
+                code: 1,
+                offset: Some(10),
+            }
+        );
+
+        let no_offset = LastError::from_worker_result(
+            r#"{"message":"UNIQUE constraint failed: t.id","code":19}"#,
+        )
+        .expect("error")
+        .expect("some error");
+        assert_eq!(no_offset.offset, None);
+
+        assert_eq!(LastError::from_worker_result("null").expect("null"), None);
+        assert!(LastError::from_worker_result("not json").is_err());
+    }
+}
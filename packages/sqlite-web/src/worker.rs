@@ -1,6 +1,9 @@
+use crate::abort::CancelSignal;
+use crate::events::{handle_worker_event_message, handle_worker_log_message, EventListeners};
+use crate::messages::WORKER_ERROR_TYPE_LEADER_ELECTION_TIMEOUT;
 use crate::ready::ReadySignal;
 use crate::utils::describe_js_value;
-use js_sys::{Array, Function, Reflect};
+use js_sys::{Array, Function, Reflect, Uint8Array};
 use serde::Deserialize;
 use std::cell::RefCell;
 use std::collections::HashMap;
@@ -11,6 +14,8 @@ use wasm_bindgen::JsCast;
 use wasm_bindgen_utils::prelude::serde_wasm_bindgen;
 use web_sys::{Blob, BlobPropertyBag, MessageEvent, Url, Worker};
 
+pub(crate) type PendingQueries = Rc<RefCell<HashMap<u32, (Function, Function)>>>;
+
 pub(crate) fn create_worker_from_code(worker_code: &str) -> Result<Worker, JsValue> {
     let blob_parts = Array::new();
     blob_parts.push(&JsValue::from_str(worker_code));
@@ -27,14 +32,23 @@ pub(crate) fn create_worker_from_code(worker_code: &str) -> Result<Worker, JsVal
 
 pub(crate) fn install_onmessage_handler(
     worker: &Worker,
-    pending_queries: Rc<RefCell<HashMap<u32, (Function, Function)>>>,
+    pending_queries: PendingQueries,
     ready_signal: ReadySignal,
+    listeners: EventListeners,
+    cancel_signal: CancelSignal,
 ) {
     let pending_queries_clone = Rc::clone(&pending_queries);
     let ready_signal_clone = ready_signal.clone();
     let onmessage = Closure::wrap(Box::new(move |event: MessageEvent| {
         let data = event.data();
         if handle_worker_control_message(&data, &ready_signal_clone) {
+            cancel_signal.adopt(&data);
+            return;
+        }
+        if handle_worker_event_message(&data, &listeners) {
+            return;
+        }
+        if handle_worker_log_message(&data, &listeners) {
             return;
         }
         handle_query_result_message(&data, &pending_queries_clone);
@@ -44,6 +58,54 @@ pub(crate) fn install_onmessage_handler(
     onmessage.forget();
 }
 
+/// Tells the worker when the page is unloaded so that, if it leads the
+/// database, it hands off to another tab before it is killed. Removed from
+/// the window when dropped.
+pub(crate) struct PagehideListener {
+    window: web_sys::Window,
+    callback: Closure<dyn FnMut(JsValue)>,
+}
+
+impl PagehideListener {
+    pub(crate) fn install(worker: Rc<RefCell<Worker>>) -> Option<Self> {
+        let window = web_sys::window()?;
+        let callback = Closure::wrap(Box::new(move |event: JsValue| {
+            if !is_unload(&event) {
+                return;
+            }
+            let message = js_sys::Object::new();
+            let _ = Reflect::set(
+                &message,
+                &JsValue::from_str("type"),
+                &JsValue::from_str("step-down"),
+            );
+            let _ = worker.borrow().post_message(&message);
+        }) as Box<dyn FnMut(JsValue)>);
+        let _ =
+            window.add_event_listener_with_callback("pagehide", callback.as_ref().unchecked_ref());
+        Some(Self { window, callback })
+    }
+}
+
+impl Drop for PagehideListener {
+    fn drop(&mut self) {
+        let _ = self.window.remove_event_listener_with_callback(
+            "pagehide",
+            self.callback.as_ref().unchecked_ref(),
+        );
+    }
+}
+
+/// Whether a `pagehide` event is a real unload. A page entering the
+/// back/forward cache (`persisted`) may be restored with its worker still
+/// running, and stepping down then would leave it with no leader.
+fn is_unload(event: &JsValue) -> bool {
+    Reflect::get(event, &JsValue::from_str("persisted"))
+        .ok()
+        .and_then(|persisted| persisted.as_bool())
+        != Some(true)
+}
+
 #[derive(Deserialize)]
 #[serde(tag = "type")]
 enum WorkerControlMessage {
@@ -60,6 +122,13 @@ pub(crate) fn handle_worker_control_message(data: &JsValue, ready_signal: &Ready
             true
         }
         Ok(WorkerControlMessage::Error) => {
+            let error_type = Reflect::get(data, &JsValue::from_str("errorType"))
+                .ok()
+                .and_then(|val| val.as_string());
+            if error_type.as_deref() == Some(WORKER_ERROR_TYPE_LEADER_ELECTION_TIMEOUT) {
+                ready_signal.mark_failed(WORKER_ERROR_TYPE_LEADER_ELECTION_TIMEOUT.to_string());
+                return true;
+            }
             let reason = Reflect::get(data, &JsValue::from_str("error"))
                 .ok()
                 .filter(|val| !val.is_null() && !val.is_undefined())
@@ -72,10 +141,7 @@ pub(crate) fn handle_worker_control_message(data: &JsValue, ready_signal: &Ready
     }
 }
 
-fn handle_query_result_message(
-    data: &JsValue,
-    pending_queries: &Rc<RefCell<HashMap<u32, (Function, Function)>>>,
-) {
+fn handle_query_result_message(data: &JsValue, pending_queries: &PendingQueries) {
     let msg_type = Reflect::get(data, &JsValue::from_str("type"))
         .ok()
         .and_then(|obj| obj.as_string());
@@ -102,15 +168,49 @@ fn handle_query_result_message(
         return;
     }
 
+    // Binary results (e.g. `export()`) arrive as a transferred `Uint8Array`.
+    if let Some(bytes) = Reflect::get(data, &JsValue::from_str("bytes"))
+        .ok()
+        .filter(|bytes| bytes.is_instance_of::<Uint8Array>())
+    {
+        let _ = resolve.call1(&JsValue::NULL, &bytes);
+        return;
+    }
+
     if let Some(result) = Reflect::get(data, &JsValue::from_str("result"))
         .ok()
         .filter(|r| !r.is_null() && !r.is_undefined())
     {
-        let result_str = result.as_string().unwrap_or_else(|| format!("{result:?}"));
+        let mut result_str = result.as_string().unwrap_or_else(|| format!("{result:?}"));
+        if let Some(timing) = Reflect::get(data, &JsValue::from_str("timing"))
+            .ok()
+            .filter(|timing| timing.is_object())
+        {
+            result_str = with_timing(&result_str, &timing);
+        }
         let _ = resolve.call1(&JsValue::NULL, &JsValue::from_str(&result_str));
     }
 }
 
+/// Wrap a profiled query's result as `{ result, timing }`. The result text
+/// is spliced in rather than parsed again; text that is not a JSON array or
+/// object, such as "Rows affected" messages, becomes a JSON string.
+fn with_timing(result: &str, timing: &JsValue) -> String {
+    let Some(timing) = js_sys::JSON::stringify(timing)
+        .ok()
+        .and_then(|timing| timing.as_string())
+    else {
+        return result.to_string();
+    };
+    let trimmed = result.trim();
+    let result = if trimmed.starts_with('[') || trimmed.starts_with('{') {
+        trimmed.to_string()
+    } else {
+        serde_json::Value::from(result).to_string()
+    };
+    format!("{{\"result\":{result},\"timing\":{timing}}}")
+}
+
 #[cfg(all(test, target_family = "wasm"))]
 mod tests {
     use super::*;
@@ -136,6 +236,16 @@ mod tests {
         (func, calls)
     }
 
+    #[wasm_bindgen_test]
+    fn pages_entering_the_bfcache_are_not_unloads() {
+        let event = js_sys::Object::new();
+        assert!(is_unload(&event));
+        let _ = js_sys::Reflect::set(&event, &JsValue::from_str("persisted"), &JsValue::TRUE);
+        assert!(!is_unload(&event));
+        let _ = js_sys::Reflect::set(&event, &JsValue::from_str("persisted"), &JsValue::FALSE);
+        assert!(is_unload(&event));
+    }
+
     #[wasm_bindgen_test]
     fn worker_control_message_marks_ready() {
         let signal = ReadySignal::new();
@@ -208,6 +318,85 @@ mod tests {
         assert!(pending_queries.borrow().is_empty());
     }
 
+    #[wasm_bindgen_test]
+    fn query_result_message_wraps_profiled_results_with_timing() {
+        let (resolve_fn, resolve_calls) = recorder_function();
+        let (reject_fn, _) = recorder_function();
+        let pending_queries = Rc::new(RefCell::new(HashMap::new()));
+        pending_queries
+            .borrow_mut()
+            .insert(3, (resolve_fn.clone(), reject_fn.clone()));
+        pending_queries
+            .borrow_mut()
+            .insert(4, (resolve_fn, reject_fn));
+
+        let message = |request_id: f64, result: &str| {
+            let msg = js_sys::Object::new();
+            let _ = js_sys::Reflect::set(
+                &msg,
+                &JsValue::from_str("type"),
+                &JsValue::from_str("query-result"),
+            );
+            let _ = js_sys::Reflect::set(
+                &msg,
+                &JsValue::from_str("requestId"),
+                &JsValue::from_f64(request_id),
+            );
+            let _ = js_sys::Reflect::set(
+                &msg,
+                &JsValue::from_str("result"),
+                &JsValue::from_str(result),
+            );
+            let timing = js_sys::JSON::parse(r#"{"queuedMs":1,"execMs":2}"#).unwrap();
+            let _ = js_sys::Reflect::set(&msg, &JsValue::from_str("timing"), &timing);
+            JsValue::from(msg)
+        };
+        handle_query_result_message(&message(3.0, "[\n  {\"n\": 1}\n]"), &pending_queries);
+        handle_query_result_message(&message(4.0, "Rows affected: 1"), &pending_queries);
+
+        let calls = resolve_calls.borrow();
+        let resolved = |index: usize| -> serde_json::Value {
+            serde_json::from_str(&calls[index].as_string().unwrap()).unwrap()
+        };
+        assert_eq!(
+            resolved(0),
+            serde_json::json!({ "result": [{ "n": 1 }], "timing": { "queuedMs": 1, "execMs": 2 } })
+        );
+        assert_eq!(resolved(1)["result"], "Rows affected: 1");
+    }
+
+    #[wasm_bindgen_test]
+    fn query_result_message_resolves_binary_payload() {
+        let (resolve_fn, resolve_calls) = recorder_function();
+        let (reject_fn, _) = recorder_function();
+        let pending_queries = Rc::new(RefCell::new(HashMap::new()));
+        pending_queries
+            .borrow_mut()
+            .insert(5, (resolve_fn, reject_fn));
+
+        let msg = js_sys::Object::new();
+        let _ = js_sys::Reflect::set(
+            &msg,
+            &JsValue::from_str("type"),
+            &JsValue::from_str("query-result"),
+        );
+        let _ = js_sys::Reflect::set(
+            &msg,
+            &JsValue::from_str("requestId"),
+            &JsValue::from_f64(5.0),
+        );
+        let _ = js_sys::Reflect::set(&msg, &JsValue::from_str("result"), &JsValue::NULL);
+        let bytes = Uint8Array::from(&[83u8, 81, 76][..]);
+        let _ = js_sys::Reflect::set(&msg, &JsValue::from_str("bytes"), &bytes);
+
+        handle_query_result_message(&msg.into(), &pending_queries);
+
+        let calls = resolve_calls.borrow();
+        assert_eq!(calls.len(), 1);
+        let resolved: Uint8Array = calls[0].clone().unchecked_into();
+        assert_eq!(resolved.to_vec(), vec![83u8, 81, 76]);
+    }
+
     #[wasm_bindgen_test]
     fn query_result_message_rejects_with_error_payload() {
         let (resolve_fn, resolve_calls) = recorder_function();
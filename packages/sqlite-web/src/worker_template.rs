@@ -1,15 +1,39 @@
+use crate::options::ConnectionOptions;
+
+/// Times a forwarded request is resent when `followerRetries` is not given.
+const DEFAULT_FOLLOWER_RETRIES: u32 = 2;
+/// Other tabs' requests a leader runs at once when `maxFollowerQueries` is
+/// not given.
+const DEFAULT_MAX_FOLLOWER_QUERIES: u32 = 64;
+/// Requests the DB worker holds queued when `maxQueueDepth` is not given.
+const DEFAULT_MAX_QUEUE_DEPTH: u32 = 1024;
+
 /// Generate self-contained worker with embedded WASM and JS glue code
-/// and inject the database name into the worker global scope so core
-/// can read it during initialization.
-pub fn generate_self_contained_worker(db_name: &str) -> String {
+/// and inject the database name and connection options into the worker
+/// global scope so core can read them during initialization.
+pub fn generate_self_contained_worker(db_name: &str, options: &ConnectionOptions) -> String {
     // Safely JSON-encode the db name for JS embedding
     let encoded = serde_json::to_string(db_name).unwrap_or_else(|_| "\"unknown\"".to_string());
     let embedded_body = serde_json::to_string(include_str!("embedded_worker.js"))
         .unwrap_or_else(|_| "\"\"".to_string());
     // __SQLITE_EMBEDDED_WORKER stores the JSON-encoded embedded worker body (embedded_body) so the coordinator can spawn a separate DB worker (see coordination.rs:301-313); set when embedded-worker mode is used and consumers must JSON-decode before instantiating the worker.
     let prefix = format!(
-        "self.__SQLITE_DB_NAME = {};\nself.__SQLITE_FOLLOWER_TIMEOUT_MS = 5000.0;\nself.__SQLITE_QUERY_TIMEOUT_MS = 30000.0;\nself.__SQLITE_EMBEDDED_WORKER = {};\n",
-        encoded, embedded_body
+        "self.__SQLITE_DB_NAME = {};\nself.__SQLITE_FOLLOWER_TIMEOUT_MS = 5000.0;\nself.__SQLITE_QUERY_TIMEOUT_MS = 30000.0;\nself.__SQLITE_MAX_FOLLOWER_QUERIES = {};\nself.__SQLITE_FOLLOWER_RETRIES = {};\nself.__SQLITE_MAX_QUEUE_DEPTH = {};\nself.__SQLITE_ANNOUNCE_INTERVAL_MS = {:?};\nself.__SQLITE_CUSTOM_FUNCTIONS = {};\nself.__SQLITE_PRAGMAS = {};\nself.__SQLITE_NAMESPACE = {};\nself.__SQLITE_MEMORY_FALLBACK = {};\nself.__SQLITE_DEBUG = {};\nself.__SQLITE_DB_ONLY = {};\nself.__SQLITE_IN_MEMORY = {};\nself.__SQLITE_EMBEDDED_WORKER = {};\n",
+        encoded,
+        options
+            .max_follower_queries
+            .unwrap_or(DEFAULT_MAX_FOLLOWER_QUERIES),
+        options.follower_retries.unwrap_or(DEFAULT_FOLLOWER_RETRIES),
+        options.max_queue_depth.unwrap_or(DEFAULT_MAX_QUEUE_DEPTH),
+        options.announce_interval_ms.unwrap_or(0.0),
+        serde_json::to_string(&options.custom_functions).unwrap_or_else(|_| "null".to_string()),
+        serde_json::to_string(&options.open_pragmas()).unwrap_or_else(|_| "[]".to_string()),
+        serde_json::to_string(&options.namespace).unwrap_or_else(|_| "null".to_string()),
+        options.allow_memory_fallback,
+        options.debug,
+        options.isolated,
+        options.in_memory,
+        embedded_body
     );
     // Use the bundled worker template with embedded WASM
     let body = include_str!("embedded_worker.js");
@@ -25,7 +49,7 @@ mod tests {
 
     #[wasm_bindgen_test]
     fn embeds_db_name_and_timeout_configuration() {
-        let output = generate_self_contained_worker("my_db");
+        let output = generate_self_contained_worker("my_db", &ConnectionOptions::default());
         assert!(
             output.contains("self.__SQLITE_DB_NAME = \"my_db\";"),
             "db name should be JSON encoded in prefix"
@@ -38,15 +62,166 @@ mod tests {
             output.contains("self.__SQLITE_QUERY_TIMEOUT_MS = 30000.0;"),
             "query timeout constant should be injected"
         );
+        assert!(
+            output.contains("self.__SQLITE_MAX_FOLLOWER_QUERIES = 64;"),
+            "follower query cap should be injected"
+        );
+        assert!(
+            output.contains("self.__SQLITE_FOLLOWER_RETRIES = 2;"),
+            "follower retry count should be injected"
+        );
+        assert!(
+            output.contains("self.__SQLITE_MAX_QUEUE_DEPTH = 1024;"),
+            "DB queue depth limit should be injected"
+        );
+        assert!(
+            output.contains("self.__SQLITE_ANNOUNCE_INTERVAL_MS = 0.0;"),
+            "ping answers are not coalesced by default"
+        );
+        assert!(
+            output.contains("self.__SQLITE_CUSTOM_FUNCTIONS = null;"),
+            "custom functions are registered by default"
+        );
+        assert!(
+            output.contains("self.__SQLITE_PRAGMAS = [];"),
+            "no pragmas are run by default"
+        );
+        assert!(
+            output.contains("self.__SQLITE_NAMESPACE = null;"),
+            "connections share the default namespace"
+        );
+        assert!(
+            output.contains("self.__SQLITE_MEMORY_FALLBACK = false;"),
+            "opening fails without OPFS by default"
+        );
+        assert!(
+            output.contains("self.__SQLITE_DEBUG = false;"),
+            "worker logs are not forwarded by default"
+        );
+        assert!(
+            output.contains("self.__SQLITE_DB_ONLY = false;"),
+            "connections join the leader election by default"
+        );
         assert!(
             output.contains("self.__SQLITE_EMBEDDED_WORKER = "),
             "embedded worker body should be stored on the global"
         );
     }
 
+    #[wasm_bindgen_test]
+    fn embeds_selected_custom_functions() {
+        let options = ConnectionOptions {
+            custom_functions: Some(vec![]),
+            ..Default::default()
+        };
+        let output = generate_self_contained_worker("my_db", &options);
+        assert!(output.contains("self.__SQLITE_CUSTOM_FUNCTIONS = [];"));
+
+        let options = ConnectionOptions {
+            custom_functions: Some(vec!["BIGINT_SUM".to_string()]),
+            ..Default::default()
+        };
+        let output = generate_self_contained_worker("my_db", &options);
+        assert!(output.contains("self.__SQLITE_CUSTOM_FUNCTIONS = [\"BIGINT_SUM\"];"));
+    }
+
+    #[wasm_bindgen_test]
+    fn runs_synchronous_level_before_other_pragmas() {
+        let options = ConnectionOptions {
+            pragmas: vec!["foreign_keys = ON".to_string()],
+            synchronous: Some("NORMAL".to_string()),
+            ..Default::default()
+        };
+        let output = generate_self_contained_worker("my_db", &options);
+        assert!(output
+            .contains("self.__SQLITE_PRAGMAS = [\"synchronous = NORMAL\",\"foreign_keys = ON\"];"));
+    }
+
+    #[wasm_bindgen_test]
+    fn embeds_namespace() {
+        let options = ConnectionOptions {
+            namespace: Some("app-one".to_string()),
+            ..Default::default()
+        };
+        let output = generate_self_contained_worker("my_db", &options);
+        assert!(output.contains("self.__SQLITE_NAMESPACE = \"app-one\";"));
+    }
+
+    #[wasm_bindgen_test]
+    fn embeds_follower_retries() {
+        let options = ConnectionOptions {
+            follower_retries: Some(0),
+            ..Default::default()
+        };
+        let output = generate_self_contained_worker("my_db", &options);
+        assert!(output.contains("self.__SQLITE_FOLLOWER_RETRIES = 0;"));
+    }
+
+    #[wasm_bindgen_test]
+    fn embeds_max_follower_queries() {
+        let options = ConnectionOptions {
+            max_follower_queries: Some(16),
+            ..Default::default()
+        };
+        let output = generate_self_contained_worker("my_db", &options);
+        assert!(output.contains("self.__SQLITE_MAX_FOLLOWER_QUERIES = 16;"));
+    }
+
+    #[wasm_bindgen_test]
+    fn embeds_max_queue_depth() {
+        let options = ConnectionOptions {
+            max_queue_depth: Some(4096),
+            ..Default::default()
+        };
+        let output = generate_self_contained_worker("my_db", &options);
+        assert!(output.contains("self.__SQLITE_MAX_QUEUE_DEPTH = 4096;"));
+    }
+
+    #[wasm_bindgen_test]
+    fn embeds_debug_flag() {
+        let options = ConnectionOptions {
+            debug: true,
+            ..Default::default()
+        };
+        let output = generate_self_contained_worker("my_db", &options);
+        assert!(output.contains("self.__SQLITE_DEBUG = true;"));
+    }
+
+    #[wasm_bindgen_test]
+    fn embeds_memory_fallback_flag() {
+        let options = ConnectionOptions {
+            allow_memory_fallback: true,
+            ..Default::default()
+        };
+        let output = generate_self_contained_worker("my_db", &options);
+        assert!(output.contains("self.__SQLITE_MEMORY_FALLBACK = true;"));
+    }
+
+    #[wasm_bindgen_test]
+    fn isolated_connections_run_the_database_in_their_own_worker() {
+        let options = ConnectionOptions {
+            isolated: true,
+            ..Default::default()
+        };
+        let output = generate_self_contained_worker("my_db", &options);
+        assert!(output.contains("self.__SQLITE_DB_ONLY = true;"));
+        assert!(output.contains("self.__SQLITE_IN_MEMORY = false;"));
+    }
+
+    #[wasm_bindgen_test]
+    fn embeds_in_memory_flag() {
+        let options = ConnectionOptions {
+            isolated: true,
+            in_memory: true,
+            ..Default::default()
+        };
+        let output = generate_self_contained_worker("my_db", &options);
+        assert!(output.contains("self.__SQLITE_IN_MEMORY = true;"));
+    }
+
     #[wasm_bindgen_test]
     fn appends_embedded_worker_body() {
-        let output = generate_self_contained_worker("whatever");
+        let output = generate_self_contained_worker("whatever", &ConnectionOptions::default());
         let body = include_str!("embedded_worker.js");
         assert!(
             output.ends_with(body),
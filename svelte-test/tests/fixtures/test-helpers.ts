@@ -175,17 +175,69 @@ export async function cleanupDatabase(db: SQLiteWasmDatabase): Promise<void> {
 			'test_items', 'type_test', 'duplicate_test', 'bulk_test',
 			'constraint_test', 'concurrency_test',
 			// Error handling test tables
-			'error_recovery_test', 'injection_test', 'parent_table', 'child_table',
+			'error_recovery_test', 'injection_test', 'single_statement_test', 'parent_table', 'child_table',
 			'long_query_test', 'many_params_test', 'nested_test', 'timeout_test',
 			'recovery_test', 'custom_function_test', 'special_chars_test', 'concurrent_error_test',
 			// Worker communication test tables  
 			'workers_test', 'shared_data', 'worker_coordination', 'message_test',
 			// Multi-SQL commands (UI) test tables
-			'multi_ui', 'semi_ui', 'gate_ui', 'trg_src_ui', 'trg_log_ui',
+			'multi_ui', 'semi_ui', 'gate_ui', 'count_ui', 'trg_src_ui', 'trg_log_ui',
 			// Database function test tables
 			'bigint_test', 'categories', 'float_test', 'float_categories', 'float_zero_usage', 'float_zero_defaults', 'float_is_zero_test',
 			// Parameter binding test tables
-			'param_test', 'param_types', 'param_blob', 'params_leader_test'
+			'param_test', 'param_types', 'param_blob', 'param_json', 'param_text_blob', 'param_typed', 'param_multi_a', 'param_multi_b', 'params_leader_test',
+			// Query cancellation test tables
+			'cancel_test',
+			// Flush test tables
+			'flush_ui',
+			// Column naming test tables
+			'dealias_ui',
+			// Affected rowid test tables
+			'rowids_ui',
+			// Change notification test tables
+			'changes_ui',
+			// Transaction hook test tables
+			'txn_hooks_ui',
+			// Export test tables
+			'export_ui',
+			// Backup test tables
+			'backup_ui',
+			// Import test tables
+			'import_ui',
+			// Vacuum test tables
+			'vacuum_ui',
+			// Stream test tables
+			'stream_ui',
+			// Truncation test tables
+			'truncation_ui',
+			// Result code test tables
+			'result_code_ui',
+			// Notice test tables
+			'notice_a_ui', 'notice_b_ui',
+			// Expanded SQL test tables
+			'expanded_sql_ui',
+			// Schema helper test tables
+			'schema_ui',
+			// explain() test tables
+			'explain_ui',
+			// synchronous option test tables
+			'sync_ui',
+			// validate() test tables
+			'validate_ui',
+			// Statement count test tables
+			'statement_counts_ui',
+			// insertMany() test tables
+			'insert_many_ui',
+			// queryIn() test tables
+			'query_in_ui',
+			// queryOne()/queryValue() test tables
+			'query_one_ui',
+			// Result detection test tables
+			'detection_ui',
+			// lastError() test tables
+			'last_error_ui',
+			// coerceAffinity test tables
+			'affinity_ui'
 		];
 		for (const table of tables) {
 			try {
@@ -0,0 +1,45 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { createTestDatabase, cleanupDatabase } from '../fixtures/test-helpers.js';
+import type { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+describe('Affected Rowids', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    db = await createTestDatabase();
+    await db.query('CREATE TABLE rowids_ui (id INTEGER PRIMARY KEY, status TEXT)');
+    await db.query(
+      "INSERT INTO rowids_ui (id, status) VALUES (1, 'new'), (2, 'done'), (3, 'new'), (5, 'new')"
+    );
+  });
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+  });
+
+  it('returns the rowids touched by an UPDATE', async () => {
+    const result = await db.query(
+      'UPDATE rowids_ui SET status = ? WHERE status = ?',
+      ['seen', 'new'],
+      { returnRowids: true }
+    );
+    expect(result.error).toBeUndefined();
+
+    const parsed = JSON.parse(result.value || '{}');
+    expect(parsed.rowids).toEqual([1, 3, 5]);
+    expect(parsed.result).toContain('Rows affected: 3');
+  });
+
+  it('returns the rowids removed by a DELETE', async () => {
+    const result = await db.query('DELETE FROM rowids_ui WHERE id >= 3', undefined, {
+      returnRowids: true
+    });
+    expect(result.error).toBeUndefined();
+    expect(JSON.parse(result.value || '{}').rowids).toEqual([3, 5]);
+  });
+
+  it('leaves the result unchanged when the option is not set', async () => {
+    const result = await db.query("UPDATE rowids_ui SET status = 'seen'");
+    expect(result.value).toContain('Rows affected: 4');
+  });
+});
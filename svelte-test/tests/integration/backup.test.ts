@@ -0,0 +1,51 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { createTestDatabase, cleanupDatabase } from '../fixtures/test-helpers.js';
+import type { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+async function pageCount(db: SQLiteWasmDatabase, schema = 'main'): Promise<number> {
+  const result = await db.query(`PRAGMA ${schema}.page_count`);
+  return JSON.parse(result.value || '[]')[0].page_count;
+}
+
+describe('backupInto()', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    db = await createTestDatabase();
+  });
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+  });
+
+  it('writes a compacted, queryable copy under a new name', async () => {
+    await db.query('CREATE TABLE backup_ui (id INTEGER PRIMARY KEY, payload TEXT)');
+    await db.query(`
+      WITH RECURSIVE seq(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < 200)
+      INSERT INTO backup_ui (payload) SELECT hex(randomblob(512)) FROM seq
+    `);
+    await db.query('DELETE FROM backup_ui WHERE id > 5');
+
+    const name = `backup-${Date.now()}`;
+    const backedUp = await db.backupInto(name);
+    expect(backedUp.error).toBeUndefined();
+
+    // The backup lives in the same OPFS pool, so attach it to inspect it.
+    const attached = await db.query(`ATTACH DATABASE 'opfs-sahpool:${name}.db' AS backup`);
+    expect(attached.error).toBeUndefined();
+    try {
+      const rows = await db.query('SELECT COUNT(*) AS count FROM backup.backup_ui');
+      expect(JSON.parse(rows.value || '[]')[0].count).toBe(5);
+      expect(await pageCount(db, 'backup')).toBeLessThan(await pageCount(db));
+    } finally {
+      await db.query('DETACH DATABASE backup');
+    }
+  });
+
+  it('refuses to overwrite an existing backup', async () => {
+    await db.query('CREATE TABLE backup_ui (id INTEGER PRIMARY KEY)');
+    const name = `backup-twice-${Date.now()}`;
+    expect((await db.backupInto(name)).error).toBeUndefined();
+    expect((await db.backupInto(name)).error).toBeDefined();
+  });
+});
@@ -0,0 +1,46 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { createTestDatabase, cleanupDatabase } from '../fixtures/test-helpers.js';
+import type { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+describe('maxCellBytes', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    db = await createTestDatabase();
+    await db.query('CREATE TABLE truncation_ui (id INTEGER PRIMARY KEY, body TEXT)');
+    await db.query("INSERT INTO truncation_ui (body) VALUES (printf('%.*c', 2000000, 'x')), ('tiny')");
+  });
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+  });
+
+  it('replaces oversized text with a preview and its total size', async () => {
+    const result = await db.query('SELECT body FROM truncation_ui ORDER BY id', undefined, {
+      maxCellBytes: 1024
+    });
+    expect(result.error).toBeUndefined();
+    expect(result.value!.length).toBeLessThan(10_000);
+
+    const rows = JSON.parse(result.value!);
+    expect(rows[0].body).toEqual({
+      __truncated: true,
+      preview: 'x'.repeat(1024),
+      totalBytes: 2000000
+    });
+    expect(rows[1].body).toBe('tiny');
+  });
+
+  it('returns full values when maxCellBytes is not set', async () => {
+    const result = await db.query('SELECT length(body) AS len, body FROM truncation_ui WHERE id = 1');
+    const rows = JSON.parse(result.value!);
+    expect(rows[0].body).toHaveLength(rows[0].len);
+  });
+
+  it('rejects a negative limit', async () => {
+    const result = await db.query('SELECT body FROM truncation_ui', undefined, {
+      maxCellBytes: -1
+    });
+    expect(result.error?.msg).toContain('maxCellBytes');
+  });
+});
@@ -0,0 +1,55 @@
+import { describe, it, expect, afterEach } from 'vitest';
+import { createTestDatabase, cleanupDatabase, waitFor } from '../fixtures/test-helpers.js';
+import type { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+interface RowChange {
+  op: 'insert' | 'update' | 'delete';
+  table: string;
+  rowid: number;
+}
+
+describe('onChange()', () => {
+  const opened: SQLiteWasmDatabase[] = [];
+
+  afterEach(async () => {
+    for (const db of opened.splice(0)) await cleanupDatabase(db);
+  });
+
+  it('reports the table and rowid of an INSERT', async () => {
+    const db = await createTestDatabase();
+    opened.push(db);
+    await db.query('CREATE TABLE changes_ui (id INTEGER PRIMARY KEY, name TEXT)');
+
+    const changes: RowChange[] = [];
+    const subscription = db.onChange((event: { changes: RowChange[] }) => {
+      changes.push(...event.changes);
+    });
+    expect(subscription.error).toBeUndefined();
+
+    const insert = await db.query("INSERT INTO changes_ui (id, name) VALUES (17, 'Ada')");
+    expect(insert.error).toBeUndefined();
+
+    await waitFor(() => changes.length > 0, 2000, 20);
+    expect(changes).toEqual([{ op: 'insert', table: 'changes_ui', rowid: 17 }]);
+
+    expect(db.removeListener(subscription.value!).value).toBe(true);
+  });
+
+  it('delivers changes made by another tab', async () => {
+    const name = `change-events-${Date.now()}`;
+    const leader = await createTestDatabase(name);
+    const follower = await createTestDatabase(name);
+    opened.push(follower, leader);
+
+    const changes: RowChange[] = [];
+    follower.onChange((event: { changes: RowChange[] }) => {
+      changes.push(...event.changes);
+    });
+
+    await leader.query('CREATE TABLE changes_ui (id INTEGER PRIMARY KEY, name TEXT)');
+    await leader.query('INSERT INTO changes_ui (name) VALUES (?)', ['Grace']);
+
+    await waitFor(() => changes.length > 0, 5000, 50);
+    expect(changes[0]).toMatchObject({ op: 'insert', table: 'changes_ui', rowid: 1 });
+  });
+});
@@ -0,0 +1,35 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { createTestDatabase, cleanupDatabase } from '../fixtures/test-helpers.js';
+import type { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+describe('coerceAffinity', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    db = await createTestDatabase();
+    await db.query('CREATE TABLE affinity_ui (id INTEGER PRIMARY KEY, r REAL)');
+    await db.query('INSERT INTO affinity_ui (id, r) VALUES (1, 1)');
+  });
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+  });
+
+  it('returns whole numbers read through a REAL column as floats', async () => {
+    const sql = 'SELECT r FROM affinity_ui UNION ALL SELECT 2';
+    const raw = await db.query(sql);
+    expect(raw.value).toContain('"r":2}');
+
+    const coerced = await db.query(sql, undefined, { coerceAffinity: true });
+    expect(coerced.error).toBeUndefined();
+    expect(coerced.value).toContain('"r":1.0');
+    expect(coerced.value).toContain('"r":2.0');
+  });
+
+  it('rejects a non-boolean flag', async () => {
+    const result = await db.query('SELECT r FROM affinity_ui', undefined, {
+      coerceAffinity: 1 as unknown as boolean
+    });
+    expect(result.error?.msg).toContain('coerceAffinity');
+  });
+});
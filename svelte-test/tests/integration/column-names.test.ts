@@ -0,0 +1,32 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { createTestDatabase, cleanupDatabase } from '../fixtures/test-helpers.js';
+import type { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+describe('Result column names', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    db = await createTestDatabase();
+    await db.query('CREATE TABLE dealias_ui (id INTEGER PRIMARY KEY, amount INTEGER)');
+    await db.query('INSERT INTO dealias_ui (amount) VALUES (3), (4)');
+  });
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+  });
+
+  it('uses SQLite column names verbatim by default', async () => {
+    const result = await db.query('SELECT count(*), sum(amount) AS total FROM dealias_ui');
+    expect(JSON.parse(result.value || '[]')).toEqual([{ 'count(*)': 2, total: 7 }]);
+  });
+
+  it('replaces expression column names with col_N when dealiasColumns is set', async () => {
+    const result = await db.query(
+      'SELECT count(*), max(amount), sum(amount) AS total FROM dealias_ui',
+      undefined,
+      { dealiasColumns: true }
+    );
+    expect(result.error).toBeUndefined();
+    expect(JSON.parse(result.value || '[]')).toEqual([{ col_0: 2, col_1: 4, total: 7 }]);
+  });
+});
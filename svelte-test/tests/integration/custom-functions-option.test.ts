@@ -0,0 +1,27 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import init, { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+import { cleanupDatabase } from '../fixtures/test-helpers.js';
+
+describe('customFunctions option', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    await init();
+    const result = await SQLiteWasmDatabase.new('ui-test-db', { customFunctions: false });
+    expect(result.error).toBeUndefined();
+    db = result.value!;
+  });
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+  });
+
+  it('leaves FLOAT_SUM unregistered while standard SQL still works', async () => {
+    const missing = await db.query("SELECT FLOAT_SUM('0x0') AS total");
+    expect(missing.error?.msg).toContain('no such function: FLOAT_SUM');
+
+    const result = await db.query('SELECT sum(n) AS total FROM (SELECT 1 AS n UNION ALL SELECT 2)');
+    expect(result.error).toBeUndefined();
+    expect(JSON.parse(result.value || '[]')).toEqual([{ total: 3 }]);
+  });
+});
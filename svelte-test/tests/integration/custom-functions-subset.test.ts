@@ -0,0 +1,37 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import init, { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+import { cleanupDatabase } from '../fixtures/test-helpers.js';
+
+describe('customFunctions option with a list of names', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    await init();
+    const result = await SQLiteWasmDatabase.new('ui-test-db', { customFunctions: ['BIGINT_SUM'] });
+    expect(result.error).toBeUndefined();
+    db = result.value!;
+  });
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+  });
+
+  it('registers only the listed functions', async () => {
+    const sum = await db.query('SELECT BIGINT_SUM(n) AS total FROM (SELECT 1 AS n UNION ALL SELECT 2)');
+    expect(sum.error).toBeUndefined();
+    expect(JSON.parse(sum.value || '[]')).toEqual([{ total: '3' }]);
+
+    for (const excluded of ["FLOAT_SUM('0x0')", 'FLOAT_ZERO_HEX()', "FLOAT_NEGATE('0x0')"]) {
+      const missing = await db.query(`SELECT ${excluded} AS value`);
+      expect(missing.error?.msg).toContain('no such function');
+    }
+  });
+
+  it('rejects an unknown function name when the connection is created', async () => {
+    const result = await SQLiteWasmDatabase.new('ui-test-db-unknown-fn', {
+      customFunctions: ['BIGINT_SUM', 'NOT_A_FUNCTION']
+    });
+    expect(result.value).toBeUndefined();
+    expect(result.error?.msg).toContain('Unknown custom function: NOT_A_FUNCTION');
+  });
+});
@@ -127,6 +127,24 @@ describe('Error Handling Tests', () => {
 				{ username: 'alice', email: 'alice@test.com' }
 			);
 		});
+
+		it('should reject extra statements when allowMultiple is false', async () => {
+			await db.query('CREATE TABLE single_statement_test (id INTEGER PRIMARY KEY)');
+
+			for (const sql of [
+				'SELECT 1; DROP TABLE single_statement_test',
+				'SELECT 1; DROP TABLE single_statement_test;'
+			]) {
+				const result = await db.query(sql, undefined, { allowMultiple: false });
+				expect(result.error?.msg).toContain('allowMultiple');
+			}
+
+			const single = await db.query('SELECT COUNT(*) AS n FROM single_statement_test;', undefined, {
+				allowMultiple: false
+			});
+			expect(single.error).toBeUndefined();
+			expect(JSON.parse(single.value!)).toEqual([{ n: 0 }]);
+		});
 	});
 
 	describe('Constraint Violations', () => {
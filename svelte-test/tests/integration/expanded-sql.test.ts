@@ -0,0 +1,39 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { createTestDatabase, cleanupDatabase } from '../fixtures/test-helpers.js';
+import type { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+describe('returnExpandedSql', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    db = await createTestDatabase();
+    await db.query('CREATE TABLE expanded_sql_ui (id INTEGER, name TEXT)');
+  });
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+  });
+
+  it('shows a parameterized insert with its values inlined', async () => {
+    const result = await db.query(
+      'INSERT INTO expanded_sql_ui (id, name) VALUES (?, ?)',
+      [7, "it's"],
+      { returnExpandedSql: true }
+    );
+    expect(result.error).toBeUndefined();
+
+    const parsed = JSON.parse(result.value!);
+    expect(parsed.expandedSql).toEqual([
+      "INSERT INTO expanded_sql_ui (id, name) VALUES (7, 'it''s')"
+    ]);
+    expect(parsed.result).toContain('Rows affected: 1');
+  });
+
+  it('leaves the result unwrapped by default', async () => {
+    const result = await db.query('INSERT INTO expanded_sql_ui (id, name) VALUES (?, ?)', [
+      1,
+      'a'
+    ]);
+    expect(result.value).toContain('Rows affected: 1');
+  });
+});
@@ -0,0 +1,33 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { createTestDatabase, cleanupDatabase } from '../fixtures/test-helpers.js';
+import type { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+describe('explain()', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    db = await createTestDatabase();
+    await db.query('CREATE TABLE explain_ui (id INTEGER PRIMARY KEY, indexed_col INTEGER)');
+    await db.query('CREATE INDEX explain_ui_indexed ON explain_ui (indexed_col)');
+  });
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+  });
+
+  it('reports use of an index', async () => {
+    const result = await db.explain('SELECT * FROM explain_ui WHERE indexed_col = 1');
+    expect(result.error).toBeUndefined();
+    const steps = result.value!;
+    expect(steps.length).toBeGreaterThan(0);
+    expect(steps.some((step) => step.detail.includes('INDEX explain_ui_indexed'))).toBe(true);
+  });
+
+  it('plans a parameterized write without running it', async () => {
+    const result = await db.explain('INSERT INTO explain_ui (indexed_col) VALUES (?)', [5]);
+    expect(result.error).toBeUndefined();
+
+    const count = await db.query('SELECT COUNT(*) AS n FROM explain_ui');
+    expect(JSON.parse(count.value || '[]')).toEqual([{ n: 0 }]);
+  });
+});
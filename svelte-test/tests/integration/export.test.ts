@@ -0,0 +1,37 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { createTestDatabase, cleanupDatabase } from '../fixtures/test-helpers.js';
+import type { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+const SQLITE_HEADER = 'SQLite format 3\0';
+
+describe('export()', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    db = await createTestDatabase();
+  });
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+  });
+
+  it('returns a SQLite file image of a populated database', async () => {
+    await db.query('CREATE TABLE export_ui (id INTEGER PRIMARY KEY, name TEXT)');
+    for (let i = 0; i < 50; i++) {
+      await db.query('INSERT INTO export_ui (name) VALUES (?)', [`row-${i}`]);
+    }
+
+    const exported = await db.export();
+    expect(exported.error).toBeUndefined();
+    const image = exported.value!;
+    expect(image).toBeInstanceOf(Uint8Array);
+
+    const header = new TextDecoder().decode(image.slice(0, SQLITE_HEADER.length));
+    expect(header).toBe(SQLITE_HEADER);
+
+    const pageSize = await db.query('PRAGMA page_size');
+    const size = JSON.parse(pageSize.value || '[]')[0].page_size;
+    expect(image.length).toBeGreaterThan(0);
+    expect(image.length % size).toBe(0);
+  });
+});
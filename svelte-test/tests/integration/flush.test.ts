@@ -0,0 +1,45 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { createTestDatabase, cleanupDatabase } from '../fixtures/test-helpers.js';
+import type { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+describe('flush()', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    db = await createTestDatabase();
+  });
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+  });
+
+  it('resolves after previously issued writes have executed', async () => {
+    await db.query('CREATE TABLE flush_ui (id INTEGER PRIMARY KEY, value TEXT)');
+
+    // Do not await the writes: flush() must still observe all of them.
+    const writes = Array.from({ length: 5 }, (_, i) =>
+      db.query('INSERT INTO flush_ui (value) VALUES (?)', [`row-${i}`])
+    );
+    const flushed = await db.flush();
+    expect(flushed.error).toBeUndefined();
+
+    const results = await Promise.all(writes);
+    results.forEach((result) => expect(result.error).toBeUndefined());
+
+    const rows = await db.query('SELECT COUNT(*) AS count FROM flush_ui');
+    expect(JSON.parse(rows.value || '[]')[0].count).toBe(5);
+  });
+
+  it('fails while a transaction is still open', async () => {
+    await db.query('CREATE TABLE flush_ui (id INTEGER PRIMARY KEY, value TEXT)');
+    await db.query('BEGIN TRANSACTION');
+    await db.query("INSERT INTO flush_ui (value) VALUES ('pending')");
+
+    const flushed = await db.flush();
+    expect(flushed.error?.msg).toContain('transaction is open');
+
+    await db.query('ROLLBACK');
+    const retry = await db.flush();
+    expect(retry.error).toBeUndefined();
+  });
+});
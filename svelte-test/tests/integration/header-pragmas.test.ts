@@ -0,0 +1,48 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { createTestDatabase, cleanupDatabase } from '../fixtures/test-helpers.js';
+import type { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+describe('userVersion() / applicationId()', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    db = await createTestDatabase();
+  });
+
+  afterEach(async () => {
+    if (db) {
+      await db.setUserVersion(0);
+      await db.setApplicationId(0);
+      await cleanupDatabase(db);
+    }
+  });
+
+  it('writes and reads back both values as integers', async () => {
+    const setVersion = await db.setUserVersion(12);
+    expect(setVersion.error).toBeUndefined();
+    expect(setVersion.value).toBe(12);
+
+    const setId = await db.setApplicationId(0x51574542);
+    expect(setId.value).toBe(0x51574542);
+
+    expect((await db.userVersion()).value).toBe(12);
+    expect((await db.applicationId()).value).toBe(0x51574542);
+  });
+
+  it('is visible to another connection to the same database', async () => {
+    await db.setUserVersion(3);
+    await db.setApplicationId(-7);
+
+    const other = await createTestDatabase();
+    expect((await other.userVersion()).value).toBe(3);
+    expect((await other.applicationId()).value).toBe(-7);
+  });
+
+  it('rolls back with an open transaction', async () => {
+    await db.setUserVersion(1);
+    await db.query('BEGIN');
+    await db.setUserVersion(2);
+    await db.query('ROLLBACK');
+    expect((await db.userVersion()).value).toBe(1);
+  });
+});
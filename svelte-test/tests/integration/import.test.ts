@@ -0,0 +1,69 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { createTestDatabase, cleanupDatabase } from '../fixtures/test-helpers.js';
+import { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+const DB_NAME = 'ui-test-db';
+
+describe('import()', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    db = await createTestDatabase(DB_NAME);
+  });
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+  });
+
+  it('restores every row of an exported database', async () => {
+    await db.query('CREATE TABLE import_ui (id INTEGER PRIMARY KEY, name TEXT)');
+    for (let i = 0; i < 25; i++) {
+      await db.query('INSERT INTO import_ui (name) VALUES (?)', [`row-${i}`]);
+    }
+    const exported = await db.export();
+    expect(exported.error).toBeUndefined();
+
+    await db.query('DROP TABLE import_ui');
+
+    const imported = await SQLiteWasmDatabase.import(DB_NAME, exported.value!);
+    expect(imported.error).toBeUndefined();
+    const restored = imported.value!;
+
+    const result = await restored.query('SELECT name FROM import_ui ORDER BY id');
+    expect(result.error).toBeUndefined();
+    const rows = JSON.parse(result.value || '[]');
+    expect(rows).toHaveLength(25);
+    expect(rows[0].name).toBe('row-0');
+    expect(rows[24].name).toBe('row-24');
+  });
+
+  it('rejects a corrupt image without touching the existing database', async () => {
+    await db.query('CREATE TABLE import_ui (id INTEGER PRIMARY KEY, name TEXT)');
+    await db.query("INSERT INTO import_ui (name) VALUES ('kept')");
+
+    const garbage = new TextEncoder().encode('this is not a sqlite database');
+    const imported = await SQLiteWasmDatabase.import(DB_NAME, garbage);
+    expect(imported.error).toBeDefined();
+    expect(imported.error?.msg).toContain('missing SQLite header');
+
+    const result = await db.query('SELECT name FROM import_ui');
+    expect(JSON.parse(result.value || '[]')).toEqual([{ name: 'kept' }]);
+  });
+
+  it('rejects an image the worker cannot read and keeps the caller usable', async () => {
+    await db.query('CREATE TABLE import_ui (id INTEGER PRIMARY KEY, name TEXT)');
+    await db.query("INSERT INTO import_ui (name) VALUES ('kept')");
+
+    // A valid header followed by garbage passes the main-thread check, so
+    // the failure comes from the worker.
+    const corrupt = new Uint8Array(4096);
+    corrupt.set(new TextEncoder().encode('SQLite format 3\0'));
+    corrupt.fill(0xff, 16);
+    const imported = await SQLiteWasmDatabase.import(DB_NAME, corrupt);
+    expect(imported.error?.msg).toContain('Invalid database image');
+    expect(corrupt[0]).toBe('S'.charCodeAt(0));
+
+    const result = await db.query('SELECT name FROM import_ui');
+    expect(JSON.parse(result.value || '[]')).toEqual([{ name: 'kept' }]);
+  });
+});
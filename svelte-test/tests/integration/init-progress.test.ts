@@ -0,0 +1,34 @@
+import { describe, it, expect, afterEach } from 'vitest';
+import init, { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+describe('onProgress option', () => {
+  let db: SQLiteWasmDatabase | undefined;
+
+  afterEach(async () => {
+    await db?.close();
+    db = undefined;
+  });
+
+  it('reports each initialization stage in order before new() resolves', async () => {
+    await init();
+    const stages: string[] = [];
+    // A namespace of its own so no connection from another test leads it.
+    const result = await SQLiteWasmDatabase.new('ui-test-db', {
+      namespace: 'init-progress',
+      onProgress: (event: { kind: string; stage: string }) => {
+        expect(event.kind).toBe('init-progress');
+        stages.push(event.stage);
+      }
+    });
+    expect(result.error).toBeUndefined();
+    db = result.value!;
+
+    expect(stages).toEqual(['worker-spawned', 'leader-elected', 'opfs-installed', 'db-open']);
+  });
+
+  it('rejects a callback that is not a function', async () => {
+    await init();
+    const result = await SQLiteWasmDatabase.new('ui-test-db', { onProgress: 'yes' });
+    expect(result.error?.msg).toContain('options.onProgress must be a function');
+  });
+});
@@ -0,0 +1,57 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { createTestDatabase, cleanupDatabase } from '../fixtures/test-helpers.js';
+import type { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+describe('insertMany()', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    db = await createTestDatabase();
+    await db.query('CREATE TABLE insert_many_ui (id INTEGER PRIMARY KEY, name TEXT, score REAL)');
+  });
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+  });
+
+  it('inserts 5000 rows across several chunks in one call', async () => {
+    const rows = Array.from({ length: 5000 }, (_, i) => [i, `row ${i}`, i / 2]);
+    const result = await db.insertMany('insert_many_ui', ['id', 'name', 'score'], rows);
+    expect(result.error).toBeUndefined();
+    expect(result.value).toBe(5000);
+
+    const check = await db.query(
+      'SELECT COUNT(*) AS n, MIN(id) AS lo, MAX(id) AS hi, SUM(score) AS total FROM insert_many_ui'
+    );
+    expect(JSON.parse(check.value || '[]')).toEqual([
+      { n: 5000, lo: 0, hi: 4999, total: (4999 * 5000) / 4 }
+    ]);
+
+    // Rows either side of the 333-row chunk boundaries landed intact.
+    const edges = await db.query(
+      'SELECT id, name FROM insert_many_ui WHERE id IN (332, 333, 4994, 4995) ORDER BY id'
+    );
+    expect(JSON.parse(edges.value || '[]')).toEqual([
+      { id: 332, name: 'row 332' },
+      { id: 333, name: 'row 333' },
+      { id: 4994, name: 'row 4994' },
+      { id: 4995, name: 'row 4995' }
+    ]);
+  });
+
+  it('inserts nothing when any chunk fails', async () => {
+    const rows = Array.from({ length: 1000 }, (_, i) => [i, `row ${i}`, 0]);
+    rows.push([5, 'duplicate', 0]);
+    const result = await db.insertMany('insert_many_ui', ['id', 'name', 'score'], rows);
+    expect(result.error).toBeDefined();
+
+    const check = await db.query('SELECT COUNT(*) AS n FROM insert_many_ui');
+    expect(JSON.parse(check.value || '[]')).toEqual([{ n: 0 }]);
+  });
+
+  it('rejects rows that do not match the columns', async () => {
+    const result = await db.insertMany('insert_many_ui', ['id', 'name'], [[1]]);
+    expect(result.error).toBeDefined();
+    expect(result.error?.msg).toMatch(/Row 0 must be an array of 2 values/);
+  });
+});
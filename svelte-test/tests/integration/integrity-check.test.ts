@@ -0,0 +1,21 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { createTestDatabase, cleanupDatabase } from '../fixtures/test-helpers.js';
+import type { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+describe('integrityCheck()', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    db = await createTestDatabase();
+  });
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+  });
+
+  it('reports ok for a freshly created database', async () => {
+    const result = await db.integrityCheck();
+    expect(result.error).toBeUndefined();
+    expect(result.value).toEqual({ ok: true, problems: [] });
+  });
+});
@@ -0,0 +1,47 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { createTestDatabase, cleanupDatabase } from '../fixtures/test-helpers.js';
+import type { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+describe('lastError()', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    db = await createTestDatabase();
+    await db.query('CREATE TABLE last_error_ui (id INTEGER PRIMARY KEY)');
+  });
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+  });
+
+  it('points at the offending token of a syntax error', async () => {
+    const sql = 'SELECT id FROM last_error_ui WHER id = 1';
+    const failed = await db.query(sql);
+    expect(failed.error?.msg).toMatch(/\(SQLite error 1 at offset \d+\)$/);
+
+    const last = await db.lastError();
+    expect(last.error).toBeUndefined();
+    expect(last.value?.message).toContain('syntax error');
+    expect(last.value?.code).toBe(1);
+    expect(last.value?.offset).toBeGreaterThanOrEqual(0);
+    expect(last.value?.offset).toBeLessThan(sql.length);
+    expect(sql.slice(last.value!.offset!)).toMatch(/^(WHER|id)/);
+    expect(failed.error?.msg).toContain(`at offset ${last.value!.offset})`);
+  });
+
+  it('reports constraint failures without an offset and clears on success', async () => {
+    await db.query('INSERT INTO last_error_ui (id) VALUES (1)');
+    const failed = await db.query('INSERT INTO last_error_ui (id) VALUES (1)');
+    expect(failed.error?.msg).toMatch(/\(SQLite error 19\)$/);
+
+    const last = await db.lastError();
+    expect(last.value?.code).toBe(19);
+    expect(last.value?.message).toContain('UNIQUE constraint failed');
+    expect(last.value?.offset).toBeUndefined();
+
+    await db.query('SELECT 1');
+    const cleared = await db.lastError();
+    expect(cleared.error).toBeUndefined();
+    expect(cleared.value ?? null).toBeNull();
+  });
+});
@@ -0,0 +1,40 @@
+import { describe, it, expect } from 'vitest';
+import { createTestDatabase, waitFor } from '../fixtures/test-helpers.js';
+
+interface LeadershipChange {
+  leaderId: string;
+  isLeader: boolean;
+}
+
+describe('onLeadershipChange()', () => {
+  it('fires with the new leader id after the leader worker goes away', async () => {
+    const name = `leadership-change-${Date.now()}`;
+    const leader = await createTestDatabase(name);
+    const follower = await createTestDatabase(name);
+
+    const before = await follower.role();
+    expect(before.value?.role).toBe('follower');
+    const oldLeaderId = before.value?.leaderId;
+
+    const events: LeadershipChange[] = [];
+    const subscription = follower.onLeadershipChange((event: LeadershipChange) => {
+      events.push(event);
+    });
+    expect(subscription.error).toBeUndefined();
+
+    // Recreating terminates the leader's worker, releasing the election lock
+    // so the follower is promoted.
+    await leader.wipeAndRecreate();
+
+    await waitFor(() => events.some((event) => event.isLeader), 5000, 50);
+    const promoted = events.find((event) => event.isLeader)!;
+    expect(promoted.leaderId).toBeTruthy();
+    expect(promoted.leaderId).not.toBe(oldLeaderId);
+
+    const after = await follower.role();
+    expect(after.value?.role).toBe('leader');
+    expect(after.value?.leaderId).toBe(promoted.leaderId);
+
+    expect(follower.removeListener(subscription.value!).value).toBe(true);
+  });
+});
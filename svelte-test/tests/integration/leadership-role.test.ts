@@ -0,0 +1,21 @@
+import { describe, it, expect } from 'vitest';
+import { createTestDatabase } from '../fixtures/test-helpers.js';
+
+describe('role()', () => {
+  it('reports the first connection as leader and the second as follower', async () => {
+    // A fresh database name guarantees no connection from another test leads it.
+    const name = `role-test-${Date.now()}`;
+    const first = await createTestDatabase(name);
+    const second = await createTestDatabase(name);
+
+    const firstRole = await first.role();
+    expect(firstRole.error).toBeUndefined();
+    expect(firstRole.value?.role).toBe('leader');
+    expect(firstRole.value?.leaderId).toBeTruthy();
+
+    const secondRole = await second.role();
+    expect(secondRole.error).toBeUndefined();
+    expect(secondRole.value?.role).toBe('follower');
+    expect(secondRole.value?.leaderId).toBe(firstRole.value?.leaderId);
+  });
+});
@@ -0,0 +1,36 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { createTestDatabase, cleanupDatabase } from '../fixtures/test-helpers.js';
+import type { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+const HUGE_RESULT = `WITH RECURSIVE seq(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < 50000)
+  SELECT n, printf('%.*c', 100, 'x') AS pad FROM seq`;
+
+describe('maxResultBytes', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    db = await createTestDatabase();
+  });
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+  });
+
+  it('rejects a result larger than the limit and points at queryStream', async () => {
+    const result = await db.query(HUGE_RESULT, undefined, { maxResultBytes: 1024 * 1024 });
+    expect(result.value).toBeUndefined();
+    expect(result.error?.msg).toContain('Result too large');
+    expect(result.error?.msg).toContain('queryStream');
+  });
+
+  it('returns results within the limit unchanged', async () => {
+    const result = await db.query('SELECT 1 AS one', undefined, { maxResultBytes: 1024 });
+    expect(result.error).toBeUndefined();
+    expect(JSON.parse(result.value!)).toEqual([{ one: 1 }]);
+  });
+
+  it('rejects a limit that is not a positive integer', async () => {
+    const result = await db.query('SELECT 1', undefined, { maxResultBytes: 0 });
+    expect(result.error?.msg).toContain('maxResultBytes');
+  });
+});
@@ -0,0 +1,38 @@
+import { describe, it, expect, afterEach } from 'vitest';
+import init, { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+describe('allowMemoryFallback', () => {
+  let db: SQLiteWasmDatabase | undefined;
+
+  afterEach(async () => {
+    await db?.close();
+    db = undefined;
+  });
+
+  it('keeps using OPFS when it is available', async () => {
+    await init();
+    const stages: string[] = [];
+    // A namespace of its own so no connection from another test leads it.
+    const result = await SQLiteWasmDatabase.new('ui-test-db', {
+      namespace: 'memory-fallback',
+      allowMemoryFallback: true,
+      onProgress: (event: { stage: string }) => stages.push(event.stage)
+    });
+    expect(result.error).toBeUndefined();
+    db = result.value!;
+
+    expect(stages).toContain('opfs-installed');
+    expect(stages).not.toContain('memory-fallback');
+    const memoryOnly = await db.isMemoryOnly();
+    expect(memoryOnly.error).toBeUndefined();
+    expect(memoryOnly.value).toBe(false);
+  });
+
+  it('rejects a flag that is not a boolean', async () => {
+    await init();
+    const result = await SQLiteWasmDatabase.new('ui-test-db', {
+      allowMemoryFallback: 'yes'
+    });
+    expect(result.error?.msg).toContain('allowMemoryFallback');
+  });
+});
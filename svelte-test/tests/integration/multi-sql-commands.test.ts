@@ -83,6 +83,20 @@ describe("Multi-SQL Commands (UI Integration)", () => {
     await assertions.assertRowCount(db, "SELECT * FROM gate_ui", 1);
   });
 
+  it("returns the COUNT(*) row whether or not the query ends with a semicolon", async () => {
+    await db.query(`CREATE TABLE count_ui (id INTEGER)`);
+
+    for (const sql of [
+      "SELECT COUNT(*) AS c FROM count_ui",
+      "SELECT COUNT(*) AS c FROM count_ui;",
+      "INSERT INTO count_ui (id) VALUES (1); DELETE FROM count_ui; SELECT COUNT(*) AS c FROM count_ui;",
+    ]) {
+      const res = await db.query(sql);
+      expect(res.error).toBeUndefined();
+      expect(JSON.parse(res.value || "[]")).toEqual([{ c: 0 }]);
+    }
+  });
+
   it("supports triggers with semicolons in the body (BEGIN ... END)", async () => {
     await db.query(`CREATE TABLE trg_src_ui (id INTEGER)`);
     await db.query(`CREATE TABLE trg_log_ui (msg TEXT)`);
@@ -0,0 +1,61 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import init, { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+async function open(namespace: string): Promise<SQLiteWasmDatabase> {
+  const result = await SQLiteWasmDatabase.new('ns-test-db', { namespace });
+  expect(result.error).toBeUndefined();
+  return result.value!;
+}
+
+describe('namespace option', () => {
+  let appOne: SQLiteWasmDatabase;
+  let appTwo: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    await init();
+    appOne = await open('app-one');
+    appTwo = await open('app-two');
+  });
+
+  afterEach(async () => {
+    await appOne?.query('DROP TABLE IF EXISTS ns_ui');
+    await appTwo?.query('DROP TABLE IF EXISTS ns_ui');
+    await appOne?.close();
+    await appTwo?.close();
+  });
+
+  it('gives each namespace its own leader', async () => {
+    const one = await appOne.role();
+    const two = await appTwo.role();
+    expect(one.value?.role).toBe('leader');
+    expect(two.value?.role).toBe('leader');
+    expect(one.value?.leaderId).not.toBe(two.value?.leaderId);
+  });
+
+  it('keeps data written under one namespace out of the other', async () => {
+    await appOne.query('CREATE TABLE IF NOT EXISTS ns_ui (owner TEXT)');
+    await appOne.query("INSERT INTO ns_ui (owner) VALUES ('app-one')");
+
+    const missing = await appTwo.query('SELECT owner FROM ns_ui');
+    expect(missing.error?.msg).toContain('no such table: ns_ui');
+
+    await appTwo.query('CREATE TABLE IF NOT EXISTS ns_ui (owner TEXT)');
+    await appTwo.query("INSERT INTO ns_ui (owner) VALUES ('app-two')");
+
+    const one = await appOne.query('SELECT owner FROM ns_ui');
+    const two = await appTwo.query('SELECT owner FROM ns_ui');
+    expect(JSON.parse(one.value || '[]')).toEqual([{ owner: 'app-one' }]);
+    expect(JSON.parse(two.value || '[]')).toEqual([{ owner: 'app-two' }]);
+  });
+
+  it('does not relay change events across namespaces', async () => {
+    await appOne.query('CREATE TABLE IF NOT EXISTS ns_ui (owner TEXT)');
+    await appTwo.query('CREATE TABLE IF NOT EXISTS ns_ui (owner TEXT)');
+    const seen: unknown[] = [];
+    appTwo.onChange((change: unknown) => seen.push(change));
+
+    await appOne.query("INSERT INTO ns_ui (owner) VALUES ('app-one')");
+    await new Promise((resolve) => setTimeout(resolve, 200));
+    expect(seen).toEqual([]);
+  });
+});
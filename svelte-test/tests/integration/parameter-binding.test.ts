@@ -14,6 +14,24 @@ describe('Parameter Binding (UI Integration)', () => {
   });
 
   describe('Positional ? placeholders', () => {
+    it('binds a bare value to a single placeholder unless strictParams is set', async () => {
+      const lenient = await db.query('SELECT ? AS v', 42);
+      expect(lenient.error).toBeUndefined();
+      expect(JSON.parse(lenient.value || '[]')).toEqual([{ v: 42 }]);
+
+      let caught: unknown = null;
+      let result: any;
+      try {
+        result = await db.query('SELECT ? AS v', 42, { strictParams: true });
+      } catch (e) {
+        caught = e;
+      }
+      const msg = caught
+        ? typeof caught === 'string' ? caught : (caught as any).message || String(caught)
+        : result.error?.msg || result.error?.readableMsg || JSON.stringify(result.error);
+      expect(msg).toMatch(/params must be an array/i);
+    });
+
     it('binds sequential ? parameters for INSERT and SELECT', async () => {
       await db.query(`
         CREATE TABLE param_test (
@@ -180,6 +198,27 @@ describe('Parameter Binding (UI Integration)', () => {
     });
   });
 
+  describe('Multi-statement scripts with allowMultiple', () => {
+    it('binds a shared ?1 value in every statement', async () => {
+      await db.query('CREATE TABLE param_multi_a (id INTEGER, label TEXT)');
+      await db.query('CREATE TABLE param_multi_b (id INTEGER)');
+
+      const result = await db.query(
+        `INSERT INTO param_multi_a (id, label) VALUES (?1, ?2);
+         INSERT INTO param_multi_b (id) VALUES (?1);`,
+        [42, 'answer'],
+        { allowMultiple: true }
+      );
+      expect(result.error).toBeUndefined();
+      expect(result.value).toBe('Query executed successfully. Rows affected: 2');
+
+      const rows = await db.query(
+        'SELECT a.label FROM param_multi_a a JOIN param_multi_b b ON b.id = a.id'
+      );
+      expect(JSON.parse(rows.value || '[]')).toEqual([{ label: 'answer' }]);
+    });
+  });
+
   describe('Type mapping', () => {
     it('binds null, boolean, number, string types correctly', async () => {
       await db.query(`
@@ -245,6 +284,51 @@ describe('Parameter Binding (UI Integration)', () => {
       expect(rows[0].len).toBe(5);
     });
 
+    it('binds arrays as JSON text', async () => {
+      await db.query('CREATE TABLE param_json (tags TEXT)');
+      const ins = await db.query('INSERT INTO param_json (tags) VALUES (?)', [[1, 2, 3]]);
+      expect(ins.error).toBeUndefined();
+
+      const sel = await db.query('SELECT tags, json_array_length(tags) AS len FROM param_json');
+      const rows = JSON.parse(sel.value || '[]');
+      expect(rows).toHaveLength(1);
+      expect(JSON.parse(rows[0].tags)).toEqual([1, 2, 3]);
+      expect(rows[0].len).toBe(3);
+    });
+
+    it('binds text_blob params as their UTF-8 bytes', async () => {
+      await db.query('CREATE TABLE param_text_blob (data BLOB)');
+      const text = 'héllo wörld ✓';
+      const ins = await db.query('INSERT INTO param_text_blob (data) VALUES (?)', [
+        { __type: 'text_blob', value: text }
+      ] as any);
+      expect(ins.error).toBeUndefined();
+
+      const sel = await db.query('SELECT typeof(data) AS kind, length(data) AS len FROM param_text_blob');
+      const rows = JSON.parse(sel.value || '[]');
+      expect(rows).toHaveLength(1);
+      expect(rows[0].kind).toBe('blob');
+      expect(rows[0].len).toBe(new TextEncoder().encode(text).length);
+    });
+
+    it('binds explicitly typed int/double params and typed numeric arrays', async () => {
+      await db.query('CREATE TABLE param_typed (n INTEGER, r REAL) STRICT');
+      const ins = await db.query('INSERT INTO param_typed (n, r) VALUES (?, ?)', [
+        { __type: 'int', value: '7' },
+        { __type: 'double', value: 2 }
+      ] as any);
+      expect(ins.error).toBeUndefined();
+
+      const sel = await db.query('SELECT typeof(n) AS n, typeof(r) AS r, r AS rv FROM param_typed');
+      expect(JSON.parse(sel.value || '[]')).toEqual([{ n: 'integer', r: 'real', rv: 2 }]);
+
+      const ints = await db.query('SELECT typeof(?1) AS a, ?2 AS b', new Int32Array([5, -3]));
+      expect(JSON.parse(ints.value || '[]')).toEqual([{ a: 'integer', b: -3 }]);
+
+      const doubles = await db.query('SELECT typeof(?) AS a', new Float64Array([4]));
+      expect(JSON.parse(doubles.value || '[]')).toEqual([{ a: 'real' }]);
+    });
+
     it('rejects NaN/Infinity numbers at normalization', async () => {
       let caught: unknown = null;
       let result: any;
@@ -0,0 +1,22 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { createTestDatabase, cleanupDatabase } from '../fixtures/test-helpers.js';
+import type { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+describe('ping()', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    db = await createTestDatabase();
+  });
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+  });
+
+  it('resolves with the round-trip latency once ready', async () => {
+    const result = await db.ping();
+    expect(result.error).toBeUndefined();
+    expect(typeof result.value).toBe('number');
+    expect(result.value).toBeGreaterThanOrEqual(0);
+  });
+});
@@ -0,0 +1,115 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { createTestDatabase, cleanupDatabase } from '../fixtures/test-helpers.js';
+import type { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+// Recursive CTE that calls a custom function on every row so the DB worker
+// stays busy long enough for an abort to land mid-flight.
+const SLOW_SELECT = `
+  WITH RECURSIVE seq(n) AS (
+    SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < 200000
+  )
+  SELECT FLOAT_SUM(FLOAT_NEGATE(FLOAT_ZERO_HEX())) AS total FROM seq
+`;
+
+// Takes far longer than the test timeout unless it is interrupted.
+const ENDLESS_SELECT = `
+  WITH RECURSIVE seq(n) AS (
+    SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < 1000000000
+  )
+  SELECT COUNT(*) AS total FROM seq
+`;
+
+describe('Query Cancellation', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    db = await createTestDatabase();
+  });
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+  });
+
+  it('rejects a slow SELECT with an aborted error when its signal fires', async () => {
+    const controller = new AbortController();
+    const pending = db.query(SLOW_SELECT, undefined, { signal: controller.signal });
+    controller.abort();
+
+    const result = await pending;
+    expect(result.value).toBeUndefined();
+    expect(result.error?.msg).toContain('Query aborted');
+
+    // The connection keeps working once the interrupted job settles.
+    const after = await db.query('SELECT 1 AS ok');
+    expect(JSON.parse(after.value || '[]')).toEqual([{ ok: 1 }]);
+  });
+
+  it('interrupts a statement that is already running', async () => {
+    const controller = new AbortController();
+    const pending = db.query(ENDLESS_SELECT, undefined, { signal: controller.signal });
+    // Let the statement start stepping before aborting it.
+    await new Promise((resolve) => setTimeout(resolve, 200));
+    controller.abort();
+    expect((await pending).error?.msg).toContain('Query aborted');
+
+    // The next query only runs once the DB worker has stopped the CTE.
+    const started = performance.now();
+    const after = await db.query('SELECT 1 AS ok');
+    expect(JSON.parse(after.value || '[]')).toEqual([{ ok: 1 }]);
+    expect(performance.now() - started).toBeLessThan(2000);
+  });
+
+  it('cancelAll interrupts the running statement and drops queued ones', async () => {
+    const running = db.query(ENDLESS_SELECT);
+    const queued = db.query(ENDLESS_SELECT);
+    await new Promise((resolve) => setTimeout(resolve, 200));
+
+    expect(db.cancelAll().value).toBe(2);
+    expect((await running).error?.msg).toContain('Query aborted');
+    expect((await queued).error?.msg).toContain('Query aborted');
+
+    const started = performance.now();
+    const after = await db.query('SELECT 1 AS ok');
+    expect(JSON.parse(after.value || '[]')).toEqual([{ ok: 1 }]);
+    expect(performance.now() - started).toBeLessThan(2000);
+  });
+
+  it('drops a queued query that is aborted before it starts', async () => {
+    await db.query('CREATE TABLE cancel_test (id INTEGER PRIMARY KEY, label TEXT)');
+
+    const busy = db.query(SLOW_SELECT);
+    const controller = new AbortController();
+    const queued = db.query(
+      "INSERT INTO cancel_test (label) VALUES ('should-not-run')",
+      undefined,
+      { signal: controller.signal }
+    );
+    controller.abort();
+
+    const queuedResult = await queued;
+    expect(queuedResult.error?.msg).toContain('Query aborted');
+    await busy;
+
+    const rows = await db.query('SELECT COUNT(*) AS count FROM cancel_test');
+    expect(JSON.parse(rows.value || '[]')[0].count).toBe(0);
+  });
+
+  it('treats aborting an already-completed query as a no-op', async () => {
+    const controller = new AbortController();
+    const result = await db.query('SELECT 42 AS answer', undefined, {
+      signal: controller.signal,
+    });
+    controller.abort();
+
+    expect(result.error).toBeUndefined();
+    expect(JSON.parse(result.value || '[]')).toEqual([{ answer: 42 }]);
+  });
+
+  it('rejects immediately when the signal is already aborted', async () => {
+    const controller = new AbortController();
+    controller.abort();
+
+    const result = await db.query('SELECT 1', undefined, { signal: controller.signal });
+    expect(result.error?.msg).toContain('Query aborted');
+  });
+});
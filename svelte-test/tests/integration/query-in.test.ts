@@ -0,0 +1,45 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { createTestDatabase, cleanupDatabase } from '../fixtures/test-helpers.js';
+import type { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+describe('queryIn()', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    db = await createTestDatabase();
+    await db.query('CREATE TABLE query_in_ui (id INTEGER PRIMARY KEY, kind TEXT)');
+    await db.query(
+      "INSERT INTO query_in_ui (id, kind) VALUES (1, 'a'), (2, 'b'), (3, 'a'), (4, 'b'), (5, 'a')"
+    );
+  });
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+  });
+
+  it('expands an array into an IN list', async () => {
+    const result = await db.queryIn(
+      'SELECT id FROM query_in_ui WHERE id IN (:ids) AND kind = :kind ORDER BY id',
+      { ids: [1, 2, 3, 5], kind: 'a' }
+    );
+    expect(result.error).toBeUndefined();
+    expect(JSON.parse(result.value || '[]')).toEqual([{ id: 1 }, { id: 3 }, { id: 5 }]);
+  });
+
+  it('matches nothing for an empty array', async () => {
+    const none = await db.queryIn('SELECT id FROM query_in_ui WHERE id IN (:ids)', { ids: [] });
+    expect(none.error).toBeUndefined();
+    expect(JSON.parse(none.value || '[]')).toEqual([]);
+
+    const all = await db.queryIn(
+      'SELECT COUNT(*) AS n FROM query_in_ui WHERE id NOT IN (:ids)',
+      { ids: [] }
+    );
+    expect(JSON.parse(all.value || '[]')).toEqual([{ n: 5 }]);
+  });
+
+  it('rejects a placeholder without a value', async () => {
+    const result = await db.queryIn('SELECT id FROM query_in_ui WHERE id IN (:ids)', {});
+    expect(result.error?.msg).toContain('No value given for :ids');
+  });
+});
@@ -0,0 +1,40 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { createTestDatabase, cleanupDatabase } from '../fixtures/test-helpers.js';
+import type { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+describe('returnNotices', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    db = await createTestDatabase();
+    await db.query(`
+      CREATE TABLE notice_a_ui (x INTEGER);
+      CREATE TABLE notice_b_ui (x INTEGER);
+      WITH RECURSIVE seq(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < 50)
+      INSERT INTO notice_a_ui SELECT n FROM seq;
+      INSERT INTO notice_b_ui SELECT x FROM notice_a_ui;
+    `);
+  });
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+  });
+
+  it('reports an automatic index built for an unindexed join', async () => {
+    const result = await db.query(
+      'SELECT count(*) AS n FROM notice_a_ui a, notice_b_ui b WHERE a.x = b.x',
+      undefined,
+      { returnNotices: true }
+    );
+    expect(result.error).toBeUndefined();
+
+    const parsed = JSON.parse(result.value!);
+    expect(parsed.result).toEqual([{ n: 50 }]);
+    expect(parsed.notices.some((n: string) => n.startsWith('automatic index: 50 rows'))).toBe(true);
+  });
+
+  it('returns an empty list when nothing was noticed', async () => {
+    const result = await db.query('SELECT 1 AS one', undefined, { returnNotices: true });
+    expect(JSON.parse(result.value!)).toEqual({ result: [{ one: 1 }], notices: [] });
+  });
+});
@@ -0,0 +1,53 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { createTestDatabase, cleanupDatabase } from '../fixtures/test-helpers.js';
+import type { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+describe('queryOne() and queryValue()', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    db = await createTestDatabase();
+    await db.query('CREATE TABLE query_one_ui (id INTEGER PRIMARY KEY, name TEXT)');
+    await db.query("INSERT INTO query_one_ui (id, name) VALUES (1, 'Alice'), (2, 'Bob')");
+  });
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+  });
+
+  it('returns the first row, or null on a miss', async () => {
+    const hit = await db.queryOne('SELECT id, name FROM query_one_ui WHERE id = ?', [2]);
+    expect(hit.error).toBeUndefined();
+    expect(hit.value).toEqual({ id: 2, name: 'Bob' });
+
+    const miss = await db.queryOne('SELECT id, name FROM query_one_ui WHERE id = ?', [99]);
+    expect(miss.error).toBeUndefined();
+    expect(miss.value).toBeNull();
+  });
+
+  it('returns a single value, or null on a miss', async () => {
+    const count = await db.queryValue('SELECT COUNT(*) FROM query_one_ui');
+    expect(count.value).toBe(2);
+
+    const name = await db.queryValue('SELECT name FROM query_one_ui WHERE id = ?', [1]);
+    expect(name.value).toBe('Alice');
+
+    const miss = await db.queryValue('SELECT name FROM query_one_ui WHERE id = ?', [99]);
+    expect(miss.error).toBeUndefined();
+    expect(miss.value).toBeNull();
+
+    const bare = await db.queryValue('SELECT name FROM query_one_ui WHERE id = ?', 2);
+    expect(bare.value).toBe('Bob');
+
+    const wide = await db.queryValue('SELECT id, name FROM query_one_ui');
+    expect(wide.error?.msg).toContain('queryValue expects one column');
+  });
+
+  it('rejects statements that do not return rows', async () => {
+    const one = await db.queryOne("UPDATE query_one_ui SET name = 'Carol' WHERE id = 1");
+    expect(one.error?.msg).toContain('queryOne requires a statement that returns rows');
+
+    const value = await db.queryValue('DELETE FROM query_one_ui WHERE id = 99');
+    expect(value.error?.msg).toContain('queryValue requires a statement that returns rows');
+  });
+});
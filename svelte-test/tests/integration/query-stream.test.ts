@@ -0,0 +1,76 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { createTestDatabase, cleanupDatabase } from '../fixtures/test-helpers.js';
+import type { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+describe('queryStream()', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    db = await createTestDatabase();
+    await db.query('CREATE TABLE stream_ui (n INTEGER PRIMARY KEY)');
+    await db.query(
+      `WITH RECURSIVE seq(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < 5000)
+       INSERT INTO stream_ui (n) SELECT n FROM seq`
+    );
+  });
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+  });
+
+  it('yields every row in chunks and finalizes the statement', async () => {
+    const sizes: number[] = [];
+    let expected = 1;
+    const stream = db.queryStream('SELECT n FROM stream_ui WHERE n >= ? ORDER BY n', [1], {
+      chunkSize: 500
+    });
+    expect(stream.error).toBeUndefined();
+    for await (const rows of stream.value!) {
+      sizes.push(rows.length);
+      for (const row of rows) {
+        expect(row.n).toBe(expected++);
+      }
+    }
+
+    expect(sizes).toEqual(Array(10).fill(500));
+    expect(expected - 1).toBe(5000);
+
+    // VACUUM fails while any statement is still open on the connection.
+    const vacuum = await db.vacuum();
+    expect(vacuum.error).toBeUndefined();
+  });
+
+  it('finalizes the statement when the loop exits early', async () => {
+    let seen = 0;
+    for await (const rows of db.queryStream('SELECT n FROM stream_ui', undefined, { chunkSize: 100 })
+      .value!) {
+      seen += rows.length;
+      break;
+    }
+    expect(seen).toBe(100);
+
+    const vacuum = await db.vacuum();
+    expect(vacuum.error).toBeUndefined();
+  });
+
+  it('rejects statements that do not return rows', async () => {
+    const stream = db.queryStream('DELETE FROM stream_ui').value!;
+    await expect(stream.next()).rejects.toThrow('return rows');
+
+    const count = await db.query('SELECT COUNT(*) AS c FROM stream_ui');
+    expect(JSON.parse(count.value!)[0].c).toBe(5000);
+  });
+
+  it('truncates cells to maxCellBytes', async () => {
+    const stream = db.queryStream("SELECT 'abcdefghij' AS text", undefined, { maxCellBytes: 4 });
+    const first = await stream.value!.next();
+    expect(first.value![0].text).toEqual({ __truncated: true, preview: 'abcd', totalBytes: 10 });
+    await stream.value!.return!();
+  });
+
+  it('reports an invalid chunkSize as an error result', () => {
+    const result = db.queryStream('SELECT n FROM stream_ui', undefined, { chunkSize: 0 });
+    expect(result.value).toBeUndefined();
+    expect(result.error?.msg).toContain('chunkSize');
+  });
+});
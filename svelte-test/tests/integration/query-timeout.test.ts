@@ -0,0 +1,53 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { createTestDatabase, cleanupDatabase } from '../fixtures/test-helpers.js';
+import type { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+// Recursive CTE that keeps the DB worker busy far longer than the timeouts
+// used below.
+const SLOW_SELECT = `
+  WITH RECURSIVE seq(n) AS (
+    SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < 5000000
+  )
+  SELECT FLOAT_SUM(FLOAT_NEGATE(FLOAT_ZERO_HEX())) AS total FROM seq
+`;
+
+describe('Query Timeout', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    db = await createTestDatabase();
+  });
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+  });
+
+  it('resolves a fast query that finishes before its timeout', async () => {
+    const result = await db.query('SELECT 7 AS lucky', undefined, { timeoutMs: 2000 });
+    expect(result.error).toBeUndefined();
+    expect(JSON.parse(result.value || '[]')).toEqual([{ lucky: 7 }]);
+
+    // Waiting past a short timeout must not surface a late rejection.
+    const quick = await db.query('SELECT 1 AS ok', undefined, { timeoutMs: 20 });
+    await new Promise((resolve) => setTimeout(resolve, 50));
+    expect(quick.error).toBeUndefined();
+  });
+
+  it('rejects a slow query with a timeout error and interrupts it', async () => {
+    const started = Date.now();
+    const result = await db.query(SLOW_SELECT, undefined, { timeoutMs: 50 });
+    expect(result.value).toBeUndefined();
+    expect(result.error?.msg).toContain('Query timed out');
+    expect(Date.now() - started).toBeLessThan(5000);
+
+    // The interrupted statement releases the worker for the next query.
+    const after = await db.query('SELECT 1 AS ok', undefined, { timeoutMs: 5000 });
+    expect(after.error).toBeUndefined();
+    expect(JSON.parse(after.value || '[]')).toEqual([{ ok: 1 }]);
+  });
+
+  it('rejects invalid timeout values', async () => {
+    const result = await db.query('SELECT 1', undefined, { timeoutMs: -1 });
+    expect(result.error?.msg).toContain('timeoutMs');
+  });
+});
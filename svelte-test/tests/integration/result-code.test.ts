@@ -0,0 +1,45 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { createTestDatabase, cleanupDatabase } from '../fixtures/test-helpers.js';
+import type { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+const SQLITE_DONE = 101;
+
+describe('returnResultCode', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    db = await createTestDatabase();
+    await db.query('CREATE TABLE result_code_ui (id INTEGER PRIMARY KEY, name TEXT)');
+  });
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+  });
+
+  it('reports SQLITE_DONE for a DML statement', async () => {
+    const result = await db.query('INSERT INTO result_code_ui (name) VALUES (?)', ['a'], {
+      returnResultCode: true
+    });
+    expect(result.error).toBeUndefined();
+
+    const parsed = JSON.parse(result.value!);
+    expect(parsed.resultCode).toBe(SQLITE_DONE);
+    expect(parsed.result).toContain('Rows affected: 1');
+  });
+
+  it('wraps query rows alongside the code', async () => {
+    await db.query("INSERT INTO result_code_ui (name) VALUES ('a'), ('b')");
+    const result = await db.query('SELECT name FROM result_code_ui ORDER BY id', undefined, {
+      returnResultCode: true
+    });
+
+    const parsed = JSON.parse(result.value!);
+    expect(parsed.resultCode).toBe(SQLITE_DONE);
+    expect(parsed.result).toEqual([{ name: 'a' }, { name: 'b' }]);
+  });
+
+  it('leaves the result unwrapped by default', async () => {
+    const result = await db.query('INSERT INTO result_code_ui (name) VALUES (?)', ['a']);
+    expect(result.value).toContain('Rows affected: 1');
+  });
+});
@@ -0,0 +1,35 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { createTestDatabase, cleanupDatabase } from '../fixtures/test-helpers.js';
+import type { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+describe('Rows vs summary detection', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    db = await createTestDatabase();
+    await db.query('CREATE TABLE detection_ui (id INTEGER PRIMARY KEY, label TEXT)');
+  });
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+  });
+
+  it('returns rows for a WITH query', async () => {
+    const result = await db.query('WITH t AS (SELECT 1 AS n) SELECT * FROM t');
+    expect(result.error).toBeUndefined();
+    expect(JSON.parse(result.value!)).toEqual([{ n: 1 }]);
+  });
+
+  it('returns rows for PRAGMA table_info', async () => {
+    const result = await db.query('PRAGMA table_info(detection_ui)');
+    expect(result.error).toBeUndefined();
+    const rows = JSON.parse(result.value!);
+    expect(Array.isArray(rows)).toBe(true);
+    expect(rows.map((row: { name: string }) => row.name)).toEqual(['id', 'label']);
+  });
+
+  it('returns a summary for statements without columns', async () => {
+    const result = await db.query("INSERT INTO detection_ui (label) VALUES ('a')");
+    expect(result.value).toBe('Query executed successfully. Rows affected: 1');
+  });
+});
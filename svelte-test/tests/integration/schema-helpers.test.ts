@@ -0,0 +1,127 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { createTestDatabase, cleanupDatabase } from '../fixtures/test-helpers.js';
+import type { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+describe('schema helpers', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    db = await createTestDatabase();
+  });
+
+  afterEach(async () => {
+    if (db) {
+      await db.query('DROP VIEW IF EXISTS schema_view_ui');
+      await cleanupDatabase(db);
+    }
+  });
+
+  describe('insert()', () => {
+    beforeEach(async () => {
+      await db.query(
+        `CREATE TABLE schema_ui (
+           id INTEGER PRIMARY KEY,
+           price INTEGER,
+           qty INTEGER,
+           total INTEGER GENERATED ALWAYS AS (price * qty) STORED,
+           label TEXT GENERATED ALWAYS AS ('x' || qty) VIRTUAL
+         )`
+      );
+    });
+
+    it('returns generated column values when requested', async () => {
+      const result = await db.insert('schema_ui', { price: 4, qty: 3 }, { returnGenerated: true });
+      expect(result.error).toBeUndefined();
+      expect(JSON.parse(result.value!)).toEqual([{ total: 12, label: 'x3' }]);
+    });
+
+    it('reports affected rows without the option', async () => {
+      const result = await db.insert('schema_ui', { price: 2, qty: 5 });
+      expect(result.error).toBeUndefined();
+      expect(result.value).toContain('Rows affected: 1');
+
+      const rows = await db.query('SELECT total FROM schema_ui');
+      expect(JSON.parse(rows.value || '[]')).toEqual([{ total: 10 }]);
+    });
+  });
+
+  describe('tableExists()', () => {
+    it('finds an existing table regardless of case', async () => {
+      await db.query('CREATE TABLE schema_ui (id INTEGER PRIMARY KEY, name TEXT)');
+
+      expect((await db.tableExists('schema_ui')).value).toBe(true);
+      expect((await db.tableExists('SCHEMA_UI')).value).toBe(true);
+    });
+
+    it('reports false for a missing table and binds the name', async () => {
+      expect((await db.tableExists('schema_missing_ui')).value).toBe(false);
+      expect((await db.tableExists("x' OR '1'='1")).value).toBe(false);
+    });
+
+    it('ignores views and indexes unless their types are requested', async () => {
+      await db.query('CREATE TABLE schema_ui (id INTEGER PRIMARY KEY, name TEXT)');
+      await db.query('CREATE VIEW schema_view_ui AS SELECT name FROM schema_ui');
+      await db.query('CREATE INDEX schema_ui_name ON schema_ui (name)');
+
+      expect((await db.tableExists('schema_view_ui')).value).toBe(false);
+      expect((await db.tableExists('schema_ui_name')).value).toBe(false);
+      expect(
+        (await db.tableExists('schema_view_ui', { types: ['table', 'view'] })).value
+      ).toBe(true);
+      expect((await db.tableExists('schema_ui_name', { types: ['index'] })).value).toBe(true);
+    });
+
+    it('rejects unknown object types', async () => {
+      const result = await db.tableExists('schema_ui', { types: ['column'] });
+      expect(result.error?.msg).toContain('options.types');
+    });
+  });
+
+  describe('columnExists()', () => {
+    beforeEach(async () => {
+      await db.query('CREATE TABLE schema_ui (id INTEGER PRIMARY KEY, display_name TEXT)');
+    });
+
+    it('finds existing columns regardless of case', async () => {
+      expect((await db.columnExists('schema_ui', 'display_name')).value).toBe(true);
+      expect((await db.columnExists('schema_ui', 'Display_Name')).value).toBe(true);
+      expect((await db.columnExists('SCHEMA_UI', 'id')).value).toBe(true);
+    });
+
+    it('reports false for missing columns and tables', async () => {
+      expect((await db.columnExists('schema_ui', 'email')).value).toBe(false);
+      expect((await db.columnExists('schema_missing_ui', 'id')).value).toBe(false);
+    });
+  });
+
+  describe('addColumnIfMissing()', () => {
+    it('adds a column only the first time', async () => {
+      await db.query('CREATE TABLE schema_ui (id INTEGER PRIMARY KEY)');
+
+      const first = await db.addColumnIfMissing('schema_ui', "email TEXT DEFAULT ''");
+      expect(first.error).toBeUndefined();
+      expect(first.value).toBe(true);
+
+      const second = await db.addColumnIfMissing('schema_ui', 'EMAIL TEXT');
+      expect(second.error).toBeUndefined();
+      expect(second.value).toBe(false);
+
+      const columns = await db.query("SELECT name FROM pragma_table_info('schema_ui')");
+      expect(JSON.parse(columns.value || '[]')).toEqual([{ name: 'id' }, { name: 'email' }]);
+    });
+
+    it('fails for a missing table', async () => {
+      const result = await db.addColumnIfMissing('schema_missing_ui', 'email TEXT');
+      expect(result.error?.msg).toContain('no such table');
+    });
+
+    it('rejects a column definition holding another statement', async () => {
+      await db.query('CREATE TABLE schema_ui (id INTEGER PRIMARY KEY)');
+
+      const result = await db.addColumnIfMissing('schema_ui', 'note TEXT; DROP TABLE schema_ui');
+      expect(result.error?.msg).toContain('allowMultiple');
+      expect((await db.tableExists('schema_ui')).value).toBe(true);
+      expect((await db.columnExists('schema_ui', 'note')).value).toBe(false);
+    });
+  });
+});
@@ -0,0 +1,42 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { createTestDatabase, cleanupDatabase } from '../fixtures/test-helpers.js';
+import type { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+describe('returnStatementCounts', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    db = await createTestDatabase();
+    await db.query('CREATE TABLE statement_counts_ui (id INTEGER PRIMARY KEY, v INTEGER)');
+  });
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+  });
+
+  it('reports the rows each statement of a script changed', async () => {
+    const result = await db.query(
+      `INSERT INTO statement_counts_ui (v) VALUES (1), (2), (3);
+       UPDATE statement_counts_ui SET v = 0 WHERE v >= 2;
+       DELETE FROM statement_counts_ui;`,
+      undefined,
+      { returnStatementCounts: true }
+    );
+    expect(result.error).toBeUndefined();
+
+    const parsed = JSON.parse(result.value!);
+    expect(parsed.result).toContain('Rows affected: 8');
+    expect(parsed.statementCounts).toEqual([
+      { statementIndex: 0, rowsAffected: 3, isQuery: false },
+      { statementIndex: 1, rowsAffected: 2, isQuery: false },
+      { statementIndex: 2, rowsAffected: 3, isQuery: false }
+    ]);
+  });
+
+  it('keeps the summary string by default', async () => {
+    const result = await db.query(
+      'INSERT INTO statement_counts_ui (v) VALUES (1); INSERT INTO statement_counts_ui (v) VALUES (2);'
+    );
+    expect(result.value).toBe('Query executed successfully. Rows affected: 2');
+  });
+});
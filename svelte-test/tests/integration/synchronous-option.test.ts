@@ -0,0 +1,46 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import init, { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+import { cleanupDatabase } from '../fixtures/test-helpers.js';
+
+describe('synchronous option', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    await init();
+    const result = await SQLiteWasmDatabase.new('ui-test-db', { synchronous: 'NORMAL' });
+    expect(result.error).toBeUndefined();
+    db = result.value!;
+  });
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+  });
+
+  it('applies the level when the database opens', async () => {
+    const result = await db.query('PRAGMA synchronous');
+    expect(result.error).toBeUndefined();
+    expect(JSON.parse(result.value || '[]')).toEqual([{ synchronous: 1 }]);
+  });
+
+  it('keeps committed writes across a reconnect', async () => {
+    await db.query('CREATE TABLE IF NOT EXISTS sync_ui (id INTEGER PRIMARY KEY, note TEXT)');
+    await db.query('DELETE FROM sync_ui');
+    const insert = await db.query("INSERT INTO sync_ui (note) VALUES ('kept')");
+    expect(insert.error).toBeUndefined();
+
+    const reconnected = await db.reconnect();
+    expect(reconnected.error).toBeUndefined();
+
+    const rows = await db.query('SELECT note FROM sync_ui');
+    expect(rows.error).toBeUndefined();
+    expect(JSON.parse(rows.value || '[]')).toEqual([{ note: 'kept' }]);
+
+    const level = await db.query('PRAGMA synchronous');
+    expect(JSON.parse(level.value || '[]')).toEqual([{ synchronous: 1 }]);
+  });
+
+  it('rejects unknown levels', async () => {
+    const result = await SQLiteWasmDatabase.new('ui-test-db', { synchronous: 'SOMETIMES' });
+    expect(result.error?.msg).toContain('options.synchronous must be one of');
+  });
+});
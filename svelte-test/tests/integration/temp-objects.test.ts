@@ -0,0 +1,33 @@
+import { describe, it, expect } from 'vitest';
+import { createTestDatabase } from '../fixtures/test-helpers.js';
+
+describe('temporary objects', () => {
+  it("are rejected from a follower but created on the leader's connection", async () => {
+    // A fresh database name guarantees no connection from another test leads it.
+    const name = `temp-objects-${Date.now()}`;
+    const leader = await createTestDatabase(name);
+    const follower = await createTestDatabase(name);
+    expect((await follower.role()).value?.role).toBe('follower');
+
+    const fromFollower = await follower.query('CREATE TEMP TABLE scratch (a INTEGER)');
+    expect(fromFollower.error?.msg).toContain('leader tab');
+    const qualified = await follower.query('CREATE TABLE temp.scratch (a INTEGER)');
+    expect(qualified.error?.msg).toContain('leader tab');
+    const quoted = await follower.query('CREATE TABLE "temp".scratch (a INTEGER)');
+    expect(quoted.error?.msg).toContain('leader tab');
+    const inTransaction = await follower.transaction([
+      { sql: 'CREATE TABLE IF NOT EXISTS temp_objects_kept (a INTEGER)' },
+      { sql: 'CREATE TEMP TABLE scratch (a INTEGER)' },
+    ]);
+    expect(inTransaction.error?.msg).toContain('leader tab');
+
+    const fromLeader = await leader.query('CREATE TEMP TABLE scratch (a INTEGER)');
+    expect(fromLeader.error).toBeUndefined();
+    const rows = await leader.query('INSERT INTO scratch (a) VALUES (1) RETURNING a');
+    expect(JSON.parse(rows.value!)).toEqual([{ a: 1 }]);
+
+    // Followers still read and write regular tables through the leader.
+    const regular = await follower.query("SELECT 'CREATE TEMP TABLE x (a)' AS text");
+    expect(regular.error).toBeUndefined();
+  });
+});
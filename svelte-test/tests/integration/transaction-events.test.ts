@@ -0,0 +1,50 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { createTestDatabase, cleanupDatabase, waitFor } from '../fixtures/test-helpers.js';
+import type { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+describe('Transaction Events', () => {
+  let db: SQLiteWasmDatabase;
+  let commits: number;
+  let rollbacks: number;
+
+  beforeEach(async () => {
+    db = await createTestDatabase();
+    await db.query('CREATE TABLE txn_hooks_ui (id INTEGER PRIMARY KEY)');
+    await db.query('INSERT INTO txn_hooks_ui (id) VALUES (1)');
+
+    commits = 0;
+    rollbacks = 0;
+    db.onCommit(() => commits++);
+    db.onRollback(() => rollbacks++);
+  });
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+  });
+
+  it('emits a commit event for an explicit COMMIT', async () => {
+    await db.query('BEGIN TRANSACTION');
+    await db.query('INSERT INTO txn_hooks_ui (id) VALUES (2)');
+    // Give any stray event time to arrive before checking nothing fired yet.
+    await new Promise((resolve) => setTimeout(resolve, 50));
+    expect(commits).toBe(0);
+
+    const commit = await db.query('COMMIT');
+    expect(commit.error).toBeUndefined();
+    await waitFor(() => commits === 1, 2000, 20);
+    expect(rollbacks).toBe(0);
+  });
+
+  it('emits a rollback event when a failing statement aborts the transaction', async () => {
+    await db.query('BEGIN TRANSACTION');
+    await db.query('INSERT INTO txn_hooks_ui (id) VALUES (3)');
+
+    const failed = await db.query('INSERT OR ROLLBACK INTO txn_hooks_ui (id) VALUES (1)');
+    expect(failed.error).toBeDefined();
+    await waitFor(() => rollbacks === 1, 2000, 20);
+    expect(commits).toBe(0);
+
+    const rows = await db.query('SELECT COUNT(*) AS count FROM txn_hooks_ui');
+    expect(JSON.parse(rows.value || '[]')[0].count).toBe(1);
+  });
+});
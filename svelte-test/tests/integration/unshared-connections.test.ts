@@ -0,0 +1,59 @@
+import { describe, it, expect, afterEach } from 'vitest';
+import init, { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+describe('shared: false', () => {
+  const opened: SQLiteWasmDatabase[] = [];
+
+  afterEach(async () => {
+    for (const db of opened.splice(0)) {
+      await db.close();
+    }
+  });
+
+  async function openUnshared(): Promise<SQLiteWasmDatabase> {
+    await init();
+    const result = await SQLiteWasmDatabase.new('ui-test-db', { shared: false });
+    expect(result.error).toBeUndefined();
+    opened.push(result.value!);
+    return result.value!;
+  }
+
+  it("keeps same-named connections from seeing each other's writes", async () => {
+    const first = await openUnshared();
+    const second = await openUnshared();
+
+    await first.query('CREATE TABLE private_ui (v TEXT)');
+    await first.query("INSERT INTO private_ui (v) VALUES ('first')");
+    await second.query('CREATE TABLE private_ui (v TEXT)');
+    await second.query("INSERT INTO private_ui (v) VALUES ('second')");
+
+    const firstRows = await first.query('SELECT v FROM private_ui');
+    expect(JSON.parse(firstRows.value!)).toEqual([{ v: 'first' }]);
+    const secondRows = await second.query('SELECT v FROM private_ui');
+    expect(JSON.parse(secondRows.value!)).toEqual([{ v: 'second' }]);
+  });
+
+  it('leads its own database without an election', async () => {
+    const db = await openUnshared();
+    const role = await db.role();
+    expect(role.value?.role).toBe('leader');
+
+    const temp = await db.query('CREATE TEMP TABLE scratch (a INTEGER)');
+    expect(temp.error).toBeUndefined();
+  });
+
+  it('deletes pools left behind by closed connections, keeping open ones', async () => {
+    const live = await openUnshared();
+    await live.query('CREATE TABLE kept_ui (v TEXT)');
+
+    const root = await navigator.storage.getDirectory();
+    const stale = '.opfs-sahpool-private-1-00000000';
+    await root.getDirectoryHandle(stale, { create: true });
+
+    await openUnshared();
+
+    await expect(root.getDirectoryHandle(stale)).rejects.toThrow();
+    const rows = await live.query('SELECT COUNT(*) AS n FROM kept_ui');
+    expect(JSON.parse(rows.value!)).toEqual([{ n: 0 }]);
+  });
+});
@@ -0,0 +1,37 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { createTestDatabase, cleanupDatabase } from '../fixtures/test-helpers.js';
+import type { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+describe('vacuum()', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    db = await createTestDatabase();
+  });
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+  });
+
+  it('reports a smaller page count after deleting many rows', async () => {
+    await db.query('CREATE TABLE vacuum_ui (id INTEGER PRIMARY KEY, payload TEXT)');
+    await db.query(
+      `WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 2000)
+       INSERT INTO vacuum_ui (payload) SELECT hex(randomblob(256)) FROM n`
+    );
+    await db.query('DELETE FROM vacuum_ui');
+
+    const result = await db.vacuum();
+    expect(result.error).toBeUndefined();
+    const report = result.value!;
+    expect(report.pageCountAfter).toBeLessThan(report.pageCountBefore);
+    expect(report.bytesFreed).toBeGreaterThan(0);
+  });
+
+  it('refuses to run inside an open transaction', async () => {
+    await db.query('BEGIN');
+    const result = await db.vacuum();
+    expect(result.error?.msg).toContain('transaction is open');
+    await db.query('ROLLBACK');
+  });
+});
@@ -0,0 +1,35 @@
+import { describe, it, expect, beforeEach, afterEach } from 'vitest';
+import { createTestDatabase, cleanupDatabase } from '../fixtures/test-helpers.js';
+import type { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+
+describe('validate()', () => {
+  let db: SQLiteWasmDatabase;
+
+  beforeEach(async () => {
+    db = await createTestDatabase();
+    await db.query('CREATE TABLE validate_ui (id INTEGER PRIMARY KEY, name TEXT)');
+  });
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+  });
+
+  it('accepts a valid multi-statement script without running it', async () => {
+    const result = await db.validate(
+      "INSERT INTO validate_ui (name) VALUES ('a'); UPDATE validate_ui SET name = 'b'; SELECT * FROM validate_ui;"
+    );
+    expect(result.error).toBeUndefined();
+    expect(result.value).toEqual({ valid: true });
+
+    const count = await db.query('SELECT COUNT(*) AS n FROM validate_ui');
+    expect(JSON.parse(count.value || '[]')).toEqual([{ n: 0 }]);
+  });
+
+  it('reports a syntax error in the second statement', async () => {
+    const result = await db.validate('SELECT * FROM validate_ui; SELEC name FROM validate_ui;');
+    expect(result.error).toBeUndefined();
+    expect(result.value?.valid).toBe(false);
+    expect(result.value?.statementIndex).toBe(1);
+    expect(result.value?.error).toContain('syntax error');
+  });
+});
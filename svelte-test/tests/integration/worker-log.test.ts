@@ -0,0 +1,49 @@
+import { describe, it, expect, afterEach } from 'vitest';
+import init, { SQLiteWasmDatabase } from '@rainlanguage/sqlite-web';
+import { cleanupDatabase } from '../fixtures/test-helpers.js';
+
+type LogLine = { level: string; message: string; timestamp: number };
+
+describe('onLog()', () => {
+  let db: SQLiteWasmDatabase | undefined;
+
+  afterEach(async () => {
+    if (db) await cleanupDatabase(db);
+    db = undefined;
+  });
+
+  it('surfaces the lines logged during initialization when debug is on', async () => {
+    await init();
+    const result = await SQLiteWasmDatabase.new('ui-test-db', { debug: true });
+    expect(result.error).toBeUndefined();
+    db = result.value!;
+
+    const lines: LogLine[] = [];
+    db.onLog((line: LogLine) => lines.push(line));
+
+    expect(lines.length).toBeGreaterThan(0);
+    expect(lines.some((line) => line.message.includes('ui-test-db'))).toBe(true);
+    for (const line of lines) {
+      expect(['debug', 'info', 'warn', 'error']).toContain(line.level);
+      expect(typeof line.timestamp).toBe('number');
+    }
+  });
+
+  it('logs nothing without debug', async () => {
+    await init();
+    const result = await SQLiteWasmDatabase.new('ui-test-db');
+    expect(result.error).toBeUndefined();
+    db = result.value!;
+
+    const lines: LogLine[] = [];
+    db.onLog((line: LogLine) => lines.push(line));
+    await db.query('SELECT 1');
+    expect(lines).toEqual([]);
+  });
+
+  it('rejects a non-boolean debug option', async () => {
+    await init();
+    const result = await SQLiteWasmDatabase.new('ui-test-db', { debug: 'yes' });
+    expect(result.error?.msg).toContain('options.debug must be a boolean');
+  });
+});